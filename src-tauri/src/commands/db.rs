@@ -1,13 +1,42 @@
 use std::collections::HashMap;
+use std::path::{Component, Path};
 
-use tauri::{Runtime, Window};
-use taurpc;
+use tauri::{Manager, Runtime, Window};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use taurpc::{self, Windows};
+use url::Url;
 
-use crate::db::types::{DbEntity, QueryResult};
+use crate::db::binary::{detect_mime, mime_extension};
+use crate::db::browse::{BrowsePage, BrowseRequest};
+use crate::db::catalog::CatalogSnapshot;
+use crate::db::changes::{RowChange, StagedChange};
+use crate::db::client::create_client;
+use crate::db::driver::{find_driver, DriverInfo, DRIVERS};
+use crate::db::dump::{DumpRequest, DumpSummary};
+use crate::db::editing::ColumnChanges;
+use crate::db::errors::DbError;
+use crate::db::export::columnar::ColumnarFormat;
+use crate::db::export::csv::CsvOptions;
+use crate::db::export::{
+    check_complete, render_result, write_result, ExportSource, ExportSummary, ExportTarget,
+    ExportWriter, ResultFormat, TextEncoding,
+};
+use crate::db::import::{
+    self, ImportFormat, ImportPreview, ImportProgress, ImportReport, ImportRequest,
+};
+use crate::db::schema_diff::{self, SchemaDiff, SnapshotSource};
+use crate::db::search::{SearchCancel, SearchProgress, SearchRequest, SearchSummary};
+use crate::db::typegen::{TypegenRequest, TypegenSummary};
+use crate::db::types::{DbEntity, QueryResult, Row, SavedBlob, ServerInfo, TimeZoneMode};
 use crate::errors::AppError;
-use crate::state::get_window_client;
+use crate::project::SnapshotInfo;
+use crate::state::{
+    get_window_changes, get_window_client, get_window_import_path, get_window_project,
+    get_window_search,
+};
+use crate::utils::dialogs::{pick_open_path, pick_save_path};
 
-#[taurpc::procedures(path = "db", export_to = "../src/lib/taurpc.ts")]
+#[taurpc::procedures(path = "db", export_to = "../src/lib/taurpc.ts", event_trigger = DbEventTrigger)]
 pub trait DbApi {
     // Test connection with raw connection string
     // TODO: Implement this w/o a current connection
@@ -22,16 +51,227 @@ pub trait DbApi {
     // Disconnect from database
     async fn disconnect(window: Window<impl Runtime>) -> Result<(), AppError>;
 
+    // List every available driver and what it supports
+    async fn list_drivers() -> Result<Vec<DriverInfo>, AppError>;
+
+    // Get the driver for current window, so unsupported features can be hidden
+    async fn get_driver(window: Window<impl Runtime>) -> Result<DriverInfo, AppError>;
+
+    // Get the flavor and version of the connected server
+    async fn get_server_info(window: Window<impl Runtime>) -> Result<Option<ServerInfo>, AppError>;
+
+    // Change the zone timestamps with a time zone are displayed in
+    async fn set_time_zone(
+        window: Window<impl Runtime>,
+        mode: TimeZoneMode,
+    ) -> Result<(), AppError>;
+
     // Execute a single query
     async fn execute_query(
         window: Window<impl Runtime>,
         query: String,
     ) -> Result<QueryResult, AppError>;
 
+    // Read a page of a table with server-side filtering, sorting and pagination
+    async fn browse_table(
+        window: Window<impl Runtime>,
+        request: BrowseRequest,
+    ) -> Result<BrowsePage, AppError>;
+
+    // Search text-like and UUID columns across schemas, emitting search_progress per table
+    async fn search(
+        window: Window<impl Runtime>,
+        request: SearchRequest,
+    ) -> Result<SearchSummary, AppError>;
+
+    // Stop the window's running search; it returns what was found so far
+    async fn cancel_search(window: Window<impl Runtime>) -> Result<(), AppError>;
+
+    // Emitted to the searching window after each table is searched
+    #[taurpc(event)]
+    async fn search_progress(progress: SearchProgress);
+
     // Get all entities including schemas as a flat list
     async fn get_all_entities(
         window: Window<impl Runtime>,
     ) -> Result<HashMap<String, DbEntity>, AppError>;
+
+    // The DDL that recreates an entity, for "Show CREATE"
+    async fn get_entity_ddl(
+        window: Window<impl Runtime>,
+        entity_id: String,
+    ) -> Result<String, AppError>;
+
+    // Update one row by its primary or unique key and return it as stored
+    async fn update_row(
+        window: Window<impl Runtime>,
+        table_id: String,
+        key: HashMap<String, String>,
+        changes: ColumnChanges,
+    ) -> Result<Row, AppError>;
+
+    // Add a row change to the window's change set
+    async fn stage_change(
+        window: Window<impl Runtime>,
+        change: RowChange,
+    ) -> Result<Vec<StagedChange>, AppError>;
+
+    // Remove a change from the window's change set
+    async fn unstage_change(
+        window: Window<impl Runtime>,
+        id: u32,
+    ) -> Result<Vec<StagedChange>, AppError>;
+
+    // Get the window's staged changes
+    async fn get_change_set(window: Window<impl Runtime>) -> Result<Vec<StagedChange>, AppError>;
+
+    // Render the SQL the change set would run
+    async fn preview_change_set(window: Window<impl Runtime>) -> Result<Vec<String>, AppError>;
+
+    // Apply all staged changes in one transaction and return the number of rows written
+    async fn apply_change_set(window: Window<impl Runtime>) -> Result<u64, AppError>;
+
+    // Drop all staged changes
+    async fn discard_change_set(window: Window<impl Runtime>) -> Result<(), AppError>;
+
+    // Ask where to save, then write a query's full result or a whole table as CSV/TSV.
+    // Returns None if the save dialog was cancelled.
+    async fn export_csv(
+        window: Window<impl Runtime>,
+        source: ExportSource,
+        options: CsvOptions,
+    ) -> Result<Option<ExportSummary>, AppError>;
+
+    // Export a query's full result or a whole table as JSON, NDJSON, Markdown or INSERTs,
+    // to a file chosen in a save dialog or to the clipboard.
+    // Returns None if the save dialog was cancelled.
+    async fn export_result(
+        window: Window<impl Runtime>,
+        source: ExportSource,
+        format: ResultFormat,
+        target: ExportTarget,
+    ) -> Result<Option<ExportSummary>, AppError>;
+
+    // Ask where to save, then write a query's full result or a whole table as Parquet or
+    // Arrow IPC, keeping column types. Returns None if the save dialog was cancelled.
+    async fn export_columnar(
+        window: Window<impl Runtime>,
+        source: ExportSource,
+        format: ColumnarFormat,
+    ) -> Result<Option<ExportSummary>, AppError>;
+
+    // Ask where to save, then write a SQL script recreating the chosen schemas and tables,
+    // optionally with their rows. Returns None if the save dialog was cancelled.
+    async fn dump_sql(
+        window: Window<impl Runtime>,
+        request: DumpRequest,
+    ) -> Result<Option<DumpSummary>, AppError>;
+
+    // Write TypeScript interfaces, Zod schemas or Rust structs for the chosen schemas' tables,
+    // views and enums. Returns None if no path was given and the save dialog was cancelled.
+    async fn generate_types(
+        window: Window<impl Runtime>,
+        request: TypegenRequest,
+    ) -> Result<Option<TypegenSummary>, AppError>;
+
+    // Ask for a CSV, NDJSON or Parquet file and preview its columns, inferred types and first rows.
+    // Returns None if the open dialog was cancelled.
+    async fn preview_import(
+        window: Window<impl Runtime>,
+        format: ImportFormat,
+    ) -> Result<Option<ImportPreview>, AppError>;
+
+    // Load the file picked in the last preview into a table, emitting import_progress per batch
+    async fn import_file(
+        window: Window<impl Runtime>,
+        request: ImportRequest,
+    ) -> Result<ImportReport, AppError>;
+
+    // Emitted to the importing window after each batch is loaded
+    #[taurpc(event)]
+    async fn import_progress(progress: ImportProgress);
+
+    // Fetch the full value of a binary cell by row key and save it to a file picked in a
+    // save dialog; None if the dialog is cancelled
+    async fn save_blob(
+        window: Window<impl Runtime>,
+        table_id: String,
+        key: HashMap<String, String>,
+        column: String,
+    ) -> Result<Option<SavedBlob>, AppError>;
+
+    // Save the current database's tables, functions and enums in the project under a name,
+    // for comparing against later
+    async fn save_schema_snapshot(
+        window: Window<impl Runtime>,
+        name: String,
+        schemas: Vec<String>,
+    ) -> Result<SnapshotInfo, AppError>;
+
+    // Compare two databases or snapshots, with the migration that makes target match source.
+    // An empty schema list compares every non-system schema.
+    async fn diff_schemas(
+        window: Window<impl Runtime>,
+        source: SnapshotSource,
+        target: SnapshotSource,
+        schemas: Vec<String>,
+    ) -> Result<SchemaDiff, AppError>;
+
+    // Write the current database's schema to .sqratch/schema, to be committed with the project
+    async fn write_schema_snapshot(
+        window: Window<impl Runtime>,
+        schemas: Vec<String>,
+    ) -> Result<SnapshotInfo, AppError>;
+
+    // Compare the committed schema snapshot with the current database; None if nothing has
+    // been committed yet. The migration brings the database back in line with the snapshot.
+    async fn check_schema_drift(
+        window: Window<impl Runtime>,
+    ) -> Result<Option<SchemaDiff>, AppError>;
+}
+
+/// Reads one side of a schema comparison
+async fn read_snapshot(
+    window: &Window<impl Runtime>,
+    source: &SnapshotSource,
+    schemas: &[String],
+) -> Result<CatalogSnapshot, AppError> {
+    match source {
+        SnapshotSource::Current => {
+            let client = get_window_client(window)?;
+            let mut guard = client.lock().await;
+
+            if !guard.is_connected().await? {
+                guard.connect().await?;
+            }
+
+            Ok(guard.catalog_snapshot(schemas).await?)
+        }
+        SnapshotSource::Connection { url } => {
+            let url = Url::parse(url).map_err(DbError::from)?;
+            let mut client = create_client(&url)?;
+            client.connect().await?;
+            let snapshot = client.catalog_snapshot(schemas).await;
+            let _ = client.disconnect().await;
+            Ok(snapshot?)
+        }
+        SnapshotSource::Saved { name } => {
+            let project = get_window_project(window)?;
+            let mut snapshot = project.handle.load_snapshot(name)?;
+            snapshot.retain_schemas(schemas);
+            Ok(snapshot)
+        }
+        SnapshotSource::Committed => {
+            let project = get_window_project(window)?;
+            let mut snapshot = project.handle.read_schema_snapshot()?.ok_or_else(|| {
+                AppError::Other(
+                    "No schema snapshot has been written to .sqratch/schema yet".to_string(),
+                )
+            })?;
+            snapshot.retain_schemas(schemas);
+            Ok(snapshot)
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -57,6 +297,43 @@ impl DbApi for DbApiImpl {
         Ok(guard.disconnect().await?)
     }
 
+    async fn list_drivers(self) -> Result<Vec<DriverInfo>, AppError> {
+        Ok(DRIVERS.iter().map(DriverInfo::from).collect())
+    }
+
+    async fn get_driver(self, window: Window<impl Runtime>) -> Result<DriverInfo, AppError> {
+        let client = get_window_client(&window)?;
+        let connection_string = client.lock().await.get_connection_string();
+        let url = Url::parse(&connection_string).map_err(DbError::from)?;
+
+        Ok(find_driver(url.scheme())?.into())
+    }
+
+    async fn get_server_info(
+        self,
+        window: Window<impl Runtime>,
+    ) -> Result<Option<ServerInfo>, AppError> {
+        let client = get_window_client(&window)?;
+        let mut guard = client.lock().await;
+
+        if !guard.is_connected().await? {
+            guard.connect().await?;
+        }
+
+        Ok(guard.server_info())
+    }
+
+    async fn set_time_zone(
+        self,
+        window: Window<impl Runtime>,
+        mode: TimeZoneMode,
+    ) -> Result<(), AppError> {
+        let client = get_window_client(&window)?;
+        let mut guard = client.lock().await;
+        guard.set_time_zone(mode);
+        Ok(())
+    }
+
     async fn execute_query(
         self,
         window: Window<impl Runtime>,
@@ -72,6 +349,61 @@ impl DbApi for DbApiImpl {
         Ok(guard.execute_query(&query).await?)
     }
 
+    async fn browse_table(
+        self,
+        window: Window<impl Runtime>,
+        request: BrowseRequest,
+    ) -> Result<BrowsePage, AppError> {
+        let client = get_window_client(&window)?;
+        let mut guard = client.lock().await;
+
+        if !guard.is_connected().await? {
+            guard.connect().await?;
+        }
+
+        Ok(guard.browse_table(&request).await?)
+    }
+
+    async fn search(
+        self,
+        window: Window<impl Runtime>,
+        request: SearchRequest,
+    ) -> Result<SearchSummary, AppError> {
+        // Starting a search stops the previous one
+        let (cancel, token) = SearchCancel::new();
+        if let Some(previous) = get_window_search(&window)?.lock().await.replace(cancel) {
+            previous.cancel();
+        }
+
+        let trigger = DbEventTrigger::new(window.app_handle().clone());
+        let label = window.label().to_string();
+        let progress = move |progress: SearchProgress| {
+            // The window may have closed, and there's no one left to tell
+            let _ = trigger
+                .send_to(Windows::One(label.clone()))
+                .search_progress(progress);
+        };
+
+        let searcher = {
+            let client = get_window_client(&window)?;
+            let mut guard = client.lock().await;
+
+            if !guard.is_connected().await? {
+                guard.connect().await?;
+            }
+            guard.searcher()?
+        };
+
+        Ok(searcher.search(&request, token, &progress).await?)
+    }
+
+    async fn cancel_search(self, window: Window<impl Runtime>) -> Result<(), AppError> {
+        if let Some(cancel) = get_window_search(&window)?.lock().await.take() {
+            cancel.cancel();
+        }
+        Ok(())
+    }
+
     async fn get_all_entities(
         self,
         window: Window<impl Runtime>,
@@ -85,4 +417,529 @@ impl DbApi for DbApiImpl {
 
         Ok(guard.get_all_entities().await?)
     }
+
+    async fn get_entity_ddl(
+        self,
+        window: Window<impl Runtime>,
+        entity_id: String,
+    ) -> Result<String, AppError> {
+        let client = get_window_client(&window)?;
+        let mut guard = client.lock().await;
+
+        if !guard.is_connected().await? {
+            guard.connect().await?;
+        }
+
+        Ok(guard.entity_ddl(&entity_id).await?)
+    }
+
+    async fn update_row(
+        self,
+        window: Window<impl Runtime>,
+        table_id: String,
+        key: HashMap<String, String>,
+        changes: ColumnChanges,
+    ) -> Result<Row, AppError> {
+        let client = get_window_client(&window)?;
+        let mut guard = client.lock().await;
+
+        if !guard.is_connected().await? {
+            guard.connect().await?;
+        }
+
+        Ok(guard.update_row(&table_id, &key, &changes).await?)
+    }
+
+    async fn stage_change(
+        self,
+        window: Window<impl Runtime>,
+        change: RowChange,
+    ) -> Result<Vec<StagedChange>, AppError> {
+        let changes = get_window_changes(&window)?;
+        let mut changes = changes.lock().await;
+        changes.stage(change);
+        Ok(changes.changes().to_vec())
+    }
+
+    async fn unstage_change(
+        self,
+        window: Window<impl Runtime>,
+        id: u32,
+    ) -> Result<Vec<StagedChange>, AppError> {
+        let changes = get_window_changes(&window)?;
+        let mut changes = changes.lock().await;
+        changes.unstage(id)?;
+        Ok(changes.changes().to_vec())
+    }
+
+    async fn get_change_set(
+        self,
+        window: Window<impl Runtime>,
+    ) -> Result<Vec<StagedChange>, AppError> {
+        let changes = get_window_changes(&window)?;
+        let changes = changes.lock().await;
+        Ok(changes.changes().to_vec())
+    }
+
+    async fn preview_change_set(
+        self,
+        window: Window<impl Runtime>,
+    ) -> Result<Vec<String>, AppError> {
+        let changes = get_window_changes(&window)?;
+        let changes = changes.lock().await;
+
+        let client = get_window_client(&window)?;
+        let mut guard = client.lock().await;
+
+        if !guard.is_connected().await? {
+            guard.connect().await?;
+        }
+
+        Ok(guard.preview_changes(changes.changes()).await?)
+    }
+
+    async fn apply_change_set(self, window: Window<impl Runtime>) -> Result<u64, AppError> {
+        let changes = get_window_changes(&window)?;
+        let mut changes = changes.lock().await;
+
+        let client = get_window_client(&window)?;
+        let mut guard = client.lock().await;
+
+        if !guard.is_connected().await? {
+            guard.connect().await?;
+        }
+
+        // Conflicts leave the change set as it was so they can be resolved
+        let rows_written = guard.apply_changes(changes.changes()).await?;
+        changes.clear();
+        Ok(rows_written)
+    }
+
+    async fn discard_change_set(self, window: Window<impl Runtime>) -> Result<(), AppError> {
+        let changes = get_window_changes(&window)?;
+        changes.lock().await.clear();
+        Ok(())
+    }
+
+    async fn export_csv(
+        self,
+        window: Window<impl Runtime>,
+        source: ExportSource,
+        options: CsvOptions,
+    ) -> Result<Option<ExportSummary>, AppError> {
+        options.validate()?;
+
+        let (filter_name, extension) = if options.delimiter == '\t' {
+            ("TSV", "tsv")
+        } else {
+            ("CSV", "csv")
+        };
+        let file_name = format!("export.{}", extension);
+        let Some(path) = pick_save_path(&window, filter_name, &[extension], &file_name).await
+        else {
+            return Ok(None);
+        };
+
+        let client = get_window_client(&window)?;
+        let mut guard = client.lock().await;
+
+        if !guard.is_connected().await? {
+            guard.connect().await?;
+        }
+
+        // Created once connected, so a failed connection doesn't leave an empty file
+        let file = tokio::fs::File::create(&path).await?;
+        let mut out = ExportWriter::new(file, options.encoding);
+
+        let exported = guard.export_csv(&source, &options, &mut out).await;
+        drop(guard);
+
+        let written = match exported {
+            Ok(rows) => out
+                .finish()
+                .await
+                .map(|bytes| (rows, bytes))
+                .map_err(AppError::from),
+            Err(e) => {
+                drop(out);
+                Err(e.into())
+            }
+        };
+        let (rows, bytes) = match written {
+            Ok(written) => written,
+            Err(e) => {
+                // Don't leave a truncated file behind
+                let _ = tokio::fs::remove_file(&path).await;
+                return Err(e);
+            }
+        };
+
+        Ok(Some(ExportSummary {
+            path: Some(path.to_string_lossy().to_string()),
+            rows,
+            bytes,
+        }))
+    }
+
+    async fn export_result(
+        self,
+        window: Window<impl Runtime>,
+        source: ExportSource,
+        format: ResultFormat,
+        target: ExportTarget,
+    ) -> Result<Option<ExportSummary>, AppError> {
+        // Pick the file first, so a cancelled dialog doesn't run the query
+        let path = match target {
+            ExportTarget::File => {
+                let (filter_name, extension) = format.file_type();
+                let file_name = format!("export.{}", extension);
+                match pick_save_path(&window, filter_name, &[extension], &file_name).await {
+                    Some(path) => Some(path),
+                    None => return Ok(None),
+                }
+            }
+            ExportTarget::Clipboard => None,
+        };
+
+        let client = get_window_client(&window)?;
+        let mut guard = client.lock().await;
+
+        if !guard.is_connected().await? {
+            guard.connect().await?;
+        }
+
+        let result = guard.read_export_source(&source).await?;
+        drop(guard);
+        check_complete(&result)?;
+
+        let mut exporter = format.exporter();
+        let rows = result.rows.len() as u64;
+
+        match path {
+            Some(path) => {
+                let file = tokio::fs::File::create(&path).await?;
+                let mut out = ExportWriter::new(file, TextEncoding::Utf8);
+                let written = match write_result(exporter.as_mut(), &result, &mut out).await {
+                    Ok(_) => out.finish().await,
+                    Err(e) => {
+                        drop(out);
+                        Err(e)
+                    }
+                };
+                let bytes = match written {
+                    Ok(bytes) => bytes,
+                    Err(e) => {
+                        // Don't leave a truncated file behind
+                        let _ = tokio::fs::remove_file(&path).await;
+                        return Err(e.into());
+                    }
+                };
+
+                Ok(Some(ExportSummary {
+                    path: Some(path.to_string_lossy().to_string()),
+                    rows,
+                    bytes,
+                }))
+            }
+            None => {
+                let text = render_result(exporter.as_mut(), &result);
+                let bytes = text.len() as u64;
+                window
+                    .clipboard()
+                    .write_text(text)
+                    .map_err(|e| AppError::Other(e.to_string()))?;
+
+                Ok(Some(ExportSummary {
+                    path: None,
+                    rows,
+                    bytes,
+                }))
+            }
+        }
+    }
+
+    async fn export_columnar(
+        self,
+        window: Window<impl Runtime>,
+        source: ExportSource,
+        format: ColumnarFormat,
+    ) -> Result<Option<ExportSummary>, AppError> {
+        let (filter_name, extension) = format.file_type();
+        let file_name = format!("export.{}", extension);
+        let Some(path) = pick_save_path(&window, filter_name, &[extension], &file_name).await
+        else {
+            return Ok(None);
+        };
+
+        let client = get_window_client(&window)?;
+        let mut guard = client.lock().await;
+
+        if !guard.is_connected().await? {
+            guard.connect().await?;
+        }
+
+        let rows = match guard.export_columnar(&source, format, &path).await {
+            Ok(rows) => rows,
+            Err(e) => {
+                // Don't leave a truncated file behind
+                let _ = tokio::fs::remove_file(&path).await;
+                return Err(e.into());
+            }
+        };
+        drop(guard);
+
+        Ok(Some(ExportSummary {
+            path: Some(path.to_string_lossy().to_string()),
+            rows,
+            bytes: tokio::fs::metadata(&path).await?.len(),
+        }))
+    }
+
+    async fn dump_sql(
+        self,
+        window: Window<impl Runtime>,
+        request: DumpRequest,
+    ) -> Result<Option<DumpSummary>, AppError> {
+        let Some(path) = pick_save_path(&window, "SQL", &["sql"], "dump.sql").await else {
+            return Ok(None);
+        };
+
+        let client = get_window_client(&window)?;
+        let mut guard = client.lock().await;
+
+        if !guard.is_connected().await? {
+            guard.connect().await?;
+        }
+
+        // Created once connected, so a failed connection doesn't leave an empty file
+        let file = tokio::fs::File::create(&path).await?;
+        let mut out = ExportWriter::new(file, TextEncoding::Utf8);
+
+        let dumped = guard.dump_sql(&request, &mut out).await;
+        drop(guard);
+
+        let written = match dumped {
+            Ok(stats) => out
+                .finish()
+                .await
+                .map(|bytes| (stats, bytes))
+                .map_err(AppError::from),
+            Err(e) => {
+                drop(out);
+                Err(e.into())
+            }
+        };
+        let (stats, bytes) = match written {
+            Ok(written) => written,
+            Err(e) => {
+                // Don't leave a truncated script behind
+                let _ = tokio::fs::remove_file(&path).await;
+                return Err(e);
+            }
+        };
+
+        Ok(Some(DumpSummary {
+            path: path.to_string_lossy().to_string(),
+            objects: stats.objects,
+            rows: stats.rows,
+            bytes,
+        }))
+    }
+
+    async fn generate_types(
+        self,
+        window: Window<impl Runtime>,
+        request: TypegenRequest,
+    ) -> Result<Option<TypegenSummary>, AppError> {
+        let path = match &request.path {
+            Some(relative) => {
+                let project = get_window_project(&window)?;
+                let root = project
+                    .handle
+                    .path
+                    .parent()
+                    .filter(|_| !project.handle.is_temp)
+                    .ok_or_else(|| {
+                        AppError::Other("Open a project to write types to a path in it".to_string())
+                    })?;
+                // Kept inside the project, so a shared config can't write anywhere else
+                let inside = Path::new(relative)
+                    .components()
+                    .all(|part| matches!(part, Component::Normal(_) | Component::CurDir));
+                if !inside || relative.is_empty() {
+                    return Err(AppError::Other(format!(
+                        "Types are written to a path relative to the project root, without '..'; got \"{}\"",
+                        relative
+                    )));
+                }
+                root.join(relative)
+            }
+            None => {
+                let (filter, extension, file_name) = request.target.file_type();
+                match pick_save_path(&window, filter, &[extension], file_name).await {
+                    Some(path) => path,
+                    None => return Ok(None),
+                }
+            }
+        };
+
+        let client = get_window_client(&window)?;
+        let mut guard = client.lock().await;
+
+        if !guard.is_connected().await? {
+            guard.connect().await?;
+        }
+
+        let model = guard.type_model(&request.schemas).await?;
+        drop(guard);
+
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        tokio::fs::write(&path, model.render(request.target)).await?;
+
+        Ok(Some(TypegenSummary {
+            path: path.to_string_lossy().to_string(),
+            types: model.type_count(),
+        }))
+    }
+
+    async fn preview_import(
+        self,
+        window: Window<impl Runtime>,
+        format: ImportFormat,
+    ) -> Result<Option<ImportPreview>, AppError> {
+        let (filter_name, extensions) = format.file_type();
+        let Some(path) = pick_open_path(&window, filter_name, extensions).await else {
+            return Ok(None);
+        };
+
+        let preview = import::preview(&path, &format).await?;
+        // Kept here rather than sent back with the import, so only a picked file is read
+        *get_window_import_path(&window)?.lock().await = Some(path);
+        Ok(Some(preview))
+    }
+
+    async fn import_file(
+        self,
+        window: Window<impl Runtime>,
+        request: ImportRequest,
+    ) -> Result<ImportReport, AppError> {
+        let trigger = DbEventTrigger::new(window.app_handle().clone());
+        let label = window.label().to_string();
+        let progress = move |progress: ImportProgress| {
+            let _ = trigger
+                .send_to(Windows::One(label.clone()))
+                .import_progress(progress);
+        };
+
+        let Some(path) = get_window_import_path(&window)?.lock().await.clone() else {
+            return Err(AppError::Other(
+                "Preview a file before importing it".to_string(),
+            ));
+        };
+
+        let importer = {
+            let client = get_window_client(&window)?;
+            let mut guard = client.lock().await;
+
+            if !guard.is_connected().await? {
+                guard.connect().await?;
+            }
+            guard.importer()?
+        };
+
+        Ok(importer.import(&request, &path, &progress).await?)
+    }
+
+    async fn save_blob(
+        self,
+        window: Window<impl Runtime>,
+        table_id: String,
+        key: HashMap<String, String>,
+        column: String,
+    ) -> Result<Option<SavedBlob>, AppError> {
+        let client = get_window_client(&window)?;
+        let mut guard = client.lock().await;
+
+        if !guard.is_connected().await? {
+            guard.connect().await?;
+        }
+
+        let bytes = guard.fetch_blob(&table_id, &key, &column).await?;
+        drop(guard);
+
+        // Suggest an extension matching the content, so the file opens in the right app
+        let mime_type = detect_mime(&bytes);
+        let (filter_name, extension) = match mime_type.and_then(mime_extension) {
+            Some(extension) => (extension.to_uppercase(), extension),
+            None => ("Binary".to_string(), "bin"),
+        };
+        let file_name = format!("{}.{}", column, extension);
+        let Some(path) = pick_save_path(&window, &filter_name, &[extension], &file_name).await
+        else {
+            return Ok(None);
+        };
+
+        tokio::fs::write(&path, &bytes).await?;
+
+        Ok(Some(SavedBlob {
+            path: path.to_string_lossy().into_owned(),
+            size: bytes.len() as u64,
+            mime_type: mime_type.map(str::to_string),
+        }))
+    }
+
+    async fn save_schema_snapshot(
+        self,
+        window: Window<impl Runtime>,
+        name: String,
+        schemas: Vec<String>,
+    ) -> Result<SnapshotInfo, AppError> {
+        let snapshot = read_snapshot(&window, &SnapshotSource::Current, &schemas).await?;
+        let project = get_window_project(&window)?;
+        project.handle.save_snapshot(&name, &snapshot)
+    }
+
+    async fn diff_schemas(
+        self,
+        window: Window<impl Runtime>,
+        source: SnapshotSource,
+        target: SnapshotSource,
+        schemas: Vec<String>,
+    ) -> Result<SchemaDiff, AppError> {
+        let source = read_snapshot(&window, &source, &schemas).await?;
+        let target = read_snapshot(&window, &target, &schemas).await?;
+        Ok(schema_diff::diff(&source, &target))
+    }
+
+    async fn write_schema_snapshot(
+        self,
+        window: Window<impl Runtime>,
+        schemas: Vec<String>,
+    ) -> Result<SnapshotInfo, AppError> {
+        let snapshot = read_snapshot(&window, &SnapshotSource::Current, &schemas).await?;
+        let project = get_window_project(&window)?;
+        project.handle.write_schema_snapshot(&snapshot)
+    }
+
+    async fn check_schema_drift(
+        self,
+        window: Window<impl Runtime>,
+    ) -> Result<Option<SchemaDiff>, AppError> {
+        let project = get_window_project(&window)?;
+        let Some(committed) = project.handle.read_schema_snapshot()? else {
+            return Ok(None);
+        };
+
+        // Read the database the way the snapshot was written: only the schemas it asked for,
+        // or every non-system schema when it asked for none
+        let live = read_snapshot(
+            &window,
+            &SnapshotSource::Current,
+            &committed.requested_schemas,
+        )
+        .await?;
+        Ok(Some(schema_diff::diff(&committed, &live)))
+    }
 }
@@ -1,13 +1,24 @@
 use tauri::{Runtime, Window};
 use taurpc;
 
+use crate::db::errors::DbError;
 use crate::errors::AppError;
-use crate::project::Project;
-use crate::state::get_window_project;
+use crate::project::{MigrationReport, Project, SnapshotInfo};
+use crate::state::{get_window_client, get_window_project};
 
 #[taurpc::procedures(path = "projects", export_to = "../src/lib/taurpc.ts", event_trigger = ProjectEventTrigger)]
 pub trait ProjectsApi {
     async fn get_project(window: Window<impl Runtime>) -> Result<Project, AppError>;
+
+    // Schema snapshots saved in the project, by name
+    async fn list_schema_snapshots(
+        window: Window<impl Runtime>,
+    ) -> Result<Vec<SnapshotInfo>, AppError>;
+
+    // Migration directories next to the project, with each migration's status in the database
+    async fn get_migration_status(
+        window: Window<impl Runtime>,
+    ) -> Result<Vec<MigrationReport>, AppError>;
 }
 
 #[derive(Clone)]
@@ -19,4 +30,42 @@ impl ProjectsApi for ProjectsApiImpl {
         let project = get_window_project(&window)?;
         Ok((*project).clone())
     }
+
+    async fn list_schema_snapshots(
+        self,
+        window: Window<impl Runtime>,
+    ) -> Result<Vec<SnapshotInfo>, AppError> {
+        let project = get_window_project(&window)?;
+        project.handle.list_snapshots()
+    }
+
+    async fn get_migration_status(
+        self,
+        window: Window<impl Runtime>,
+    ) -> Result<Vec<MigrationReport>, AppError> {
+        let project = get_window_project(&window)?;
+        let dirs = project.handle.migration_dirs()?;
+        if dirs.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let client = get_window_client(&window)?;
+        let mut guard = client.lock().await;
+
+        if !guard.is_connected().await? {
+            guard.connect().await?;
+        }
+
+        let mut reports = Vec::with_capacity(dirs.len());
+        for dir in dirs {
+            // Drivers that can't read tracking tables report every directory as untracked
+            let applied = match guard.migration_history(dir.tool).await {
+                Ok(applied) => applied,
+                Err(DbError::Unsupported(_)) => None,
+                Err(e) => return Err(e.into()),
+            };
+            reports.push(dir.report(applied));
+        }
+        Ok(reports)
+    }
 }
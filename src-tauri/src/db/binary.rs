@@ -0,0 +1,295 @@
+use serde_json::Value as JsonValue;
+
+/// Default number of bytes included in a binary cell preview
+pub const DEFAULT_PREVIEW_BYTES: usize = 64;
+
+/// Encoding used for binary cell previews
+#[derive(Debug, Clone, Copy, Default, serde::Serialize, serde::Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum BinaryEncoding {
+    #[default]
+    Hex,
+    Base64,
+}
+
+/// Controls how binary values are sent over IPC, set by the project's `binaryPreview` setting
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct BinaryFormat {
+    pub encoding: BinaryEncoding,
+    /// Maximum number of bytes included in the preview
+    pub max_bytes: usize,
+}
+
+impl Default for BinaryFormat {
+    fn default() -> Self {
+        Self {
+            encoding: BinaryEncoding::default(),
+            max_bytes: DEFAULT_PREVIEW_BYTES,
+        }
+    }
+}
+
+/// A truncated preview of a binary cell; the full value is fetched separately
+#[derive(Debug, Clone, serde::Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct BinaryPreview {
+    pub encoding: BinaryEncoding,
+    /// Encoded bytes, at most `max_bytes` long before encoding
+    pub preview: String,
+    /// Full length of the value in bytes
+    pub size: usize,
+    pub truncated: bool,
+    /// Detected MIME type, if the content is a known format
+    pub mime_type: Option<String>,
+}
+
+impl BinaryPreview {
+    pub fn new(bytes: &[u8], format: &BinaryFormat) -> Self {
+        let end = bytes.len().min(format.max_bytes);
+        let head = &bytes[..end];
+
+        let preview = match format.encoding {
+            BinaryEncoding::Hex => encode_hex(head),
+            BinaryEncoding::Base64 => encode_base64(head),
+        };
+
+        Self {
+            encoding: format.encoding,
+            preview,
+            size: bytes.len(),
+            truncated: end < bytes.len(),
+            mime_type: detect_mime(bytes).map(str::to_string),
+        }
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        let mut value = serde_json::to_value(self).unwrap_or_default();
+        if let JsonValue::Object(map) = &mut value {
+            map.insert("$type".to_string(), JsonValue::from("binary"));
+        }
+        value
+    }
+}
+
+/// Sniffs the MIME type from the leading magic bytes of common formats
+pub fn detect_mime(bytes: &[u8]) -> Option<&'static str> {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"BZh", "application/x-bzip2"),
+        (b"\x28\xb5\x2f\xfd", "application/zstd"),
+        (b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed"),
+        (b"SQLite format 3\0", "application/vnd.sqlite3"),
+        (b"PAR1", "application/vnd.apache.parquet"),
+    ];
+
+    // RIFF containers carry the actual format at offset 8
+    if bytes.len() >= 12 && &bytes[..4] == b"RIFF" {
+        return match &bytes[8..12] {
+            b"WEBP" => Some("image/webp"),
+            b"WAVE" => Some("audio/wav"),
+            b"AVI " => Some("video/x-msvideo"),
+            _ => None,
+        };
+    }
+
+    // "BM" and the ICO header are short enough to start plain data, so their headers
+    // are checked too: a known DIB header size, and a non-empty icon directory
+    if bytes.len() >= 18 && bytes.starts_with(b"BM") {
+        let dib_size = u32::from_le_bytes([bytes[14], bytes[15], bytes[16], bytes[17]]);
+        if matches!(dib_size, 12 | 40 | 52 | 56 | 64 | 108 | 124) {
+            return Some("image/bmp");
+        }
+    }
+    if bytes.len() >= 22 && bytes.starts_with(b"\0\0\x01\0") {
+        let count = u16::from_le_bytes([bytes[4], bytes[5]]);
+        // Each directory entry has a reserved zero byte and 1 or 0 color planes
+        if count > 0 && bytes[9] == 0 && bytes[10] <= 1 && bytes[11] == 0 {
+            return Some("image/x-icon");
+        }
+    }
+
+    SIGNATURES
+        .iter()
+        .find(|(magic, _)| bytes.starts_with(magic))
+        .map(|(_, mime)| *mime)
+}
+
+/// Returns a file extension matching a MIME type from [`detect_mime`]
+pub fn mime_extension(mime: &str) -> Option<&'static str> {
+    let ext = match mime {
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "image/bmp" => "bmp",
+        "image/x-icon" => "ico",
+        "application/pdf" => "pdf",
+        "application/gzip" => "gz",
+        "application/zip" => "zip",
+        "application/x-bzip2" => "bz2",
+        "application/zstd" => "zst",
+        "application/x-7z-compressed" => "7z",
+        "application/vnd.sqlite3" => "sqlite",
+        "application/vnd.apache.parquet" => "parquet",
+        "audio/wav" => "wav",
+        "video/x-msvideo" => "avi",
+        _ => return None,
+    };
+    Some(ext)
+}
+
+pub fn encode_hex(bytes: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        out.push(DIGITS[(b >> 4) as usize] as char);
+        out.push(DIGITS[(b & 0x0f) as usize] as char);
+    }
+    out
+}
+
+/// Decodes hex digits, as in Postgres's `\\x` bytea output
+pub fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+pub fn encode_base64(bytes: &[u8]) -> String {
+    use base64::Engine;
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+pub fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    use base64::Engine;
+    base64::engine::general_purpose::STANDARD.decode(text).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bmp(dib_size: u32) -> Vec<u8> {
+        let mut bytes = b"BM".to_vec();
+        bytes.extend_from_slice(&[0; 12]);
+        bytes.extend_from_slice(&dib_size.to_le_bytes());
+        bytes
+    }
+
+    fn ico(count: u16, planes: u8) -> Vec<u8> {
+        let mut bytes = b"\0\0\x01\0".to_vec();
+        bytes.extend_from_slice(&count.to_le_bytes());
+        // Width, height, colors, reserved, planes
+        bytes.extend_from_slice(&[16, 16, 0, 0, planes, 0]);
+        bytes.extend_from_slice(&[0; 10]);
+        bytes
+    }
+
+    #[test]
+    fn detects_magic_bytes() {
+        assert_eq!(detect_mime(b"\x89PNG\r\n\x1a\n\0\0"), Some("image/png"));
+        assert_eq!(detect_mime(b"%PDF-1.7"), Some("application/pdf"));
+        assert_eq!(detect_mime(b"\x1f\x8b\x08\0"), Some("application/gzip"));
+        assert_eq!(detect_mime(b"RIFF\0\0\0\0WEBPVP8 "), Some("image/webp"));
+        assert_eq!(detect_mime(b"RIFF\0\0\0\0XXXX"), None);
+        assert_eq!(detect_mime(b"hello"), None);
+        assert_eq!(detect_mime(b""), None);
+    }
+
+    #[test]
+    fn checks_bmp_and_ico_headers() {
+        assert_eq!(detect_mime(&bmp(40)), Some("image/bmp"));
+        assert_eq!(detect_mime(&bmp(124)), Some("image/bmp"));
+        // Text that happens to start with "BM"
+        assert_eq!(detect_mime(b"BMW 3 Series, 2019 model"), None);
+        assert_eq!(detect_mime(b"BM"), None);
+
+        assert_eq!(detect_mime(&ico(1, 1)), Some("image/x-icon"));
+        assert_eq!(detect_mime(&ico(0, 1)), None);
+        assert_eq!(detect_mime(&ico(1, 4)), None);
+        assert_eq!(detect_mime(&[0, 0, 1, 0]), None);
+    }
+
+    #[test]
+    fn every_detected_type_has_an_extension() {
+        let bmp = bmp(40);
+        let ico = ico(1, 1);
+        let samples: &[&[u8]] = &[
+            b"\x89PNG\r\n\x1a\n",
+            b"\xff\xd8\xff\xe0",
+            b"GIF89a",
+            b"%PDF-",
+            b"\x1f\x8b",
+            b"PK\x03\x04",
+            b"BZh9",
+            b"\x28\xb5\x2f\xfd",
+            b"7z\xbc\xaf\x27\x1c",
+            b"SQLite format 3\0",
+            b"PAR1",
+            b"RIFF\0\0\0\0WAVE",
+            b"RIFF\0\0\0\0AVI ",
+            &bmp,
+            &ico,
+        ];
+        for sample in samples {
+            let mime = detect_mime(sample).unwrap();
+            assert!(mime_extension(mime).is_some(), "{}", mime);
+        }
+        assert_eq!(mime_extension("text/plain"), None);
+    }
+
+    #[test]
+    fn previews_are_truncated_and_encoded() {
+        let bytes = b"\x89PNG\r\n\x1a\nmore";
+        let format = BinaryFormat {
+            encoding: BinaryEncoding::Hex,
+            max_bytes: 4,
+        };
+        let preview = BinaryPreview::new(bytes, &format);
+        assert_eq!(preview.preview, "89504e47");
+        assert_eq!(preview.size, 12);
+        assert!(preview.truncated);
+        assert_eq!(preview.mime_type.as_deref(), Some("image/png"));
+
+        let format = BinaryFormat {
+            encoding: BinaryEncoding::Base64,
+            max_bytes: 64,
+        };
+        let preview = BinaryPreview::new(b"hello", &format);
+        assert_eq!(preview.preview, "aGVsbG8=");
+        assert!(!preview.truncated);
+        assert_eq!(decode_base64(&preview.preview).unwrap(), b"hello");
+    }
+
+    #[test]
+    fn hex_round_trips() {
+        assert_eq!(encode_hex(&[0x00, 0xab, 0xff]), "00abff");
+        assert_eq!(decode_hex("00abff").unwrap(), vec![0x00, 0xab, 0xff]);
+        assert_eq!(decode_hex("abc"), None);
+        assert_eq!(decode_hex("zz"), None);
+    }
+
+    #[test]
+    fn format_setting_fills_in_defaults() {
+        let format: BinaryFormat = serde_json::from_str(r#"{ "encoding": "base64" }"#).unwrap();
+        assert!(matches!(format.encoding, BinaryEncoding::Base64));
+        assert_eq!(format.max_bytes, DEFAULT_PREVIEW_BYTES);
+
+        let format: BinaryFormat = serde_json::from_str(r#"{ "maxBytes": 16 }"#).unwrap();
+        assert!(matches!(format.encoding, BinaryEncoding::Hex));
+        assert_eq!(format.max_bytes, 16);
+
+        assert!(serde_json::from_str::<BinaryFormat>(r#"{ "bytes": 16 }"#).is_err());
+    }
+}
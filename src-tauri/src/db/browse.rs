@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::db::types::{ColumnDefinition, Row};
+
+/// A condition on a table's rows. Values are text and cast to the column's type.
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub enum Filter {
+    And {
+        filters: Vec<Filter>,
+    },
+    Or {
+        filters: Vec<Filter>,
+    },
+    Not {
+        filter: Box<Filter>,
+    },
+    Equals {
+        column: String,
+        value: String,
+    },
+    /// Case-insensitive substring match on the column's text form
+    Contains {
+        column: String,
+        value: String,
+    },
+    /// Inclusive range
+    Between {
+        column: String,
+        low: String,
+        high: String,
+    },
+    IsNull {
+        column: String,
+    },
+    In {
+        column: String,
+        values: Vec<String>,
+    },
+    /// Compares the text at `path` inside a JSON column; a `None` value matches a missing key
+    JsonPath {
+        column: String,
+        path: Vec<String>,
+        value: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum SortDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SortKey {
+    pub column: String,
+    #[serde(default)]
+    pub direction: SortDirection,
+}
+
+/// Sort values of the last row of a page, by column; `None` is NULL
+pub type Cursor = HashMap<String, Option<String>>;
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Pagination {
+    Offset {
+        offset: u64,
+        limit: u32,
+    },
+    /// Continues after the row identified by `after`, a cursor from a previous page.
+    /// Sorting stops at the first binary or array column, then follows the row key.
+    Keyset {
+        after: Option<Cursor>,
+        limit: u32,
+    },
+}
+
+#[taurpc::ipc_type]
+#[serde(rename_all = "camelCase")]
+#[derive(Debug)]
+pub struct BrowseRequest {
+    pub table_id: String,
+    pub filter: Option<Filter>,
+    pub sort: Vec<SortKey>,
+    pub page: Pagination,
+    /// Count matching rows exactly instead of using the planner's estimate
+    pub exact_count: bool,
+}
+
+#[taurpc::ipc_type]
+#[serde(rename_all = "camelCase")]
+#[derive(Debug)]
+pub struct BrowsePage {
+    pub columns: Vec<ColumnDefinition>,
+    pub rows: Vec<Row>,
+    /// Number of rows matching the filter
+    pub total: u64,
+    pub total_is_exact: bool,
+    pub has_more: bool,
+    /// Cursor for the next page when using keyset pagination
+    pub next_cursor: Option<Cursor>,
+}
+
+/// Text value of a displayed cell (see `Row::values`), for use as a cursor value;
+/// `None` for NULL
+pub fn cursor_value(display: &str) -> Option<String> {
+    match serde_json::from_str::<serde_json::Value>(display) {
+        Ok(serde_json::Value::Null) => None,
+        Ok(serde_json::Value::String(s)) => Some(s),
+        Ok(value) => Some(value.to_string()),
+        // "NULL" isn't valid JSON
+        Err(_) => None,
+    }
+}
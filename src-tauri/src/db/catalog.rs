@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+
+/// Version of the snapshot format, bumped when fields change meaning
+pub const SNAPSHOT_VERSION: u32 = 3;
+
+/// The parts of a database's schema that are compared between databases. Every list is
+/// sorted, so the same schema always serializes to the same JSON.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CatalogSnapshot {
+    pub version: u32,
+    /// Schemas the snapshot was asked for; empty when it covers every non-system schema
+    pub requested_schemas: Vec<String>,
+    pub schemas: Vec<String>,
+    pub tables: Vec<TableSnapshot>,
+    pub functions: Vec<FunctionSnapshot>,
+    pub enums: Vec<EnumSnapshot>,
+    pub sequences: Vec<SequenceSnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TableSnapshot {
+    pub schema: String,
+    pub name: String,
+    /// In table order, which is kept because it's visible to `SELECT *`
+    pub columns: Vec<ColumnSnapshot>,
+    pub indexes: Vec<IndexSnapshot>,
+    pub constraints: Vec<ConstraintSnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnSnapshot {
+    pub name: String,
+    pub data_type: String,
+    pub nullable: bool,
+    pub default_value: Option<String>,
+    /// Identity or generated-column clause, e.g. `GENERATED ALWAYS AS IDENTITY`
+    pub generated: Option<String>,
+}
+
+impl ColumnSnapshot {
+    /// The column as written in CREATE TABLE, after its name
+    pub fn definition(&self) -> String {
+        let mut definition = self.data_type.clone();
+        if let Some(default_value) = &self.default_value {
+            definition.push_str(&format!(" DEFAULT {}", default_value));
+        }
+        if let Some(generated) = &self.generated {
+            definition.push_str(&format!(" {}", generated));
+        }
+        if !self.nullable {
+            definition.push_str(" NOT NULL");
+        }
+        definition
+    }
+}
+
+/// An index that doesn't back a constraint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexSnapshot {
+    pub name: String,
+    /// Full CREATE INDEX statement
+    pub definition: String,
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, specta::Type,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum ConstraintKind {
+    PrimaryKey,
+    Unique,
+    Check,
+    Exclusion,
+    ForeignKey,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ConstraintSnapshot {
+    pub name: String,
+    pub kind: ConstraintKind,
+    /// What follows `ADD CONSTRAINT <name>`, e.g. `PRIMARY KEY (id)`
+    pub definition: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionSnapshot {
+    pub schema: String,
+    pub name: String,
+    /// Argument types that tell overloads apart, e.g. `integer, text`
+    pub arguments: String,
+    pub is_procedure: bool,
+    /// Full CREATE OR REPLACE statement
+    pub definition: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct EnumSnapshot {
+    pub schema: String,
+    pub name: String,
+    /// In sort order
+    pub labels: Vec<String>,
+}
+
+/// A sequence that isn't behind an identity column, which creates its own
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SequenceSnapshot {
+    pub schema: String,
+    pub name: String,
+    /// What follows `CREATE SEQUENCE <name>`, e.g. `AS bigint INCREMENT BY 1 ...`
+    pub options: String,
+    /// Quoted `schema.table.column` the sequence is dropped along with, as for a serial column
+    pub owned_by: Option<String>,
+}
+
+impl CatalogSnapshot {
+    /// Keeps only what's in the given schemas; an empty list keeps everything
+    pub fn retain_schemas(&mut self, schemas: &[String]) {
+        if schemas.is_empty() {
+            return;
+        }
+        self.schemas.retain(|s| schemas.contains(s));
+        self.tables.retain(|t| schemas.contains(&t.schema));
+        self.functions.retain(|f| schemas.contains(&f.schema));
+        self.enums.retain(|e| schemas.contains(&e.schema));
+        self.sequences.retain(|s| schemas.contains(&s.schema));
+    }
+
+    /// Puts every list in a stable order
+    pub fn sort(&mut self) {
+        self.requested_schemas.sort();
+        self.schemas.sort();
+        self.tables
+            .sort_by(|a, b| (&a.schema, &a.name).cmp(&(&b.schema, &b.name)));
+        for table in &mut self.tables {
+            table.indexes.sort_by(|a, b| a.name.cmp(&b.name));
+            table
+                .constraints
+                .sort_by(|a, b| (a.kind, &a.name).cmp(&(b.kind, &b.name)));
+        }
+        self.functions.sort_by(|a, b| {
+            (&a.schema, &a.name, &a.arguments).cmp(&(&b.schema, &b.name, &b.arguments))
+        });
+        self.enums
+            .sort_by(|a, b| (&a.schema, &a.name).cmp(&(&b.schema, &b.name)));
+        self.sequences
+            .sort_by(|a, b| (&a.schema, &a.name).cmp(&(&b.schema, &b.name)));
+    }
+}
@@ -0,0 +1,696 @@
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::db::editing::{self, ColumnChanges};
+use crate::db::errors::{DbError, DbResult};
+use crate::db::ident::{quote_literal_with, quote_with};
+use crate::db::types::Row;
+
+/// Values of a row as they were displayed (see `Row::values`), used to detect
+/// whether someone else changed the row since it was read
+pub type OriginalValues = HashMap<String, String>;
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct InsertRow {
+    pub table_id: String,
+    pub values: ColumnChanges,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateRow {
+    pub table_id: String,
+    /// Primary or unique key of the row
+    pub key: HashMap<String, String>,
+    pub original: OriginalValues,
+    pub changes: ColumnChanges,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteRow {
+    pub table_id: String,
+    /// Primary or unique key of the row
+    pub key: HashMap<String, String>,
+    pub original: OriginalValues,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(tag = "kind")]
+pub enum RowChange {
+    Insert(InsertRow),
+    Update(UpdateRow),
+    Delete(DeleteRow),
+}
+
+impl RowChange {
+    pub fn table_id(&self) -> &str {
+        match self {
+            RowChange::Insert(c) => &c.table_id,
+            RowChange::Update(c) => &c.table_id,
+            RowChange::Delete(c) => &c.table_id,
+        }
+    }
+}
+
+/// A change waiting in a window's change set
+#[taurpc::ipc_type]
+#[serde(rename_all = "camelCase")]
+#[derive(Debug)]
+pub struct StagedChange {
+    pub id: u32,
+    pub change: RowChange,
+}
+
+/// Pending changes of a window, applied together or not at all
+#[derive(Debug, Default)]
+pub struct ChangeSet {
+    next_id: u32,
+    changes: Vec<StagedChange>,
+}
+
+impl ChangeSet {
+    pub fn stage(&mut self, change: RowChange) -> u32 {
+        self.next_id += 1;
+        self.changes.push(StagedChange {
+            id: self.next_id,
+            change,
+        });
+        self.next_id
+    }
+
+    pub fn unstage(&mut self, id: u32) -> DbResult<()> {
+        let len = self.changes.len();
+        self.changes.retain(|c| c.id != id);
+        if self.changes.len() == len {
+            return Err(DbError::NotFound(format!("Staged change {}", id)));
+        }
+        Ok(())
+    }
+
+    pub fn changes(&self) -> &[StagedChange] {
+        &self.changes
+    }
+
+    pub fn clear(&mut self) {
+        self.changes.clear();
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictKind {
+    /// The row no longer exists
+    Missing,
+    /// The row was changed since it was read
+    Modified,
+}
+
+/// A staged change that no longer matches the database
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeConflict {
+    pub change_id: u32,
+    pub table_id: String,
+    pub key: HashMap<String, String>,
+    pub kind: ConflictKind,
+    /// Columns whose current value differs from the original
+    pub columns: Vec<String>,
+    /// The row as it is now, if it still exists
+    pub current: Option<HashMap<String, String>>,
+}
+
+/// How a backend spells parameters, identifiers and literals
+#[derive(Debug, Clone, Copy)]
+pub struct Dialect {
+    pub quote: char,
+    /// `$1, $2, ...` rather than `?`
+    pub numbered_params: bool,
+    /// Whether `SELECT ... FOR UPDATE` is supported
+    pub lock_rows: bool,
+    /// Whether backslashes escape characters in string literals
+    pub backslash_escapes: bool,
+}
+
+/// What a change set needs to know about a table
+#[derive(Debug, Clone)]
+pub struct TableInfo {
+    /// Quoted, qualified name for use in SQL
+    pub sql_name: String,
+    /// Name for messages
+    pub name: String,
+    pub key_columns: Vec<String>,
+    /// Column names, with the type to cast text parameters to where the backend needs it
+    pub columns: HashMap<String, Option<String>>,
+}
+
+impl TableInfo {
+    fn cast(&self, column: &str) -> DbResult<Option<&str>> {
+        self.columns
+            .get(column)
+            .map(Option::as_deref)
+            .ok_or_else(|| DbError::NotFound(format!("Column {} on {}", column, self.name)))
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Fragment {
+    Sql(String),
+    Param {
+        value: Option<String>,
+        cast: Option<String>,
+    },
+}
+
+/// A statement whose values can be bound as parameters or inlined as literals
+#[derive(Debug, Clone, Default)]
+pub struct Statement {
+    fragments: Vec<Fragment>,
+}
+
+impl Statement {
+    fn sql(&mut self, sql: impl Into<String>) -> &mut Self {
+        self.fragments.push(Fragment::Sql(sql.into()));
+        self
+    }
+
+    fn param(&mut self, value: Option<&str>, cast: Option<&str>) -> &mut Self {
+        self.fragments.push(Fragment::Param {
+            value: value.map(str::to_string),
+            cast: cast.map(str::to_string),
+        });
+        self
+    }
+
+    /// SQL with placeholders, and the text values to bind to them
+    pub fn to_query(&self, dialect: &Dialect) -> (String, Vec<Option<&str>>) {
+        let mut sql = String::new();
+        let mut params = Vec::new();
+
+        for fragment in &self.fragments {
+            match fragment {
+                Fragment::Sql(s) => sql.push_str(s),
+                Fragment::Param { value, cast } => {
+                    params.push(value.as_deref());
+                    if dialect.numbered_params {
+                        sql.push_str(&format!("${}", params.len()));
+                    } else {
+                        sql.push('?');
+                    }
+                    if let Some(cast) = cast {
+                        sql.push_str(&format!("::{}", cast));
+                    }
+                }
+            }
+        }
+
+        (sql, params)
+    }
+
+    /// SQL with values inlined as literals, for review
+    pub fn to_preview(&self, dialect: &Dialect) -> String {
+        let mut sql = String::new();
+
+        for fragment in &self.fragments {
+            match fragment {
+                Fragment::Sql(s) => sql.push_str(s),
+                Fragment::Param { value: None, .. } => sql.push_str("NULL"),
+                Fragment::Param {
+                    value: Some(value),
+                    cast,
+                } => {
+                    sql.push_str(&quote_literal_with(value, dialect.backslash_escapes));
+                    if let Some(cast) = cast {
+                        sql.push_str(&format!("::{}", cast));
+                    }
+                }
+            }
+        }
+
+        sql
+    }
+}
+
+/// One step of applying a staged change
+#[derive(Debug, Clone)]
+pub enum Step {
+    /// Read the key and changed columns of the row and compare them to the original
+    /// values before writing
+    Check {
+        change_id: u32,
+        table_id: String,
+        key: HashMap<String, String>,
+        original: OriginalValues,
+        query: Statement,
+    },
+    /// Write the change; updates and deletes must affect exactly one row
+    Write {
+        change_id: u32,
+        table_id: String,
+        key: HashMap<String, String>,
+        query: Statement,
+        expect_one: bool,
+    },
+}
+
+fn key_predicate(
+    stmt: &mut Statement,
+    table: &TableInfo,
+    key: &HashMap<String, String>,
+    dialect: &Dialect,
+) -> DbResult<()> {
+    for (i, name) in editing::sorted(key.keys()).into_iter().enumerate() {
+        if i > 0 {
+            stmt.sql(" AND ");
+        }
+        stmt.sql(format!("{} = ", quote_with(name, dialect.quote)))
+            .param(Some(&key[name]), table.cast(name)?);
+    }
+    Ok(())
+}
+
+/// Builds the statements that apply a staged change
+pub fn plan(staged: &StagedChange, table: &TableInfo, dialect: &Dialect) -> DbResult<Vec<Step>> {
+    let change_id = staged.id;
+    let table_id = staged.change.table_id().to_string();
+
+    // Only the key and the columns being written are read back, so the check doesn't
+    // depend on being able to decode every column of the table
+    let check = |key: &HashMap<String, String>,
+                 original: &OriginalValues,
+                 changed: Vec<&String>|
+     -> DbResult<Step> {
+        let mut names = editing::sorted(key.keys());
+        names.extend(changed.into_iter().filter(|name| !key.contains_key(*name)));
+
+        let mut columns = Vec::with_capacity(names.len());
+        for name in &names {
+            table.cast(name)?;
+            columns.push(quote_with(name, dialect.quote));
+        }
+
+        let mut query = Statement::default();
+        query.sql(format!(
+            "SELECT {} FROM {} WHERE ",
+            columns.join(", "),
+            table.sql_name
+        ));
+        key_predicate(&mut query, table, key, dialect)?;
+        if dialect.lock_rows {
+            query.sql(" FOR UPDATE");
+        }
+
+        Ok(Step::Check {
+            change_id,
+            table_id: table_id.clone(),
+            key: key.clone(),
+            original: original
+                .iter()
+                .filter(|(name, _)| names.contains(name))
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+            query,
+        })
+    };
+
+    match &staged.change {
+        RowChange::Insert(insert) => {
+            editing::check_changes(&insert.values)?;
+            let names = editing::sorted(insert.values.keys());
+
+            let mut query = Statement::default();
+            query.sql(format!(
+                "INSERT INTO {} ({}) VALUES (",
+                table.sql_name,
+                names
+                    .iter()
+                    .map(|name| quote_with(name, dialect.quote))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+            for (i, name) in names.iter().enumerate() {
+                if i > 0 {
+                    query.sql(", ");
+                }
+                query.param(insert.values[*name].as_deref(), table.cast(name)?);
+            }
+            query.sql(")");
+
+            Ok(vec![Step::Write {
+                change_id,
+                table_id,
+                key: HashMap::new(),
+                query,
+                expect_one: false,
+            }])
+        }
+        RowChange::Update(update) => {
+            editing::check_key(&table.name, &table.key_columns, &update.key)?;
+            editing::check_changes(&update.changes)?;
+
+            let mut query = Statement::default();
+            query.sql(format!("UPDATE {} SET ", table.sql_name));
+            for (i, name) in editing::sorted(update.changes.keys())
+                .into_iter()
+                .enumerate()
+            {
+                if i > 0 {
+                    query.sql(", ");
+                }
+                query
+                    .sql(format!("{} = ", quote_with(name, dialect.quote)))
+                    .param(update.changes[name].as_deref(), table.cast(name)?);
+            }
+            query.sql(" WHERE ");
+            key_predicate(&mut query, table, &update.key, dialect)?;
+
+            Ok(vec![
+                check(
+                    &update.key,
+                    &update.original,
+                    editing::sorted(update.changes.keys()),
+                )?,
+                Step::Write {
+                    change_id,
+                    table_id: table_id.clone(),
+                    key: update.key.clone(),
+                    query,
+                    expect_one: true,
+                },
+            ])
+        }
+        RowChange::Delete(delete) => {
+            editing::check_key(&table.name, &table.key_columns, &delete.key)?;
+
+            let mut query = Statement::default();
+            query.sql(format!("DELETE FROM {} WHERE ", table.sql_name));
+            key_predicate(&mut query, table, &delete.key, dialect)?;
+
+            Ok(vec![
+                // A delete only needs the row to still be there
+                check(&delete.key, &delete.original, Vec::new())?,
+                Step::Write {
+                    change_id,
+                    table_id: table_id.clone(),
+                    key: delete.key.clone(),
+                    query,
+                    expect_one: true,
+                },
+            ])
+        }
+    }
+}
+
+/// Renders the statements a change set would run, with values inlined
+pub fn preview(steps: &[Step], dialect: &Dialect) -> Vec<String> {
+    let writes = steps.iter().filter_map(|step| match step {
+        Step::Write { query, .. } => Some(format!("{};", query.to_preview(dialect))),
+        Step::Check { .. } => None,
+    });
+
+    std::iter::once("BEGIN;".to_string())
+        .chain(writes)
+        .chain(std::iter::once("COMMIT;".to_string()))
+        .collect()
+}
+
+/// Compares a row as it is now with the values it had when it was read
+pub fn compare(
+    change_id: u32,
+    table_id: &str,
+    key: &HashMap<String, String>,
+    original: &OriginalValues,
+    current: Option<Row>,
+) -> Option<ChangeConflict> {
+    let conflict = |kind, columns, current| ChangeConflict {
+        change_id,
+        table_id: table_id.to_string(),
+        key: key.clone(),
+        kind,
+        columns,
+        current,
+    };
+
+    let Some(current) = current else {
+        return Some(conflict(ConflictKind::Missing, Vec::new(), None));
+    };
+
+    let columns = editing::sorted(original.keys())
+        .into_iter()
+        .filter(|name| {
+            !current
+                .values
+                .get(*name)
+                .is_some_and(|value| same_value(value, &original[*name]))
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+
+    if columns.is_empty() {
+        None
+    } else {
+        Some(conflict(
+            ConflictKind::Modified,
+            columns,
+            Some(current.values),
+        ))
+    }
+}
+
+/// Whether two displayed values are the same, treating timestamps with an offset as
+/// instants so that changing the display zone between reading and applying isn't a conflict.
+/// Row values are JSON, so a timestamp arrives as a quoted string
+fn same_value(a: &str, b: &str) -> bool {
+    if a == b {
+        return true;
+    }
+    match (instant(a), instant(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn instant(value: &str) -> Option<DateTime<FixedOffset>> {
+    let text = serde_json::from_str::<String>(value).ok()?;
+    DateTime::parse_from_rfc3339(&text).ok()
+}
+
+/// Conflict for a write that matched no row, e.g. when the row was deleted after the check
+pub fn missing(change_id: u32, table_id: &str, key: &HashMap<String, String>) -> ChangeConflict {
+    ChangeConflict {
+        change_id,
+        table_id: table_id.to_string(),
+        key: key.clone(),
+        kind: ConflictKind::Missing,
+        columns: Vec::new(),
+        current: None,
+    }
+}
+
+/// Loads what a change set needs to know about the tables it touches
+#[async_trait]
+pub trait TableLoader: Send {
+    async fn load_table(&mut self, table_id: &str) -> DbResult<TableInfo>;
+}
+
+/// Runs the statements of a change set inside a backend's transaction
+#[async_trait]
+pub trait StepRunner: Send {
+    /// Runs a query and decodes the row it returns, if any
+    async fn fetch_row(&mut self, sql: &str, params: Vec<Option<&str>>) -> DbResult<Option<Row>>;
+
+    /// Runs a statement and returns the number of rows it affected
+    async fn execute(&mut self, sql: &str, params: Vec<Option<&str>>) -> DbResult<u64>;
+}
+
+/// Loads the tables a change set touches and builds its statements
+pub async fn plan_all(
+    loader: &mut impl TableLoader,
+    staged: &[StagedChange],
+    dialect: &Dialect,
+) -> DbResult<Vec<Step>> {
+    let mut tables: HashMap<String, TableInfo> = HashMap::new();
+    let mut steps = Vec::new();
+
+    for change in staged {
+        let table_id = change.change.table_id();
+        if !tables.contains_key(table_id) {
+            let table = loader.load_table(table_id).await?;
+            tables.insert(table_id.to_string(), table);
+        }
+
+        steps.extend(plan(change, &tables[table_id], dialect)?);
+    }
+
+    Ok(steps)
+}
+
+/// Runs the steps of a change set and returns the number of rows written.
+/// Conflicts are collected rather than stopping at the first one, and returned as
+/// `DbError::Conflict`; the caller rolls back on any error.
+pub async fn run(runner: &mut impl StepRunner, steps: &[Step], dialect: &Dialect) -> DbResult<u64> {
+    let mut conflicts = Vec::new();
+    let mut rows_written = 0;
+
+    for step in steps {
+        match step {
+            Step::Check {
+                change_id,
+                table_id,
+                key,
+                original,
+                query,
+            } => {
+                let (sql, params) = query.to_query(dialect);
+                let current = runner.fetch_row(&sql, params).await?;
+                conflicts.extend(compare(*change_id, table_id, key, original, current));
+            }
+            Step::Write {
+                change_id,
+                table_id,
+                key,
+                query,
+                expect_one,
+            } => {
+                // Don't write a change whose check already failed
+                if conflicts.iter().any(|c| c.change_id == *change_id) {
+                    continue;
+                }
+
+                let (sql, params) = query.to_query(dialect);
+                let rows = runner.execute(&sql, params).await?;
+                if *expect_one && rows == 0 {
+                    conflicts.push(missing(*change_id, table_id, key));
+                }
+                rows_written += rows;
+            }
+        }
+    }
+
+    if conflicts.is_empty() {
+        Ok(rows_written)
+    } else {
+        Err(DbError::Conflict(conflicts))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIALECT: Dialect = Dialect {
+        quote: '"',
+        numbered_params: true,
+        lock_rows: true,
+        backslash_escapes: false,
+    };
+
+    fn table() -> TableInfo {
+        TableInfo {
+            sql_name: "\"public\".\"events\"".to_string(),
+            name: "events".to_string(),
+            key_columns: vec!["id".to_string()],
+            columns: ["id", "title", "starts_at", "payload"]
+                .into_iter()
+                .map(|name| (name.to_string(), None))
+                .collect(),
+        }
+    }
+
+    fn strings(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn update(changes: &[(&str, &str)]) -> StagedChange {
+        StagedChange {
+            id: 1,
+            change: RowChange::Update(UpdateRow {
+                table_id: "events".to_string(),
+                key: strings(&[("id", "7")]),
+                original: strings(&[
+                    ("id", "7"),
+                    ("title", "Launch"),
+                    ("starts_at", "\"2024-03-01T10:00:00Z\""),
+                    ("payload", "{\"preview\":true}"),
+                ]),
+                changes: changes
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), Some(v.to_string())))
+                    .collect(),
+            }),
+        }
+    }
+
+    #[test]
+    fn checks_only_key_and_changed_columns() {
+        let steps = plan(&update(&[("title", "Relaunch")]), &table(), &DIALECT).unwrap();
+        let Step::Check {
+            query, original, ..
+        } = &steps[0]
+        else {
+            panic!("expected a check first");
+        };
+
+        let (sql, params) = query.to_query(&DIALECT);
+        assert_eq!(
+            sql,
+            "SELECT \"id\", \"title\" FROM \"public\".\"events\" WHERE \"id\" = $1 FOR UPDATE"
+        );
+        assert_eq!(params, vec![Some("7")]);
+        assert_eq!(original, &strings(&[("id", "7"), ("title", "Launch")]));
+    }
+
+    #[test]
+    fn rejects_unknown_columns() {
+        let error = plan(&update(&[("missing", "x")]), &table(), &DIALECT).unwrap_err();
+        assert!(matches!(error, DbError::NotFound(_)), "{:?}", error);
+    }
+
+    #[test]
+    fn same_instant_in_another_zone_is_not_a_conflict() {
+        let original = strings(&[("starts_at", "\"2024-03-01T10:00:00Z\"")]);
+        let current = Row {
+            values: strings(&[("starts_at", "\"2024-03-01T11:00:00+01:00\"")]),
+        };
+        assert!(compare(1, "events", &HashMap::new(), &original, Some(current)).is_none());
+
+        let current = Row {
+            values: strings(&[("starts_at", "\"2024-03-01T11:00:00Z\"")]),
+        };
+        assert!(compare(1, "events", &HashMap::new(), &original, Some(current)).is_some());
+    }
+
+    #[test]
+    fn unquoted_timestamps_are_compared_as_text() {
+        assert!(same_value(
+            "\"2024-03-01T10:00:00Z\"",
+            "\"2024-03-01T10:00:00+00:00\""
+        ));
+        assert!(!same_value(
+            "2024-03-01T10:00:00Z",
+            "2024-03-01T10:00:00+00:00"
+        ));
+    }
+
+    #[test]
+    fn changed_and_missing_rows_are_conflicts() {
+        let key = strings(&[("id", "7")]);
+        let original = strings(&[("id", "7"), ("title", "Launch")]);
+
+        let current = Row {
+            values: strings(&[("id", "7"), ("title", "Relaunch")]),
+        };
+        let conflict = compare(1, "events", &key, &original, Some(current)).unwrap();
+        assert!(matches!(conflict.kind, ConflictKind::Modified));
+        assert_eq!(conflict.columns, vec!["title".to_string()]);
+
+        let conflict = compare(1, "events", &key, &original, None).unwrap();
+        assert!(matches!(conflict.kind, ConflictKind::Missing));
+    }
+}
@@ -0,0 +1,59 @@
+use chrono::{DateTime, Utc};
+use serde_json::Value as JsonValue;
+
+use crate::db::temporal::DisplayZone;
+
+/// Options controlling how values are decoded for the frontend
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DecodeOptions {
+    /// Zone DateTime values are rendered in
+    pub time_zone: DisplayZone,
+}
+
+/// Strips the `Nullable(...)` and `LowCardinality(...)` wrappers, which don't change
+/// how a value is encoded
+pub(crate) fn base_type(type_name: &str) -> &str {
+    let mut ty = type_name.trim();
+    loop {
+        let inner = ["Nullable(", "LowCardinality("]
+            .iter()
+            .find_map(|wrapper| ty.strip_prefix(wrapper)?.strip_suffix(')'));
+        match inner {
+            Some(inner) => ty = inner.trim(),
+            None => return ty,
+        }
+    }
+}
+
+pub(crate) fn is_nullable(type_name: &str) -> bool {
+    let ty = type_name.trim();
+    ty.starts_with("Nullable(") || ty.starts_with("LowCardinality(Nullable(")
+}
+
+/// Converts a value from `JSONCompactEachRowWithNamesAndTypes` output.
+/// 64-bit integers arrive quoted and are parsed back into numbers; wider integers and
+/// decimals stay strings so no precision is lost.
+pub(crate) fn to_json(value: JsonValue, type_name: &str, opts: &DecodeOptions) -> JsonValue {
+    if value.is_null() {
+        return JsonValue::Null;
+    }
+
+    let ty = base_type(type_name);
+    let name = ty.split('(').next().unwrap_or(ty);
+
+    match (name, value) {
+        ("Int64", JsonValue::String(s)) => s
+            .parse::<i64>()
+            .map(|n| JsonValue::Number(n.into()))
+            .unwrap_or(JsonValue::String(s)),
+        ("UInt64", JsonValue::String(s)) => s
+            .parse::<u64>()
+            .map(|n| JsonValue::Number(n.into()))
+            .unwrap_or(JsonValue::String(s)),
+        // `date_time_output_format=iso` renders every DateTime as a UTC instant
+        ("DateTime" | "DateTime64", JsonValue::String(s)) => DateTime::parse_from_rfc3339(&s)
+            .map(|dt| JsonValue::String(opts.time_zone.format(dt.with_timezone(&Utc))))
+            .unwrap_or(JsonValue::String(s)),
+        (_, value) => value,
+    }
+}
@@ -1,14 +1,39 @@
 #![allow(dead_code)]
 
 use std::collections::HashMap;
+use std::path::Path;
 
 use async_trait::async_trait;
 use url::Url;
 
+use crate::db::binary::BinaryFormat;
+use crate::db::browse::{BrowsePage, BrowseRequest};
+use crate::db::catalog::CatalogSnapshot;
+use crate::db::changes::StagedChange;
+use crate::db::driver::find_driver;
+use crate::db::dump::{DumpRequest, DumpStats};
+use crate::db::editing::ColumnChanges;
 use crate::db::errors::{DbError, DbResult};
-use crate::db::types::{DbEntity, QueryResult};
+use crate::db::export::columnar::ColumnarFormat;
+use crate::db::export::csv::CsvOptions;
+use crate::db::export::{self, ExportSource, ExportWriter};
+use crate::db::import::Importer;
+use crate::db::migrations::{AppliedMigration, MigrationTool};
+use crate::db::search::Searcher;
+use crate::db::typegen::TypeModel;
+use crate::db::types::{DbEntity, QueryResult, Row, ServerInfo, TimeZoneMode};
 
-/// Core database client interface for all database operations
+/// Error for a feature a driver doesn't implement; `DriverCapabilities` tells the UI
+/// up front, so this is only reached by callers that don't check
+fn unsupported<T>(feature: &str) -> DbResult<T> {
+    Err(DbError::Unsupported(format!(
+        "{} isn't available for this database yet",
+        feature
+    )))
+}
+
+/// Core database client interface for all database operations.
+/// Features not every driver has default to an Unsupported error.
 #[async_trait]
 pub trait DatabaseClient: Send + Sync {
     fn get_connection_string(&self) -> String;
@@ -31,25 +56,136 @@ pub trait DatabaseClient: Send + Sync {
     /// Update the connection string & attempt to reconnect
     async fn reconnect_with_string(&mut self, connection_string: &str) -> DbResult<()>;
 
+    /// Set the zone timestamps with a time zone are rendered in
+    fn set_time_zone(&mut self, mode: TimeZoneMode);
+
+    /// Set how binary cells are previewed
+    fn set_binary_format(&mut self, format: BinaryFormat);
+
+    /// Flavor and version of the connected server, if connected
+    fn server_info(&self) -> Option<ServerInfo>;
+
     /// Execute a raw SQL query
     async fn execute_query(&self, sql: &str) -> DbResult<QueryResult>;
 
+    /// Read a page of a table's rows with a filter, sort order and pagination
+    async fn browse_table(&self, _request: &BrowseRequest) -> DbResult<BrowsePage> {
+        unsupported("Table browsing")
+    }
+
+    /// A handle for searching all tables that doesn't borrow the client, so a long
+    /// search doesn't block other work on the connection
+    fn searcher(&self) -> DbResult<Box<dyn Searcher>> {
+        unsupported("Searching all tables")
+    }
+
+    /// Write a query's full result or a whole table as CSV, returning the number of rows.
+    /// Drivers that can stream should override this; by default the result is read into memory.
+    async fn export_csv(
+        &self,
+        source: &ExportSource,
+        options: &CsvOptions,
+        out: &mut ExportWriter,
+    ) -> DbResult<u64> {
+        options.validate()?;
+        let result = self.read_export_source(source).await?;
+        export::check_complete(&result)?;
+        Ok(export::write_result(&mut options.clone(), &result, out).await?)
+    }
+
+    /// Write a query's full result or a whole table to `path` as Parquet or Arrow IPC,
+    /// returning the number of rows
+    async fn export_columnar(
+        &self,
+        _source: &ExportSource,
+        _format: ColumnarFormat,
+        _path: &Path,
+    ) -> DbResult<u64> {
+        unsupported("Parquet and Arrow export")
+    }
+
+    /// Read everything an export covers into memory
+    async fn read_export_source(&self, source: &ExportSource) -> DbResult<QueryResult> {
+        match source {
+            ExportSource::Query { sql } => self.execute_query(sql).await,
+            ExportSource::Table { .. } => unsupported("Exporting whole tables"),
+        }
+    }
+
+    /// A handle for loading a CSV, NDJSON or Parquet file into a table that doesn't
+    /// borrow the client, so the connection stays usable while a large file loads
+    fn importer(&self) -> DbResult<Box<dyn Importer>> {
+        unsupported("Importing files")
+    }
+
+    /// Write a SQL script recreating the chosen schemas and tables, with their rows if asked
+    async fn dump_sql(
+        &self,
+        _request: &DumpRequest,
+        _out: &mut ExportWriter,
+    ) -> DbResult<DumpStats> {
+        unsupported("Dumping to a SQL script")
+    }
+
+    /// The DDL that recreates an entity, e.g. a table's CREATE TABLE with its indexes
+    async fn entity_ddl(&self, _entity_id: &str) -> DbResult<String> {
+        unsupported("Generating DDL")
+    }
+
+    /// Read the tables, functions and enums of the chosen schemas, or of every
+    /// non-system schema when none are chosen, for comparing with another database
+    async fn catalog_snapshot(&self, _schemas: &[String]) -> DbResult<CatalogSnapshot> {
+        unsupported("Schema snapshots")
+    }
+
+    /// Read a migration tool's tracking table, or None if the database doesn't have one
+    async fn migration_history(
+        &self,
+        _tool: MigrationTool,
+    ) -> DbResult<Option<Vec<AppliedMigration>>> {
+        unsupported("Migration status")
+    }
+
+    /// Read the column types of the chosen schemas' tables and views, and the enums they use
+    async fn type_model(&self, _schemas: &[String]) -> DbResult<TypeModel> {
+        unsupported("Type generation")
+    }
+
+    /// Fetch the full value of a binary column for the row matching `key`
+    async fn fetch_blob(
+        &self,
+        table_id: &str,
+        key: &HashMap<String, String>,
+        column: &str,
+    ) -> DbResult<Vec<u8>>;
+
+    /// Update the row matching `key`, which must be the table's primary or unique key,
+    /// and return the row as stored
+    async fn update_row(
+        &self,
+        _table_id: &str,
+        _key: &HashMap<String, String>,
+        _changes: &ColumnChanges,
+    ) -> DbResult<Row> {
+        unsupported("Editing rows")
+    }
+
+    /// Render the statements a change set would run, with values inlined for review
+    async fn preview_changes(&self, _changes: &[StagedChange]) -> DbResult<Vec<String>> {
+        unsupported("Staging changes")
+    }
+
+    /// Apply a change set in a single transaction, after checking every updated or deleted
+    /// row still has its original values. Returns the number of rows written
+    async fn apply_changes(&self, _changes: &[StagedChange]) -> DbResult<u64> {
+        unsupported("Staging changes")
+    }
+
     /// Get a flat list of all entities including schemas
     async fn get_all_entities(&self) -> DbResult<HashMap<String, DbEntity>>;
 }
 
 /// Creates a database client based on connection info without establishing a connection
-pub fn create_client(url: &Url) -> DbResult<impl DatabaseClient> {
-    use crate::db::postgres::PostgresClient;
-
-    match url.scheme() {
-        "postgres" | "postgresql" => {
-            let client = PostgresClient::new(url.to_string().as_str())?;
-            Ok(client)
-        }
-        _ => Err(DbError::Unsupported(format!(
-            "Unsupported database type: {}",
-            url.scheme()
-        ))),
-    }
+pub fn create_client(url: &Url) -> DbResult<Box<dyn DatabaseClient>> {
+    find_driver(url.scheme())?.create_client(url.as_str())
 }
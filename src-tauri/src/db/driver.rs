@@ -0,0 +1,222 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use url::Url;
+
+use crate::db::clickhouse::{self, ClickHouseClient};
+use crate::db::client::DatabaseClient;
+use crate::db::duckdb::DuckDbClient;
+use crate::db::errors::{DbError, DbResult};
+use crate::db::mysql::MySqlClient;
+use crate::db::postgres::PostgresClient;
+use crate::db::sqlite::SqliteClient;
+
+/// Features a driver supports, so the UI can hide what isn't available.
+/// Procedures for a feature a driver lacks return an Unsupported error.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DriverCapabilities {
+    /// Statements can be grouped into a transaction and rolled back
+    pub transactions: bool,
+    /// Tables are grouped into schemas (or databases, for MySQL and ClickHouse)
+    pub schemas: bool,
+    /// Statements can be prefixed with EXPLAIN to show their plan
+    pub explain: bool,
+    /// A running statement can be cancelled from another connection
+    pub cancel: bool,
+    /// Rows can be edited in place by key, and changes staged and applied together
+    pub editing: bool,
+    /// Tables can be paged through with filters and sorting
+    pub browse: bool,
+    /// Every table can be searched for a term
+    pub search: bool,
+    /// Whole tables can be exported, and results written as Parquet or Arrow
+    pub export: bool,
+    /// CSV, NDJSON and Parquet files can be imported into tables
+    pub import: bool,
+    /// Schemas and tables can be dumped to a SQL script
+    pub dump: bool,
+    /// An entity's CREATE statement can be generated
+    pub ddl: bool,
+    /// Schema snapshots can be taken and compared
+    pub diff: bool,
+    /// Migration tools' tracking tables can be read
+    pub migrations: bool,
+    /// TypeScript, Zod and Rust types can be generated from the schema
+    pub typegen: bool,
+    /// Port used when the URL doesn't name one; None for file-backed databases
+    pub default_port: Option<u16>,
+}
+
+impl DriverCapabilities {
+    /// Only the basics every driver has: running queries and listing entities
+    const BASIC: Self = Self {
+        transactions: false,
+        schemas: false,
+        explain: false,
+        cancel: false,
+        editing: false,
+        browse: false,
+        search: false,
+        export: false,
+        import: false,
+        dump: false,
+        ddl: false,
+        diff: false,
+        migrations: false,
+        typegen: false,
+        default_port: None,
+    };
+}
+
+/// A database backend and the URL schemes it is registered under
+pub struct Driver {
+    /// Stable identifier, e.g. "postgres"
+    pub id: &'static str,
+    /// Display name, e.g. "PostgreSQL"
+    pub name: &'static str,
+    /// URL schemes routed to this driver; the first is canonical
+    pub schemes: &'static [&'static str],
+    pub capabilities: DriverCapabilities,
+    /// Creates a client without establishing a connection
+    create: fn(&str) -> DbResult<Box<dyn DatabaseClient>>,
+}
+
+impl Driver {
+    pub fn create_client(&self, connection_string: &str) -> DbResult<Box<dyn DatabaseClient>> {
+        (self.create)(connection_string)
+    }
+}
+
+/// All drivers, in the order they're offered to the user
+pub static DRIVERS: &[Driver] = &[
+    Driver {
+        id: "postgres",
+        name: "PostgreSQL",
+        schemes: &["postgres", "postgresql"],
+        // CockroachDB speaks the same protocol but can't be dumped, diffed or have DDL
+        // generated; those procedures report it when connected to one
+        capabilities: DriverCapabilities {
+            transactions: true,
+            schemas: true,
+            explain: true,
+            cancel: true,
+            editing: true,
+            browse: true,
+            search: true,
+            export: true,
+            import: true,
+            dump: true,
+            ddl: true,
+            diff: true,
+            migrations: true,
+            typegen: true,
+            default_port: Some(5432),
+        },
+        create: |s| Ok(Box::new(PostgresClient::new(s)?)),
+    },
+    Driver {
+        id: "mysql",
+        name: "MySQL / MariaDB",
+        schemes: &["mysql", "mariadb"],
+        // Running statements are stopped with KILL QUERY
+        capabilities: DriverCapabilities {
+            transactions: true,
+            schemas: true,
+            explain: true,
+            cancel: true,
+            editing: true,
+            migrations: true,
+            default_port: Some(3306),
+            ..DriverCapabilities::BASIC
+        },
+        create: |s| Ok(Box::new(MySqlClient::new(s)?)),
+    },
+    Driver {
+        id: "sqlite",
+        name: "SQLite",
+        schemes: &["sqlite"],
+        // Attached databases are listed where schemas would be, but no schema can be
+        // created. In-process, so there's no other connection to cancel from. Plans are
+        // shown with EXPLAIN QUERY PLAN, as plain EXPLAIN lists the bytecode.
+        capabilities: DriverCapabilities {
+            transactions: true,
+            explain: true,
+            editing: true,
+            migrations: true,
+            ..DriverCapabilities::BASIC
+        },
+        create: |s| Ok(Box::new(SqliteClient::new(s)?)),
+    },
+    Driver {
+        id: "duckdb",
+        name: "DuckDB",
+        schemes: &["duckdb"],
+        // Unlike SQLite's attached databases, DuckDB has real schemas (CREATE SCHEMA).
+        // In-process, so there's no other connection to cancel from.
+        capabilities: DriverCapabilities {
+            transactions: true,
+            schemas: true,
+            explain: true,
+            editing: true,
+            ..DriverCapabilities::BASIC
+        },
+        create: |s| Ok(Box::new(DuckDbClient::new(s)?)),
+    },
+    Driver {
+        id: "clickhouse",
+        name: "ClickHouse",
+        schemes: &["clickhouse"],
+        // No transactions, and updates are asynchronous mutations, not row-level writes.
+        // The default port is the HTTP interface's; `?secure=true` switches to HTTPS on 8443.
+        capabilities: DriverCapabilities {
+            schemas: true,
+            explain: true,
+            cancel: true,
+            default_port: Some(clickhouse::DEFAULT_PORT),
+            ..DriverCapabilities::BASIC
+        },
+        create: |s| Ok(Box::new(ClickHouseClient::new(s)?)),
+    },
+];
+
+/// Finds the driver registered for a URL scheme
+pub fn find_driver(scheme: &str) -> DbResult<&'static Driver> {
+    DRIVERS
+        .iter()
+        .find(|driver| driver.schemes.contains(&scheme))
+        .ok_or_else(|| DbError::Unsupported(format!("Unsupported database type: {}", scheme)))
+}
+
+/// Turns the path of a file-backed URL like `sqlite:///C:/data.db` back into a file path.
+/// The path is read as a `file://` URL's, so Windows drive paths come back as `C:\data.db`
+/// rather than `/C:/data.db`. None for URLs without an absolute path, like `sqlite::memory:`.
+pub fn url_file_path(url: &Url) -> Option<PathBuf> {
+    if !url.path().starts_with('/') || url.host_str().is_some_and(|host| !host.is_empty()) {
+        return None;
+    }
+    let mut file_url = Url::parse("file:///").ok()?;
+    file_url.set_path(url.path());
+    file_url.to_file_path().ok()
+}
+
+/// A driver as described to the frontend
+#[taurpc::ipc_type]
+#[serde(rename_all = "camelCase")]
+#[derive(Debug)]
+pub struct DriverInfo {
+    pub id: String,
+    pub name: String,
+    pub schemes: Vec<String>,
+    pub capabilities: DriverCapabilities,
+}
+
+impl From<&Driver> for DriverInfo {
+    fn from(driver: &Driver) -> Self {
+        Self {
+            id: driver.id.to_string(),
+            name: driver.name.to_string(),
+            schemes: driver.schemes.iter().map(|s| s.to_string()).collect(),
+            capabilities: driver.capabilities,
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use ::duckdb::arrow::datatypes::DataType;
+use ::duckdb::types::{TimeUnit, Value};
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeDelta};
+use serde_json::Value as JsonValue;
+
+use crate::db::binary::{BinaryFormat, BinaryPreview};
+use crate::db::temporal::{DisplayZone, DATETIME_FORMAT, DATE_FORMAT, TIME_FORMAT};
+
+/// Options controlling how values are decoded for the frontend
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DecodeOptions {
+    pub binary: BinaryFormat,
+    /// Zone TIMESTAMPTZ values are rendered in
+    pub time_zone: DisplayZone,
+}
+
+fn to_micros(unit: TimeUnit, value: i64) -> i64 {
+    match unit {
+        TimeUnit::Second => value.saturating_mul(1_000_000),
+        TimeUnit::Millisecond => value.saturating_mul(1_000),
+        TimeUnit::Microsecond => value,
+        TimeUnit::Nanosecond => value / 1_000,
+    }
+}
+
+fn timestamp(micros: i64, zoned: bool, opts: &DecodeOptions) -> JsonValue {
+    // DuckDB encodes ±infinity as the extreme values of the underlying integer
+    if micros == i64::MAX {
+        return JsonValue::from("infinity");
+    }
+    if micros <= -i64::MAX {
+        return JsonValue::from("-infinity");
+    }
+
+    match DateTime::from_timestamp_micros(micros) {
+        Some(utc) if zoned => JsonValue::String(opts.time_zone.format(utc)),
+        Some(utc) => JsonValue::String(utc.naive_utc().format(DATETIME_FORMAT).to_string()),
+        None => JsonValue::Null,
+    }
+}
+
+/// Converts a DuckDB value to JSON. `data_type` is the declared column type,
+/// which is needed to tell TIMESTAMP and TIMESTAMPTZ apart.
+pub(crate) fn to_json(v: Value, data_type: Option<&DataType>, opts: &DecodeOptions) -> JsonValue {
+    match v {
+        Value::Null => JsonValue::Null,
+        Value::Boolean(v) => JsonValue::Bool(v),
+        Value::TinyInt(v) => JsonValue::from(v),
+        Value::SmallInt(v) => JsonValue::from(v),
+        Value::Int(v) => JsonValue::from(v),
+        Value::BigInt(v) => JsonValue::from(v),
+        Value::UTinyInt(v) => JsonValue::from(v),
+        Value::USmallInt(v) => JsonValue::from(v),
+        Value::UInt(v) => JsonValue::from(v),
+        Value::UBigInt(v) => JsonValue::from(v),
+        // 128-bit integers and decimals travel as strings so no precision is lost
+        Value::HugeInt(v) => JsonValue::String(v.to_string()),
+        Value::Decimal(v) => JsonValue::String(v.to_string()),
+        Value::Float(v) => JsonValue::from(v),
+        Value::Double(v) => JsonValue::from(v),
+        Value::Text(v) | Value::Enum(v) => JsonValue::String(v),
+        Value::Blob(v) => BinaryPreview::new(&v, &opts.binary).to_json(),
+        Value::Date32(days) => match days {
+            i32::MAX => JsonValue::from("infinity"),
+            days if days <= -i32::MAX => JsonValue::from("-infinity"),
+            days => NaiveDate::from_ymd_opt(1970, 1, 1)
+                .and_then(|epoch| epoch.checked_add_signed(TimeDelta::days(days as i64)))
+                .map(|d| JsonValue::String(d.format(DATE_FORMAT).to_string()))
+                .unwrap_or(JsonValue::Null),
+        },
+        Value::Time64(unit, v) => {
+            let micros = to_micros(unit, v);
+            NaiveTime::from_num_seconds_from_midnight_opt(
+                (micros / 1_000_000) as u32,
+                ((micros % 1_000_000) * 1_000) as u32,
+            )
+            .map(|t| JsonValue::String(t.format(TIME_FORMAT).to_string()))
+            .unwrap_or(JsonValue::Null)
+        }
+        Value::Timestamp(unit, v) => {
+            let zoned = matches!(data_type, Some(DataType::Timestamp(_, Some(_))));
+            timestamp(to_micros(unit, v), zoned, opts)
+        }
+        Value::Interval {
+            months,
+            days,
+            nanos,
+        } => JsonValue::String(format!(
+            "P{}M{}DT{}S",
+            months,
+            days,
+            nanos as f64 / 1_000_000_000.0
+        )),
+        Value::List(items) | Value::Array(items) => JsonValue::Array(
+            items
+                .into_iter()
+                .map(|item| to_json(item, None, opts))
+                .collect(),
+        ),
+        Value::Struct(fields) => JsonValue::Object(
+            fields
+                .iter()
+                .map(|(k, v)| (k.clone(), to_json(v.clone(), None, opts)))
+                .collect(),
+        ),
+        Value::Map(entries) => JsonValue::Array(
+            entries
+                .iter()
+                .map(|(k, v)| {
+                    JsonValue::Array(vec![
+                        to_json(k.clone(), None, opts),
+                        to_json(v.clone(), None, opts),
+                    ])
+                })
+                .collect(),
+        ),
+        Value::Union(v) => to_json(*v, None, opts),
+        #[allow(unreachable_patterns)]
+        other => JsonValue::String(format!("{:?}", other)),
+    }
+}
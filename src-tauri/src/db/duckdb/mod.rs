@@ -0,0 +1,774 @@
+mod decode;
+
+use ::duckdb::arrow::datatypes::DataType;
+use ::duckdb::{params_from_iter, types::Value, Connection};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use url::Url;
+
+use crate::db::{
+    binary::BinaryFormat,
+    changes::{self, Dialect, StagedChange, StepRunner, TableInfo, TableLoader},
+    client::DatabaseClient,
+    driver,
+    editing::{self, ColumnChanges},
+    errors::{DbError, DbResult},
+    ident::{quote_double as quote_ident, split_quoted},
+    temporal::DisplayZone,
+    types::{
+        ColumnDefinition, DbEntity, QueryResult, Row, SchemaEntity, SchemaLevelEntity,
+        ServerFlavor, ServerInfo, TimeZoneMode,
+    },
+};
+
+use self::decode::DecodeOptions;
+
+/// File extensions opened directly as DuckDB databases
+pub const DATABASE_EXTENSIONS: &[&str] = &["duckdb", "ddb"];
+
+/// Data file extensions that are exposed as views of an in-memory database
+pub const DATA_FILE_EXTENSIONS: &[&str] = &["parquet", "csv", "tsv", "json", "ndjson"];
+
+const SYSTEM_DATABASES: &[&str] = &["system", "temp"];
+
+fn quote_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Entity IDs are quoted identifiers, e.g. `"db"."main"` or `"db"."main"."users"`
+fn entity_id(parts: &[&str]) -> String {
+    parts
+        .iter()
+        .map(|p| quote_ident(p))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+}
+
+pub fn is_database_file(path: &Path) -> bool {
+    extension(path).is_some_and(|ext| DATABASE_EXTENSIONS.contains(&ext.as_str()))
+}
+
+pub fn is_data_file(path: &Path) -> bool {
+    extension(path).is_some_and(|ext| DATA_FILE_EXTENSIONS.contains(&ext.as_str()))
+}
+
+/// Whether a directory contains any data files DuckDB can query
+pub fn is_data_dir(path: &Path) -> bool {
+    std::fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .any(|entry| is_data_file(&entry.path()))
+        })
+        .unwrap_or(false)
+}
+
+/// Builds a `duckdb://` URL for a database file, data file or folder of data files
+pub fn file_url(path: &Path) -> Option<Url> {
+    let file_url = Url::from_file_path(path).ok()?;
+    let mut url = Url::parse("duckdb://").ok()?;
+    url.set_path(file_url.path());
+    Some(url)
+}
+
+/// Opens the target of a `duckdb://` URL. Database files are opened directly,
+/// while data files and folders are exposed as views of an in-memory database.
+fn open(connection_string: &str) -> DbResult<Connection> {
+    let url = Url::parse(connection_string)?;
+    if url.path().is_empty() || url.path() == "/:memory:" {
+        return Ok(Connection::open_in_memory()?);
+    }
+    let path = driver::url_file_path(&url)
+        .ok_or_else(|| DbError::Config(format!("Invalid DuckDB path: {}", url.path())))?;
+
+    if !path.exists() {
+        return Err(DbError::Config(format!(
+            "Path does not exist: {}",
+            path.display()
+        )));
+    }
+
+    let files = if path.is_dir() {
+        let mut files = std::fs::read_dir(&path)?
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|p| p.is_file() && is_data_file(p))
+            .collect::<Vec<_>>();
+        files.sort();
+        files
+    } else if is_data_file(&path) {
+        vec![path]
+    } else {
+        return Ok(Connection::open(&path)?);
+    };
+
+    let conn = Connection::open_in_memory()?;
+    let mut used_names = Vec::new();
+    for file in files {
+        let stem = file
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("data")
+            .to_string();
+        // Fall back to the full file name when e.g. both orders.csv and orders.parquet exist
+        let name = if used_names.contains(&stem) {
+            file.file_name()
+                .and_then(|s| s.to_str())
+                .unwrap_or("data")
+                .to_string()
+        } else {
+            stem
+        };
+
+        let source = quote_literal(&file.to_string_lossy());
+        let reader = match extension(&file).as_deref() {
+            Some("parquet") => format!("read_parquet({})", source),
+            Some("json") | Some("ndjson") => format!("read_json_auto({})", source),
+            _ => format!("read_csv_auto({})", source),
+        };
+
+        conn.execute_batch(&format!(
+            "CREATE VIEW {} AS SELECT * FROM {}",
+            quote_ident(&name),
+            reader
+        ))?;
+        used_names.push(name);
+    }
+
+    Ok(conn)
+}
+
+/// Decodes all rows of a result, along with its column names and types
+fn collect_rows(
+    mut rows: ::duckdb::Rows<'_>,
+    opts: &DecodeOptions,
+) -> DbResult<(Vec<String>, Vec<DataType>, Vec<Row>)> {
+    let mut names: Vec<String> = Vec::new();
+    let mut types = Vec::new();
+    let mut result_rows = Vec::new();
+
+    while let Some(row) = rows.next()? {
+        if names.is_empty() {
+            let stmt = row.as_ref();
+            names = stmt.column_names();
+            types = (0..stmt.column_count())
+                .map(|i| stmt.column_type(i))
+                .collect();
+        }
+
+        let mut values = HashMap::new();
+        for (i, name) in names.iter().enumerate() {
+            let value: Value = row.get(i)?;
+            let value = decode::to_json(value, types.get(i), opts);
+            values.insert(
+                name.clone(),
+                if value.is_null() {
+                    "NULL".to_string()
+                } else {
+                    value.to_string()
+                },
+            );
+        }
+        result_rows.push(Row { values });
+    }
+
+    Ok((names, types, result_rows))
+}
+
+/// Columns of the primary key, or of the first unique constraint on non-null columns
+fn key_columns(
+    conn: &Connection,
+    database: &str,
+    schema: &str,
+    table: &str,
+) -> DbResult<Vec<String>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT k.constraint_index, k.constraint_type, k.column_name, c.is_nullable
+        FROM (
+            SELECT
+                constraint_index,
+                constraint_type,
+                unnest(constraint_column_names) AS column_name,
+                generate_subscripts(constraint_column_names, 1) AS ord
+            FROM duckdb_constraints()
+            WHERE database_name = ? AND schema_name = ? AND table_name = ?
+              AND constraint_type IN ('PRIMARY KEY', 'UNIQUE')
+        ) k
+        JOIN duckdb_columns() c
+          ON c.database_name = ? AND c.schema_name = ? AND c.table_name = ?
+         AND c.column_name = k.column_name
+        ORDER BY k.constraint_type = 'PRIMARY KEY' DESC, k.constraint_index, k.ord
+        "#,
+    )?;
+    let rows = stmt
+        .query_map([database, schema, table, database, schema, table], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, bool>(3)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut constraints: Vec<(i64, bool, Vec<String>, bool)> = Vec::new();
+    for (index, kind, column, nullable) in rows {
+        match constraints.last_mut() {
+            Some((i, _, columns, any_nullable)) if *i == index => {
+                columns.push(column);
+                *any_nullable |= nullable;
+            }
+            _ => constraints.push((index, kind == "PRIMARY KEY", vec![column], nullable)),
+        }
+    }
+
+    Ok(constraints
+        .into_iter()
+        .find(|(_, is_primary, _, any_nullable)| *is_primary || !any_nullable)
+        .map(|(_, _, columns, _)| columns)
+        .unwrap_or_default())
+}
+
+const DIALECT: Dialect = Dialect {
+    quote: '"',
+    numbered_params: false,
+    // DuckDB uses optimistic concurrency and has no row locks
+    lock_rows: false,
+    backslash_escapes: false,
+};
+
+/// Loads the tables a change set touches
+struct ChangeTables<'a>(&'a mut Connection);
+
+#[async_trait]
+impl TableLoader for ChangeTables<'_> {
+    async fn load_table(&mut self, table_id: &str) -> DbResult<TableInfo> {
+        let [database, schema, table] = <[String; 3]>::try_from(split_quoted(table_id, '"')?)
+            .map_err(|_| DbError::NotFound(format!("Relation {}", table_id)))?;
+
+        let mut stmt = self.0.prepare(
+            r#"
+            SELECT column_name, data_type
+            FROM duckdb_columns()
+            WHERE database_name = ? AND schema_name = ? AND table_name = ?
+            "#,
+        )?;
+        // DuckDB won't compare VARCHAR parameters to other types implicitly
+        let columns = stmt
+            .query_map([&database, &schema, &table], |row| {
+                Ok((row.get::<_, String>(0)?, Some(row.get::<_, String>(1)?)))
+            })?
+            .collect::<Result<HashMap<_, _>, _>>()?;
+
+        Ok(TableInfo {
+            sql_name: table_id.to_string(),
+            key_columns: key_columns(self.0, &database, &schema, &table)?,
+            columns,
+            name: table,
+        })
+    }
+}
+
+/// Runs the statements of a change set; the caller opens the transaction
+struct ChangeRunner<'a> {
+    conn: &'a mut Connection,
+    opts: &'a DecodeOptions,
+}
+
+#[async_trait]
+impl StepRunner for ChangeRunner<'_> {
+    async fn fetch_row(&mut self, sql: &str, params: Vec<Option<&str>>) -> DbResult<Option<Row>> {
+        let mut stmt = self.conn.prepare(sql)?;
+        let (_, _, mut rows) = collect_rows(stmt.query(params_from_iter(params))?, self.opts)?;
+        Ok(rows.pop())
+    }
+
+    async fn execute(&mut self, sql: &str, params: Vec<Option<&str>>) -> DbResult<u64> {
+        Ok(self.conn.execute(sql, params_from_iter(params))? as u64)
+    }
+}
+
+pub struct DuckDbClient {
+    connection_string: String,
+    conn: Option<Arc<Mutex<Connection>>>,
+    decode_options: DecodeOptions,
+    /// Version of the embedded DuckDB library, read on connect
+    server_info: Option<ServerInfo>,
+}
+
+impl DuckDbClient {
+    pub fn new(connection_string: &str) -> DbResult<Self> {
+        Ok(Self {
+            connection_string: connection_string.to_string(),
+            conn: None,
+            decode_options: DecodeOptions::default(),
+            server_info: None,
+        })
+    }
+
+    // This function gets the connection or returns an error if not connected
+    fn get_conn(&self) -> DbResult<Arc<Mutex<Connection>>> {
+        self.conn
+            .clone()
+            .ok_or_else(|| DbError::Connection("Database client is not connected".to_string()))
+    }
+
+    /// DuckDB is synchronous, so all work runs on the blocking thread pool
+    async fn run<T, F>(&self, f: F) -> DbResult<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> DbResult<T> + Send + 'static,
+    {
+        let conn = self.get_conn()?;
+        tokio::task::spawn_blocking(move || {
+            let conn = conn
+                .lock()
+                .map_err(|_| DbError::Connection("DuckDB connection poisoned".to_string()))?;
+            f(&conn)
+        })
+        .await
+        .map_err(|e| DbError::Other(e.to_string()))?
+    }
+
+    /// Like `run`, for the change set code, which is async but never waits while it runs
+    /// on DuckDB; it's driven to completion on the blocking thread
+    async fn run_changes<T, F>(&self, f: F) -> DbResult<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection, &tokio::runtime::Handle) -> DbResult<T> + Send + 'static,
+    {
+        let conn = self.get_conn()?;
+        let handle = tokio::runtime::Handle::current();
+        tokio::task::spawn_blocking(move || {
+            let mut conn = conn
+                .lock()
+                .map_err(|_| DbError::Connection("DuckDB connection poisoned".to_string()))?;
+            f(&mut conn, &handle)
+        })
+        .await
+        .map_err(|e| DbError::Other(e.to_string()))?
+    }
+}
+
+#[async_trait]
+impl DatabaseClient for DuckDbClient {
+    fn get_connection_string(&self) -> String {
+        self.connection_string.clone()
+    }
+
+    async fn is_connected(&self) -> DbResult<bool> {
+        Ok(self.conn.is_some())
+    }
+
+    async fn test_connection(&self) -> DbResult<()> {
+        self.run(|conn| {
+            conn.execute_batch("SELECT 1")?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn connect(&mut self) -> DbResult<()> {
+        // Check if already connected
+        if let Ok(true) = self.is_connected().await {
+            return Ok(());
+        }
+
+        let connection_string = self.connection_string.clone();
+        let (conn, version) = tokio::task::spawn_blocking(move || {
+            let conn = open(&connection_string)?;
+            let version: String = conn.query_row("SELECT version()", [], |row| row.get(0))?;
+            Ok::<_, DbError>((conn, version))
+        })
+        .await
+        .map_err(|e| DbError::Other(e.to_string()))??;
+
+        self.server_info = Some(ServerInfo {
+            flavor: ServerFlavor::DuckDb,
+            version,
+            version_num: None,
+        });
+        self.conn = Some(Arc::new(Mutex::new(conn)));
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> DbResult<()> {
+        // The connection closes once the last in-flight query releases it
+        self.conn = None;
+        self.server_info = None;
+        Ok(())
+    }
+
+    async fn reconnect(&mut self) -> DbResult<()> {
+        self.disconnect().await?;
+        self.connect().await
+    }
+
+    async fn reconnect_with_string(&mut self, connection_string: &str) -> DbResult<()> {
+        self.disconnect().await?;
+        self.connection_string = connection_string.to_string();
+        self.connect().await
+    }
+
+    fn set_time_zone(&mut self, mode: TimeZoneMode) {
+        // DuckDB runs in-process, so its session zone is the local one
+        self.decode_options.time_zone = match mode {
+            TimeZoneMode::Session => DisplayZone::Local,
+            mode => DisplayZone::resolve(mode, None),
+        };
+    }
+
+    fn set_binary_format(&mut self, format: BinaryFormat) {
+        self.decode_options.binary = format;
+    }
+
+    fn server_info(&self) -> Option<ServerInfo> {
+        self.server_info.clone()
+    }
+
+    async fn execute_query(&self, sql: &str) -> DbResult<QueryResult> {
+        let sql = sql.to_string();
+        let opts = self.decode_options.clone();
+
+        self.run(move |conn| {
+            let mut stmt = conn.prepare(&sql)?;
+            let (names, types, result_rows) = collect_rows(stmt.query([])?, &opts)?;
+
+            let columns = names
+                .iter()
+                .zip(types.iter())
+                .map(|(name, data_type)| ColumnDefinition {
+                    name: name.clone(),
+                    data_type: data_type.to_string(),
+                    nullable: true, // Default to true since we can't easily determine
+                    primary_key: false, // Cannot determine from result alone
+                    default_value: None, // Cannot determine from result alone
+                })
+                .collect();
+
+            Ok(QueryResult {
+                timestamp: std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs(),
+                query: sql.clone(),
+                rows_affected: None,
+                execution_time_ms: 0,
+                columns,
+                rows: result_rows,
+                warnings: Vec::new(),
+                result_index: 0,
+            })
+        })
+        .await
+    }
+
+    async fn fetch_blob(
+        &self,
+        table_id: &str,
+        key: &HashMap<String, String>,
+        column: &str,
+    ) -> DbResult<Vec<u8>> {
+        let [database, schema, table] = <[String; 3]>::try_from(split_quoted(table_id, '"')?)
+            .map_err(|_| DbError::NotFound(format!("Relation {}", table_id)))?;
+
+        if key.is_empty() {
+            return Err(DbError::Query("A row key is required".to_string()));
+        }
+
+        let table_id = table_id.to_string();
+        let column = column.to_string();
+        let mut key = key.iter().collect::<Vec<_>>();
+        key.sort();
+        let key = key
+            .into_iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect::<Vec<_>>();
+
+        self.run(move |conn| {
+            let mut stmt = conn.prepare(
+                r#"
+                SELECT column_name
+                FROM duckdb_columns()
+                WHERE database_name = ? AND schema_name = ? AND table_name = ?
+                "#,
+            )?;
+            let columns = stmt
+                .query_map([&database, &schema, &table], |row| row.get::<_, String>(0))?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            for name in key.iter().map(|(k, _)| k).chain([&column]) {
+                if !columns.contains(name) {
+                    return Err(DbError::NotFound(format!("Column {} on {}", name, table)));
+                }
+            }
+
+            let predicate = key
+                .iter()
+                .map(|(name, _)| format!("{} = ?", quote_ident(name)))
+                .collect::<Vec<_>>()
+                .join(" AND ");
+
+            let sql = format!(
+                "SELECT {}::BLOB FROM {} WHERE {} LIMIT 2",
+                quote_ident(&column),
+                table_id,
+                predicate
+            );
+
+            let mut stmt = conn.prepare(&sql)?;
+            let mut values = stmt
+                .query_map(params_from_iter(key.iter().map(|(_, v)| v)), |row| {
+                    row.get::<_, Option<Vec<u8>>>(0)
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            match values.len() {
+                0 => Err(DbError::NotFound("Row not found".to_string())),
+                1 => Ok(values.pop().flatten().unwrap_or_default()),
+                _ => Err(DbError::Query(
+                    "Row key matches more than one row".to_string(),
+                )),
+            }
+        })
+        .await
+    }
+
+    async fn update_row(
+        &self,
+        table_id: &str,
+        key: &HashMap<String, String>,
+        changes: &ColumnChanges,
+    ) -> DbResult<Row> {
+        let [database, schema, table] = <[String; 3]>::try_from(split_quoted(table_id, '"')?)
+            .map_err(|_| DbError::NotFound(format!("Relation {}", table_id)))?;
+
+        editing::check_changes(changes)?;
+
+        let table_id = table_id.to_string();
+        let key = key.clone();
+        let changes = changes.clone();
+        let opts = self.decode_options.clone();
+
+        self.run(move |conn| {
+            let key_columns = key_columns(conn, &database, &schema, &table)?;
+            editing::check_key(&table, &key_columns, &key)?;
+
+            let mut stmt = conn.prepare(
+                r#"
+                SELECT column_name, data_type
+                FROM duckdb_columns()
+                WHERE database_name = ? AND schema_name = ? AND table_name = ?
+                "#,
+            )?;
+            let column_types = stmt
+                .query_map([&database, &schema, &table], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+                })?
+                .collect::<Result<HashMap<_, _>, _>>()?;
+
+            // Values are bound as text, and DuckDB won't compare VARCHAR to other types implicitly
+            let typed_param = |name: &str| match column_types.get(name) {
+                Some(data_type) => Ok(format!("{} = ?::{}", quote_ident(name), data_type)),
+                None => Err(DbError::NotFound(format!("Column {} on {}", name, table))),
+            };
+
+            let names = editing::sorted(changes.keys());
+            let assignments = names
+                .iter()
+                .map(|name| typed_param(name))
+                .collect::<DbResult<Vec<_>>>()?
+                .join(", ");
+
+            let key_names = editing::sorted(key.keys());
+            let predicate = key_names
+                .iter()
+                .map(|name| typed_param(name))
+                .collect::<DbResult<Vec<_>>>()?
+                .join(" AND ");
+
+            let sql = format!(
+                "UPDATE {} SET {} WHERE {} RETURNING *",
+                table_id, assignments, predicate
+            );
+
+            let params = names
+                .iter()
+                .map(|name| changes[*name].clone())
+                .chain(key_names.iter().map(|name| Some(key[*name].clone())))
+                .collect::<Vec<_>>();
+
+            // Commit only once the row decodes, so an error never hides a saved edit
+            conn.execute_batch("BEGIN TRANSACTION")?;
+            let updated = conn
+                .prepare(&sql)
+                .map_err(DbError::from)
+                .and_then(|mut stmt| {
+                    let (_, _, mut rows) =
+                        collect_rows(stmt.query(params_from_iter(params))?, &opts)?;
+                    rows.pop()
+                        .ok_or_else(|| DbError::NotFound("Row not found".to_string()))
+                });
+            match updated {
+                Ok(row) => {
+                    conn.execute_batch("COMMIT")?;
+                    Ok(row)
+                }
+                Err(e) => {
+                    conn.execute_batch("ROLLBACK")?;
+                    Err(e)
+                }
+            }
+        })
+        .await
+    }
+
+    async fn preview_changes(&self, staged: &[StagedChange]) -> DbResult<Vec<String>> {
+        let staged = staged.to_vec();
+        self.run_changes(move |conn, handle| {
+            let steps = handle.block_on(changes::plan_all(
+                &mut ChangeTables(conn),
+                &staged,
+                &DIALECT,
+            ))?;
+            Ok(changes::preview(&steps, &DIALECT))
+        })
+        .await
+    }
+
+    async fn apply_changes(&self, staged: &[StagedChange]) -> DbResult<u64> {
+        let staged = staged.to_vec();
+        let opts = self.decode_options.clone();
+
+        self.run_changes(move |conn, handle| {
+            let steps = handle.block_on(changes::plan_all(
+                &mut ChangeTables(conn),
+                &staged,
+                &DIALECT,
+            ))?;
+
+            conn.execute_batch("BEGIN TRANSACTION")?;
+            let mut runner = ChangeRunner { conn, opts: &opts };
+            match handle.block_on(changes::run(&mut runner, &steps, &DIALECT)) {
+                Ok(rows_written) => {
+                    runner.conn.execute_batch("COMMIT")?;
+                    Ok(rows_written)
+                }
+                Err(e) => {
+                    runner.conn.execute_batch("ROLLBACK")?;
+                    Err(e)
+                }
+            }
+        })
+        .await
+    }
+
+    async fn get_all_entities(&self) -> DbResult<HashMap<String, DbEntity>> {
+        self.run(|conn| {
+            let mut entities = HashMap::new();
+            let mut schema_children_map: HashMap<String, Vec<String>> = HashMap::new();
+
+            // Query 1: Get all schemas of all attached databases
+            let mut stmt = conn.prepare(
+                r#"
+                SELECT database_name, schema_name, internal
+                FROM duckdb_schemas()
+                ORDER BY database_name, schema_name
+                "#,
+            )?;
+            let schema_rows = stmt
+                .query_map([], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, bool>(2)?,
+                    ))
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            for (database, name, internal) in schema_rows {
+                let id = entity_id(&[&database, &name]);
+                let is_system = internal || SYSTEM_DATABASES.contains(&database.as_str());
+
+                schema_children_map.insert(id.clone(), Vec::new());
+                entities.insert(
+                    id.clone(),
+                    DbEntity::Schema(SchemaEntity {
+                        id,
+                        name,
+                        is_system,
+                        extension_name: None,
+                        children: Vec::new(),
+                    }),
+                );
+            }
+
+            // Query 2: Get tables and views
+            let mut stmt = conn.prepare(
+                r#"
+                SELECT database_name, schema_name, table_name, internal, 'table' AS kind
+                FROM duckdb_tables()
+                UNION ALL
+                SELECT database_name, schema_name, view_name, internal, 'view' AS kind
+                FROM duckdb_views()
+                ORDER BY 1, 2, 3
+                "#,
+            )?;
+            let table_rows = stmt
+                .query_map([], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, bool>(3)?,
+                        row.get::<_, String>(4)?,
+                    ))
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            for (database, schema, name, internal, kind) in table_rows {
+                let id = entity_id(&[&database, &schema, &name]);
+                let schema_id = entity_id(&[&database, &schema]);
+                let is_system = internal || SYSTEM_DATABASES.contains(&database.as_str());
+
+                if let Some(children) = schema_children_map.get_mut(&schema_id) {
+                    children.push(id.clone());
+                }
+
+                let schema_level = SchemaLevelEntity {
+                    id: id.clone(),
+                    name,
+                    is_system,
+                    schema_id,
+                    extension_name: None,
+                };
+
+                let entity = match kind.as_str() {
+                    "table" => DbEntity::Table(schema_level),
+                    _ => DbEntity::View(schema_level),
+                };
+
+                entities.insert(id, entity);
+            }
+
+            // Update schema entities with their children
+            for (schema_id, children) in schema_children_map {
+                if let Some(DbEntity::Schema(schema)) = entities.get_mut(&schema_id) {
+                    schema.children = children;
+                }
+            }
+
+            Ok(entities)
+        })
+        .await
+    }
+}
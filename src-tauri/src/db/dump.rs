@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+/// How table rows are written into a dump, if at all
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum DumpData {
+    /// Schema only
+    None,
+    /// `COPY ... FROM stdin` blocks, as psql reads them
+    Copy,
+    /// `INSERT` statements that any SQL client can run
+    Insert { batch_size: u32 },
+}
+
+#[taurpc::ipc_type]
+#[serde(rename_all = "camelCase")]
+#[derive(Debug)]
+pub struct DumpRequest {
+    /// Schemas dumped with everything in them
+    pub schemas: Vec<String>,
+    /// Tables, views and sequences dumped on their own, by entity ID
+    pub table_ids: Vec<String>,
+    pub data: DumpData,
+}
+
+/// What a dump wrote, counted by the driver
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DumpStats {
+    /// Schemas, types, functions, sequences, tables and views created
+    pub objects: u32,
+    pub rows: u64,
+}
+
+#[taurpc::ipc_type]
+#[serde(rename_all = "camelCase")]
+#[derive(Debug)]
+pub struct DumpSummary {
+    pub path: String,
+    pub objects: u32,
+    pub rows: u64,
+    pub bytes: u64,
+}
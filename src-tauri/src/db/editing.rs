@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+use crate::db::errors::{DbError, DbResult};
+
+/// New values by column name, as text; `None` sets the column to NULL
+pub type ColumnChanges = HashMap<String, Option<String>>;
+
+/// Checks that `key` names exactly the columns of the table's primary or unique key,
+/// so an edit can never match more than one row
+pub fn check_key(
+    table: &str,
+    key_columns: &[String],
+    key: &HashMap<String, String>,
+) -> DbResult<()> {
+    if key_columns.is_empty() {
+        return Err(DbError::Unsupported(format!(
+            "{} has no primary key or unique key, so its rows can't be edited",
+            table
+        )));
+    }
+
+    let matches = key.len() == key_columns.len() && key_columns.iter().all(|c| key.contains_key(c));
+    if !matches {
+        return Err(DbError::Query(format!(
+            "Rows of {} are identified by ({}), but the key has ({})",
+            table,
+            key_columns.join(", "),
+            sorted(key.keys()).join(", ")
+        )));
+    }
+
+    Ok(())
+}
+
+/// Checks there is something to write
+pub fn check_changes(changes: &ColumnChanges) -> DbResult<()> {
+    if changes.is_empty() {
+        return Err(DbError::Query("No column changes were given".to_string()));
+    }
+    Ok(())
+}
+
+/// Keys in a stable order, so generated SQL and bind parameters line up
+pub fn sorted<'a, I: IntoIterator<Item = &'a String>>(names: I) -> Vec<&'a String> {
+    let mut names = names.into_iter().collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+/// The key of the row after `changes` are applied, for reading it back
+pub fn updated_key(
+    key: &HashMap<String, String>,
+    changes: &ColumnChanges,
+) -> DbResult<HashMap<String, String>> {
+    key.iter()
+        .map(|(name, value)| match changes.get(name) {
+            Some(Some(new)) => Ok((name.clone(), new.clone())),
+            Some(None) => Err(DbError::Query(format!(
+                "Key column {} can't be set to NULL",
+                name
+            ))),
+            None => Ok((name.clone(), value.clone())),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_changes_needs_a_column() {
+        assert!(check_changes(&ColumnChanges::new()).is_err());
+        assert!(check_changes(&ColumnChanges::from([("name".to_string(), None)])).is_ok());
+    }
+
+    #[test]
+    fn check_key_matches_key_columns_exactly() {
+        let columns = vec!["a".to_string(), "b".to_string()];
+        let key = |names: &[&str]| {
+            names
+                .iter()
+                .map(|n| (n.to_string(), "1".to_string()))
+                .collect::<HashMap<_, _>>()
+        };
+
+        assert!(check_key("t", &columns, &key(&["b", "a"])).is_ok());
+        assert!(check_key("t", &columns, &key(&["a"])).is_err());
+        assert!(check_key("t", &columns, &key(&["a", "c"])).is_err());
+        assert!(matches!(
+            check_key("t", &[], &key(&["a"])),
+            Err(DbError::Unsupported(_))
+        ));
+    }
+}
@@ -1,6 +1,8 @@
 use sqlx::Error as SqlxError;
 use std::fmt;
 
+use crate::db::changes::ChangeConflict;
+
 /// Database error types
 #[derive(Debug)]
 pub enum DbError {
@@ -22,6 +24,8 @@ pub enum DbError {
     // Transaction(String),
     /// SQL parsing error
     Parsing(String),
+    /// Staged changes no longer match the database
+    Conflict(Vec<ChangeConflict>),
     /// Other error
     Other(String),
 }
@@ -38,6 +42,11 @@ impl fmt::Display for DbError {
             DbError::Unsupported(msg) => write!(f, "Operation not supported: {}", msg),
             // DbError::Transaction(msg) => write!(f, "Transaction error: {}", msg),
             DbError::Parsing(msg) => write!(f, "SQL parsing error: {}", msg),
+            DbError::Conflict(conflicts) => write!(
+                f,
+                "{} staged change(s) conflict with the database",
+                conflicts.len()
+            ),
             DbError::Other(msg) => write!(f, "Database error: {}", msg),
         }
     }
@@ -70,5 +79,36 @@ impl From<std::io::Error> for DbError {
     }
 }
 
+impl From<duckdb::Error> for DbError {
+    fn from(error: duckdb::Error) -> Self {
+        match error {
+            duckdb::Error::QueryReturnedNoRows => DbError::NotFound("Row not found".to_string()),
+            _ => DbError::Query(error.to_string()),
+        }
+    }
+}
+
+impl From<reqwest::Error> for DbError {
+    fn from(error: reqwest::Error) -> Self {
+        if error.is_connect() || error.is_timeout() {
+            DbError::Connection(error.to_string())
+        } else {
+            DbError::Query(error.to_string())
+        }
+    }
+}
+
+impl From<arrow::error::ArrowError> for DbError {
+    fn from(error: arrow::error::ArrowError) -> Self {
+        DbError::Other(format!("Arrow error: {}", error))
+    }
+}
+
+impl From<parquet::errors::ParquetError> for DbError {
+    fn from(error: parquet::errors::ParquetError) -> Self {
+        DbError::Other(format!("Parquet error: {}", error))
+    }
+}
+
 /// Result type for database operations
 pub type DbResult<T> = Result<T, DbError>;
@@ -0,0 +1,76 @@
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use arrow::datatypes::SchemaRef;
+use arrow::ipc::writer::FileWriter;
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::AsyncArrowWriter;
+use parquet::basic::{Compression, ZstdLevel};
+use parquet::file::properties::WriterProperties;
+use serde::{Deserialize, Serialize};
+
+use crate::db::errors::DbResult;
+
+/// Typed, columnar file formats for handing data to other tools
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum ColumnarFormat {
+    Parquet,
+    /// The Arrow IPC file format, also known as Feather v2
+    ArrowIpc,
+}
+
+impl ColumnarFormat {
+    /// File type shown in the save dialog and its extension
+    pub fn file_type(&self) -> (&'static str, &'static str) {
+        match self {
+            ColumnarFormat::Parquet => ("Parquet", "parquet"),
+            ColumnarFormat::ArrowIpc => ("Arrow IPC", "arrow"),
+        }
+    }
+}
+
+/// Writes record batches to a file in a columnar format
+pub enum ColumnarWriter {
+    Parquet(AsyncArrowWriter<tokio::fs::File>),
+    ArrowIpc(FileWriter<BufWriter<std::fs::File>>),
+}
+
+impl ColumnarWriter {
+    pub async fn create(path: &Path, format: ColumnarFormat, schema: SchemaRef) -> DbResult<Self> {
+        Ok(match format {
+            ColumnarFormat::Parquet => {
+                let file = tokio::fs::File::create(path).await?;
+                let properties = WriterProperties::builder()
+                    .set_compression(Compression::ZSTD(ZstdLevel::default()))
+                    .build();
+                ColumnarWriter::Parquet(AsyncArrowWriter::try_new(file, schema, Some(properties))?)
+            }
+            ColumnarFormat::ArrowIpc => {
+                let file = std::fs::File::create(path)?;
+                ColumnarWriter::ArrowIpc(FileWriter::try_new(BufWriter::new(file), &schema)?)
+            }
+        })
+    }
+
+    pub async fn write(&mut self, batch: &RecordBatch) -> DbResult<()> {
+        match self {
+            ColumnarWriter::Parquet(writer) => writer.write(batch).await?,
+            ColumnarWriter::ArrowIpc(writer) => writer.write(batch)?,
+        }
+        Ok(())
+    }
+
+    /// Writes the footer; the file is incomplete until this is called
+    pub async fn finish(self) -> DbResult<()> {
+        match self {
+            ColumnarWriter::Parquet(writer) => {
+                writer.close().await?;
+            }
+            ColumnarWriter::ArrowIpc(writer) => {
+                writer.into_inner()?.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
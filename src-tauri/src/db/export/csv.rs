@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use crate::db::errors::{DbError, DbResult};
+use crate::db::export::{cell_value, ResultExporter, TextEncoding};
+use crate::db::types::{ColumnDefinition, Row};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum QuoteStyle {
+    /// Quote only values containing the delimiter, the quote, or a line break
+    #[default]
+    Minimal,
+    /// Quote every non-NULL value
+    Always,
+}
+
+#[taurpc::ipc_type]
+#[serde(rename_all = "camelCase")]
+#[derive(Debug)]
+pub struct CsvOptions {
+    /// `,` for CSV or a tab for TSV
+    pub delimiter: char,
+    pub quote: char,
+    pub quote_style: QuoteStyle,
+    /// Written unquoted for NULL values
+    pub null_value: String,
+    pub header: bool,
+    pub encoding: TextEncoding,
+}
+
+impl CsvOptions {
+    /// Checks the options describe an unambiguous format
+    pub fn validate(&self) -> DbResult<()> {
+        for (name, c) in [("Delimiter", self.delimiter), ("Quote", self.quote)] {
+            if !c.is_ascii() || c == '\r' || c == '\n' {
+                return Err(DbError::Config(format!(
+                    "{} must be a single ASCII character other than a line break",
+                    name
+                )));
+            }
+        }
+        if self.delimiter == self.quote {
+            return Err(DbError::Config(
+                "Delimiter and quote must be different characters".to_string(),
+            ));
+        }
+        if self
+            .null_value
+            .contains(['\r', '\n', self.delimiter, self.quote])
+        {
+            return Err(DbError::Config(
+                "NULL value can't contain the delimiter, the quote, or a line break".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn quoted(&self, value: &str) -> String {
+        let needs_quotes = match self.quote_style {
+            QuoteStyle::Always => true,
+            QuoteStyle::Minimal => {
+                value.contains(['\r', '\n', self.delimiter, self.quote])
+                    // Keeps a value that looks like NULL from reading back as NULL
+                    || value == self.null_value
+            }
+        };
+
+        if needs_quotes {
+            let quote = self.quote.to_string();
+            format!(
+                "{}{}{}",
+                quote,
+                value.replace(&quote, &quote.repeat(2)),
+                quote
+            )
+        } else {
+            value.to_string()
+        }
+    }
+
+    /// One record, including its line break
+    pub fn record<'a>(&self, fields: impl IntoIterator<Item = Option<&'a str>>) -> String {
+        let mut line = fields
+            .into_iter()
+            .map(|field| match field {
+                Some(value) => self.quoted(value),
+                None => self.null_value.clone(),
+            })
+            .collect::<Vec<_>>()
+            .join(&self.delimiter.to_string());
+        line.push('\n');
+        line
+    }
+}
+
+impl ResultExporter for CsvOptions {
+    fn begin(&mut self, columns: &[ColumnDefinition]) -> String {
+        if self.header {
+            self.record(columns.iter().map(|c| Some(c.name.as_str())))
+        } else {
+            String::new()
+        }
+    }
+
+    fn row(&mut self, columns: &[ColumnDefinition], row: &Row) -> String {
+        let values = columns
+            .iter()
+            .map(|column| match cell_value(row, &column.name) {
+                JsonValue::Null => None,
+                JsonValue::String(s) => Some(s),
+                value => Some(value.to_string()),
+            })
+            .collect::<Vec<_>>();
+        self.record(values.iter().map(Option::as_deref))
+    }
+}
+
+/// Counts records in streamed CSV, where line breaks inside quotes don't end a record
+#[derive(Debug)]
+pub struct RecordCounter {
+    quote: u8,
+    in_quotes: bool,
+    pub records: u64,
+}
+
+impl RecordCounter {
+    pub fn new(quote: char) -> Self {
+        Self {
+            quote: quote as u8,
+            in_quotes: false,
+            records: 0,
+        }
+    }
+
+    pub fn feed(&mut self, chunk: &[u8]) {
+        for &b in chunk {
+            // A doubled quote inside quotes toggles twice and changes nothing
+            if b == self.quote {
+                self.in_quotes = !self.in_quotes;
+            } else if b == b'\n' && !self.in_quotes {
+                self.records += 1;
+            }
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use crate::db::binary::encode_hex;
+use crate::db::export::{cell_bytes, cell_value, ResultExporter};
+use crate::db::ident::{quote_backtick, quote_double, quote_literal_with};
+use crate::db::types::{ColumnDefinition, Row};
+
+/// Database the statements are written for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum InsertDialect {
+    #[default]
+    Postgres,
+    Mysql,
+    Sqlite,
+}
+
+impl InsertDialect {
+    fn quote_ident(self, ident: &str) -> String {
+        match self {
+            InsertDialect::Postgres | InsertDialect::Sqlite => quote_double(ident),
+            InsertDialect::Mysql => quote_backtick(ident),
+        }
+    }
+
+    fn quote_literal(self, value: &str) -> String {
+        quote_literal_with(value, self == InsertDialect::Mysql)
+    }
+
+    fn binary_literal(self, bytes: &[u8]) -> String {
+        match self {
+            InsertDialect::Postgres => format!("'\\x{}'::bytea", encode_hex(bytes)),
+            InsertDialect::Mysql | InsertDialect::Sqlite => format!("X'{}'", encode_hex(bytes)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct InsertOptions {
+    /// Table the statements insert into, written as given so it can be schema-qualified
+    pub table_name: String,
+    /// Rows per statement; 1 writes one statement per row
+    pub batch_size: u32,
+    #[serde(default)]
+    pub dialect: InsertDialect,
+}
+
+/// SQL literal for a cell; JSON objects and arrays become JSON text
+fn literal(dialect: InsertDialect, row: &Row, column: &str) -> String {
+    if let Some(bytes) = cell_bytes(row, column) {
+        return dialect.binary_literal(&bytes);
+    }
+
+    match cell_value(row, column) {
+        JsonValue::Null => "NULL".to_string(),
+        JsonValue::Bool(true) => "TRUE".to_string(),
+        JsonValue::Bool(false) => "FALSE".to_string(),
+        JsonValue::Number(n) => n.to_string(),
+        JsonValue::String(s) => dialect.quote_literal(&s),
+        value => dialect.quote_literal(&value.to_string()),
+    }
+}
+
+/// Writes `INSERT INTO ... VALUES` statements, batching rows per statement
+#[derive(Debug)]
+pub struct InsertExporter {
+    options: InsertOptions,
+    /// Rows written in the statement still open
+    in_batch: u32,
+}
+
+impl InsertExporter {
+    pub fn new(options: InsertOptions) -> Self {
+        Self {
+            options,
+            in_batch: 0,
+        }
+    }
+}
+
+impl ResultExporter for InsertExporter {
+    fn row(&mut self, columns: &[ColumnDefinition], row: &Row) -> String {
+        let values = columns
+            .iter()
+            .map(|column| literal(self.options.dialect, row, &column.name))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut text = if self.in_batch == 0 {
+            let names = columns
+                .iter()
+                .map(|column| self.options.dialect.quote_ident(&column.name))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!(
+                "INSERT INTO {} ({}) VALUES\n  ({})",
+                self.options.table_name, names, values
+            )
+        } else {
+            format!(",\n  ({})", values)
+        };
+
+        self.in_batch += 1;
+        if self.in_batch >= self.options.batch_size.max(1) {
+            self.in_batch = 0;
+            text.push_str(";\n");
+        }
+        text
+    }
+
+    fn end(&mut self) -> String {
+        if self.in_batch > 0 {
+            self.in_batch = 0;
+            ";\n".to_string()
+        } else {
+            String::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns() -> Vec<ColumnDefinition> {
+        ["id", "note", "data"]
+            .into_iter()
+            .map(|name| ColumnDefinition {
+                name: name.to_string(),
+                data_type: String::new(),
+                nullable: true,
+                primary_key: false,
+                default_value: None,
+            })
+            .collect()
+    }
+
+    fn export(dialect: InsertDialect) -> String {
+        let row = Row {
+            values: [
+                ("id", "1"),
+                ("note", r#""it's \\ here""#),
+                (
+                    "data",
+                    r#"{"$type":"binary","encoding":"hex","preview":"00ff","size":2,"truncated":false}"#,
+                ),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+        };
+        let mut exporter = InsertExporter::new(InsertOptions {
+            table_name: "t".to_string(),
+            batch_size: 1,
+            dialect,
+        });
+        exporter.row(&columns(), &row)
+    }
+
+    #[test]
+    fn writes_each_dialect() {
+        assert_eq!(
+            export(InsertDialect::Postgres),
+            "INSERT INTO t (\"id\", \"note\", \"data\") VALUES\n  (1, 'it''s \\ here', '\\x00ff'::bytea);\n"
+        );
+        assert_eq!(
+            export(InsertDialect::Mysql),
+            "INSERT INTO t (`id`, `note`, `data`) VALUES\n  (1, 'it''s \\\\ here', X'00ff');\n"
+        );
+        assert_eq!(
+            export(InsertDialect::Sqlite),
+            "INSERT INTO t (\"id\", \"note\", \"data\") VALUES\n  (1, 'it''s \\ here', X'00ff');\n"
+        );
+    }
+}
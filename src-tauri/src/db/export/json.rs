@@ -0,0 +1,110 @@
+use serde_json::Value as JsonValue;
+
+use crate::db::export::{cell_value, ResultExporter};
+use crate::db::types::{ColumnDefinition, Row};
+
+/// A row as a JSON object with keys in column order
+pub(crate) fn row_object(columns: &[ColumnDefinition], row: &Row) -> String {
+    let fields = columns
+        .iter()
+        .map(|column| {
+            format!(
+                "{}:{}",
+                JsonValue::String(column.name.clone()),
+                cell_value(row, &column.name)
+            )
+        })
+        .collect::<Vec<_>>();
+    format!("{{{}}}", fields.join(","))
+}
+
+/// Writes a JSON array with one object per row
+#[derive(Debug, Default)]
+pub struct JsonExporter {
+    rows: u64,
+}
+
+impl ResultExporter for JsonExporter {
+    fn begin(&mut self, _columns: &[ColumnDefinition]) -> String {
+        "[".to_string()
+    }
+
+    fn row(&mut self, columns: &[ColumnDefinition], row: &Row) -> String {
+        let separator = if self.rows == 0 { "\n  " } else { ",\n  " };
+        self.rows += 1;
+        format!("{}{}", separator, row_object(columns, row))
+    }
+
+    fn end(&mut self) -> String {
+        if self.rows == 0 {
+            "]\n".to_string()
+        } else {
+            "\n]\n".to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns() -> Vec<ColumnDefinition> {
+        ["id", "note", "data", "extra"]
+            .into_iter()
+            .map(|name| ColumnDefinition {
+                name: name.to_string(),
+                data_type: String::new(),
+                nullable: true,
+                primary_key: false,
+                default_value: None,
+            })
+            .collect()
+    }
+
+    fn row(id: &str, note: &str) -> Row {
+        Row {
+            values: [
+                ("id", id),
+                ("note", note),
+                (
+                    "data",
+                    r#"{"$type":"binary","encoding":"base64","preview":"AP8=","size":2,"truncated":false}"#,
+                ),
+                ("extra", "NULL"),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+        }
+    }
+
+    #[test]
+    fn writes_typed_values_nulls_and_binary_as_hex() {
+        assert_eq!(
+            row_object(&columns(), &row("1", r#""say \"hi\"\n""#)),
+            r#"{"id":1,"note":"say \"hi\"\n","data":"\\x00ff","extra":null}"#
+        );
+    }
+
+    #[test]
+    fn writes_an_array_of_rows() {
+        let columns = &columns()[..2];
+        let mut exporter = JsonExporter::default();
+        let mut out = exporter.begin(columns);
+        out.push_str(&exporter.row(columns, &row("1", r#""a""#)));
+        out.push_str(&exporter.row(columns, &row("2", "NULL")));
+        out.push_str(&exporter.end());
+        assert_eq!(
+            out,
+            "[\n  {\"id\":1,\"note\":\"a\"},\n  {\"id\":2,\"note\":null}\n]\n"
+        );
+    }
+
+    #[test]
+    fn writes_an_empty_array_without_rows() {
+        let mut exporter = JsonExporter::default();
+        let mut out = exporter.begin(&columns());
+        out.push_str(&exporter.end());
+        assert_eq!(out, "[]\n");
+    }
+}
@@ -0,0 +1,102 @@
+use serde_json::Value as JsonValue;
+
+use crate::db::export::{cell_value, ResultExporter};
+use crate::db::types::{ColumnDefinition, Row};
+
+/// Escapes text so it stays inside one table cell
+fn cell(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace("\r\n", "<br>")
+        .replace(['\r', '\n'], "<br>")
+}
+
+fn line(cells: impl IntoIterator<Item = String>) -> String {
+    format!(
+        "| {} |\n",
+        cells.into_iter().collect::<Vec<_>>().join(" | ")
+    )
+}
+
+/// Writes a GitHub-flavored Markdown table
+#[derive(Debug)]
+pub struct MarkdownExporter;
+
+impl ResultExporter for MarkdownExporter {
+    fn begin(&mut self, columns: &[ColumnDefinition]) -> String {
+        let names = line(columns.iter().map(|c| cell(&c.name)));
+        let rule = line(columns.iter().map(|_| "---".to_string()));
+        format!("{}{}", names, rule)
+    }
+
+    fn row(&mut self, columns: &[ColumnDefinition], row: &Row) -> String {
+        line(
+            columns
+                .iter()
+                .map(|column| match cell_value(row, &column.name) {
+                    JsonValue::Null => "_NULL_".to_string(),
+                    JsonValue::String(s) => cell(&s),
+                    value => cell(&value.to_string()),
+                }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns() -> Vec<ColumnDefinition> {
+        ["id", "a|b", "data", "doc"]
+            .into_iter()
+            .map(|name| ColumnDefinition {
+                name: name.to_string(),
+                data_type: String::new(),
+                nullable: true,
+                primary_key: false,
+                default_value: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn escapes_names_and_cells() {
+        let row = Row {
+            values: [
+                ("id", "1"),
+                ("a|b", r#""pipe | and \\ on\r\ntwo lines\n""#),
+                (
+                    "data",
+                    r#"{"$type":"binary","encoding":"hex","preview":"00ff","size":2,"truncated":false}"#,
+                ),
+                ("doc", r#"{"k":[1,"|"]}"#),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+        };
+
+        let mut exporter = MarkdownExporter;
+        let out = exporter.begin(&columns()) + &exporter.row(&columns(), &row);
+        assert_eq!(
+            out,
+            concat!(
+                "| id | a\\|b | data | doc |\n",
+                "| --- | --- | --- | --- |\n",
+                "| 1 | pipe \\| and \\\\ on<br>two lines<br> | \\\\x00ff | {\"k\":[1,\"\\|\"]} |\n",
+            )
+        );
+    }
+
+    #[test]
+    fn marks_nulls() {
+        let row = Row {
+            values: [("id", "NULL")]
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        };
+        let columns = &columns()[..1];
+        assert_eq!(MarkdownExporter.row(columns, &row), "| _NULL_ |\n");
+    }
+}
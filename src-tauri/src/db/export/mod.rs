@@ -0,0 +1,288 @@
+pub mod columnar;
+pub mod csv;
+pub mod insert;
+pub mod json;
+pub mod markdown;
+pub mod ndjson;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::io;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::db::binary;
+use crate::db::errors::{DbError, DbResult};
+use crate::db::types::{ColumnDefinition, QueryResult, Row};
+
+/// What to export: the full result of a query, or every row of a table
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ExportSource {
+    Query { sql: String },
+    Table { table_id: String },
+}
+
+/// Character encoding of exported text
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum TextEncoding {
+    #[default]
+    Utf8,
+    /// UTF-8 with a byte order mark, which Excel needs to detect UTF-8
+    Utf8Bom,
+    /// UTF-16 little endian with a byte order mark
+    Utf16Le,
+    /// ISO-8859-1; fails on characters it can't represent
+    Latin1,
+}
+
+/// Formats a query result can be exported in besides CSV
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ResultFormat {
+    /// A JSON array of objects
+    Json,
+    /// One JSON object per line
+    Ndjson,
+    Markdown,
+    Insert(insert::InsertOptions),
+}
+
+impl ResultFormat {
+    pub fn exporter(&self) -> Box<dyn ResultExporter + Send> {
+        match self {
+            ResultFormat::Json => Box::new(json::JsonExporter::default()),
+            ResultFormat::Ndjson => Box::new(ndjson::NdjsonExporter),
+            ResultFormat::Markdown => Box::new(markdown::MarkdownExporter),
+            ResultFormat::Insert(options) => Box::new(insert::InsertExporter::new(options.clone())),
+        }
+    }
+
+    /// File type shown in the save dialog and its extension
+    pub fn file_type(&self) -> (&'static str, &'static str) {
+        match self {
+            ResultFormat::Json => ("JSON", "json"),
+            ResultFormat::Ndjson => ("NDJSON", "ndjson"),
+            ResultFormat::Markdown => ("Markdown", "md"),
+            ResultFormat::Insert(_) => ("SQL", "sql"),
+        }
+    }
+}
+
+/// Where an export is written
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum ExportTarget {
+    /// A file chosen in a save dialog
+    File,
+    Clipboard,
+}
+
+#[taurpc::ipc_type]
+#[serde(rename_all = "camelCase")]
+#[derive(Debug)]
+pub struct ExportSummary {
+    /// Written file, or `None` when copied to the clipboard
+    pub path: Option<String>,
+    /// Number of data rows written, not counting a header
+    pub rows: u64,
+    /// Size of the written text in bytes
+    pub bytes: u64,
+}
+
+/// Renders a query result as text in one format, a row at a time
+pub trait ResultExporter {
+    /// Text before the first row
+    fn begin(&mut self, _columns: &[ColumnDefinition]) -> String {
+        String::new()
+    }
+
+    fn row(&mut self, columns: &[ColumnDefinition], row: &Row) -> String;
+
+    /// Text after the last row
+    fn end(&mut self) -> String {
+        String::new()
+    }
+}
+
+/// Binary cells are displayed as a preview object (see `BinaryPreview::to_json`)
+fn binary_preview(value: &JsonValue) -> Option<&serde_json::Map<String, JsonValue>> {
+    value
+        .as_object()
+        .filter(|map| map.get("$type").and_then(JsonValue::as_str) == Some("binary"))
+}
+
+fn parse_cell(row: &Row, column: &str) -> JsonValue {
+    row.values
+        .get(column)
+        .and_then(|v| serde_json::from_str(v).ok())
+        .unwrap_or(JsonValue::Null)
+}
+
+fn preview_bytes(preview: &serde_json::Map<String, JsonValue>) -> Option<Vec<u8>> {
+    let encoded = preview.get("preview").and_then(JsonValue::as_str)?;
+    match preview.get("encoding").and_then(JsonValue::as_str) {
+        Some("base64") => binary::decode_base64(encoded),
+        _ => binary::decode_hex(encoded),
+    }
+}
+
+/// Bytes of a binary cell; `check_complete` makes sure they're the whole value
+pub fn cell_bytes(row: &Row, column: &str) -> Option<Vec<u8>> {
+    binary_preview(&parse_cell(row, column)).and_then(preview_bytes)
+}
+
+/// Typed value of a displayed cell (see `Row::values`); NULL becomes `Null`.
+/// Binary cells become hex text as `\x...`, like Postgres's bytea output.
+pub fn cell_value(row: &Row, column: &str) -> JsonValue {
+    let value = parse_cell(row, column);
+    match binary_preview(&value).and_then(preview_bytes) {
+        Some(bytes) => JsonValue::from(format!("\\x{}", binary::encode_hex(&bytes))),
+        None => value,
+    }
+}
+
+/// Fails if a binary cell was read as a truncated preview, since exporting it would
+/// silently cut the value short
+pub fn check_complete(result: &QueryResult) -> DbResult<()> {
+    for row in &result.rows {
+        for (column, display) in &row.values {
+            let Ok(value) = serde_json::from_str::<JsonValue>(display) else {
+                continue;
+            };
+            let truncated = binary_preview(&value)
+                .and_then(|preview| preview.get("truncated"))
+                .and_then(JsonValue::as_bool)
+                .unwrap_or(false);
+            if truncated {
+                return Err(DbError::Unsupported(format!(
+                    "{} has binary values too large to export in this format",
+                    column
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Writes a result to `out`, returning the number of rows
+pub async fn write_result(
+    exporter: &mut (dyn ResultExporter + Send),
+    result: &QueryResult,
+    out: &mut ExportWriter,
+) -> io::Result<u64> {
+    out.write_str(&exporter.begin(&result.columns)).await?;
+    for row in &result.rows {
+        out.write_str(&exporter.row(&result.columns, row)).await?;
+    }
+    out.write_str(&exporter.end()).await?;
+    Ok(result.rows.len() as u64)
+}
+
+/// Renders a result into a single string, e.g. for the clipboard
+pub fn render_result(exporter: &mut dyn ResultExporter, result: &QueryResult) -> String {
+    let mut text = exporter.begin(&result.columns);
+    for row in &result.rows {
+        text.push_str(&exporter.row(&result.columns, row));
+    }
+    text.push_str(&exporter.end());
+    text
+}
+
+/// Writes UTF-8 text to a sink in the chosen encoding.
+/// Text can arrive as `&str` or as raw UTF-8 chunks that may split a character.
+pub struct ExportWriter {
+    inner: Box<dyn AsyncWrite + Send + Unpin>,
+    encoding: TextEncoding,
+    /// Tail of the last chunk when it ended partway through a character
+    pending: Vec<u8>,
+    started: bool,
+    bytes: u64,
+}
+
+impl ExportWriter {
+    pub fn new(inner: impl AsyncWrite + Send + Unpin + 'static, encoding: TextEncoding) -> Self {
+        Self {
+            inner: Box::new(tokio::io::BufWriter::new(inner)),
+            encoding,
+            pending: Vec::new(),
+            started: false,
+            bytes: 0,
+        }
+    }
+
+    async fn write_raw(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.inner.write_all(bytes).await?;
+        self.bytes += bytes.len() as u64;
+        Ok(())
+    }
+
+    pub async fn write_str(&mut self, text: &str) -> io::Result<()> {
+        if !self.started {
+            self.started = true;
+            match self.encoding {
+                TextEncoding::Utf8Bom => self.write_raw(&[0xEF, 0xBB, 0xBF]).await?,
+                TextEncoding::Utf16Le => self.write_raw(&[0xFF, 0xFE]).await?,
+                TextEncoding::Utf8 | TextEncoding::Latin1 => {}
+            }
+        }
+
+        match self.encoding {
+            TextEncoding::Utf8 | TextEncoding::Utf8Bom => self.write_raw(text.as_bytes()).await,
+            TextEncoding::Utf16Le => {
+                let bytes = text
+                    .encode_utf16()
+                    .flat_map(u16::to_le_bytes)
+                    .collect::<Vec<_>>();
+                self.write_raw(&bytes).await
+            }
+            TextEncoding::Latin1 => {
+                let bytes = text
+                    .chars()
+                    .map(|c| {
+                        u8::try_from(c).map_err(|_| {
+                            io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                format!("'{}' can't be written as Latin-1", c),
+                            )
+                        })
+                    })
+                    .collect::<io::Result<Vec<_>>>()?;
+                self.write_raw(&bytes).await
+            }
+        }
+    }
+
+    /// Writes a chunk of UTF-8, holding back a character split across chunks
+    pub async fn write_utf8(&mut self, chunk: &[u8]) -> io::Result<()> {
+        self.pending.extend_from_slice(chunk);
+        let valid = match std::str::from_utf8(&self.pending) {
+            Ok(_) => self.pending.len(),
+            // Incomplete character at the end
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+        };
+
+        let rest = self.pending.split_off(valid);
+        let text = std::mem::replace(&mut self.pending, rest);
+        // Checked above
+        let text =
+            String::from_utf8(text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.write_str(&text).await
+    }
+
+    /// Flushes everything written and returns the total size in bytes
+    pub async fn finish(mut self) -> io::Result<u64> {
+        if !self.pending.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Export ended partway through a character",
+            ));
+        }
+        // Empty exports still get their byte order mark
+        self.write_str("").await?;
+        self.inner.flush().await?;
+        self.inner.shutdown().await?;
+        Ok(self.bytes)
+    }
+}
@@ -0,0 +1,55 @@
+use crate::db::export::json::row_object;
+use crate::db::export::ResultExporter;
+use crate::db::types::{ColumnDefinition, Row};
+
+/// Writes one JSON object per line
+#[derive(Debug)]
+pub struct NdjsonExporter;
+
+impl ResultExporter for NdjsonExporter {
+    fn row(&mut self, columns: &[ColumnDefinition], row: &Row) -> String {
+        format!("{}\n", row_object(columns, row))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_one_object_per_line() {
+        let columns = ["id", "data"]
+            .into_iter()
+            .map(|name| ColumnDefinition {
+                name: name.to_string(),
+                data_type: String::new(),
+                nullable: true,
+                primary_key: false,
+                default_value: None,
+            })
+            .collect::<Vec<_>>();
+        let row = |id: &str, data: &str| Row {
+            values: [("id", id), ("data", data)]
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        };
+
+        let mut exporter = NdjsonExporter;
+        let out = [
+            exporter.row(&columns, &row(r#""line\nbreak""#, "NULL")),
+            exporter.row(
+                &columns,
+                &row(
+                    "2",
+                    r#"{"$type":"binary","encoding":"hex","preview":"00ff","size":2,"truncated":false}"#,
+                ),
+            ),
+        ]
+        .concat();
+        assert_eq!(
+            out,
+            "{\"id\":\"line\\nbreak\",\"data\":null}\n{\"id\":2,\"data\":\"\\\\x00ff\"}\n"
+        );
+    }
+}
@@ -0,0 +1,69 @@
+use crate::db::errors::{DbError, DbResult};
+
+/// Quotes an identifier with `quote`, doubling any embedded quote characters
+pub fn quote_with(ident: &str, quote: char) -> String {
+    let escaped = ident.replace(quote, &format!("{}{}", quote, quote));
+    format!("{}{}{}", quote, escaped, quote)
+}
+
+/// Quotes an identifier in double quotes, as standard SQL does: Postgres, SQLite and DuckDB
+pub fn quote_double(ident: &str) -> String {
+    quote_with(ident, '"')
+}
+
+/// Quotes an identifier in backticks, as MySQL and ClickHouse do
+pub fn quote_backtick(ident: &str) -> String {
+    quote_with(ident, '`')
+}
+
+/// Quotes a string literal, doubling embedded quotes; where backslashes are escape
+/// characters (MySQL by default) they're doubled too
+pub fn quote_literal_with(value: &str, backslash_escapes: bool) -> String {
+    let mut escaped = value.replace('\'', "''");
+    if backslash_escapes {
+        escaped = escaped.replace('\\', "\\\\");
+    }
+    format!("'{}'", escaped)
+}
+
+/// Splits a dotted list of quoted identifiers, e.g. `"main"."users"`, into its parts
+pub fn split_quoted(id: &str, quote: char) -> DbResult<Vec<String>> {
+    let invalid = || DbError::NotFound(format!("Relation {}", id));
+    let mut parts = Vec::new();
+    let mut chars = id.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != quote {
+            return Err(invalid());
+        }
+
+        let mut part = String::new();
+        loop {
+            match chars.next() {
+                Some(c) if c == quote && chars.peek() == Some(&quote) => {
+                    chars.next();
+                    part.push(quote);
+                }
+                Some(c) if c == quote => break,
+                Some(c) => part.push(c),
+                None => return Err(invalid()),
+            }
+        }
+        parts.push(part);
+
+        match chars.next() {
+            Some('.') | None => {}
+            Some(_) => return Err(invalid()),
+        }
+    }
+
+    Ok(parts)
+}
+
+/// Splits a `schema.table` ID built from quoted identifiers
+pub fn split_table_id(id: &str, quote: char) -> DbResult<(String, String)> {
+    match <[String; 2]>::try_from(split_quoted(id, quote)?) {
+        Ok([schema, name]) => Ok((schema, name)),
+        Err(_) => Err(DbError::NotFound(format!("Relation {}", id))),
+    }
+}
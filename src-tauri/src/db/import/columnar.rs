@@ -0,0 +1,255 @@
+use std::collections::VecDeque;
+use std::path::Path;
+
+use arrow::datatypes::DataType;
+use arrow::json::LineDelimitedWriter;
+use arrow::record_batch::RecordBatch;
+use futures_util::StreamExt;
+use parquet::arrow::async_reader::ParquetRecordBatchStream;
+use parquet::arrow::ParquetRecordBatchStreamBuilder;
+use serde_json::Value as JsonValue;
+
+use crate::db::errors::DbResult;
+use crate::db::import::infer::InferredType;
+use crate::db::import::{ImportColumn, ImportFormat, ImportPreview, PREVIEW_ROWS};
+
+/// Postgres type for a new column holding an Arrow type.
+/// Nested lists and structs become jsonb, as they'd need composite types otherwise.
+pub fn pg_type(data_type: &DataType) -> String {
+    match data_type {
+        DataType::Boolean => "boolean".to_string(),
+        DataType::Int8 | DataType::Int16 | DataType::UInt8 => "smallint".to_string(),
+        DataType::Int32 | DataType::UInt16 => "integer".to_string(),
+        DataType::Int64 | DataType::UInt32 => "bigint".to_string(),
+        DataType::UInt64 => "numeric(20, 0)".to_string(),
+        DataType::Float16 | DataType::Float32 => "real".to_string(),
+        DataType::Float64 => "double precision".to_string(),
+        DataType::Decimal128(precision, scale) | DataType::Decimal256(precision, scale) => {
+            format!("numeric({}, {})", precision, scale)
+        }
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => "text".to_string(),
+        DataType::Binary
+        | DataType::LargeBinary
+        | DataType::BinaryView
+        | DataType::FixedSizeBinary(_) => "bytea".to_string(),
+        DataType::Date32 | DataType::Date64 => "date".to_string(),
+        DataType::Time32(_) | DataType::Time64(_) => "time".to_string(),
+        DataType::Timestamp(_, None) => "timestamp".to_string(),
+        DataType::Timestamp(_, Some(_)) => "timestamp with time zone".to_string(),
+        DataType::Duration(_) | DataType::Interval(_) => "interval".to_string(),
+        DataType::List(field) | DataType::LargeList(field) | DataType::FixedSizeList(field, _) => {
+            match field.data_type() {
+                DataType::List(_)
+                | DataType::LargeList(_)
+                | DataType::FixedSizeList(..)
+                | DataType::Struct(_)
+                | DataType::Map(..) => "jsonb".to_string(),
+                element => format!("{}[]", pg_type(element)),
+            }
+        }
+        DataType::Struct(_) | DataType::Map(..) => "jsonb".to_string(),
+        DataType::Dictionary(_, value) => pg_type(value),
+        _ => "text".to_string(),
+    }
+}
+
+fn inferred_type(data_type: &DataType) -> InferredType {
+    match data_type {
+        DataType::Boolean => InferredType::Boolean,
+        DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::UInt8 | DataType::UInt16 => {
+            InferredType::Integer
+        }
+        DataType::Int64 | DataType::UInt32 | DataType::UInt64 => InferredType::BigInt,
+        DataType::Float16 | DataType::Float32 | DataType::Float64 => InferredType::Double,
+        DataType::Date32 | DataType::Date64 => InferredType::Date,
+        DataType::Timestamp(_, None) => InferredType::Timestamp,
+        DataType::Timestamp(_, Some(_)) => InferredType::TimestampTz,
+        DataType::List(_)
+        | DataType::LargeList(_)
+        | DataType::FixedSizeList(..)
+        | DataType::Struct(_)
+        | DataType::Map(..) => InferredType::Json,
+        DataType::Dictionary(_, value) => inferred_type(value),
+        _ => InferredType::Text,
+    }
+}
+
+/// Rows of a batch as JSON object text, keyed by column name; NULLs are left out
+fn json_rows(batch: &RecordBatch) -> DbResult<Vec<String>> {
+    let mut writer = LineDelimitedWriter::new(Vec::new());
+    writer.write(batch)?;
+    writer.finish()?;
+    let text = String::from_utf8_lossy(&writer.into_inner()).into_owned();
+    Ok(text.lines().map(str::to_string).collect())
+}
+
+/// Reads a Parquet file a row at a time, as JSON objects
+pub struct ParquetRows {
+    stream: ParquetRecordBatchStream<tokio::fs::File>,
+    pending: VecDeque<String>,
+    columns: Vec<(String, DataType)>,
+    row: u64,
+    pub total_rows: u64,
+}
+
+impl ParquetRows {
+    pub async fn open(path: &Path) -> DbResult<Self> {
+        let file = tokio::fs::File::open(path).await?;
+        let builder = ParquetRecordBatchStreamBuilder::new(file).await?;
+        let columns = builder
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| (field.name().clone(), field.data_type().clone()))
+            .collect();
+        let total_rows = builder.metadata().file_metadata().num_rows().max(0) as u64;
+
+        Ok(Self {
+            stream: builder.build()?,
+            pending: VecDeque::new(),
+            columns,
+            row: 0,
+            total_rows,
+        })
+    }
+
+    pub fn columns(&self) -> &[(String, DataType)] {
+        &self.columns
+    }
+
+    pub fn rows_read(&self) -> u64 {
+        self.row
+    }
+
+    /// The next row's number, counting from 1, and its JSON object text
+    pub async fn next_row(&mut self) -> DbResult<Option<(u64, String)>> {
+        while self.pending.is_empty() {
+            match self.stream.next().await {
+                Some(batch) => self.pending.extend(json_rows(&batch?)?),
+                None => return Ok(None),
+            }
+        }
+
+        self.row += 1;
+        Ok(self.pending.pop_front().map(|row| (self.row, row)))
+    }
+}
+
+pub async fn preview(path: &Path, format: &ImportFormat) -> DbResult<ImportPreview> {
+    let size = tokio::fs::metadata(path).await?.len();
+    let mut rows = ParquetRows::open(path).await?;
+
+    let columns = rows
+        .columns()
+        .iter()
+        .map(|(name, data_type)| ImportColumn {
+            name: name.clone(),
+            inferred_type: inferred_type(data_type),
+            data_type: pg_type(data_type),
+        })
+        .collect::<Vec<_>>();
+
+    let mut sample = Vec::new();
+    while sample.len() < PREVIEW_ROWS {
+        let Some((_, row)) = rows.next_row().await? else {
+            break;
+        };
+        let object = serde_json::from_str::<JsonValue>(&row).unwrap_or_default();
+        sample.push(
+            columns
+                .iter()
+                .map(|column| match object.get(&column.name) {
+                    None | Some(JsonValue::Null) => None,
+                    Some(JsonValue::String(s)) => Some(s.clone()),
+                    Some(value) => Some(value.to_string()),
+                })
+                .collect(),
+        );
+    }
+
+    Ok(ImportPreview {
+        path: path.to_string_lossy().to_string(),
+        format: format.clone(),
+        columns,
+        rows: sample,
+        size,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::datatypes::{Field, Fields, TimeUnit};
+    use std::sync::Arc;
+
+    fn list(data_type: DataType) -> DataType {
+        DataType::List(Arc::new(Field::new("item", data_type, true)))
+    }
+
+    #[test]
+    fn maps_arrow_types_to_postgres() {
+        assert_eq!(pg_type(&DataType::UInt8), "smallint");
+        assert_eq!(pg_type(&DataType::UInt32), "bigint");
+        assert_eq!(pg_type(&DataType::UInt64), "numeric(20, 0)");
+        assert_eq!(pg_type(&DataType::Decimal128(12, 3)), "numeric(12, 3)");
+        assert_eq!(pg_type(&DataType::FixedSizeBinary(16)), "bytea");
+        assert_eq!(
+            pg_type(&DataType::Timestamp(TimeUnit::Microsecond, None)),
+            "timestamp"
+        );
+        assert_eq!(
+            pg_type(&DataType::Timestamp(
+                TimeUnit::Millisecond,
+                Some("UTC".into())
+            )),
+            "timestamp with time zone"
+        );
+        assert_eq!(
+            pg_type(&DataType::Dictionary(
+                Box::new(DataType::Int32),
+                Box::new(DataType::Utf8)
+            )),
+            "text"
+        );
+        assert_eq!(pg_type(&DataType::Null), "text");
+    }
+
+    #[test]
+    fn nested_types_become_arrays_or_jsonb() {
+        assert_eq!(pg_type(&list(DataType::Int32)), "integer[]");
+        assert_eq!(pg_type(&list(DataType::Binary)), "bytea[]");
+        assert_eq!(pg_type(&list(list(DataType::Int32))), "jsonb");
+
+        let composite =
+            DataType::Struct(Fields::from(vec![Field::new("id", DataType::Int64, true)]));
+        assert_eq!(pg_type(&composite), "jsonb");
+        assert_eq!(pg_type(&list(composite)), "jsonb");
+    }
+
+    #[test]
+    fn infers_preview_types() {
+        assert_eq!(inferred_type(&DataType::UInt16), InferredType::Integer);
+        assert_eq!(inferred_type(&DataType::UInt32), InferredType::BigInt);
+        assert_eq!(inferred_type(&DataType::Float16), InferredType::Double);
+        assert_eq!(inferred_type(&DataType::Date64), InferredType::Date);
+        assert_eq!(
+            inferred_type(&DataType::Timestamp(
+                TimeUnit::Second,
+                Some("+01:00".into())
+            )),
+            InferredType::TimestampTz
+        );
+        assert_eq!(inferred_type(&list(DataType::Utf8)), InferredType::Json);
+        assert_eq!(
+            inferred_type(&DataType::Decimal128(10, 2)),
+            InferredType::Text
+        );
+        assert_eq!(
+            inferred_type(&DataType::Dictionary(
+                Box::new(DataType::Int8),
+                Box::new(DataType::Boolean)
+            )),
+            InferredType::Boolean
+        );
+    }
+}
@@ -0,0 +1,321 @@
+use std::io;
+use std::path::Path;
+
+use tokio::fs::File;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+
+use crate::db::errors::DbResult;
+use crate::db::export::csv::{CsvOptions, QuoteStyle};
+use crate::db::export::TextEncoding;
+use crate::db::import::Record;
+
+/// How a CSV file is laid out
+#[derive(Debug, Clone)]
+pub struct CsvLayout {
+    pub delimiter: char,
+    pub quote: char,
+    pub header: bool,
+    /// Unquoted fields equal to this are NULL
+    pub null_value: String,
+    pub encoding: TextEncoding,
+}
+
+impl CsvLayout {
+    /// Checks the layout is unambiguous, by the same rules as CSV export
+    pub fn validate(&self) -> DbResult<()> {
+        CsvOptions {
+            delimiter: self.delimiter,
+            quote: self.quote,
+            quote_style: QuoteStyle::Minimal,
+            null_value: self.null_value.clone(),
+            header: self.header,
+            encoding: self.encoding,
+        }
+        .validate()
+    }
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+/// Decodes one line of the file, without its line break
+fn decode_line(mut bytes: Vec<u8>, encoding: TextEncoding) -> io::Result<String> {
+    let mut text = match encoding {
+        TextEncoding::Utf8 | TextEncoding::Utf8Bom => String::from_utf8(std::mem::take(&mut bytes))
+            .map_err(|e| invalid_data(e.to_string()))?,
+        TextEncoding::Latin1 => bytes.iter().map(|&b| char::from(b)).collect(),
+        TextEncoding::Utf16Le => {
+            if bytes.len() % 2 != 0 {
+                return Err(invalid_data("File ends partway through a UTF-16 character"));
+            }
+            let units = bytes
+                .chunks_exact(2)
+                .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                .collect::<Vec<_>>();
+            String::from_utf16(&units).map_err(|e| invalid_data(e.to_string()))?
+        }
+    };
+
+    if text.ends_with('\n') {
+        text.pop();
+        if text.ends_with('\r') {
+            text.pop();
+        }
+    }
+    Ok(text)
+}
+
+/// Splits one record into fields, or returns `None` if a quoted field runs onto the next line
+fn parse_fields(text: &str, layout: &CsvLayout) -> Option<Vec<Option<String>>> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut was_quoted = false;
+    let mut in_quotes = false;
+
+    let finish = |field: String, was_quoted: bool| {
+        if !was_quoted && field == layout.null_value {
+            None
+        } else {
+            Some(field)
+        }
+    };
+
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            if c == layout.quote {
+                // A doubled quote is a literal quote
+                if chars.peek() == Some(&layout.quote) {
+                    field.push(c);
+                    chars.next();
+                } else {
+                    in_quotes = false;
+                }
+            } else {
+                field.push(c);
+            }
+        } else if c == layout.quote && field.is_empty() && !was_quoted {
+            in_quotes = true;
+            was_quoted = true;
+        } else if c == layout.delimiter {
+            fields.push(finish(std::mem::take(&mut field), was_quoted));
+            was_quoted = false;
+        } else {
+            field.push(c);
+        }
+    }
+
+    if in_quotes {
+        return None;
+    }
+    fields.push(finish(field, was_quoted));
+    Some(fields)
+}
+
+/// Reads CSV records a line at a time, joining lines inside quoted fields
+pub struct CsvReader {
+    reader: BufReader<File>,
+    layout: CsvLayout,
+    columns: Vec<String>,
+    /// First record when the file has no header, read early to count its fields
+    first: Option<Record>,
+    line: u64,
+    bytes_read: u64,
+}
+
+impl CsvReader {
+    pub async fn open(path: &Path, layout: CsvLayout) -> io::Result<Self> {
+        let file = File::open(path).await?;
+        let mut reader = Self {
+            reader: BufReader::new(file),
+            layout,
+            columns: Vec::new(),
+            first: None,
+            line: 0,
+            bytes_read: 0,
+        };
+
+        let first = reader.read_record().await?;
+        match first {
+            Some(record) if reader.layout.header => {
+                reader.columns = record
+                    .values
+                    .map_err(invalid_data)?
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, name)| name.unwrap_or_else(|| format!("column{}", i + 1)))
+                    .collect();
+            }
+            Some(record) => {
+                let count = record.values.as_ref().map_or(0, Vec::len);
+                reader.columns = (1..=count).map(|i| format!("column{}", i)).collect();
+                reader.first = Some(record);
+            }
+            None => {}
+        }
+
+        Ok(reader)
+    }
+
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    async fn next_line(&mut self) -> io::Result<Option<String>> {
+        let mut bytes = Vec::new();
+        loop {
+            let n = self.reader.read_until(b'\n', &mut bytes).await?;
+            if n == 0 || self.layout.encoding != TextEncoding::Utf16Le {
+                break;
+            }
+            // A UTF-16 line break is `\n` in the low byte of a unit and zero in the high
+            // one; a `\n` byte anywhere else is part of another character
+            if bytes.len() % 2 == 1 {
+                match self.reader.read_u8().await {
+                    Ok(b) => {
+                        bytes.push(b);
+                        if b == 0 {
+                            break;
+                        }
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+
+        if bytes.is_empty() {
+            return Ok(None);
+        }
+        self.bytes_read += bytes.len() as u64;
+
+        let mut line = decode_line(bytes, self.layout.encoding)?;
+        if self.line == 0 {
+            // The byte order mark decodes to U+FEFF in UTF-8 and UTF-16 alike
+            if let Some(rest) = line.strip_prefix('\u{feff}') {
+                line = rest.to_string();
+            }
+        }
+        self.line += 1;
+        Ok(Some(line))
+    }
+
+    async fn read_record(&mut self) -> io::Result<Option<Record>> {
+        let mut text = loop {
+            match self.next_line().await? {
+                Some(line) if line.is_empty() => continue,
+                Some(line) => break line,
+                None => return Ok(None),
+            }
+        };
+
+        let line = self.line;
+        loop {
+            if let Some(fields) = parse_fields(&text, &self.layout) {
+                let values = if !self.columns.is_empty() && fields.len() != self.columns.len() {
+                    Err(format!(
+                        "Expected {} fields, found {}",
+                        self.columns.len(),
+                        fields.len()
+                    ))
+                } else {
+                    Ok(fields)
+                };
+                return Ok(Some(Record { line, values }));
+            }
+
+            match self.next_line().await? {
+                Some(next) => {
+                    text.push('\n');
+                    text.push_str(&next);
+                }
+                None => {
+                    return Ok(Some(Record {
+                        line,
+                        values: Err("Quoted field is never closed".to_string()),
+                    }))
+                }
+            }
+        }
+    }
+
+    pub async fn next_record(&mut self) -> io::Result<Option<Record>> {
+        if let Some(first) = self.first.take() {
+            return Ok(Some(first));
+        }
+        self.read_record().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout() -> CsvLayout {
+        CsvLayout {
+            delimiter: ',',
+            quote: '"',
+            header: true,
+            null_value: String::new(),
+            encoding: TextEncoding::Utf8,
+        }
+    }
+
+    fn fields(text: &str) -> Option<Vec<Option<String>>> {
+        parse_fields(text, &layout())
+    }
+
+    fn expected(fields: &[Option<&str>]) -> Option<Vec<Option<String>>> {
+        Some(fields.iter().map(|f| f.map(str::to_string)).collect())
+    }
+
+    #[test]
+    fn splits_plain_and_quoted_fields() {
+        assert_eq!(
+            fields("a,b,c"),
+            expected(&[Some("a"), Some("b"), Some("c")])
+        );
+        assert_eq!(
+            fields(r#""a,b","say ""hi""",c"#),
+            expected(&[Some("a,b"), Some(r#"say "hi""#), Some("c")])
+        );
+    }
+
+    #[test]
+    fn unquoted_null_value_is_null_but_quoted_is_not() {
+        assert_eq!(fields(r#","",x"#), expected(&[None, Some(""), Some("x")]));
+    }
+
+    #[test]
+    fn open_quote_needs_the_next_line() {
+        assert_eq!(fields(r#"a,"b"#), None);
+        assert_eq!(fields("a,\"b\nc\""), expected(&[Some("a"), Some("b\nc")]));
+    }
+
+    #[test]
+    fn quotes_inside_unquoted_fields_are_literal() {
+        assert_eq!(fields(r#"a"b,c"#), expected(&[Some(r#"a"b"#), Some("c")]));
+    }
+
+    #[test]
+    fn decodes_lines_in_each_encoding() {
+        assert_eq!(
+            decode_line(b"caf\xc3\xa9\r\n".to_vec(), TextEncoding::Utf8).unwrap(),
+            "café"
+        );
+        assert_eq!(
+            decode_line(b"caf\xe9\n".to_vec(), TextEncoding::Latin1).unwrap(),
+            "café"
+        );
+        assert_eq!(
+            decode_line(b"h\0i\0\n\0".to_vec(), TextEncoding::Utf16Le).unwrap(),
+            "hi"
+        );
+        assert!(decode_line(b"\xe9".to_vec(), TextEncoding::Utf8).is_err());
+    }
+}
@@ -0,0 +1,145 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::db::search::is_uuid;
+
+/// Column type guessed from sample values, most specific first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum InferredType {
+    Boolean,
+    Integer,
+    BigInt,
+    Double,
+    Date,
+    Timestamp,
+    TimestampTz,
+    Uuid,
+    Json,
+    Text,
+}
+
+const CANDIDATES: &[InferredType] = &[
+    InferredType::Boolean,
+    InferredType::Integer,
+    InferredType::BigInt,
+    InferredType::Double,
+    InferredType::Date,
+    InferredType::Timestamp,
+    InferredType::TimestampTz,
+    InferredType::Uuid,
+    InferredType::Json,
+];
+
+impl InferredType {
+    /// Postgres type name for a new column
+    pub fn pg_type(self) -> &'static str {
+        match self {
+            InferredType::Boolean => "boolean",
+            InferredType::Integer => "integer",
+            InferredType::BigInt => "bigint",
+            InferredType::Double => "double precision",
+            InferredType::Date => "date",
+            InferredType::Timestamp => "timestamp",
+            InferredType::TimestampTz => "timestamp with time zone",
+            InferredType::Uuid => "uuid",
+            InferredType::Json => "jsonb",
+            InferredType::Text => "text",
+        }
+    }
+
+    fn accepts(self, value: &str) -> bool {
+        let value = value.trim();
+        match self {
+            // 1 and 0 are left to the integer types
+            InferredType::Boolean => matches!(
+                value.to_ascii_lowercase().as_str(),
+                "true" | "false" | "t" | "f"
+            ),
+            InferredType::Integer => value.parse::<i32>().is_ok(),
+            InferredType::BigInt => value.parse::<i64>().is_ok(),
+            InferredType::Double => value.parse::<f64>().is_ok(),
+            InferredType::Date => NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok(),
+            InferredType::Timestamp => {
+                NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f").is_ok()
+                    || NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f").is_ok()
+            }
+            InferredType::TimestampTz => {
+                DateTime::parse_from_rfc3339(value).is_ok()
+                    || DateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f%#z").is_ok()
+            }
+            InferredType::Uuid => is_uuid(value),
+            InferredType::Json => {
+                (value.starts_with('{') || value.starts_with('['))
+                    && serde_json::from_str::<serde_json::Value>(value).is_ok()
+            }
+            InferredType::Text => true,
+        }
+    }
+}
+
+/// The most specific type every non-NULL value fits; text if there are none
+pub fn infer_type<'a>(values: impl IntoIterator<Item = &'a str>) -> InferredType {
+    let mut candidates = CANDIDATES.to_vec();
+    let mut seen = false;
+    for value in values {
+        seen = true;
+        candidates.retain(|candidate| candidate.accepts(value));
+        if candidates.is_empty() {
+            break;
+        }
+    }
+
+    match candidates.first() {
+        Some(candidate) if seen => *candidate,
+        _ => InferredType::Text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_the_most_specific_type() {
+        assert_eq!(infer_type(["true", "F"]), InferredType::Boolean);
+        assert_eq!(infer_type(["1", "0", "-42"]), InferredType::Integer);
+        assert_eq!(infer_type(["1", "3000000000"]), InferredType::BigInt);
+        assert_eq!(infer_type(["1", "2.5", "1e3"]), InferredType::Double);
+        assert_eq!(infer_type(["2024-02-29"]), InferredType::Date);
+        assert_eq!(
+            infer_type(["2024-02-29 12:00:00", "2024-03-01T08:30:00.5"]),
+            InferredType::Timestamp
+        );
+        assert_eq!(
+            infer_type(["2024-02-29T12:00:00Z", "2024-02-29 12:00:00+05:30"]),
+            InferredType::TimestampTz
+        );
+        assert_eq!(
+            infer_type(["550e8400-e29b-41d4-a716-446655440000"]),
+            InferredType::Uuid
+        );
+        assert_eq!(infer_type([r#"{"a": 1}"#, "[1, 2]"]), InferredType::Json);
+    }
+
+    #[test]
+    fn mixed_values_widen_to_text() {
+        assert_eq!(infer_type(["1", "true"]), InferredType::Text);
+        assert_eq!(
+            infer_type(["2024-02-29", "2024-02-29 12:00:00"]),
+            InferredType::Text
+        );
+        // Scalars are valid JSON, but only objects and arrays are read as it
+        assert_eq!(infer_type(["\"a\"", "{}"]), InferredType::Text);
+    }
+
+    #[test]
+    fn values_are_trimmed() {
+        assert_eq!(infer_type([" 7 ", "8"]), InferredType::Integer);
+    }
+
+    #[test]
+    fn no_values_is_text() {
+        assert_eq!(infer_type(std::iter::empty()), InferredType::Text);
+    }
+}
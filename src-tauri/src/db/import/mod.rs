@@ -0,0 +1,322 @@
+pub mod columnar;
+pub mod csv;
+pub mod infer;
+pub mod ndjson;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::db::errors::{DbError, DbResult};
+use crate::db::export::TextEncoding;
+
+use self::csv::{CsvLayout, CsvReader};
+use self::infer::infer_type;
+use self::ndjson::NdjsonReader;
+
+/// Records read to infer column types
+pub const SAMPLE_ROWS: usize = 1000;
+
+/// Records included in a preview
+pub const PREVIEW_ROWS: usize = 20;
+
+/// Row errors kept in a report; later ones are only counted
+pub const MAX_REPORTED_ERRORS: usize = 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ImportFormat {
+    #[serde(rename_all = "camelCase")]
+    Csv {
+        delimiter: char,
+        quote: char,
+        /// The first record names the columns
+        header: bool,
+        null_value: String,
+        /// A byte order mark is skipped whichever encoding is chosen
+        #[serde(default)]
+        encoding: TextEncoding,
+    },
+    /// One JSON object per line
+    Ndjson,
+    Parquet,
+}
+
+impl ImportFormat {
+    /// File type shown in the open dialog and its extensions
+    pub fn file_type(&self) -> (&'static str, &'static [&'static str]) {
+        match self {
+            ImportFormat::Csv { .. } => ("CSV", &["csv", "tsv", "txt"]),
+            ImportFormat::Ndjson => ("NDJSON", &["ndjson", "jsonl", "json"]),
+            ImportFormat::Parquet => ("Parquet", &["parquet"]),
+        }
+    }
+}
+
+/// One record of the file; `values` is an error when the record couldn't be parsed
+#[derive(Debug)]
+pub struct Record {
+    /// Line the record starts on, counting from 1
+    pub line: u64,
+    pub values: Result<Vec<Option<String>>, String>,
+}
+
+/// Reads records of the text formats, with values in `columns()` order
+pub enum RecordReader {
+    Csv(CsvReader),
+    Ndjson(NdjsonReader),
+}
+
+impl RecordReader {
+    pub async fn open(path: &Path, format: &ImportFormat) -> DbResult<Self> {
+        Ok(match format {
+            ImportFormat::Csv {
+                delimiter,
+                quote,
+                header,
+                null_value,
+                encoding,
+            } => {
+                let layout = CsvLayout {
+                    delimiter: *delimiter,
+                    quote: *quote,
+                    header: *header,
+                    null_value: null_value.clone(),
+                    encoding: *encoding,
+                };
+                layout.validate()?;
+                RecordReader::Csv(CsvReader::open(path, layout).await?)
+            }
+            ImportFormat::Ndjson => RecordReader::Ndjson(NdjsonReader::open(path).await?),
+            ImportFormat::Parquet => {
+                return Err(DbError::Unsupported(
+                    "Parquet files are read as record batches, not text records".to_string(),
+                ))
+            }
+        })
+    }
+
+    pub fn columns(&self) -> &[String] {
+        match self {
+            RecordReader::Csv(reader) => reader.columns(),
+            RecordReader::Ndjson(reader) => reader.columns(),
+        }
+    }
+
+    pub fn bytes_read(&self) -> u64 {
+        match self {
+            RecordReader::Csv(reader) => reader.bytes_read(),
+            RecordReader::Ndjson(reader) => reader.bytes_read(),
+        }
+    }
+
+    pub async fn next_record(&mut self) -> DbResult<Option<Record>> {
+        Ok(match self {
+            RecordReader::Csv(reader) => reader.next_record().await?,
+            RecordReader::Ndjson(reader) => reader.next_record().await?,
+        })
+    }
+}
+
+/// A column of the file with the type its sample values suggest
+#[taurpc::ipc_type]
+#[serde(rename_all = "camelCase")]
+#[derive(Debug)]
+pub struct ImportColumn {
+    pub name: String,
+    pub inferred_type: infer::InferredType,
+    /// Type to create the column with in a new Postgres table
+    pub data_type: String,
+}
+
+#[taurpc::ipc_type]
+#[serde(rename_all = "camelCase")]
+#[derive(Debug)]
+pub struct ImportPreview {
+    pub path: String,
+    pub format: ImportFormat,
+    pub columns: Vec<ImportColumn>,
+    /// First records of the file, with values in column order
+    pub rows: Vec<Vec<Option<String>>>,
+    /// Size of the file in bytes
+    pub size: u64,
+}
+
+/// Reads the start of a file to find its columns and guess their types
+pub async fn preview(path: &Path, format: &ImportFormat) -> DbResult<ImportPreview> {
+    if let ImportFormat::Parquet = format {
+        return columnar::preview(path, format).await;
+    }
+
+    let size = tokio::fs::metadata(path).await?.len();
+    let mut reader = RecordReader::open(path, format).await?;
+
+    let mut sample = Vec::new();
+    while sample.len() < SAMPLE_ROWS {
+        match reader.next_record().await? {
+            Some(Record {
+                values: Ok(values), ..
+            }) => sample.push(values),
+            // Unreadable records don't say anything about types
+            Some(_) => continue,
+            None => break,
+        }
+    }
+
+    let columns = reader
+        .columns()
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let values = sample
+                .iter()
+                .filter_map(|values| values.get(i).and_then(|v| v.as_deref()));
+            let inferred_type = infer_type(values);
+            ImportColumn {
+                name: name.clone(),
+                inferred_type,
+                data_type: inferred_type.pg_type().to_string(),
+            }
+        })
+        .collect();
+
+    sample.truncate(PREVIEW_ROWS);
+
+    Ok(ImportPreview {
+        path: path.to_string_lossy().to_string(),
+        format: format.clone(),
+        columns,
+        rows: sample,
+        size,
+    })
+}
+
+/// Where imported rows go
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ImportTarget {
+    #[serde(rename_all = "camelCase")]
+    Existing { table_id: String },
+    /// A table created as part of the import, and dropped again if it's rolled back
+    #[serde(rename_all = "camelCase")]
+    New {
+        schema: String,
+        name: String,
+        columns: Vec<NewColumn>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct NewColumn {
+    pub name: String,
+    pub data_type: String,
+}
+
+/// A file column loaded into a table column
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnMapping {
+    pub source: String,
+    pub target: String,
+}
+
+#[taurpc::ipc_type]
+#[serde(rename_all = "camelCase")]
+#[derive(Debug)]
+pub struct ImportRequest {
+    /// Chosen by the caller and echoed in progress events. The file is the one picked
+    /// for the window's last preview.
+    pub import_id: u32,
+    pub format: ImportFormat,
+    pub target: ImportTarget,
+    /// File columns that aren't mapped are skipped
+    pub mapping: Vec<ColumnMapping>,
+    /// Rows sent per `COPY`
+    pub batch_size: u32,
+    /// Undo the whole import if any row fails, instead of keeping the rows that loaded
+    pub rollback_on_error: bool,
+}
+
+/// Loads a file into a table
+#[async_trait]
+pub trait Importer: Send + Sync {
+    /// Calls `progress` after each batch is loaded
+    async fn import(
+        &self,
+        request: &ImportRequest,
+        path: &Path,
+        progress: &(dyn Fn(ImportProgress) + Send + Sync),
+    ) -> DbResult<ImportReport>;
+}
+
+/// Emitted after each batch is loaded
+#[taurpc::ipc_type]
+#[serde(rename_all = "camelCase")]
+#[derive(Debug)]
+pub struct ImportProgress {
+    pub import_id: u32,
+    pub rows_read: u64,
+    pub rows_loaded: u64,
+    pub rows_failed: u64,
+    pub bytes_read: u64,
+    pub bytes_total: u64,
+}
+
+#[taurpc::ipc_type]
+#[serde(rename_all = "camelCase")]
+#[derive(Debug)]
+pub struct RowError {
+    /// Line the record starts on in the file
+    pub line: u64,
+    pub message: String,
+}
+
+#[taurpc::ipc_type]
+#[serde(rename_all = "camelCase")]
+#[derive(Debug)]
+pub struct ImportReport {
+    pub import_id: u32,
+    /// Table the rows were loaded into
+    pub table_id: Option<String>,
+    pub rows_read: u64,
+    /// Rows loaded, even if they were rolled back afterwards
+    pub rows_loaded: u64,
+    pub rows_failed: u64,
+    /// The first `MAX_REPORTED_ERRORS` row errors
+    pub errors: Vec<RowError>,
+    /// Whether the loaded rows were kept
+    pub committed: bool,
+}
+
+impl ImportReport {
+    pub fn new(import_id: u32) -> Self {
+        Self {
+            import_id,
+            table_id: None,
+            rows_read: 0,
+            rows_loaded: 0,
+            rows_failed: 0,
+            errors: Vec::new(),
+            committed: false,
+        }
+    }
+
+    pub fn fail(&mut self, line: u64, message: String) {
+        self.rows_failed += 1;
+        if self.errors.len() < MAX_REPORTED_ERRORS {
+            self.errors.push(RowError { line, message });
+        }
+    }
+
+    pub fn progress(&self, bytes_read: u64, bytes_total: u64) -> ImportProgress {
+        ImportProgress {
+            import_id: self.import_id,
+            rows_read: self.rows_read,
+            rows_loaded: self.rows_loaded,
+            rows_failed: self.rows_failed,
+            bytes_read,
+            bytes_total,
+        }
+    }
+}
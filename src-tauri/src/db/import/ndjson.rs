@@ -0,0 +1,200 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value as JsonValue;
+use tokio::fs::File;
+use tokio::io::{AsyncBufReadExt, BufReader, Lines};
+
+use crate::db::import::{Record, SAMPLE_ROWS};
+
+/// Text of a JSON value as it's loaded into a column; objects and arrays stay JSON
+fn json_text(value: &JsonValue) -> Option<String> {
+    match value {
+        JsonValue::Null => None,
+        JsonValue::String(s) => Some(s.clone()),
+        value => Some(value.to_string()),
+    }
+}
+
+async fn open_lines(path: &Path) -> io::Result<Lines<BufReader<File>>> {
+    Ok(BufReader::new(File::open(path).await?).lines())
+}
+
+/// Reads one JSON object per line. Columns are the keys found in the first
+/// `SAMPLE_ROWS` objects; keys that only appear later are ignored.
+pub struct NdjsonReader {
+    lines: Lines<BufReader<File>>,
+    columns: Vec<String>,
+    line: u64,
+    bytes_read: u64,
+}
+
+impl NdjsonReader {
+    pub async fn open(path: &Path) -> io::Result<Self> {
+        let path = PathBuf::from(path);
+
+        let mut columns = Vec::new();
+        let mut sample = open_lines(&path).await?;
+        let mut sampled = 0;
+        while sampled < SAMPLE_ROWS {
+            let Some(line) = sample.next_line().await? else {
+                break;
+            };
+            if let Ok(JsonValue::Object(object)) = serde_json::from_str(&line) {
+                for key in object.keys() {
+                    if !columns.contains(key) {
+                        columns.push(key.clone());
+                    }
+                }
+                sampled += 1;
+            }
+        }
+
+        Ok(Self {
+            lines: open_lines(&path).await?,
+            columns,
+            line: 0,
+            bytes_read: 0,
+        })
+    }
+
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    pub async fn next_record(&mut self) -> io::Result<Option<Record>> {
+        loop {
+            let Some(text) = self.lines.next_line().await? else {
+                return Ok(None);
+            };
+            self.line += 1;
+            self.bytes_read += text.len() as u64 + 1;
+            if text.trim().is_empty() {
+                continue;
+            }
+
+            let values = match serde_json::from_str(&text) {
+                Ok(JsonValue::Object(object)) => Ok(self
+                    .columns
+                    .iter()
+                    .map(|column| object.get(column).and_then(json_text))
+                    .collect()),
+                Ok(_) => Err("Line is not a JSON object".to_string()),
+                Err(e) => Err(format!("Invalid JSON: {}", e)),
+            };
+
+            return Ok(Some(Record {
+                line: self.line,
+                values,
+            }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A file under the system temp dir, removed when dropped
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, content: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "sqratch-ndjson-{}-{}.ndjson",
+                name,
+                std::process::id()
+            ));
+            std::fs::write(&path, content).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    async fn records(reader: &mut NdjsonReader) -> Vec<Record> {
+        let mut records = Vec::new();
+        while let Some(record) = reader.next_record().await.unwrap() {
+            records.push(record);
+        }
+        records
+    }
+
+    #[test]
+    fn objects_and_arrays_stay_json() {
+        assert_eq!(json_text(&JsonValue::Null), None);
+        assert_eq!(
+            json_text(&serde_json::json!("a \"b\"")).as_deref(),
+            Some("a \"b\"")
+        );
+        assert_eq!(json_text(&serde_json::json!(1.5)).as_deref(), Some("1.5"));
+        assert_eq!(
+            json_text(&serde_json::json!(false)).as_deref(),
+            Some("false")
+        );
+        assert_eq!(
+            json_text(&serde_json::json!({ "a": [1, null] })).as_deref(),
+            Some(r#"{"a":[1,null]}"#)
+        );
+    }
+
+    #[tokio::test]
+    async fn reads_values_in_column_order() {
+        let file = TempFile::new(
+            "columns",
+            "{\"id\": 1, \"name\": \"a\"}\n\n{\"name\": null, \"id\": 2, \"tags\": [\"x\"]}\n",
+        );
+        let mut reader = NdjsonReader::open(&file.0).await.unwrap();
+        assert_eq!(reader.columns(), ["id", "name", "tags"]);
+
+        let records = records(&mut reader).await;
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].line, 1);
+        assert_eq!(
+            records[0].values,
+            Ok(vec![Some("1".to_string()), Some("a".to_string()), None])
+        );
+        // The blank line is skipped but still counted
+        assert_eq!(records[1].line, 3);
+        assert_eq!(
+            records[1].values,
+            Ok(vec![
+                Some("2".to_string()),
+                None,
+                Some(r#"["x"]"#.to_string())
+            ])
+        );
+        assert_eq!(
+            reader.bytes_read(),
+            std::fs::metadata(&file.0).unwrap().len()
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_lines_that_are_not_objects() {
+        let file = TempFile::new("invalid", "{\"id\": 1}\n[1]\n{\"id\":\n");
+        let mut reader = NdjsonReader::open(&file.0).await.unwrap();
+        assert_eq!(reader.columns(), ["id"]);
+
+        let records = records(&mut reader).await;
+        assert_eq!(records[0].values, Ok(vec![Some("1".to_string())]));
+        assert_eq!(
+            records[1].values,
+            Err("Line is not a JSON object".to_string())
+        );
+        assert_eq!(records[2].line, 3);
+        assert!(records[2]
+            .values
+            .as_ref()
+            .unwrap_err()
+            .starts_with("Invalid JSON"));
+    }
+}
@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+
+/// Migration tools whose history Sqratch can read
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum MigrationTool {
+    /// sqlx-cli, tracked in `_sqlx_migrations`
+    Sqlx,
+    /// Prisma Migrate, tracked in `_prisma_migrations`
+    Prisma,
+    /// Drizzle Kit, tracked in `__drizzle_migrations`
+    Drizzle,
+    /// Rails (Active Record), tracked in `schema_migrations`
+    Rails,
+}
+
+impl MigrationTool {
+    /// Tracking tables the tool may use, in the order they're looked for
+    pub fn tracking_tables(self) -> &'static [&'static str] {
+        match self {
+            MigrationTool::Sqlx => &["_sqlx_migrations"],
+            MigrationTool::Prisma => &["_prisma_migrations"],
+            // Drizzle keeps its table in a `drizzle` schema on Postgres
+            MigrationTool::Drizzle => &["drizzle.__drizzle_migrations", "__drizzle_migrations"],
+            MigrationTool::Rails => &["schema_migrations"],
+        }
+    }
+}
+
+/// A migration recorded in a tool's tracking table
+#[derive(Debug, Clone)]
+pub struct AppliedMigration {
+    /// What the tool identifies the migration by: the version for sqlx and Rails, the
+    /// directory name for Prisma and the journal timestamp for Drizzle
+    pub id: String,
+    /// Hex checksum of the migration's SQL, for tools that record one
+    pub checksum: Option<String>,
+    pub applied_at: Option<String>,
+    /// False if the migration started but didn't finish
+    pub success: bool,
+}
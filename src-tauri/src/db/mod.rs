@@ -1,5 +1,24 @@
 // Define modules in the database module - only visible within this module
+pub mod binary;
+pub mod browse;
+pub mod catalog;
+pub mod changes;
+pub mod clickhouse;
 pub mod client;
+pub mod driver;
+pub mod duckdb;
+pub mod dump;
+pub mod editing;
 pub mod errors;
+pub mod export;
+pub mod ident;
+pub mod import;
+pub mod migrations;
+pub mod mysql;
 pub mod postgres;
+pub mod schema_diff;
+pub mod search;
+pub mod sqlite;
+pub mod temporal;
+pub mod typegen;
 pub mod types;
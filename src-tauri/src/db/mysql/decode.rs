@@ -0,0 +1,126 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use serde_json::Value as JsonValue;
+use sqlx::mysql::{types::MySqlTime, MySqlValueRef};
+use sqlx::{TypeInfo, Value, ValueRef};
+
+use crate::db::binary::{BinaryFormat, BinaryPreview};
+use crate::db::errors::{DbError, DbResult};
+use crate::db::temporal::{DisplayZone, DATETIME_FORMAT, DATE_FORMAT};
+
+/// Options controlling how values are decoded for the frontend
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DecodeOptions {
+    pub binary: BinaryFormat,
+    /// Zone TIMESTAMP values are rendered in
+    pub time_zone: DisplayZone,
+}
+
+pub(crate) fn to_json(v: MySqlValueRef, opts: &DecodeOptions) -> DbResult<JsonValue> {
+    if v.is_null() {
+        return Ok(JsonValue::Null);
+    }
+
+    let res = match v.type_info().name() {
+        "BOOLEAN" => {
+            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<bool>() {
+                JsonValue::Bool(v)
+            } else {
+                JsonValue::Null
+            }
+        }
+        "TINYINT" | "SMALLINT" | "MEDIUMINT" | "INT" | "BIGINT" | "YEAR" => {
+            if let Ok(v) = ValueRef::to_owned(&v).try_decode_unchecked::<i64>() {
+                JsonValue::Number(v.into())
+            } else {
+                JsonValue::Null
+            }
+        }
+        "TINYINT UNSIGNED" | "SMALLINT UNSIGNED" | "MEDIUMINT UNSIGNED" | "INT UNSIGNED"
+        | "BIGINT UNSIGNED" => {
+            if let Ok(v) = ValueRef::to_owned(&v).try_decode_unchecked::<u64>() {
+                JsonValue::Number(v.into())
+            } else {
+                JsonValue::Null
+            }
+        }
+        "FLOAT" => {
+            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<f32>() {
+                JsonValue::from(v)
+            } else {
+                JsonValue::Null
+            }
+        }
+        "DOUBLE" => {
+            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<f64>() {
+                JsonValue::from(v)
+            } else {
+                JsonValue::Null
+            }
+        }
+        // Decimals travel as strings so no precision is lost
+        "DECIMAL" | "CHAR" | "VARCHAR" | "TINYTEXT" | "TEXT" | "MEDIUMTEXT" | "LONGTEXT"
+        | "ENUM" | "SET" => {
+            if let Ok(v) = ValueRef::to_owned(&v).try_decode_unchecked::<String>() {
+                JsonValue::String(v)
+            } else {
+                JsonValue::Null
+            }
+        }
+        "JSON" => ValueRef::to_owned(&v).try_decode().unwrap_or_default(),
+        "BINARY" | "VARBINARY" | "TINYBLOB" | "BLOB" | "MEDIUMBLOB" | "LONGBLOB" | "GEOMETRY" => {
+            if let Ok(v) = ValueRef::to_owned(&v).try_decode_unchecked::<Vec<u8>>() {
+                BinaryPreview::new(&v, &opts.binary).to_json()
+            } else {
+                JsonValue::Null
+            }
+        }
+        "BIT" => {
+            if let Ok(v) = ValueRef::to_owned(&v).try_decode_unchecked::<Vec<u8>>() {
+                let n = v.iter().fold(0u64, |acc, b| (acc << 8) | *b as u64);
+                JsonValue::Number(n.into())
+            } else {
+                JsonValue::Null
+            }
+        }
+        // Zero dates ('0000-00-00') can't be represented and decode as NULL
+        "DATE" => {
+            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<NaiveDate>() {
+                JsonValue::String(v.format(DATE_FORMAT).to_string())
+            } else {
+                JsonValue::Null
+            }
+        }
+        // TIME is a duration in MySQL and may be negative or exceed 24 hours
+        "TIME" => {
+            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<MySqlTime>() {
+                JsonValue::String(v.to_string())
+            } else {
+                JsonValue::Null
+            }
+        }
+        "DATETIME" => {
+            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<NaiveDateTime>() {
+                JsonValue::String(v.format(DATETIME_FORMAT).to_string())
+            } else {
+                JsonValue::Null
+            }
+        }
+        // sqlx pins the session to UTC, so TIMESTAMP values arrive as UTC instants
+        "TIMESTAMP" => {
+            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<DateTime<Utc>>() {
+                JsonValue::String(opts.time_zone.format(v))
+            } else {
+                JsonValue::Null
+            }
+        }
+        "NULL" => JsonValue::Null,
+        _ => {
+            return Err(DbError::Unsupported(format!(
+                "Unsupported data type: {}",
+                v.type_info().name()
+            )))
+        }
+    };
+
+    Ok(res)
+}
@@ -0,0 +1,87 @@
+use sqlx::{MySql, Pool, Row as SqlxRow};
+
+use crate::db::errors::DbResult;
+use crate::db::migrations::{AppliedMigration, MigrationTool};
+
+use super::quote_ident;
+
+/// Reads a migration tool's tracking table in the current database, or None if it doesn't
+/// have one
+pub(crate) async fn history(
+    pool: &Pool<MySql>,
+    tool: MigrationTool,
+) -> DbResult<Option<Vec<AppliedMigration>>> {
+    let mut table = None;
+    // Schema-qualified names are where Postgres keeps a tool's table
+    for name in tool
+        .tracking_tables()
+        .iter()
+        .filter(|name| !name.contains('.'))
+    {
+        let exists = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM information_schema.tables WHERE table_schema = DATABASE() AND table_name = ?",
+        )
+        .bind(name)
+        .fetch_one(pool)
+        .await?;
+        if exists > 0 {
+            table = Some(quote_ident(name));
+            break;
+        }
+    }
+    let Some(table) = table else {
+        return Ok(None);
+    };
+
+    // Everything is cast to text and integers, which decode the same whatever the column types
+    let sql = match tool {
+        MigrationTool::Sqlx => format!(
+            r#"
+            SELECT CAST(version AS CHAR) AS id, LOWER(HEX(checksum)) AS checksum,
+                CAST(installed_on AS CHAR) AS applied_at, CAST(success AS SIGNED) AS success
+            FROM {}
+            "#,
+            table
+        ),
+        // Rolled back attempts stay in the table next to the retry
+        MigrationTool::Prisma => format!(
+            r#"
+            SELECT migration_name AS id, checksum, CAST(finished_at AS CHAR) AS applied_at,
+                CAST(finished_at IS NOT NULL AS SIGNED) AS success
+            FROM {}
+            WHERE rolled_back_at IS NULL
+            ORDER BY started_at
+            "#,
+            table
+        ),
+        // created_at is the migration's journal timestamp, not when it was applied
+        MigrationTool::Drizzle => format!(
+            r#"
+            SELECT CAST(created_at AS CHAR) AS id, hash AS checksum,
+                CAST(NULL AS CHAR) AS applied_at, CAST(1 AS SIGNED) AS success
+            FROM {}
+            "#,
+            table
+        ),
+        MigrationTool::Rails => format!(
+            r#"
+            SELECT CAST(version AS CHAR) AS id, CAST(NULL AS CHAR) AS checksum,
+                CAST(NULL AS CHAR) AS applied_at, CAST(1 AS SIGNED) AS success
+            FROM {}
+            "#,
+            table
+        ),
+    };
+
+    let rows = sqlx::query(&sql).fetch_all(pool).await?;
+    Ok(Some(
+        rows.iter()
+            .map(|row| AppliedMigration {
+                id: row.get("id"),
+                checksum: row.get("checksum"),
+                applied_at: row.get("applied_at"),
+                success: row.get::<i64, _>("success") != 0,
+            })
+            .collect(),
+    ))
+}
@@ -0,0 +1,640 @@
+mod decode;
+mod migrations;
+
+use async_trait::async_trait;
+use sqlx::{
+    mysql::{MySqlConnectOptions, MySqlPoolOptions, MySqlRow},
+    Column, MySql, Pool, Row as SqlxRow, Transaction, Value, ValueRef,
+};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::db::{
+    binary::BinaryFormat,
+    changes::{self, Dialect, StagedChange, StepRunner, TableInfo, TableLoader},
+    client::DatabaseClient,
+    editing::{self, ColumnChanges},
+    errors::{DbError, DbResult},
+    ident::{quote_backtick as quote_ident, split_table_id},
+    migrations::{AppliedMigration, MigrationTool},
+    temporal::DisplayZone,
+    types::{
+        ColumnDefinition, DbEntity, QueryResult, Row, SchemaEntity, SchemaLevelEntity,
+        ServerFlavor, ServerInfo, TimeZoneMode,
+    },
+};
+
+use self::decode::DecodeOptions;
+
+const SYSTEM_SCHEMAS: &[&str] = &["mysql", "information_schema", "performance_schema", "sys"];
+
+/// Entity IDs are quoted identifiers, e.g. "`shop`" or "`shop`.`orders`"
+fn table_id(schema: &str, name: &str) -> String {
+    format!("{}.{}", quote_ident(schema), quote_ident(name))
+}
+
+const DIALECT: Dialect = Dialect {
+    quote: '`',
+    numbered_params: false,
+    lock_rows: true,
+    backslash_escapes: true,
+};
+
+/// Decodes every column of a row into its display value
+fn decode_row(row: &MySqlRow, opts: &DecodeOptions) -> DbResult<Row> {
+    let mut values = HashMap::new();
+    for (i, col) in row.columns().iter().enumerate() {
+        let value = decode::to_json(row.try_get_raw(i)?, opts)?;
+        values.insert(
+            col.name().to_string(),
+            if value.is_null() {
+                "NULL".to_string()
+            } else {
+                value.to_string()
+            },
+        );
+    }
+    Ok(Row { values })
+}
+
+/// Loads the tables a change set touches
+struct ChangeTables<'a>(&'a Pool<MySql>);
+
+#[async_trait]
+impl TableLoader for ChangeTables<'_> {
+    async fn load_table(&mut self, table_id: &str) -> DbResult<TableInfo> {
+        let (schema, table) = split_table_id(table_id, '`')?;
+        Ok(TableInfo {
+            sql_name: table_id.to_string(),
+            key_columns: MySqlClient::key_columns(self.0, &schema, &table).await?,
+            // MySQL converts text parameters to the column type itself
+            columns: MySqlClient::table_columns(self.0, &schema, &table)
+                .await?
+                .into_iter()
+                .map(|name| (name, None))
+                .collect(),
+            name: table,
+        })
+    }
+}
+
+/// Runs the statements of a change set in one transaction
+struct ChangeRunner<'a> {
+    tx: Transaction<'static, MySql>,
+    opts: &'a DecodeOptions,
+}
+
+#[async_trait]
+impl StepRunner for ChangeRunner<'_> {
+    async fn fetch_row(&mut self, sql: &str, params: Vec<Option<&str>>) -> DbResult<Option<Row>> {
+        let mut query = sqlx::query(sql);
+        for param in params {
+            query = query.bind(param);
+        }
+
+        match query.fetch_optional(&mut *self.tx).await? {
+            Some(row) => Ok(Some(decode_row(&row, self.opts)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn execute(&mut self, sql: &str, params: Vec<Option<&str>>) -> DbResult<u64> {
+        let mut query = sqlx::query(sql);
+        for param in params {
+            query = query.bind(param);
+        }
+        Ok(query.execute(&mut *self.tx).await?.rows_affected())
+    }
+}
+
+pub struct MySqlClient {
+    connection_string: String,
+    pool: Option<Pool<MySql>>,
+    decode_options: DecodeOptions,
+    time_zone: TimeZoneMode,
+    /// Zone the server uses for new sessions, read on connect
+    session_time_zone: Option<DisplayZone>,
+    /// Flavor and version of the server, detected on connect
+    server_info: Option<ServerInfo>,
+}
+
+impl MySqlClient {
+    pub fn new(connection_string: &str) -> DbResult<Self> {
+        Ok(Self {
+            connection_string: connection_string.to_string(),
+            pool: None,
+            decode_options: DecodeOptions::default(),
+            time_zone: TimeZoneMode::default(),
+            session_time_zone: None,
+            server_info: None,
+        })
+    }
+
+    /// Reads the server's default time zone.
+    /// sqlx pins `time_zone='+00:00'` on every connection, so `@@session.time_zone` isn't enough.
+    /// `@@system_time_zone` is often an abbreviation like `CEST` that isn't an IANA name,
+    /// so the zone's current offset is read too.
+    async fn fetch_session_time_zone(pool: &Pool<MySql>) -> DbResult<Option<DisplayZone>> {
+        let (zone, offset): (String, Option<i64>) = sqlx::query_as(
+            r#"
+            SELECT
+                CAST(
+                    IF(@@global.time_zone = 'SYSTEM', @@system_time_zone, @@global.time_zone)
+                    AS CHAR
+                ),
+                TIMESTAMPDIFF(
+                    SECOND,
+                    UTC_TIMESTAMP(),
+                    CONVERT_TZ(UTC_TIMESTAMP(), '+00:00', @@global.time_zone)
+                )
+            "#,
+        )
+        .fetch_one(pool)
+        .await?;
+
+        if let Ok(tz) = zone.parse() {
+            return Ok(Some(DisplayZone::Named(tz)));
+        }
+        Ok(offset
+            .and_then(|seconds| i32::try_from(seconds).ok())
+            .and_then(chrono::FixedOffset::east_opt)
+            .map(DisplayZone::Fixed))
+    }
+
+    /// Reads the server version, e.g. "8.0.36" or "10.11.6-MariaDB-1:10.11.6+maria~ubu2204"
+    async fn fetch_server_info(pool: &Pool<MySql>) -> DbResult<ServerInfo> {
+        let description: String = sqlx::query_scalar("SELECT CAST(VERSION() AS CHAR)")
+            .fetch_one(pool)
+            .await?;
+
+        let flavor = if description.to_lowercase().contains("mariadb") {
+            ServerFlavor::MariaDb
+        } else {
+            ServerFlavor::MySql
+        };
+
+        let version = description
+            .split('-')
+            .next()
+            .unwrap_or(&description)
+            .to_string();
+
+        // Same scheme as MYSQL_VERSION_ID, e.g. 80036
+        let parts = version
+            .split('.')
+            .map(|part| part.parse::<u32>().ok())
+            .collect::<Option<Vec<_>>>();
+        let version_num = match parts.as_deref() {
+            Some([major, minor, patch]) => Some(major * 10_000 + minor * 100 + patch),
+            _ => None,
+        };
+
+        Ok(ServerInfo {
+            flavor,
+            version,
+            version_num,
+        })
+    }
+
+    fn update_display_zone(&mut self) {
+        self.decode_options.time_zone =
+            DisplayZone::resolve(self.time_zone, self.session_time_zone);
+    }
+
+    /// Column names of a table, in order
+    async fn table_columns(pool: &Pool<MySql>, schema: &str, table: &str) -> DbResult<Vec<String>> {
+        let columns = sqlx::query_scalar(
+            r#"
+            SELECT CAST(COLUMN_NAME AS CHAR)
+            FROM information_schema.COLUMNS
+            WHERE TABLE_SCHEMA = ? AND TABLE_NAME = ?
+            ORDER BY ORDINAL_POSITION
+            "#,
+        )
+        .bind(schema)
+        .bind(table)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(columns)
+    }
+
+    /// Columns of the primary key, or of the first unique index on non-null columns
+    async fn key_columns(pool: &Pool<MySql>, schema: &str, table: &str) -> DbResult<Vec<String>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT
+                CAST(s.INDEX_NAME AS CHAR) AS index_name,
+                CAST(s.COLUMN_NAME AS CHAR) AS column_name,
+                s.NULLABLE = 'YES' AS nullable
+            FROM information_schema.STATISTICS s
+            WHERE s.TABLE_SCHEMA = ? AND s.TABLE_NAME = ? AND s.NON_UNIQUE = 0
+            ORDER BY s.INDEX_NAME = 'PRIMARY' DESC, s.INDEX_NAME, s.SEQ_IN_INDEX
+            "#,
+        )
+        .bind(schema)
+        .bind(table)
+        .fetch_all(pool)
+        .await?;
+
+        let mut indexes: Vec<(String, Vec<Option<String>>, bool)> = Vec::new();
+        for row in rows {
+            let index_name: String = row.get("index_name");
+            // Functional key parts have no column name
+            let column_name: Option<String> = row.get("column_name");
+            let nullable: bool = row.get("nullable");
+
+            match indexes.last_mut() {
+                Some((name, columns, any_nullable)) if *name == index_name => {
+                    columns.push(column_name);
+                    *any_nullable |= nullable;
+                }
+                _ => indexes.push((index_name, vec![column_name], nullable)),
+            }
+        }
+
+        Ok(indexes
+            .into_iter()
+            .filter(|(name, _, any_nullable)| name == "PRIMARY" || !any_nullable)
+            .find_map(|(_, columns, _)| columns.into_iter().collect::<Option<Vec<_>>>())
+            .unwrap_or_default())
+    }
+
+    // This function gets the pool or returns an error if not connected
+    fn get_pool(&self) -> DbResult<&Pool<MySql>> {
+        self.pool
+            .as_ref()
+            .ok_or_else(|| DbError::Connection("Database client is not connected".to_string()))
+    }
+}
+
+#[async_trait]
+impl DatabaseClient for MySqlClient {
+    fn get_connection_string(&self) -> String {
+        self.connection_string.clone()
+    }
+
+    async fn is_connected(&self) -> DbResult<bool> {
+        match self.get_pool() {
+            Ok(pool) => Ok(!pool.is_closed()),
+            Err(_) => Ok(false),
+        }
+    }
+
+    async fn test_connection(&self) -> DbResult<()> {
+        let pool = self.get_pool()?;
+        sqlx::query("SELECT 1").execute(pool).await?;
+        Ok(())
+    }
+
+    async fn connect(&mut self) -> DbResult<()> {
+        // Check if already connected
+        if let Ok(true) = self.is_connected().await {
+            return Ok(());
+        }
+
+        // sqlx ignores the scheme, so mariadb:// URLs parse the same way
+        let options = MySqlConnectOptions::from_str(&self.connection_string)?;
+
+        let pool = MySqlPoolOptions::new()
+            .max_connections(10)
+            .connect_with(options)
+            .await?;
+
+        self.session_time_zone = Self::fetch_session_time_zone(&pool).await?;
+        self.server_info = Some(Self::fetch_server_info(&pool).await?);
+        self.update_display_zone();
+
+        self.pool = Some(pool);
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> DbResult<()> {
+        if let Ok(true) = self.is_connected().await {
+            if let Some(pool) = self.pool.take() {
+                pool.close().await;
+            }
+        }
+        self.server_info = None;
+        Ok(())
+    }
+
+    async fn reconnect(&mut self) -> DbResult<()> {
+        self.disconnect().await?;
+        self.connect().await
+    }
+
+    async fn reconnect_with_string(&mut self, connection_string: &str) -> DbResult<()> {
+        self.disconnect().await?;
+        self.connection_string = connection_string.to_string();
+        self.connect().await
+    }
+
+    fn set_time_zone(&mut self, mode: TimeZoneMode) {
+        self.time_zone = mode;
+        self.update_display_zone();
+    }
+
+    fn set_binary_format(&mut self, format: BinaryFormat) {
+        self.decode_options.binary = format;
+    }
+
+    fn server_info(&self) -> Option<ServerInfo> {
+        self.server_info.clone()
+    }
+
+    async fn execute_query(&self, sql: &str) -> DbResult<QueryResult> {
+        let pool = self.get_pool()?;
+        let rows = sqlx::query(sql).fetch_all(pool).await?;
+
+        if rows.is_empty() {
+            return Ok(QueryResult {
+                timestamp: std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs(),
+                query: sql.to_string(),
+                rows_affected: None,
+                execution_time_ms: 0,
+                columns: Vec::new(),
+                rows: Vec::new(),
+                warnings: Vec::new(),
+                result_index: 0,
+            });
+        }
+
+        let mysql_row: &MySqlRow = rows.first().unwrap();
+        let columns = mysql_row
+            .columns()
+            .iter()
+            .map(|col| ColumnDefinition {
+                name: col.name().to_string(),
+                data_type: col.type_info().to_string(),
+                nullable: true,      // Default to true since we can't easily determine
+                primary_key: false,  // Cannot determine from result alone
+                default_value: None, // Cannot determine from result alone
+            })
+            .collect();
+
+        let mut result_rows = Vec::new();
+        for row in rows {
+            result_rows.push(decode_row(&row, &self.decode_options)?);
+        }
+
+        Ok(QueryResult {
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            query: sql.to_string(),
+            rows_affected: None,
+            execution_time_ms: 0,
+            columns,
+            rows: result_rows,
+            warnings: Vec::new(),
+            result_index: 0,
+        })
+    }
+
+    async fn fetch_blob(
+        &self,
+        table_id: &str,
+        key: &HashMap<String, String>,
+        column: &str,
+    ) -> DbResult<Vec<u8>> {
+        let pool = self.get_pool()?;
+        let (schema, table) = split_table_id(table_id, '`')?;
+
+        if key.is_empty() {
+            return Err(DbError::Query("A row key is required".to_string()));
+        }
+
+        let columns = Self::table_columns(pool, &schema, &table).await?;
+
+        for name in key.keys().map(String::as_str).chain([column]) {
+            if !columns.iter().any(|c| c == name) {
+                return Err(DbError::NotFound(format!("Column {} on {}", name, table)));
+            }
+        }
+
+        let mut names = key.keys().collect::<Vec<_>>();
+        names.sort();
+
+        let predicate = names
+            .iter()
+            .map(|name| format!("{} = ?", quote_ident(name)))
+            .collect::<Vec<_>>()
+            .join(" AND ");
+
+        let sql = format!(
+            "SELECT {} FROM {} WHERE {} LIMIT 2",
+            quote_ident(column),
+            table_id,
+            predicate
+        );
+
+        let mut query = sqlx::query(&sql);
+        for name in names {
+            query = query.bind(&key[name]);
+        }
+
+        let mut rows = query.fetch_all(pool).await?;
+        match rows.len() {
+            0 => Err(DbError::NotFound("Row not found".to_string())),
+            1 => {
+                let row = rows.pop().unwrap();
+                let value = row.try_get_raw(0)?;
+                let bytes = ValueRef::to_owned(&value).try_decode_unchecked::<Option<Vec<u8>>>()?;
+                Ok(bytes.unwrap_or_default())
+            }
+            _ => Err(DbError::Query(
+                "Row key matches more than one row".to_string(),
+            )),
+        }
+    }
+
+    async fn update_row(
+        &self,
+        table_id: &str,
+        key: &HashMap<String, String>,
+        changes: &ColumnChanges,
+    ) -> DbResult<Row> {
+        let pool = self.get_pool()?;
+        let (schema, table) = split_table_id(table_id, '`')?;
+
+        let key_columns = Self::key_columns(pool, &schema, &table).await?;
+        editing::check_key(&table, &key_columns, key)?;
+        editing::check_changes(changes)?;
+
+        let columns = Self::table_columns(pool, &schema, &table).await?;
+        for name in changes.keys() {
+            if !columns.contains(name) {
+                return Err(DbError::NotFound(format!("Column {} on {}", name, table)));
+            }
+        }
+
+        let names = editing::sorted(changes.keys());
+        let assignments = names
+            .iter()
+            .map(|name| format!("{} = ?", quote_ident(name)))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let key_names = editing::sorted(key.keys());
+        let predicate = key_names
+            .iter()
+            .map(|name| format!("{} = ?", quote_ident(name)))
+            .collect::<Vec<_>>()
+            .join(" AND ");
+
+        // MySQL has no UPDATE ... RETURNING, so read the row back by its new key
+        let new_key = editing::updated_key(key, changes)?;
+        let mut tx = pool.begin().await?;
+
+        let sql = format!(
+            "UPDATE {} SET {} WHERE {}",
+            table_id, assignments, predicate
+        );
+        let mut query = sqlx::query(&sql);
+        for name in &names {
+            query = query.bind(changes[*name].as_deref());
+        }
+        for name in &key_names {
+            query = query.bind(&key[*name]);
+        }
+        // sqlx sets CLIENT_FOUND_ROWS, so this counts matched rather than changed rows
+        if query.execute(&mut *tx).await?.rows_affected() == 0 {
+            return Err(DbError::NotFound("Row not found".to_string()));
+        }
+
+        let sql = format!("SELECT * FROM {} WHERE {}", table_id, predicate);
+        let mut query = sqlx::query(&sql);
+        for name in &key_names {
+            query = query.bind(&new_key[*name]);
+        }
+        let row = query
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| DbError::NotFound("Row not found".to_string()))?;
+
+        let row = decode_row(&row, &self.decode_options)?;
+        tx.commit().await?;
+        Ok(row)
+    }
+
+    async fn preview_changes(&self, staged: &[StagedChange]) -> DbResult<Vec<String>> {
+        let steps =
+            changes::plan_all(&mut ChangeTables(self.get_pool()?), staged, &DIALECT).await?;
+        Ok(changes::preview(&steps, &DIALECT))
+    }
+
+    async fn apply_changes(&self, staged: &[StagedChange]) -> DbResult<u64> {
+        let pool = self.get_pool()?;
+        let steps = changes::plan_all(&mut ChangeTables(pool), staged, &DIALECT).await?;
+        let mut runner = ChangeRunner {
+            tx: pool.begin().await?,
+            opts: &self.decode_options,
+        };
+
+        match changes::run(&mut runner, &steps, &DIALECT).await {
+            Ok(rows_written) => {
+                runner.tx.commit().await?;
+                Ok(rows_written)
+            }
+            Err(e) => {
+                runner.tx.rollback().await?;
+                Err(e)
+            }
+        }
+    }
+
+    async fn migration_history(
+        &self,
+        tool: MigrationTool,
+    ) -> DbResult<Option<Vec<AppliedMigration>>> {
+        let pool = self.get_pool()?;
+        migrations::history(pool, tool).await
+    }
+
+    async fn get_all_entities(&self) -> DbResult<HashMap<String, DbEntity>> {
+        let pool = self.get_pool()?;
+        let mut entities = HashMap::new();
+        let mut schema_children_map: HashMap<String, Vec<String>> = HashMap::new();
+
+        // Query 1: Get all databases (schemas in MySQL are databases)
+        let schema_query = r#"
+            SELECT CAST(SCHEMA_NAME AS CHAR) AS schema_name
+            FROM information_schema.SCHEMATA
+            ORDER BY SCHEMA_NAME
+        "#;
+
+        let schema_rows = sqlx::query(schema_query).fetch_all(pool).await?;
+        for row in schema_rows {
+            let name: String = row.get("schema_name");
+            let id = quote_ident(&name);
+            let is_system = SYSTEM_SCHEMAS.contains(&name.to_lowercase().as_str());
+
+            schema_children_map.insert(id.clone(), Vec::new());
+            entities.insert(
+                id.clone(),
+                DbEntity::Schema(SchemaEntity {
+                    id,
+                    name,
+                    is_system,
+                    extension_name: None,
+                    children: Vec::new(),
+                }),
+            );
+        }
+
+        // Query 2: Get tables and views
+        let table_query = r#"
+            SELECT
+                CAST(TABLE_SCHEMA AS CHAR) AS schema_name,
+                CAST(TABLE_NAME AS CHAR) AS name,
+                CAST(TABLE_TYPE AS CHAR) AS kind
+            FROM information_schema.TABLES
+            ORDER BY TABLE_SCHEMA, TABLE_NAME
+        "#;
+
+        let table_rows = sqlx::query(table_query).fetch_all(pool).await?;
+        for row in table_rows {
+            let schema_name: String = row.get("schema_name");
+            let name: String = row.get("name");
+            let kind: String = row.get("kind");
+
+            let id = table_id(&schema_name, &name);
+            let schema_id = quote_ident(&schema_name);
+            let is_system = SYSTEM_SCHEMAS.contains(&schema_name.to_lowercase().as_str());
+
+            let schema_level = SchemaLevelEntity {
+                id: id.clone(),
+                name,
+                is_system,
+                schema_id: schema_id.clone(),
+                extension_name: None,
+            };
+
+            let entity = match kind.as_str() {
+                "BASE TABLE" | "SYSTEM VERSIONED" => DbEntity::Table(schema_level),
+                "VIEW" | "SYSTEM VIEW" => DbEntity::View(schema_level),
+                _ => continue,
+            };
+
+            if let Some(children) = schema_children_map.get_mut(&schema_id) {
+                children.push(id.clone());
+            }
+
+            entities.insert(id, entity);
+        }
+
+        // Update schema entities with their children
+        for (schema_id, children) in schema_children_map {
+            if let Some(DbEntity::Schema(schema)) = entities.get_mut(&schema_id) {
+                schema.children = children;
+            }
+        }
+
+        Ok(entities)
+    }
+}
@@ -0,0 +1,518 @@
+use sqlx::{postgres::PgArguments, query::Query, Pool, Postgres, Row as SqlxRow};
+
+use crate::db::browse::{
+    cursor_value, BrowsePage, BrowseRequest, Cursor, Filter, Pagination, SortDirection, SortKey,
+};
+use crate::db::errors::{DbError, DbResult};
+use crate::db::types::{ColumnDefinition, Row};
+
+use super::relation::{quote_ident, Relation, RelationColumn};
+
+#[derive(Debug, Clone)]
+enum Param {
+    Text(String),
+    TextArray(Vec<String>),
+    Limit(i64),
+}
+
+/// SQL under construction, with its parameters numbered as they're added
+#[derive(Debug, Default)]
+struct QueryBuilder {
+    sql: String,
+    params: Vec<Param>,
+}
+
+impl QueryBuilder {
+    fn push(&mut self, sql: &str) -> &mut Self {
+        self.sql.push_str(sql);
+        self
+    }
+
+    /// Adds a parameter and returns its placeholder
+    fn param(&mut self, param: Param) -> String {
+        self.params.push(param);
+        format!("${}", self.params.len())
+    }
+
+    fn bind<'q>(&'q self, sql: &'q str) -> Query<'q, Postgres, PgArguments> {
+        let mut query = sqlx::query(sql);
+        for param in &self.params {
+            query = match param {
+                Param::Text(s) => query.bind(s),
+                Param::TextArray(values) => query.bind(values),
+                Param::Limit(n) => query.bind(n),
+            };
+        }
+        query
+    }
+}
+
+/// Escapes LIKE wildcards so the value matches literally
+pub(super) fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+fn push_filter(q: &mut QueryBuilder, relation: &Relation, filter: &Filter) -> DbResult<()> {
+    match filter {
+        Filter::And { filters } | Filter::Or { filters } => {
+            let joiner = if matches!(filter, Filter::And { .. }) {
+                " AND "
+            } else {
+                " OR "
+            };
+            if filters.is_empty() {
+                // An empty AND matches everything, an empty OR nothing
+                q.push(if joiner == " AND " { "TRUE" } else { "FALSE" });
+                return Ok(());
+            }
+
+            q.push("(");
+            for (i, filter) in filters.iter().enumerate() {
+                if i > 0 {
+                    q.push(joiner);
+                }
+                push_filter(q, relation, filter)?;
+            }
+            q.push(")");
+        }
+        Filter::Not { filter } => {
+            q.push("NOT (");
+            push_filter(q, relation, filter)?;
+            q.push(")");
+        }
+        Filter::Equals { column, value } => {
+            let column = relation.column(column)?;
+            let p = q.param(Param::Text(value.clone()));
+            q.push(&format!(
+                "{} = {}::{}",
+                quote_ident(&column.name),
+                p,
+                column.data_type
+            ));
+        }
+        Filter::Contains { column, value } => {
+            let column = relation.column(column)?;
+            let p = q.param(Param::Text(format!("%{}%", escape_like(value))));
+            q.push(&format!("{}::TEXT ILIKE {}", quote_ident(&column.name), p));
+        }
+        Filter::Between { column, low, high } => {
+            let column = relation.column(column)?;
+            let low = q.param(Param::Text(low.clone()));
+            let high = q.param(Param::Text(high.clone()));
+            q.push(&format!(
+                "{} BETWEEN {}::{} AND {}::{}",
+                quote_ident(&column.name),
+                low,
+                column.data_type,
+                high,
+                column.data_type
+            ));
+        }
+        Filter::IsNull { column } => {
+            let column = relation.column(column)?;
+            q.push(&format!("{} IS NULL", quote_ident(&column.name)));
+        }
+        Filter::In { column, values } => {
+            let column = relation.column(column)?;
+            let p = q.param(Param::TextArray(values.clone()));
+            q.push(&format!(
+                "{} = ANY({}::TEXT[]::{}[])",
+                quote_ident(&column.name),
+                p,
+                column.data_type
+            ));
+        }
+        Filter::JsonPath {
+            column,
+            path,
+            value,
+        } => {
+            let column = relation.column(column)?;
+            if column.data_type != "json" && column.data_type != "jsonb" {
+                return Err(DbError::Query(format!(
+                    "{} is not a JSON column",
+                    column.name
+                )));
+            }
+
+            let path = q.param(Param::TextArray(path.clone()));
+            let target = format!("{} #>> {}::TEXT[]", quote_ident(&column.name), path);
+            match value {
+                Some(value) => {
+                    let p = q.param(Param::Text(value.clone()));
+                    q.push(&format!("{} = {}", target, p));
+                }
+                None => {
+                    q.push(&format!("{} IS NULL", target));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether a cursor can hold a column's value. Cursors are read from the displayed
+/// rows, where binary values are a truncated preview and arrays are JSON, neither of
+/// which casts back to the column's type.
+fn keyset_sortable(column: &RelationColumn) -> bool {
+    column.data_type != "bytea" && !column.data_type.ends_with("[]")
+}
+
+/// Sort keys with the row key appended, so every row has a unique position.
+/// With keyset pagination the requested keys stop at the first one a cursor can't hold.
+fn full_sort(relation: &Relation, sort: &[SortKey], keyset: bool) -> DbResult<Vec<SortKey>> {
+    let mut keys = Vec::new();
+    for key in sort {
+        let column = relation.column(&key.column)?;
+        if keyset && !keyset_sortable(column) {
+            break;
+        }
+        if !keys.iter().any(|k: &SortKey| k.column == key.column) {
+            keys.push(key.clone());
+        }
+    }
+
+    for column in &relation.key_columns {
+        if !keys.iter().any(|k| &k.column == column) {
+            keys.push(SortKey {
+                column: column.clone(),
+                direction: SortDirection::Asc,
+            });
+        }
+    }
+
+    Ok(keys)
+}
+
+/// Postgres sorts NULLs last ascending and first descending; `ORDER BY` spells this
+/// out so the keyset predicate below is guaranteed to match it
+fn nulls_last(direction: SortDirection) -> bool {
+    direction == SortDirection::Asc
+}
+
+/// Rows strictly after the cursor in sort order, expanded as
+/// `(a > $1) OR (a = $1 AND b < $2) OR ...` so each column can have its own direction.
+/// Comparisons never match NULL, so NULLs are placed explicitly.
+fn push_keyset(
+    q: &mut QueryBuilder,
+    relation: &Relation,
+    sort: &[SortKey],
+    after: &Cursor,
+) -> DbResult<()> {
+    let mut placeholders = Vec::with_capacity(sort.len());
+    let mut nullable = Vec::with_capacity(sort.len());
+    for key in sort {
+        let value = after.get(&key.column).ok_or_else(|| {
+            DbError::Query(format!("Cursor is missing a value for {}", key.column))
+        })?;
+        let column = relation.column(&key.column)?;
+        placeholders.push(value.as_ref().map(|value| {
+            let p = q.param(Param::Text(value.clone()));
+            format!("{}::{}", p, column.data_type)
+        }));
+        nullable.push(column.nullable);
+    }
+
+    let mut alternatives = Vec::with_capacity(sort.len());
+    for (i, key) in sort.iter().enumerate() {
+        let column = quote_ident(&key.column);
+        let after = match (&placeholders[i], nulls_last(key.direction)) {
+            // Nothing sorts after a trailing NULL
+            (None, true) => continue,
+            (None, false) => format!("{} IS NOT NULL", column),
+            (Some(p), true) if nullable[i] => {
+                format!("({} > {} OR {} IS NULL)", column, p, column)
+            }
+            (Some(p), true) => format!("{} > {}", column, p),
+            (Some(p), false) => format!("{} < {}", column, p),
+        };
+
+        let mut clauses = sort[..i]
+            .iter()
+            .zip(&placeholders)
+            .map(|(k, p)| match p {
+                Some(p) => format!("{} = {}", quote_ident(&k.column), p),
+                None => format!("{} IS NULL", quote_ident(&k.column)),
+            })
+            .collect::<Vec<_>>();
+        clauses.push(after);
+        alternatives.push(format!("({})", clauses.join(" AND ")));
+    }
+
+    if alternatives.is_empty() {
+        q.push("FALSE");
+    } else {
+        q.push("(");
+        q.push(&alternatives.join(" OR "));
+        q.push(")");
+    }
+    Ok(())
+}
+
+/// Estimated number of rows matching the query, from statistics or the planner
+async fn estimate_count(
+    pool: &Pool<Postgres>,
+    relation: &Relation,
+    filtered: &QueryBuilder,
+    has_filter: bool,
+) -> DbResult<u64> {
+    if !has_filter && matches!(relation.kind.as_str(), "r" | "m") {
+        let reltuples: f32 =
+            sqlx::query_scalar("SELECT reltuples FROM pg_class WHERE oid = $1::oid")
+                .bind(&relation.id)
+                .fetch_one(pool)
+                .await?;
+
+        // -1 means the table was never vacuumed or analyzed
+        if reltuples >= 0.0 {
+            return Ok(reltuples as u64);
+        }
+    }
+
+    let sql = format!("EXPLAIN (FORMAT JSON) {}", filtered.sql);
+    let plan: serde_json::Value = filtered.bind(&sql).fetch_one(pool).await?.try_get(0)?;
+    let rows = plan
+        .pointer("/0/Plan/Plan Rows")
+        .and_then(serde_json::Value::as_f64)
+        .unwrap_or_default();
+
+    Ok(rows as u64)
+}
+
+pub(crate) async fn browse_table(
+    pool: &Pool<Postgres>,
+    request: &BrowseRequest,
+    decode_row: impl Fn(&sqlx::postgres::PgRow) -> DbResult<Row>,
+) -> DbResult<BrowsePage> {
+    let relation = Relation::load(pool, &request.table_id).await?;
+
+    // Shared by the page and count queries
+    let mut filtered = QueryBuilder::default();
+    filtered.push(&format!("SELECT * FROM {}", relation.qualified_name()));
+    if let Some(filter) = &request.filter {
+        filtered.push(" WHERE ");
+        push_filter(&mut filtered, &relation, filter)?;
+    }
+
+    // The row key breaks ties so offsets and cursors point at the same row every time
+    if matches!(request.page, Pagination::Keyset { .. }) && relation.key_columns.is_empty() {
+        return Err(DbError::Unsupported(format!(
+            "{} has no primary key or unique key, so use offset pagination",
+            relation.name
+        )));
+    }
+    let keyset = matches!(request.page, Pagination::Keyset { .. });
+    let sort = full_sort(&relation, &request.sort, keyset)?;
+
+    let mut page = QueryBuilder {
+        sql: filtered.sql.clone(),
+        params: filtered.params.clone(),
+    };
+
+    if let Pagination::Keyset {
+        after: Some(after), ..
+    } = &request.page
+    {
+        page.push(if request.filter.is_some() {
+            " AND "
+        } else {
+            " WHERE "
+        });
+        push_keyset(&mut page, &relation, &sort, after)?;
+    }
+
+    if !sort.is_empty() {
+        let order = sort
+            .iter()
+            .map(|key| {
+                let direction = match key.direction {
+                    SortDirection::Asc => "ASC",
+                    SortDirection::Desc => "DESC",
+                };
+                let nulls = if nulls_last(key.direction) {
+                    "LAST"
+                } else {
+                    "FIRST"
+                };
+                format!("{} {} NULLS {}", quote_ident(&key.column), direction, nulls)
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        page.push(" ORDER BY ");
+        page.push(&order);
+    }
+
+    // Fetch one extra row to know whether there's another page
+    let limit = match &request.page {
+        Pagination::Offset { offset, limit } => {
+            let p = page.param(Param::Limit(*limit as i64 + 1));
+            page.push(&format!(" LIMIT {}", p));
+            let p = page.param(Param::Limit(*offset as i64));
+            page.push(&format!(" OFFSET {}", p));
+            *limit as usize
+        }
+        Pagination::Keyset { limit, .. } => {
+            let p = page.param(Param::Limit(*limit as i64 + 1));
+            page.push(&format!(" LIMIT {}", p));
+            *limit as usize
+        }
+    };
+
+    let mut pg_rows = page.bind(&page.sql).fetch_all(pool).await?;
+    let has_more = pg_rows.len() > limit;
+    pg_rows.truncate(limit);
+
+    let rows = pg_rows
+        .iter()
+        .map(&decode_row)
+        .collect::<DbResult<Vec<_>>>()?;
+
+    let next_cursor = match (&request.page, rows.last()) {
+        (Pagination::Keyset { .. }, Some(last)) if has_more => Some(
+            sort.iter()
+                .map(|key| {
+                    let value = last.values.get(&key.column).and_then(|v| cursor_value(v));
+                    (key.column.clone(), value)
+                })
+                .collect(),
+        ),
+        _ => None,
+    };
+
+    let (total, total_is_exact) = if request.exact_count {
+        let sql = format!("SELECT count(*) FROM ({}) AS t", filtered.sql);
+        let count: i64 = filtered.bind(&sql).fetch_one(pool).await?.try_get(0)?;
+        (count as u64, true)
+    } else {
+        let estimate = estimate_count(pool, &relation, &filtered, request.filter.is_some()).await?;
+        (estimate, false)
+    };
+
+    let columns = relation
+        .columns
+        .iter()
+        .map(|column| ColumnDefinition {
+            name: column.name.clone(),
+            data_type: column.data_type.clone(),
+            nullable: column.nullable,
+            primary_key: relation.key_columns.contains(&column.name),
+            default_value: column.default_value.clone(),
+        })
+        .collect();
+
+    Ok(BrowsePage {
+        columns,
+        rows,
+        total,
+        total_is_exact,
+        has_more,
+        next_cursor,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn relation() -> Relation {
+        Relation {
+            id: "1".to_string(),
+            schema: "public".to_string(),
+            name: "users".to_string(),
+            kind: "r".to_string(),
+            columns: [
+                ("id", "text"),
+                ("name", "text"),
+                ("avatar", "bytea"),
+                ("tags", "text[]"),
+            ]
+            .into_iter()
+            .map(|(name, data_type)| RelationColumn {
+                name: name.to_string(),
+                data_type: data_type.to_string(),
+                nullable: name != "id",
+                default_value: None,
+            })
+            .collect(),
+            key_columns: vec!["id".to_string()],
+        }
+    }
+
+    fn keyset(direction: SortDirection, name: Option<&str>) -> String {
+        let relation = relation();
+        let sort = full_sort(
+            &relation,
+            &[SortKey {
+                column: "name".to_string(),
+                direction,
+            }],
+            true,
+        )
+        .unwrap();
+        let after = Cursor::from([
+            ("id".to_string(), Some("7".to_string())),
+            ("name".to_string(), name.map(str::to_string)),
+        ]);
+
+        let mut q = QueryBuilder::default();
+        push_keyset(&mut q, &relation, &sort, &after).unwrap();
+        q.sql
+    }
+
+    fn sort_key(column: &str) -> SortKey {
+        SortKey {
+            column: column.to_string(),
+            direction: SortDirection::Asc,
+        }
+    }
+
+    fn sort_columns(sort: &[SortKey], keyset: bool) -> Vec<String> {
+        full_sort(&relation(), sort, keyset)
+            .unwrap()
+            .into_iter()
+            .map(|key| key.column)
+            .collect()
+    }
+
+    #[test]
+    fn sorts_by_key_after_the_requested_columns() {
+        assert_eq!(sort_columns(&[], false), ["id"]);
+        assert_eq!(sort_columns(&[sort_key("name")], true), ["name", "id"]);
+    }
+
+    #[test]
+    fn keyset_sort_stops_at_columns_a_cursor_cant_hold() {
+        let sort = [sort_key("name"), sort_key("avatar"), sort_key("id")];
+        assert_eq!(sort_columns(&sort, false), ["name", "avatar", "id"]);
+        assert_eq!(sort_columns(&sort, true), ["name", "id"]);
+        assert_eq!(sort_columns(&[sort_key("tags")], true), ["id"]);
+    }
+
+    #[test]
+    fn ascending_keyset_includes_trailing_nulls() {
+        assert_eq!(
+            keyset(SortDirection::Asc, Some("bob")),
+            r#"((("name" > $1::text OR "name" IS NULL)) OR ("name" = $1::text AND "id" > $2::text))"#
+        );
+        assert_eq!(
+            keyset(SortDirection::Asc, None),
+            r#"(("name" IS NULL AND "id" > $1::text))"#
+        );
+    }
+
+    #[test]
+    fn descending_keyset_moves_past_leading_nulls() {
+        assert_eq!(
+            keyset(SortDirection::Desc, None),
+            r#"(("name" IS NOT NULL) OR ("name" IS NULL AND "id" > $1::text))"#
+        );
+        assert_eq!(
+            keyset(SortDirection::Desc, Some("bob")),
+            r#"(("name" < $1::text) OR ("name" = $1::text AND "id" > $2::text))"#
+        );
+    }
+}
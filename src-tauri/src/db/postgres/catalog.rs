@@ -0,0 +1,257 @@
+use sqlx::{Pool, Postgres, Row as SqlxRow};
+use std::collections::HashMap;
+
+use crate::db::catalog::{
+    CatalogSnapshot, ColumnSnapshot, ConstraintKind, ConstraintSnapshot, EnumSnapshot,
+    FunctionSnapshot, IndexSnapshot, SequenceSnapshot, TableSnapshot, SNAPSHOT_VERSION,
+};
+use crate::db::errors::DbResult;
+use crate::db::types::ServerFlavor;
+
+use super::{ddl, flavor};
+
+/// Reads the chosen schemas' tables, functions, enums and sequences, or every non-system schema's
+/// when none are chosen. Extension members are left out, since the extension recreates them.
+pub(crate) async fn snapshot(
+    pool: &Pool<Postgres>,
+    flavor: ServerFlavor,
+    schemas: &[String],
+) -> DbResult<CatalogSnapshot> {
+    // Qualified names make the snapshot independent of each server's search path
+    let mut tx = ddl::begin(pool).await?;
+
+    let schema_filter = format!(
+        r#"CASE
+            WHEN cardinality($1::TEXT[]) = 0 THEN NOT {}
+            ELSE n.nspname = ANY($1::TEXT[])
+        END"#,
+        flavor::system_schema_predicate(flavor)
+    );
+    let not_extension_member = |classid: &str, oid: &str| {
+        format!(
+            "NOT EXISTS (SELECT 1 FROM pg_depend d WHERE d.classid = '{}'::regclass AND d.objid = {} AND d.deptype = 'e')",
+            classid, oid
+        )
+    };
+
+    let schema_names = sqlx::query_scalar::<_, String>(&format!(
+        "SELECT n.nspname::TEXT FROM pg_namespace n WHERE {} AND {}",
+        schema_filter,
+        not_extension_member("pg_namespace", "n.oid")
+    ))
+    .bind(schemas)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let column_rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            c.oid::TEXT AS table_id,
+            n.nspname AS schema_name,
+            c.relname AS table_name,
+            a.attname AS name,
+            format_type(a.atttypid, a.atttypmod) AS data_type,
+            NOT a.attnotnull AS nullable,
+            CASE WHEN a.attgenerated = '' THEN pg_get_expr(d.adbin, d.adrelid) END AS default_value,
+            CASE
+                WHEN a.attidentity = 'a' THEN 'GENERATED ALWAYS AS IDENTITY'
+                WHEN a.attidentity = 'd' THEN 'GENERATED BY DEFAULT AS IDENTITY'
+                WHEN a.attgenerated = 's' THEN 'GENERATED ALWAYS AS (' || pg_get_expr(d.adbin, d.adrelid) || ') STORED'
+            END AS generated
+        FROM pg_class c
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        JOIN pg_attribute a ON a.attrelid = c.oid AND a.attnum > 0 AND NOT a.attisdropped
+        LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum
+        WHERE c.relkind IN ('r', 'p') AND {} AND {}
+        ORDER BY n.nspname, c.relname, a.attnum
+        "#,
+        schema_filter,
+        not_extension_member("pg_class", "c.oid")
+    ))
+    .bind(schemas)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut tables = Vec::<TableSnapshot>::new();
+    let mut table_index = HashMap::new();
+    for row in &column_rows {
+        let table_id: String = row.get("table_id");
+        let i = *table_index.entry(table_id).or_insert_with(|| {
+            tables.push(TableSnapshot {
+                schema: row.get("schema_name"),
+                name: row.get("table_name"),
+                columns: Vec::new(),
+                indexes: Vec::new(),
+                constraints: Vec::new(),
+            });
+            tables.len() - 1
+        });
+        tables[i].columns.push(ColumnSnapshot {
+            name: row.get("name"),
+            data_type: row.get("data_type"),
+            nullable: row.get("nullable"),
+            default_value: row.get("default_value"),
+            generated: row.get("generated"),
+        });
+    }
+
+    let index_rows = sqlx::query(
+        r#"
+        SELECT i.indrelid::TEXT AS table_id, ic.relname AS name, pg_get_indexdef(i.indexrelid) AS definition
+        FROM pg_index i
+        JOIN pg_class ic ON ic.oid = i.indexrelid
+        WHERE i.indrelid = ANY($1::TEXT[]::oid[])
+            AND NOT EXISTS (
+                SELECT 1 FROM pg_constraint
+                WHERE conindid = i.indexrelid AND contype IN ('p', 'u', 'x')
+            )
+            -- Partitions' copies of a partitioned table's indexes
+            AND NOT EXISTS (SELECT 1 FROM pg_inherits WHERE inhrelid = i.indexrelid)
+        "#,
+    )
+    .bind(table_index.keys().cloned().collect::<Vec<_>>())
+    .fetch_all(&mut *tx)
+    .await?;
+    for row in &index_rows {
+        if let Some(&i) = table_index.get(&row.get::<String, _>("table_id")) {
+            tables[i].indexes.push(IndexSnapshot {
+                name: row.get("name"),
+                definition: row.get("definition"),
+            });
+        }
+    }
+
+    let constraint_rows = sqlx::query(
+        r#"
+        SELECT
+            conrelid::TEXT AS table_id,
+            conname AS name,
+            contype::TEXT AS kind,
+            pg_get_constraintdef(oid, true) AS definition
+        FROM pg_constraint
+        WHERE conrelid = ANY($1::TEXT[]::oid[]) AND contype IN ('p', 'u', 'c', 'x', 'f')
+            AND conparentid = 0
+        "#,
+    )
+    .bind(table_index.keys().cloned().collect::<Vec<_>>())
+    .fetch_all(&mut *tx)
+    .await?;
+    for row in &constraint_rows {
+        let kind = match row.get::<String, _>("kind").as_str() {
+            "p" => ConstraintKind::PrimaryKey,
+            "u" => ConstraintKind::Unique,
+            "c" => ConstraintKind::Check,
+            "x" => ConstraintKind::Exclusion,
+            _ => ConstraintKind::ForeignKey,
+        };
+        if let Some(&i) = table_index.get(&row.get::<String, _>("table_id")) {
+            tables[i].constraints.push(ConstraintSnapshot {
+                name: row.get("name"),
+                kind,
+                definition: row.get("definition"),
+            });
+        }
+    }
+
+    let function_rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            n.nspname AS schema_name,
+            p.proname AS name,
+            pg_get_function_identity_arguments(p.oid) AS arguments,
+            p.prokind = 'p' AS is_procedure,
+            pg_get_functiondef(p.oid) AS definition
+        FROM pg_proc p
+        JOIN pg_namespace n ON n.oid = p.pronamespace
+        WHERE p.prokind IN ('f', 'p') AND {} AND {}
+        "#,
+        schema_filter,
+        not_extension_member("pg_proc", "p.oid")
+    ))
+    .bind(schemas)
+    .fetch_all(&mut *tx)
+    .await?;
+    let functions = function_rows
+        .iter()
+        .map(|row| FunctionSnapshot {
+            schema: row.get("schema_name"),
+            name: row.get("name"),
+            arguments: row.get("arguments"),
+            is_procedure: row.get("is_procedure"),
+            definition: format!("{};", row.get::<String, _>("definition").trim_end()),
+        })
+        .collect();
+
+    let enum_rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            n.nspname AS schema_name,
+            t.typname AS name,
+            ARRAY(
+                SELECT e.enumlabel::TEXT FROM pg_enum e
+                WHERE e.enumtypid = t.oid
+                ORDER BY e.enumsortorder
+            ) AS labels
+        FROM pg_type t
+        JOIN pg_namespace n ON n.oid = t.typnamespace
+        WHERE t.typtype = 'e' AND {} AND {}
+        "#,
+        schema_filter,
+        not_extension_member("pg_type", "t.oid")
+    ))
+    .bind(schemas)
+    .fetch_all(&mut *tx)
+    .await?;
+    let enums = enum_rows
+        .iter()
+        .map(|row| EnumSnapshot {
+            schema: row.get("schema_name"),
+            name: row.get("name"),
+            labels: row.get("labels"),
+        })
+        .collect();
+
+    let sequence_rows = sqlx::query(&format!(
+        r#"
+        SELECT {}
+        FROM pg_class c
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        JOIN pg_sequence s ON s.seqrelid = c.oid
+        WHERE {} AND {}
+            -- Identity columns create their own sequences
+            AND NOT EXISTS (
+                SELECT 1 FROM pg_depend d
+                WHERE d.classid = 'pg_class'::regclass AND d.objid = c.oid AND d.deptype = 'i'
+            )
+        "#,
+        ddl::SEQUENCE_COLUMNS,
+        schema_filter,
+        not_extension_member("pg_class", "c.oid")
+    ))
+    .bind(schemas)
+    .fetch_all(&mut *tx)
+    .await?;
+    let sequences = sequence_rows
+        .iter()
+        .map(|row| SequenceSnapshot {
+            schema: row.get("schema_name"),
+            name: row.get("name"),
+            options: ddl::sequence_options(row).join(" "),
+            owned_by: row.get("owned_by"),
+        })
+        .collect();
+
+    tx.rollback().await?;
+
+    let mut snapshot = CatalogSnapshot {
+        version: SNAPSHOT_VERSION,
+        requested_schemas: schemas.to_vec(),
+        schemas: schema_names,
+        tables,
+        functions,
+        enums,
+        sequences,
+    };
+    snapshot.sort();
+    Ok(snapshot)
+}
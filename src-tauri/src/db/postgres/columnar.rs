@@ -0,0 +1,379 @@
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::datatypes::{DataType, Field, Fields, Schema, TimeUnit};
+use arrow::json::ReaderBuilder;
+use futures_util::future::BoxFuture;
+use futures_util::StreamExt;
+use sqlx::{Column, Executor, Pool, Postgres, Row as SqlxRow, TypeInfo};
+
+use crate::db::errors::{DbError, DbResult};
+use crate::db::export::columnar::{ColumnarFormat, ColumnarWriter};
+use crate::db::export::ExportSource;
+
+use super::export::source_query;
+use super::relation::{quote_ident, quote_literal};
+
+/// Rows per record batch, and so per Parquet row group page
+const ROWS_PER_BATCH: usize = 8192;
+
+/// Arrow type for a Postgres type
+struct ArrowType {
+    data_type: DataType,
+    /// JSON values can't be decoded into a fixed Arrow type, so a column holding
+    /// them anywhere is exported as its text form instead
+    contains_json: bool,
+}
+
+impl ArrowType {
+    fn plain(data_type: DataType) -> Self {
+        Self {
+            data_type,
+            contains_json: false,
+        }
+    }
+}
+
+/// `numeric(p, s)` as a decimal when it fits; unconstrained numerics stay exact as text
+fn numeric_type(typmod: i32) -> DataType {
+    if typmod < 4 {
+        return DataType::Utf8;
+    }
+    let precision = ((typmod - 4) >> 16) & 0xffff;
+    // The scale is an 11-bit signed field, negative since Postgres 15
+    let scale = (((typmod - 4) & 0x7ff) ^ 0x400) - 0x400;
+    if (1..=38).contains(&precision) && scale >= 0 && scale <= precision {
+        DataType::Decimal128(precision as u8, scale as i8)
+    } else {
+        DataType::Utf8
+    }
+}
+
+/// Whether values of an Arrow type can hold something `to_jsonb` has no Arrow form for
+fn needs_mapping(data_type: &DataType) -> bool {
+    match data_type {
+        DataType::Timestamp(..) | DataType::Date32 | DataType::Decimal128(..) => true,
+        DataType::List(field) => needs_mapping(field.data_type()),
+        DataType::Struct(fields) => fields.iter().any(|f| needs_mapping(f.data_type())),
+        _ => false,
+    }
+}
+
+/// `jsonb` expression for a value, with the values Arrow types have no form for mapped
+/// explicitly: ±infinity becomes the largest and smallest value, as DuckDB writes them,
+/// and a NaN numeric in a decimal becomes NULL. Arrays and composites are rebuilt
+/// element by element so the mapping applies inside them too; `depth` keeps the
+/// aliases of nested arrays apart.
+fn jsonb_value(expr: &str, data_type: &DataType, depth: usize) -> String {
+    let infinity = match data_type {
+        DataType::Timestamp(..) => i64::MAX.to_string(),
+        DataType::Date32 => i32::MAX.to_string(),
+        DataType::Decimal128(..) => return format!("to_jsonb(NULLIF({}, 'NaN'))", expr),
+        DataType::List(field) if needs_mapping(field.data_type()) => {
+            let alias = format!("e{}", depth);
+            let element = jsonb_value(&format!("{}.v", alias), field.data_type(), depth + 1);
+            // Multidimensional arrays are flattened, matching their one-level list type
+            return format!(
+                "CASE WHEN {expr} IS NULL THEN NULL ELSE COALESCE((SELECT jsonb_agg({element} \
+                 ORDER BY {alias}.i) FROM unnest({expr}) WITH ORDINALITY AS {alias}(v, i)), \
+                 '[]'::jsonb) END"
+            );
+        }
+        DataType::Struct(fields) if needs_mapping(data_type) => {
+            let pairs = fields
+                .iter()
+                .map(|field| {
+                    let attribute = format!("({}).{}", expr, quote_ident(field.name()));
+                    format!(
+                        "{}, {}",
+                        quote_literal(field.name()),
+                        jsonb_value(&attribute, field.data_type(), depth)
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            // A composite IS NULL when all its fields are, so test the value itself
+            return format!(
+                "CASE WHEN num_nulls({}) = 1 THEN NULL ELSE jsonb_build_object({}) END",
+                expr, pairs
+            );
+        }
+        _ => return format!("to_jsonb({})", expr),
+    };
+    format!(
+        "CASE {} WHEN 'infinity' THEN to_jsonb({}) WHEN '-infinity' THEN to_jsonb(-{}) \
+         ELSE to_jsonb({}) END",
+        expr, infinity, infinity, expr
+    )
+}
+
+/// Select-list entry for a column of the exported query
+fn project(name: &str, data_type: &DataType) -> String {
+    let column = format!("q.{}", name);
+    if needs_mapping(data_type) {
+        format!("{} AS {}", jsonb_value(&column, data_type, 0), name)
+    } else {
+        column
+    }
+}
+
+/// Maps a type by OID, following domains to their base type, arrays to lists and
+/// composite types to structs. Base types match the ones `decode::to_json` renders.
+fn arrow_type(pool: &Pool<Postgres>, oid: u32, typmod: i32) -> BoxFuture<'_, DbResult<ArrowType>> {
+    Box::pin(async move {
+        let row = sqlx::query(
+            r#"
+            SELECT
+                typname::TEXT AS name,
+                typtype::TEXT AS kind,
+                typcategory::TEXT AS category,
+                typelem::INT8 AS element,
+                typrelid::INT8 AS relation,
+                typbasetype::INT8 AS base,
+                typtypmod AS base_typmod
+            FROM pg_type
+            WHERE oid = $1::INT8::oid
+            "#,
+        )
+        .bind(oid as i64)
+        .fetch_one(pool)
+        .await?;
+
+        let name: String = row.get("name");
+        let kind: String = row.get("kind");
+        let category: String = row.get("category");
+
+        if kind == "d" {
+            let base: i64 = row.get("base");
+            return arrow_type(pool, base as u32, row.get("base_typmod")).await;
+        }
+
+        if category == "A" {
+            let element: i64 = row.get("element");
+            // An array column's typmod applies to its elements
+            let element = arrow_type(pool, element as u32, typmod).await?;
+            return Ok(ArrowType {
+                data_type: DataType::List(Arc::new(Field::new("item", element.data_type, true))),
+                contains_json: element.contains_json,
+            });
+        }
+
+        if kind == "c" {
+            let relation: i64 = row.get("relation");
+            let attributes = sqlx::query(
+                r#"
+                SELECT attname::TEXT AS name, atttypid::INT8 AS type_id, atttypmod AS typmod
+                FROM pg_attribute
+                WHERE attrelid = $1::INT8::oid AND attnum > 0 AND NOT attisdropped
+                ORDER BY attnum
+                "#,
+            )
+            .bind(relation)
+            .fetch_all(pool)
+            .await?;
+
+            let mut fields = Vec::with_capacity(attributes.len());
+            let mut contains_json = false;
+            for attribute in attributes {
+                let type_id: i64 = attribute.get("type_id");
+                let field_type = arrow_type(pool, type_id as u32, attribute.get("typmod")).await?;
+                contains_json |= field_type.contains_json;
+                fields.push(Field::new(
+                    attribute.get::<String, _>("name"),
+                    field_type.data_type,
+                    true,
+                ));
+            }
+            return Ok(ArrowType {
+                data_type: DataType::Struct(Fields::from(fields)),
+                contains_json,
+            });
+        }
+
+        Ok(match name.as_str() {
+            "bool" => ArrowType::plain(DataType::Boolean),
+            "int2" => ArrowType::plain(DataType::Int16),
+            "int4" => ArrowType::plain(DataType::Int32),
+            "int8" => ArrowType::plain(DataType::Int64),
+            "float4" => ArrowType::plain(DataType::Float32),
+            "float8" => ArrowType::plain(DataType::Float64),
+            "numeric" => ArrowType::plain(numeric_type(typmod)),
+            "date" => ArrowType::plain(DataType::Date32),
+            "time" => ArrowType::plain(DataType::Time64(TimeUnit::Microsecond)),
+            "timestamp" => ArrowType::plain(DataType::Timestamp(TimeUnit::Microsecond, None)),
+            "timestamptz" => ArrowType::plain(DataType::Timestamp(
+                TimeUnit::Microsecond,
+                Some("UTC".into()),
+            )),
+            "json" | "jsonb" => ArrowType {
+                data_type: DataType::Utf8,
+                contains_json: true,
+            },
+            // Text, UUIDs, enums, intervals, bytea and anything else keep their text form
+            _ => ArrowType::plain(DataType::Utf8),
+        })
+    })
+}
+
+/// Exports a query's full result or a whole table to Parquet or Arrow IPC.
+/// Rows are read as `to_jsonb` text, which keeps numerics exact and nests arrays and
+/// composites, then decoded against the Arrow schema a batch at a time.
+pub(crate) async fn export_columnar(
+    pool: &Pool<Postgres>,
+    source: &ExportSource,
+    format: ColumnarFormat,
+    path: &Path,
+) -> DbResult<u64> {
+    let query = source_query(pool, source).await?;
+    let description = pool.describe(&query).await?;
+
+    let mut names = HashSet::new();
+    let mut fields = Vec::new();
+    let mut projection = Vec::new();
+    for column in description.columns() {
+        if !names.insert(column.name()) {
+            return Err(DbError::Query(format!(
+                "Column names must be unique, but {} appears more than once",
+                column.name()
+            )));
+        }
+
+        let oid = column.type_info().oid().ok_or_else(|| {
+            DbError::Unsupported(format!("Unknown type {}", column.type_info().name()))
+        })?;
+        let typmod = match (column.relation_id(), column.relation_attribute_no()) {
+            (Some(relation), Some(attribute)) => sqlx::query_scalar(
+                "SELECT atttypmod FROM pg_attribute WHERE attrelid = $1 AND attnum = $2",
+            )
+            .bind(relation)
+            .bind(attribute)
+            .fetch_optional(pool)
+            .await?
+            .unwrap_or(-1),
+            _ => -1,
+        };
+
+        let column_type = arrow_type(pool, oid.0, typmod).await?;
+        let name = quote_ident(column.name());
+        if column_type.contains_json {
+            fields.push(Field::new(column.name(), DataType::Utf8, true));
+            projection.push(format!("q.{}::TEXT AS {}", name, name));
+        } else {
+            projection.push(project(&name, &column_type.data_type));
+            fields.push(Field::new(column.name(), column_type.data_type, true));
+        }
+    }
+
+    let schema = Arc::new(Schema::new(fields));
+    let sql = format!(
+        "SELECT to_jsonb(t)::TEXT FROM (SELECT {} FROM ({}\n) AS q) AS t",
+        projection.join(", "),
+        query
+    );
+
+    // Coercion lets numbers land in text columns with their exact digits
+    let mut decoder = ReaderBuilder::new(schema.clone())
+        .with_batch_size(ROWS_PER_BATCH)
+        .with_coerce_primitive(true)
+        .build_decoder()?;
+    let mut writer = ColumnarWriter::create(path, format, schema).await?;
+
+    let mut rows = sqlx::query_scalar::<_, String>(&sql).fetch(pool);
+    let mut buffered = 0;
+    let mut total = 0;
+    while let Some(row) = rows.next().await {
+        decoder.decode(row?.as_bytes())?;
+        buffered += 1;
+        total += 1;
+
+        if buffered == ROWS_PER_BATCH {
+            if let Some(batch) = decoder.flush()? {
+                writer.write(&batch).await?;
+            }
+            buffered = 0;
+        }
+    }
+
+    if let Some(batch) = decoder.flush()? {
+        writer.write(&batch).await?;
+    }
+    writer.finish().await?;
+
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn typmod(precision: i32, scale: i32) -> i32 {
+        ((precision << 16) | (scale & 0x7ff)) + 4
+    }
+
+    fn list(data_type: DataType) -> DataType {
+        DataType::List(Arc::new(Field::new("item", data_type, true)))
+    }
+
+    #[test]
+    fn numerics_fit_decimals_when_constrained() {
+        assert_eq!(numeric_type(-1), DataType::Utf8);
+        assert_eq!(numeric_type(typmod(10, 2)), DataType::Decimal128(10, 2));
+        assert_eq!(numeric_type(typmod(38, 0)), DataType::Decimal128(38, 0));
+        assert_eq!(numeric_type(typmod(39, 2)), DataType::Utf8);
+        // Negative scales and scales past the precision are allowed since Postgres 15
+        assert_eq!(numeric_type(typmod(5, -2)), DataType::Utf8);
+        assert_eq!(numeric_type(typmod(3, 5)), DataType::Utf8);
+    }
+
+    #[test]
+    fn plain_columns_are_selected_as_is() {
+        assert_eq!(project("\"id\"", &DataType::Int64), "q.\"id\"");
+        assert_eq!(project("\"tags\"", &list(DataType::Utf8)), "q.\"tags\"");
+    }
+
+    #[test]
+    fn maps_infinity_and_nan() {
+        let timestamp = DataType::Timestamp(TimeUnit::Microsecond, None);
+        assert_eq!(
+            project("\"at\"", &timestamp),
+            "CASE q.\"at\" WHEN 'infinity' THEN to_jsonb(9223372036854775807) \
+             WHEN '-infinity' THEN to_jsonb(-9223372036854775807) \
+             ELSE to_jsonb(q.\"at\") END AS \"at\""
+        );
+        assert_eq!(
+            project("\"price\"", &DataType::Decimal128(10, 2)),
+            "to_jsonb(NULLIF(q.\"price\", 'NaN')) AS \"price\""
+        );
+    }
+
+    #[test]
+    fn maps_values_inside_arrays_and_composites() {
+        let timestamps = project(
+            "\"at\"",
+            &list(DataType::Timestamp(TimeUnit::Microsecond, None)),
+        );
+        assert!(timestamps.contains("unnest(q.\"at\") WITH ORDINALITY AS e0(v, i)"));
+        assert!(timestamps.contains("CASE e0.v WHEN 'infinity'"));
+
+        let composite = DataType::Struct(Fields::from(vec![
+            Field::new("name", DataType::Utf8, true),
+            Field::new("since", DataType::Date32, true),
+        ]));
+        let projected = project("\"member\"", &composite);
+        assert!(projected.starts_with("CASE WHEN num_nulls(q.\"member\") = 1 THEN NULL"));
+        assert!(projected.contains("'name', to_jsonb((q.\"member\").\"name\")"));
+        assert!(projected.contains("'since', CASE (q.\"member\").\"since\" WHEN 'infinity'"));
+
+        // An array of composites holding arrays, e.g. a shift[] whose shift has a date[]
+        let shift = DataType::Struct(Fields::from(vec![Field::new(
+            "days",
+            list(DataType::Date32),
+            true,
+        )]));
+        let shifts = project("\"shifts\"", &list(shift));
+        assert!(shifts.contains("unnest(q.\"shifts\") WITH ORDINALITY AS e0(v, i)"));
+        assert!(shifts.contains("unnest((e0.v).\"days\") WITH ORDINALITY AS e1(v, i)"));
+        assert!(shifts.contains("CASE e1.v WHEN 'infinity' THEN to_jsonb(2147483647)"));
+    }
+}
@@ -0,0 +1,895 @@
+use sqlx::postgres::PgRow;
+use sqlx::{PgConnection, Pool, Postgres, Row as SqlxRow, Transaction};
+
+use crate::db::errors::{DbError, DbResult};
+
+use super::relation::{quote_ident, quote_literal};
+
+/// Rebuilds CREATE statements from the catalog. Expressions come from the server's own
+/// deparsers (pg_get_expr, pg_get_viewdef, ...), which only schema-qualify names that aren't
+/// on the search path, so callers clear it first when the output must run anywhere.
+pub(crate) fn qualified(schema: &str, name: &str) -> String {
+    format!("{}.{}", quote_ident(schema), quote_ident(name))
+}
+
+/// Starts a read-only, repeatable-read transaction with an empty search path, so every
+/// statement built in it sees one snapshot and prints fully qualified names
+pub(crate) async fn begin(pool: &Pool<Postgres>) -> DbResult<Transaction<'static, Postgres>> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("SELECT set_config('search_path', '', true)")
+        .execute(&mut *tx)
+        .await?;
+    Ok(tx)
+}
+
+/// Keyword naming a kind of relation in COMMENT ON, ALTER ... OWNER TO and GRANT
+fn relation_keyword(relkind: &str) -> &'static str {
+    match relkind {
+        "v" => "VIEW",
+        "m" => "MATERIALIZED VIEW",
+        "f" => "FOREIGN TABLE",
+        "S" => "SEQUENCE",
+        // Privileges on every other relation are granted ON TABLE
+        _ => "TABLE",
+    }
+}
+
+pub(crate) fn create_schema(name: &str) -> String {
+    format!("CREATE SCHEMA IF NOT EXISTS {};", quote_ident(name))
+}
+
+/// A table's CREATE TABLE and the statements that have to wait until its rows are loaded
+#[derive(Debug, Default)]
+pub(crate) struct TableDdl {
+    pub create: String,
+    /// Primary key, unique and exclusion constraints
+    pub keys: Vec<String>,
+    /// Kept apart so every referenced key exists before they're added
+    pub foreign_keys: Vec<String>,
+    /// Indexes not backing a constraint
+    pub indexes: Vec<String>,
+    pub triggers: Vec<String>,
+}
+
+/// A sequence and, when it belongs to a column, the statement tying it to the column
+#[derive(Debug)]
+pub(crate) struct SequenceDdl {
+    pub create: String,
+    pub owned_by: Option<String>,
+}
+
+fn not_found(kind: &str, id: &str) -> DbError {
+    DbError::NotFound(format!("{} {}", kind, id))
+}
+
+pub(crate) async fn table(conn: &mut PgConnection, id: &str) -> DbResult<TableDdl> {
+    let row = sqlx::query(
+        r#"
+        SELECT
+            n.nspname AS schema_name,
+            c.relname AS name,
+            c.relkind::TEXT AS kind,
+            c.relpersistence = 'u' AS unlogged,
+            c.relispartition AS is_partition,
+            CASE WHEN c.relkind = 'p' THEN pg_get_partkeydef(c.oid) END AS partition_key,
+            pg_get_expr(c.relpartbound, c.oid) AS partition_bound,
+            array_to_string(c.reloptions, ', ') AS options,
+            ARRAY(
+                SELECT format('%I.%I', pn.nspname, pc.relname)
+                FROM pg_inherits i
+                JOIN pg_class pc ON pc.oid = i.inhparent
+                JOIN pg_namespace pn ON pn.oid = pc.relnamespace
+                WHERE i.inhrelid = c.oid
+                ORDER BY i.inhseqno
+            ) AS parents
+        FROM pg_class c
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE c.oid = $1::oid AND c.relkind IN ('r', 'p', 'f')
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| not_found("Table", id))?;
+
+    let name = qualified(row.get("schema_name"), row.get("name"));
+    let kind = row.get::<String, _>("kind");
+    let is_partitioned = kind == "p";
+    let is_partition: bool = row.get("is_partition");
+    let parents: Vec<String> = row.get("parents");
+
+    let column_rows = sqlx::query(
+        r#"
+        SELECT
+            a.attname AS name,
+            format_type(a.atttypid, a.atttypmod) AS data_type,
+            a.attnotnull AS not_null,
+            pg_get_expr(d.adbin, d.adrelid) AS default_expr,
+            a.attidentity::TEXT AS identity,
+            a.attgenerated::TEXT AS generated,
+            CASE WHEN a.attcollation <> t.typcollation THEN (
+                SELECT format('%I.%I', cn.nspname, co.collname)
+                FROM pg_collation co
+                JOIN pg_namespace cn ON cn.oid = co.collnamespace
+                WHERE co.oid = a.attcollation
+            ) END AS collation
+        FROM pg_attribute a
+        JOIN pg_type t ON t.oid = a.atttypid
+        LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum
+        WHERE a.attrelid = $1::oid AND a.attnum > 0 AND NOT a.attisdropped AND a.attislocal
+        ORDER BY a.attnum
+        "#,
+    )
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut elements = Vec::new();
+    // A partition takes its columns from the parent
+    if !is_partition {
+        for column in &column_rows {
+            let mut line = format!(
+                "{} {}",
+                quote_ident(column.get("name")),
+                column.get::<String, _>("data_type")
+            );
+            if let Some(collation) = column.get::<Option<String>, _>("collation") {
+                line.push_str(&format!(" COLLATE {}", collation));
+            }
+            let default_expr: Option<String> = column.get("default_expr");
+            match (column.get::<String, _>("generated").as_str(), default_expr) {
+                ("s", Some(expr)) => {
+                    line.push_str(&format!(" GENERATED ALWAYS AS ({}) STORED", expr))
+                }
+                (_, Some(expr)) => line.push_str(&format!(" DEFAULT {}", expr)),
+                _ => {}
+            }
+            match column.get::<String, _>("identity").as_str() {
+                "a" => line.push_str(" GENERATED ALWAYS AS IDENTITY"),
+                "d" => line.push_str(" GENERATED BY DEFAULT AS IDENTITY"),
+                _ => {}
+            }
+            if column.get("not_null") {
+                line.push_str(" NOT NULL");
+            }
+            elements.push(line);
+        }
+    }
+
+    let check_rows = sqlx::query(
+        r#"
+        SELECT conname AS name, pg_get_constraintdef(oid, true) AS definition
+        FROM pg_constraint
+        WHERE conrelid = $1::oid AND contype = 'c' AND conislocal
+        ORDER BY conname
+        "#,
+    )
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await?;
+    elements.extend(check_rows.iter().map(|check| {
+        format!(
+            "CONSTRAINT {} {}",
+            quote_ident(check.get("name")),
+            check.get::<String, _>("definition")
+        )
+    }));
+
+    let mut create = if kind == "f" {
+        format!("CREATE FOREIGN TABLE {}", name)
+    } else {
+        format!(
+            "CREATE {}TABLE {}",
+            if row.get("unlogged") { "UNLOGGED " } else { "" },
+            name
+        )
+    };
+    if is_partition {
+        create.push_str(&format!(" PARTITION OF {}", parents.join(", ")));
+        if !elements.is_empty() {
+            create.push_str(&format!(" (\n    {}\n)", elements.join(",\n    ")));
+        }
+        if let Some(bound) = row.get::<Option<String>, _>("partition_bound") {
+            create.push_str(&format!("\n{}", bound));
+        }
+    } else {
+        create.push_str(&format!(" (\n    {}\n)", elements.join(",\n    ")));
+        if !parents.is_empty() {
+            create.push_str(&format!("\nINHERITS ({})", parents.join(", ")));
+        }
+    }
+    if let Some(key) = row.get::<Option<String>, _>("partition_key") {
+        create.push_str(&format!("\nPARTITION BY {}", key));
+    }
+    if kind == "f" {
+        let server = sqlx::query(
+            r#"
+            SELECT
+                s.srvname AS server,
+                (
+                    SELECT string_agg(quote_ident(option_name) || ' ' || quote_literal(option_value), ', ')
+                    FROM pg_options_to_table(ft.ftoptions)
+                ) AS options
+            FROM pg_foreign_table ft
+            JOIN pg_foreign_server s ON s.oid = ft.ftserver
+            WHERE ft.ftrelid = $1::oid
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *conn)
+        .await?;
+        create.push_str(&format!("\nSERVER {}", quote_ident(server.get("server"))));
+        if let Some(options) = server.get::<Option<String>, _>("options") {
+            create.push_str(&format!("\nOPTIONS ({})", options));
+        }
+    }
+    if let Some(options) = row.get::<Option<String>, _>("options") {
+        create.push_str(&format!("\nWITH ({})", options));
+    }
+    create.push(';');
+
+    // Constraints on a partitioned table are created on its partitions too,
+    // so the copies the partitions hold are left out
+    let constraint_rows = sqlx::query(
+        r#"
+        SELECT conname AS name, contype::TEXT AS kind, pg_get_constraintdef(oid, true) AS definition
+        FROM pg_constraint
+        WHERE conrelid = $1::oid AND contype IN ('p', 'u', 'x', 'f') AND conparentid = 0
+        ORDER BY array_position(ARRAY['p', 'u', 'x', 'f']::"char"[], contype), conname
+        "#,
+    )
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    // ONLY keeps inheritance children from getting the constraint, but a partitioned
+    // table's constraints have to reach its partitions
+    let alter = if is_partitioned {
+        format!("ALTER TABLE {}", name)
+    } else {
+        format!("ALTER TABLE ONLY {}", name)
+    };
+
+    let mut ddl = TableDdl {
+        create,
+        ..Default::default()
+    };
+    for constraint in &constraint_rows {
+        let statement = format!(
+            "{} ADD CONSTRAINT {} {};",
+            alter,
+            quote_ident(constraint.get("name")),
+            constraint.get::<String, _>("definition")
+        );
+        if constraint.get::<String, _>("kind") == "f" {
+            ddl.foreign_keys.push(statement);
+        } else {
+            ddl.keys.push(statement);
+        }
+    }
+
+    let index_rows = sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT pg_get_indexdef(i.indexrelid)
+        FROM pg_index i
+        JOIN pg_class ic ON ic.oid = i.indexrelid
+        WHERE i.indrelid = $1::oid
+            AND NOT EXISTS (SELECT 1 FROM pg_constraint WHERE conindid = i.indexrelid AND contype IN ('p', 'u', 'x'))
+            AND NOT EXISTS (SELECT 1 FROM pg_inherits WHERE inhrelid = i.indexrelid)
+        ORDER BY ic.relname
+        "#,
+    )
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await?;
+    // Without ONLY, an index on a partitioned table is built on every partition as well
+    ddl.indexes = index_rows
+        .into_iter()
+        .map(|index| format!("{};", index.replacen(" ON ONLY ", " ON ", 1)))
+        .collect();
+
+    // Triggers a partition got from its parent are created along with the parent's
+    ddl.triggers = sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT pg_get_triggerdef(t.oid, true)
+        FROM pg_trigger t
+        WHERE t.tgrelid = $1::oid
+            AND NOT t.tgisinternal
+            AND NOT EXISTS (
+                SELECT 1
+                FROM pg_inherits i
+                JOIN pg_trigger pt ON pt.tgrelid = i.inhparent AND pt.tgname = t.tgname
+                WHERE i.inhrelid = t.tgrelid
+            )
+        ORDER BY t.tgname
+        "#,
+    )
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .map(|trigger| format!("{};", trigger))
+    .collect();
+
+    Ok(ddl)
+}
+
+/// CREATE VIEW or CREATE MATERIALIZED VIEW; materialized views are created empty
+pub(crate) async fn view(conn: &mut PgConnection, id: &str) -> DbResult<String> {
+    let row = sqlx::query(
+        r#"
+        SELECT
+            n.nspname AS schema_name,
+            c.relname AS name,
+            c.relkind::TEXT AS kind,
+            pg_get_viewdef(c.oid, true) AS definition,
+            array_to_string(c.reloptions, ', ') AS options
+        FROM pg_class c
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE c.oid = $1::oid AND c.relkind IN ('v', 'm')
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| not_found("View", id))?;
+
+    let name = qualified(row.get("schema_name"), row.get("name"));
+    let definition = row.get::<String, _>("definition");
+    let definition = definition.trim().trim_end_matches(';');
+    let options = row
+        .get::<Option<String>, _>("options")
+        .map(|options| format!(" WITH ({})", options))
+        .unwrap_or_default();
+
+    Ok(if row.get::<String, _>("kind") == "m" {
+        format!(
+            "CREATE MATERIALIZED VIEW {}{} AS\n{}\nWITH NO DATA;",
+            name, options, definition
+        )
+    } else {
+        format!("CREATE VIEW {}{} AS\n{};", name, options, definition)
+    })
+}
+
+/// Columns describing a sequence, selected from `pg_class c`, `pg_namespace n` and
+/// `pg_sequence s`, and read by `sequence_options`
+pub(crate) const SEQUENCE_COLUMNS: &str = r#"
+    n.nspname AS schema_name,
+    c.relname AS name,
+    format_type(s.seqtypid, NULL) AS data_type,
+    s.seqincrement AS increment,
+    s.seqmin AS min_value,
+    s.seqmax AS max_value,
+    s.seqstart AS start,
+    s.seqcache AS cache,
+    s.seqcycle AS cycle,
+    (
+        SELECT format('%I.%I.%I', tn.nspname, t.relname, a.attname)
+        FROM pg_depend d
+        JOIN pg_class t ON t.oid = d.refobjid
+        JOIN pg_namespace tn ON tn.oid = t.relnamespace
+        JOIN pg_attribute a ON a.attrelid = d.refobjid AND a.attnum = d.refobjsubid
+        WHERE d.classid = 'pg_class'::regclass
+            AND d.objid = c.oid
+            AND d.refclassid = 'pg_class'::regclass
+            AND d.deptype = 'a'
+    ) AS owned_by
+"#;
+
+/// Options following `CREATE SEQUENCE <name>`, which ALTER SEQUENCE takes as well
+pub(crate) fn sequence_options(row: &PgRow) -> Vec<String> {
+    vec![
+        format!("AS {}", row.get::<String, _>("data_type")),
+        format!("INCREMENT BY {}", row.get::<i64, _>("increment")),
+        format!("MINVALUE {}", row.get::<i64, _>("min_value")),
+        format!("MAXVALUE {}", row.get::<i64, _>("max_value")),
+        format!("START WITH {}", row.get::<i64, _>("start")),
+        format!("CACHE {}", row.get::<i64, _>("cache")),
+        if row.get("cycle") {
+            "CYCLE"
+        } else {
+            "NO CYCLE"
+        }
+        .to_string(),
+    ]
+}
+
+pub(crate) async fn sequence(conn: &mut PgConnection, id: &str) -> DbResult<SequenceDdl> {
+    let row = sqlx::query(&format!(
+        r#"
+        SELECT {}
+        FROM pg_class c
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        JOIN pg_sequence s ON s.seqrelid = c.oid
+        WHERE c.oid = $1::oid
+        "#,
+        SEQUENCE_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| not_found("Sequence", id))?;
+
+    let name = qualified(row.get("schema_name"), row.get("name"));
+    let create = format!(
+        "CREATE SEQUENCE {}\n    {};",
+        name,
+        sequence_options(&row).join("\n    ")
+    );
+    let owned_by = row
+        .get::<Option<String>, _>("owned_by")
+        .map(|column| format!("ALTER SEQUENCE {} OWNED BY {};", name, column));
+
+    Ok(SequenceDdl { create, owned_by })
+}
+
+/// CREATE TYPE or CREATE DOMAIN for enums, domains, ranges and composite types
+pub(crate) async fn custom_type(conn: &mut PgConnection, id: &str) -> DbResult<String> {
+    let row = sqlx::query(
+        r#"
+        SELECT
+            n.nspname AS schema_name,
+            t.typname AS name,
+            t.typtype::TEXT AS kind,
+            t.typrelid::TEXT AS relation_id,
+            format_type(t.typbasetype, t.typtypmod) AS base_type,
+            t.typnotnull AS not_null,
+            t.typdefault AS default_expr
+        FROM pg_type t
+        JOIN pg_namespace n ON n.oid = t.typnamespace
+        WHERE t.oid = $1::oid
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| not_found("Type", id))?;
+
+    let name = qualified(row.get("schema_name"), row.get("name"));
+    match row.get::<String, _>("kind").as_str() {
+        "e" => {
+            let labels = sqlx::query_scalar::<_, String>(
+                "SELECT enumlabel::TEXT FROM pg_enum WHERE enumtypid = $1::oid ORDER BY enumsortorder",
+            )
+            .bind(id)
+            .fetch_all(&mut *conn)
+            .await?;
+            let labels = labels
+                .iter()
+                .map(|label| quote_literal(label))
+                .collect::<Vec<_>>();
+            Ok(format!(
+                "CREATE TYPE {} AS ENUM (\n    {}\n);",
+                name,
+                labels.join(",\n    ")
+            ))
+        }
+        "d" => {
+            let mut ddl = format!(
+                "CREATE DOMAIN {} AS {}",
+                name,
+                row.get::<String, _>("base_type")
+            );
+            if let Some(default_expr) = row.get::<Option<String>, _>("default_expr") {
+                ddl.push_str(&format!("\n    DEFAULT {}", default_expr));
+            }
+            if row.get("not_null") {
+                ddl.push_str("\n    NOT NULL");
+            }
+            let checks = sqlx::query(
+                r#"
+                SELECT conname AS name, pg_get_constraintdef(oid, true) AS definition
+                FROM pg_constraint
+                WHERE contypid = $1::oid AND contype = 'c'
+                ORDER BY conname
+                "#,
+            )
+            .bind(id)
+            .fetch_all(&mut *conn)
+            .await?;
+            for check in &checks {
+                ddl.push_str(&format!(
+                    "\n    CONSTRAINT {} {}",
+                    quote_ident(check.get("name")),
+                    check.get::<String, _>("definition")
+                ));
+            }
+            ddl.push(';');
+            Ok(ddl)
+        }
+        "c" => {
+            let attributes = sqlx::query(
+                r#"
+                SELECT a.attname AS name, format_type(a.atttypid, a.atttypmod) AS data_type
+                FROM pg_attribute a
+                WHERE a.attrelid = $1::oid AND a.attnum > 0 AND NOT a.attisdropped
+                ORDER BY a.attnum
+                "#,
+            )
+            .bind(row.get::<String, _>("relation_id"))
+            .fetch_all(&mut *conn)
+            .await?;
+            let attributes = attributes
+                .iter()
+                .map(|a| {
+                    format!(
+                        "{} {}",
+                        quote_ident(a.get("name")),
+                        a.get::<String, _>("data_type")
+                    )
+                })
+                .collect::<Vec<_>>();
+            Ok(format!(
+                "CREATE TYPE {} AS (\n    {}\n);",
+                name,
+                attributes.join(",\n    ")
+            ))
+        }
+        "r" => {
+            let subtype = sqlx::query_scalar::<_, String>(
+                "SELECT format_type(rngsubtype, NULL) FROM pg_range WHERE rngtypid = $1::oid",
+            )
+            .bind(id)
+            .fetch_one(&mut *conn)
+            .await?;
+            Ok(format!(
+                "CREATE TYPE {} AS RANGE (SUBTYPE = {});",
+                name, subtype
+            ))
+        }
+        _ => Err(DbError::Unsupported(format!(
+            "{} is a base type, which can only be created from C code",
+            name
+        ))),
+    }
+}
+
+/// CREATE OR REPLACE FUNCTION or PROCEDURE, as the server prints it
+pub(crate) async fn function(conn: &mut PgConnection, id: &str) -> DbResult<String> {
+    let definition = sqlx::query_scalar::<_, String>(
+        "SELECT pg_get_functiondef(p.oid) FROM pg_proc p WHERE p.oid = $1::oid AND p.prokind IN ('f', 'p')",
+    )
+    .bind(id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| not_found("Function", id))?;
+
+    Ok(format!("{};", definition.trim_end()))
+}
+
+/// COMMENT ON statements for a relation and its columns
+pub(crate) async fn comments(conn: &mut PgConnection, id: &str) -> DbResult<Vec<String>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+            n.nspname AS schema_name,
+            c.relname AS name,
+            c.relkind::TEXT AS kind,
+            a.attname AS column_name,
+            d.description
+        FROM pg_description d
+        JOIN pg_class c ON c.oid = d.objoid
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        LEFT JOIN pg_attribute a ON a.attrelid = c.oid AND a.attnum = d.objsubid
+        WHERE d.objoid = $1::oid AND d.classoid = 'pg_class'::regclass
+        ORDER BY d.objsubid
+        "#,
+    )
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| {
+            let name = qualified(row.get("schema_name"), row.get("name"));
+            let description = quote_literal(row.get("description"));
+            match row.get::<Option<&str>, _>("column_name") {
+                Some(column) => format!(
+                    "COMMENT ON COLUMN {}.{} IS {};",
+                    name,
+                    quote_ident(column),
+                    description
+                ),
+                None => format!(
+                    "COMMENT ON {} {} IS {};",
+                    relation_keyword(row.get("kind")),
+                    name,
+                    description
+                ),
+            }
+        })
+        .collect())
+}
+
+/// Whether row level security is on for a table, and its policies
+pub(crate) async fn row_security(conn: &mut PgConnection, id: &str) -> DbResult<Vec<String>> {
+    let row = sqlx::query(
+        r#"
+        SELECT
+            n.nspname AS schema_name,
+            c.relname AS name,
+            c.relrowsecurity AS enabled,
+            c.relforcerowsecurity AS forced
+        FROM pg_class c
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE c.oid = $1::oid
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| not_found("Table", id))?;
+
+    let name = qualified(row.get("schema_name"), row.get("name"));
+    let mut statements = Vec::new();
+    if row.get("enabled") {
+        statements.push(format!("ALTER TABLE {} ENABLE ROW LEVEL SECURITY;", name));
+    }
+    if row.get("forced") {
+        statements.push(format!("ALTER TABLE {} FORCE ROW LEVEL SECURITY;", name));
+    }
+
+    let policies = sqlx::query(
+        r#"
+        SELECT
+            p.polname AS name,
+            p.polpermissive AS permissive,
+            CASE p.polcmd
+                WHEN 'r' THEN 'SELECT'
+                WHEN 'a' THEN 'INSERT'
+                WHEN 'w' THEN 'UPDATE'
+                WHEN 'd' THEN 'DELETE'
+                ELSE 'ALL'
+            END AS command,
+            CASE WHEN p.polroles = '{0}' THEN ARRAY['PUBLIC']
+            ELSE ARRAY(
+                SELECT quote_ident(r.rolname) FROM pg_roles r
+                WHERE r.oid = ANY(p.polroles)
+                ORDER BY r.rolname
+            ) END AS roles,
+            pg_get_expr(p.polqual, p.polrelid) AS using_expr,
+            pg_get_expr(p.polwithcheck, p.polrelid) AS check_expr
+        FROM pg_policy p
+        WHERE p.polrelid = $1::oid
+        ORDER BY p.polname
+        "#,
+    )
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for policy in &policies {
+        let mut statement = format!(
+            "CREATE POLICY {} ON {}\n    AS {}\n    FOR {}\n    TO {}",
+            quote_ident(policy.get("name")),
+            name,
+            if policy.get("permissive") {
+                "PERMISSIVE"
+            } else {
+                "RESTRICTIVE"
+            },
+            policy.get::<String, _>("command"),
+            policy.get::<Vec<String>, _>("roles").join(", ")
+        );
+        if let Some(using_expr) = policy.get::<Option<String>, _>("using_expr") {
+            statement.push_str(&format!("\n    USING ({})", using_expr));
+        }
+        if let Some(check_expr) = policy.get::<Option<String>, _>("check_expr") {
+            statement.push_str(&format!("\n    WITH CHECK ({})", check_expr));
+        }
+        statement.push(';');
+        statements.push(statement);
+    }
+
+    Ok(statements)
+}
+
+/// The owner of a relation and the privileges granted on it to other roles
+pub(crate) async fn privileges(conn: &mut PgConnection, id: &str) -> DbResult<Vec<String>> {
+    let row = sqlx::query(
+        r#"
+        SELECT
+            n.nspname AS schema_name,
+            c.relname AS name,
+            c.relkind::TEXT AS kind,
+            pg_get_userbyid(c.relowner) AS owner
+        FROM pg_class c
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE c.oid = $1::oid
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| not_found("Relation", id))?;
+
+    let name = qualified(row.get("schema_name"), row.get("name"));
+    let kind = row.get::<String, _>("kind");
+    let mut statements = vec![format!(
+        "ALTER {} {} OWNER TO {};",
+        relation_keyword(&kind),
+        name,
+        quote_ident(row.get("owner"))
+    )];
+
+    // The owner's own privileges are implied by ownership
+    let grants = sqlx::query(
+        r#"
+        SELECT
+            CASE WHEN a.grantee = 0 THEN 'PUBLIC' ELSE quote_ident(pg_get_userbyid(a.grantee)) END AS grantee,
+            string_agg(a.privilege_type, ', ' ORDER BY a.privilege_type) AS privileges,
+            a.is_grantable
+        FROM pg_class c
+        CROSS JOIN LATERAL aclexplode(c.relacl) AS a
+        WHERE c.oid = $1::oid AND a.grantee <> c.relowner
+        GROUP BY a.grantee, a.is_grantable
+        ORDER BY 1, a.is_grantable
+        "#,
+    )
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    // Views and foreign tables are granted on as tables
+    let object = if kind == "S" { "SEQUENCE" } else { "TABLE" };
+    statements.extend(grants.iter().map(|grant| {
+        format!(
+            "GRANT {} ON {} {} TO {}{};",
+            grant.get::<String, _>("privileges"),
+            object,
+            name,
+            grant.get::<String, _>("grantee"),
+            if grant.get("is_grantable") {
+                " WITH GRANT OPTION"
+            } else {
+                ""
+            }
+        )
+    }));
+
+    Ok(statements)
+}
+
+/// Every statement needed to recreate one entity, found by its OID in whichever
+/// catalog holds it. Tables come with their keys, indexes, triggers, comments, row
+/// level security and privileges.
+pub(crate) async fn entity(conn: &mut PgConnection, id: &str) -> DbResult<String> {
+    let kind = sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT 'schema' FROM pg_namespace WHERE oid = $1::oid
+        UNION ALL
+        SELECT relkind::TEXT FROM pg_class WHERE oid = $1::oid
+        UNION ALL
+        SELECT 'function' FROM pg_proc WHERE oid = $1::oid
+        UNION ALL
+        SELECT 'trigger' FROM pg_trigger WHERE oid = $1::oid
+        UNION ALL
+        SELECT 'type' FROM pg_type WHERE oid = $1::oid
+        LIMIT 1
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| not_found("Entity", id))?;
+
+    let mut statements = Vec::new();
+    match kind.as_str() {
+        "schema" => {
+            let row = sqlx::query(
+                r#"
+                SELECT
+                    n.nspname AS name,
+                    pg_get_userbyid(n.nspowner) AS owner,
+                    obj_description(n.oid, 'pg_namespace') AS description
+                FROM pg_namespace n
+                WHERE n.oid = $1::oid
+                "#,
+            )
+            .bind(id)
+            .fetch_one(&mut *conn)
+            .await?;
+            let name = quote_ident(row.get("name"));
+            statements.push(format!(
+                "CREATE SCHEMA {} AUTHORIZATION {};",
+                name,
+                quote_ident(row.get("owner"))
+            ));
+            if let Some(description) = row.get::<Option<&str>, _>("description") {
+                statements.push(format!(
+                    "COMMENT ON SCHEMA {} IS {};",
+                    name,
+                    quote_literal(description)
+                ));
+            }
+        }
+        "r" | "p" | "f" => {
+            let table = table(conn, id).await?;
+            statements.push(table.create);
+            statements.extend(table.keys);
+            statements.extend(table.foreign_keys);
+            statements.extend(table.indexes);
+            statements.extend(table.triggers);
+            statements.extend(comments(conn, id).await?);
+            statements.extend(row_security(conn, id).await?);
+            statements.extend(privileges(conn, id).await?);
+        }
+        "v" | "m" => {
+            statements.push(view(conn, id).await?);
+            let indexes = sqlx::query_scalar::<_, String>(
+                r#"
+                SELECT pg_get_indexdef(i.indexrelid)
+                FROM pg_index i
+                JOIN pg_class ic ON ic.oid = i.indexrelid
+                WHERE i.indrelid = $1::oid
+                ORDER BY ic.relname
+                "#,
+            )
+            .bind(id)
+            .fetch_all(&mut *conn)
+            .await?;
+            statements.extend(indexes.into_iter().map(|index| format!("{};", index)));
+            statements.extend(comments(conn, id).await?);
+            statements.extend(privileges(conn, id).await?);
+        }
+        "S" => {
+            let sequence = sequence(conn, id).await?;
+            statements.push(sequence.create);
+            statements.extend(sequence.owned_by);
+            statements.extend(comments(conn, id).await?);
+            statements.extend(privileges(conn, id).await?);
+        }
+        "i" | "I" => {
+            let index = sqlx::query_scalar::<_, String>("SELECT pg_get_indexdef($1::oid)")
+                .bind(id)
+                .fetch_one(&mut *conn)
+                .await?;
+            statements.push(format!("{};", index));
+        }
+        "c" => {
+            let type_id = sqlx::query_scalar::<_, String>(
+                "SELECT reltype::TEXT FROM pg_class WHERE oid = $1::oid",
+            )
+            .bind(id)
+            .fetch_one(&mut *conn)
+            .await?;
+            statements.push(custom_type(conn, &type_id).await?);
+        }
+        "function" => {
+            let prokind = sqlx::query_scalar::<_, String>(
+                "SELECT prokind::TEXT FROM pg_proc WHERE oid = $1::oid",
+            )
+            .bind(id)
+            .fetch_one(&mut *conn)
+            .await?;
+            if prokind == "a" {
+                return Err(DbError::Unsupported(
+                    "The server can't print the definition of an aggregate".to_string(),
+                ));
+            }
+            statements.push(function(conn, id).await?);
+        }
+        "trigger" => {
+            let trigger =
+                sqlx::query_scalar::<_, String>("SELECT pg_get_triggerdef($1::oid, true)")
+                    .bind(id)
+                    .fetch_one(&mut *conn)
+                    .await?;
+            statements.push(format!("{};", trigger));
+        }
+        "type" => statements.push(custom_type(conn, id).await?),
+        kind => {
+            return Err(DbError::Unsupported(format!(
+                "Can't generate DDL for relations of kind '{}'",
+                kind
+            )))
+        }
+    }
+
+    Ok(statements.join("\n\n"))
+}
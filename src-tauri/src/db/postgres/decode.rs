@@ -1,103 +1,472 @@
 use serde_json::Value as JsonValue;
-use sqlx::{postgres::PgValueRef, TypeInfo, Value, ValueRef};
-use time::{Date, OffsetDateTime, PrimitiveDateTime, Time};
+use sqlx::postgres::{PgTypeInfo, PgTypeKind, PgValueFormat, PgValueRef};
+use sqlx::{TypeInfo, ValueRef};
+use std::net::{Ipv4Addr, Ipv6Addr};
 
-use crate::Error;
+use crate::db::binary::{self, BinaryFormat, BinaryPreview};
+use crate::db::errors::{DbError, DbResult};
+use crate::db::temporal::DisplayZone;
+use crate::db::types::ServerFlavor;
 
-pub(crate) fn to_json(v: PgValueRef) -> Result<JsonValue, Error> {
+use super::temporal;
+
+/// Options controlling how values are decoded for the frontend
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DecodeOptions {
+    pub binary: BinaryFormat,
+    /// Zone TIMESTAMPTZ values are rendered in
+    pub time_zone: DisplayZone,
+    /// Server flavor, as some forks send types vanilla Postgres doesn't have
+    pub flavor: ServerFlavor,
+}
+
+pub(crate) fn to_json(v: PgValueRef, opts: &DecodeOptions) -> DbResult<JsonValue> {
     if v.is_null() {
         return Ok(JsonValue::Null);
     }
 
-    let res = match v.type_info().name() {
-        "CHAR" | "VARCHAR" | "TEXT" | "NAME" | "UUID" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode() {
-                JsonValue::String(v)
-            } else {
-                JsonValue::Null
-            }
+    let type_info = v.type_info().into_owned();
+    match v.format() {
+        PgValueFormat::Binary => {
+            let bytes = v.as_bytes().map_err(|e| DbError::Parsing(e.to_string()))?;
+            from_binary(&type_info, bytes, opts)
         }
-        "FLOAT4" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<f32>() {
-                JsonValue::from(v)
-            } else {
-                JsonValue::Null
-            }
+        PgValueFormat::Text => {
+            let text = v.as_str().map_err(|e| DbError::Parsing(e.to_string()))?;
+            Ok(from_text(&type_info, text, opts))
         }
-        "FLOAT8" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<f64>() {
-                JsonValue::from(v)
-            } else {
-                JsonValue::Null
-            }
+    }
+}
+
+fn invalid(type_info: &PgTypeInfo) -> DbError {
+    DbError::Parsing(format!("Invalid {} value", type_info.name()))
+}
+
+fn fixed<const N: usize>(bytes: &[u8], type_info: &PgTypeInfo) -> DbResult<[u8; N]> {
+    bytes
+        .get(..N)
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| invalid(type_info))
+}
+
+/// Decodes a value from Postgres's binary encoding. Array elements are decoded the same
+/// way, so this works from the bytes rather than a `PgValueRef`.
+fn from_binary(type_info: &PgTypeInfo, bytes: &[u8], opts: &DecodeOptions) -> DbResult<JsonValue> {
+    match type_info.kind() {
+        PgTypeKind::Array(element) => return array(type_info, element, bytes, opts),
+        PgTypeKind::Domain(base) => return from_binary(base, bytes, opts),
+        _ => {}
+    }
+
+    let res = match type_info.name() {
+        "CHAR" | "VARCHAR" | "TEXT" | "NAME" | "\"CHAR\"" | "XML" => {
+            JsonValue::String(String::from_utf8_lossy(bytes).into_owned())
         }
-        "INT2" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<i16>() {
-                JsonValue::Number(v.into())
-            } else {
-                JsonValue::Null
-            }
+        "UUID" => {
+            let hex = binary::encode_hex(&fixed::<16>(bytes, type_info)?);
+            JsonValue::String(format!(
+                "{}-{}-{}-{}-{}",
+                &hex[..8],
+                &hex[8..12],
+                &hex[12..16],
+                &hex[16..20],
+                &hex[20..]
+            ))
         }
-        "INT4" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<i32>() {
-                JsonValue::Number(v.into())
-            } else {
-                JsonValue::Null
-            }
+        "FLOAT4" => JsonValue::from(f32::from_be_bytes(fixed(bytes, type_info)?)),
+        "FLOAT8" => JsonValue::from(f64::from_be_bytes(fixed(bytes, type_info)?)),
+        "INT2" => JsonValue::from(i16::from_be_bytes(fixed(bytes, type_info)?)),
+        "INT4" => JsonValue::from(i32::from_be_bytes(fixed(bytes, type_info)?)),
+        "INT8" => JsonValue::from(i64::from_be_bytes(fixed(bytes, type_info)?)),
+        "OID" => JsonValue::from(u32::from_be_bytes(fixed(bytes, type_info)?)),
+        "BOOL" => JsonValue::Bool(fixed::<1>(bytes, type_info)?[0] != 0),
+        // Kept as strings so precision and NaN/Infinity survive
+        "NUMERIC" => JsonValue::String(numeric(bytes).ok_or_else(|| invalid(type_info))?),
+        "MONEY" => JsonValue::String(money(i64::from_be_bytes(fixed(bytes, type_info)?))),
+        "INTERVAL" => JsonValue::String(interval(bytes).ok_or_else(|| invalid(type_info))?),
+        "INET" | "CIDR" => JsonValue::String(inet(bytes).ok_or_else(|| invalid(type_info))?),
+        "DATE" => temporal::date(bytes)?,
+        "TIME" => temporal::time(bytes)?,
+        "TIMETZ" => temporal::timetz(bytes)?,
+        "TIMESTAMP" => temporal::timestamp(bytes)?,
+        "TIMESTAMPTZ" => temporal::timestamptz(bytes, &opts.time_zone)?,
+        "JSON" => serde_json::from_slice(bytes).unwrap_or_default(),
+        // JSONB's binary form is the JSON text behind a version byte
+        "JSONB" => serde_json::from_slice(bytes.get(1..).unwrap_or_default()).unwrap_or_default(),
+        "BYTEA" => BinaryPreview::new(bytes, &opts.binary).to_json(),
+        "VOID" => JsonValue::Null,
+        // CockroachDB and YugabyteDB ship engine-specific types (e.g. GEOGRAPHY, BOX2D)
+        // whose binary encoding we don't know; show their raw bytes
+        _ if matches!(
+            opts.flavor,
+            ServerFlavor::CockroachDb | ServerFlavor::YugabyteDb
+        ) =>
+        {
+            BinaryPreview::new(bytes, &opts.binary).to_json()
         }
-        "INT8" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<i64>() {
-                JsonValue::Number(v.into())
-            } else {
-                JsonValue::Null
+        // Enums and extension types like citext or ltree send their text; anything else
+        // is shown as bytes rather than failing the whole result
+        _ => match std::str::from_utf8(bytes) {
+            Ok(text) if text.chars().all(|c| !c.is_control() || c.is_whitespace()) => {
+                JsonValue::String(text.to_string())
             }
+            _ => BinaryPreview::new(bytes, &opts.binary).to_json(),
+        },
+    };
+
+    Ok(res)
+}
+
+/// Decodes a text-format value, as the simple query protocol sends them
+fn from_text(type_info: &PgTypeInfo, text: &str, opts: &DecodeOptions) -> JsonValue {
+    if let PgTypeKind::Domain(base) = type_info.kind() {
+        return from_text(base, text, opts);
+    }
+
+    match type_info.name() {
+        "INT2" | "INT4" | "INT8" | "OID" | "FLOAT4" | "FLOAT8" => text
+            .parse::<serde_json::Number>()
+            .map(JsonValue::Number)
+            .unwrap_or_else(|_| JsonValue::String(text.to_string())),
+        "BOOL" => JsonValue::Bool(text == "t"),
+        "JSON" | "JSONB" => serde_json::from_str(text).unwrap_or_default(),
+        "DATE" | "TIME" | "TIMETZ" | "TIMESTAMP" | "TIMESTAMPTZ" => {
+            temporal::from_text(type_info.name(), text, &opts.time_zone)
         }
-        "BOOL" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode() {
-                JsonValue::Bool(v)
-            } else {
-                JsonValue::Null
+        "BYTEA" => match text.strip_prefix("\\x").and_then(binary::decode_hex) {
+            Some(bytes) => BinaryPreview::new(&bytes, &opts.binary).to_json(),
+            None => JsonValue::String(text.to_string()),
+        },
+        "VOID" => JsonValue::Null,
+        _ => JsonValue::String(text.to_string()),
+    }
+}
+
+/// Reads a binary array into nested JSON arrays, one level per dimension
+fn array(
+    type_info: &PgTypeInfo,
+    element: &PgTypeInfo,
+    bytes: &[u8],
+    opts: &DecodeOptions,
+) -> DbResult<JsonValue> {
+    let mut reader = Reader { bytes, at: 0 };
+    let dimensions = reader.i32().ok_or_else(|| invalid(type_info))?;
+    // Flags and element type, which `element` already describes
+    reader.take(8).ok_or_else(|| invalid(type_info))?;
+
+    let mut lengths = Vec::new();
+    for _ in 0..dimensions {
+        let length = reader.i32().ok_or_else(|| invalid(type_info))?;
+        reader.i32().ok_or_else(|| invalid(type_info))?;
+        lengths.push(usize::try_from(length).map_err(|_| invalid(type_info))?);
+    }
+
+    // An empty array has no dimensions
+    let count = if lengths.is_empty() {
+        0
+    } else {
+        lengths.iter().product::<usize>()
+    };
+    let mut values = Vec::with_capacity(count.min(bytes.len()));
+    for _ in 0..count {
+        let len = reader.i32().ok_or_else(|| invalid(type_info))?;
+        values.push(match usize::try_from(len) {
+            Ok(len) => {
+                let element_bytes = reader.take(len).ok_or_else(|| invalid(type_info))?;
+                from_binary(element, element_bytes, opts)?
             }
+            // A length of -1 is NULL
+            Err(_) => JsonValue::Null,
+        });
+    }
+
+    // Group the flat elements from the innermost dimension out
+    for &length in lengths.iter().skip(1).rev() {
+        values = values
+            .chunks(length.max(1))
+            .map(|chunk| JsonValue::Array(chunk.to_vec()))
+            .collect();
+    }
+    Ok(JsonValue::Array(values))
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let slice = self.bytes.get(self.at..self.at.checked_add(len)?)?;
+        self.at += len;
+        Some(slice)
+    }
+
+    fn i16(&mut self) -> Option<i16> {
+        self.take(2)?.try_into().ok().map(i16::from_be_bytes)
+    }
+
+    fn i32(&mut self) -> Option<i32> {
+        self.take(4)?.try_into().ok().map(i32::from_be_bytes)
+    }
+
+    fn i64(&mut self) -> Option<i64> {
+        self.take(8)?.try_into().ok().map(i64::from_be_bytes)
+    }
+}
+
+/// NUMERIC is base-10000 digits with a weight (position of the first digit's group)
+/// and a display scale
+fn numeric(bytes: &[u8]) -> Option<String> {
+    let mut reader = Reader { bytes, at: 0 };
+    let ndigits = usize::try_from(reader.i16()?).ok()?;
+    let weight = reader.i16()? as i32;
+    let sign = reader.i16()? as u16;
+    let scale = usize::try_from(reader.i16()?).ok()?;
+
+    match sign {
+        0xC000 => return Some("NaN".to_string()),
+        0xD000 => return Some("Infinity".to_string()),
+        0xF000 => return Some("-Infinity".to_string()),
+        _ => {}
+    }
+
+    let mut digits = Vec::with_capacity(ndigits);
+    for _ in 0..ndigits {
+        digits.push(reader.i16()?);
+    }
+    let group = |i: i32| {
+        usize::try_from(i)
+            .ok()
+            .and_then(|i| digits.get(i).copied())
+            .unwrap_or(0)
+    };
+
+    let mut out = String::new();
+    if sign == 0x4000 {
+        out.push('-');
+    }
+
+    // Groups 0..=weight are before the point
+    if weight < 0 {
+        out.push('0');
+    } else {
+        out.push_str(&group(0).to_string());
+        for i in 1..=weight {
+            out.push_str(&format!("{:04}", group(i)));
         }
-        "DATE" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<Date>() {
-                JsonValue::String(v.to_string())
-            } else {
-                JsonValue::Null
-            }
+    }
+
+    if scale > 0 {
+        let mut fraction = String::new();
+        let mut i = weight + 1;
+        while fraction.len() < scale {
+            fraction.push_str(&format!("{:04}", group(i)));
+            i += 1;
         }
-        "TIME" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<Time>() {
-                JsonValue::String(v.to_string())
-            } else {
-                JsonValue::Null
-            }
+        fraction.truncate(scale);
+        out.push('.');
+        out.push_str(&fraction);
+    }
+    Some(out)
+}
+
+/// MONEY is a count of cents; locales with another number of fraction digits are rare
+fn money(cents: i64) -> String {
+    let sign = if cents < 0 { "-" } else { "" };
+    let cents = cents.unsigned_abs();
+    format!("{}{}.{:02}", sign, cents / 100, cents % 100)
+}
+
+/// Formats an interval as an ISO 8601 duration, like `IntervalStyle=iso_8601`
+fn interval(bytes: &[u8]) -> Option<String> {
+    let mut reader = Reader { bytes, at: 0 };
+    let micros = reader.i64()?;
+    let days = reader.i32()?;
+    let months = reader.i32()?;
+
+    let mut out = "P".to_string();
+    let (years, months) = (months / 12, months % 12);
+    for (value, unit) in [
+        (years as i64, 'Y'),
+        (months as i64, 'M'),
+        (days as i64, 'D'),
+    ] {
+        if value != 0 {
+            out.push_str(&format!("{}{}", value, unit));
         }
-        "TIMESTAMP" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<PrimitiveDateTime>() {
-                JsonValue::String(v.to_string())
-            } else {
-                JsonValue::Null
-            }
+    }
+
+    if micros != 0 {
+        out.push('T');
+        let (hours, rest) = (micros / 3_600_000_000, micros % 3_600_000_000);
+        let (minutes, rest) = (rest / 60_000_000, rest % 60_000_000);
+        if hours != 0 {
+            out.push_str(&format!("{}H", hours));
         }
-        "TIMESTAMPTZ" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<OffsetDateTime>() {
-                JsonValue::String(v.to_string())
-            } else {
-                JsonValue::Null
-            }
+        if minutes != 0 {
+            out.push_str(&format!("{}M", minutes));
         }
-        "JSON" | "JSONB" => ValueRef::to_owned(&v).try_decode().unwrap_or_default(),
-        "BYTEA" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<Vec<u8>>() {
-                JsonValue::Array(v.into_iter().map(|n| JsonValue::Number(n.into())).collect())
-            } else {
-                JsonValue::Null
+        if rest != 0 {
+            let sign = if rest < 0 { "-" } else { "" };
+            let rest = rest.unsigned_abs();
+            let fraction = format!("{:06}", rest % 1_000_000);
+            let fraction = fraction.trim_end_matches('0');
+            out.push_str(&format!("{}{}", sign, rest / 1_000_000));
+            if !fraction.is_empty() {
+                out.push_str(&format!(".{}", fraction));
             }
+            out.push('S');
         }
-        "VOID" => JsonValue::Null,
-        _ => return Err(Error::UnsupportedDatatype(v.type_info().name().to_string())),
+    }
+
+    if out == "P" {
+        out.push_str("T0S");
+    }
+    Some(out)
+}
+
+/// INET and CIDR are a family, prefix length, CIDR flag and the address bytes
+fn inet(bytes: &[u8]) -> Option<String> {
+    let [family, bits, is_cidr, len]: [u8; 4] = bytes.get(..4)?.try_into().ok()?;
+    let address = bytes.get(4..4 + len as usize)?;
+    let (address, max_bits) = match family {
+        2 => (
+            Ipv4Addr::from(<[u8; 4]>::try_from(address).ok()?).to_string(),
+            32,
+        ),
+        3 => (
+            Ipv6Addr::from(<[u8; 16]>::try_from(address).ok()?).to_string(),
+            128,
+        ),
+        _ => return None,
     };
 
-    Ok(res)
+    // inet leaves out a full-length prefix, as Postgres does
+    if is_cidr == 0 && bits == max_bits {
+        Some(address)
+    } else {
+        Some(format!("{}/{}", address, bits))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::{Postgres, Type};
+
+    /// NUMERIC's binary form: digit count, weight, sign, scale, then base-10000 digits
+    fn numeric_bytes(weight: i16, sign: u16, scale: i16, digits: &[i16]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&(digits.len() as i16).to_be_bytes());
+        bytes.extend_from_slice(&weight.to_be_bytes());
+        bytes.extend_from_slice(&sign.to_be_bytes());
+        bytes.extend_from_slice(&scale.to_be_bytes());
+        for digit in digits {
+            bytes.extend_from_slice(&digit.to_be_bytes());
+        }
+        bytes
+    }
+
+    fn interval_bytes(micros: i64, days: i32, months: i32) -> Vec<u8> {
+        let mut bytes = micros.to_be_bytes().to_vec();
+        bytes.extend_from_slice(&days.to_be_bytes());
+        bytes.extend_from_slice(&months.to_be_bytes());
+        bytes
+    }
+
+    #[test]
+    fn formats_numerics() {
+        assert_eq!(
+            numeric(&numeric_bytes(0, 0, 4, &[1234, 5678])).unwrap(),
+            "1234.5678"
+        );
+        assert_eq!(numeric(&numeric_bytes(1, 0, 0, &[1])).unwrap(), "10000");
+        assert_eq!(
+            numeric(&numeric_bytes(-1, 0x4000, 2, &[500])).unwrap(),
+            "-0.05"
+        );
+        assert_eq!(numeric(&numeric_bytes(0, 0, 3, &[7])).unwrap(), "7.000");
+        assert_eq!(numeric(&numeric_bytes(0, 0, 0, &[])).unwrap(), "0");
+        assert_eq!(numeric(&numeric_bytes(0, 0xC000, 0, &[])).unwrap(), "NaN");
+        assert_eq!(
+            numeric(&numeric_bytes(0, 0xF000, 0, &[])).unwrap(),
+            "-Infinity"
+        );
+        assert_eq!(numeric(&[0, 1]), None);
+    }
+
+    #[test]
+    fn formats_money_in_cents() {
+        assert_eq!(money(123456), "1234.56");
+        assert_eq!(money(5), "0.05");
+        assert_eq!(money(-1234), "-12.34");
+        assert_eq!(money(i64::MIN), "-92233720368547758.08");
+    }
+
+    #[test]
+    fn formats_intervals_as_iso_durations() {
+        assert_eq!(
+            interval(&interval_bytes(3_723_500_000, 4, 14)).unwrap(),
+            "P1Y2M4DT1H2M3.5S"
+        );
+        assert_eq!(interval(&interval_bytes(0, 0, 0)).unwrap(), "PT0S");
+        assert_eq!(
+            interval(&interval_bytes(-1_500_000, 0, 0)).unwrap(),
+            "PT-1.5S"
+        );
+        assert_eq!(interval(&interval_bytes(0, -3, 0)).unwrap(), "P-3D");
+        assert_eq!(interval(&[0; 8]), None);
+    }
+
+    #[test]
+    fn formats_inet_and_cidr() {
+        assert_eq!(inet(&[2, 32, 0, 4, 192, 168, 0, 1]).unwrap(), "192.168.0.1");
+        assert_eq!(
+            inet(&[2, 24, 0, 4, 192, 168, 0, 1]).unwrap(),
+            "192.168.0.1/24"
+        );
+        assert_eq!(inet(&[2, 32, 1, 4, 10, 0, 0, 1]).unwrap(), "10.0.0.1/32");
+
+        let mut v6 = vec![3, 64, 1, 16];
+        v6.extend_from_slice(&Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0).octets());
+        assert_eq!(inet(&v6).unwrap(), "2001:db8::/64");
+
+        assert_eq!(inet(&[2, 32, 0, 4, 10, 0]), None);
+        assert_eq!(inet(&[9, 32, 0, 4, 10, 0, 0, 1]), None);
+    }
+
+    #[test]
+    fn decodes_multidimensional_arrays() {
+        // int4[][] holding {{1,2},{3,NULL}}
+        let mut bytes = Vec::new();
+        for value in [2, 1, 23, 2, 1, 2, 1] {
+            bytes.extend_from_slice(&i32::to_be_bytes(value));
+        }
+        for value in [1, 2, 3] {
+            bytes.extend_from_slice(&4i32.to_be_bytes());
+            bytes.extend_from_slice(&i32::to_be_bytes(value));
+        }
+        bytes.extend_from_slice(&(-1i32).to_be_bytes());
+
+        let type_info = <Vec<i32> as Type<Postgres>>::type_info();
+        let value = from_binary(&type_info, &bytes, &DecodeOptions::default()).unwrap();
+        assert_eq!(value, serde_json::json!([[1, 2], [3, null]]));
+
+        // An empty array has no dimensions
+        let empty = [0i32, 0, 23]
+            .iter()
+            .flat_map(|value| value.to_be_bytes())
+            .collect::<Vec<_>>();
+        let value = from_binary(&type_info, &empty, &DecodeOptions::default()).unwrap();
+        assert_eq!(value, serde_json::json!([]));
+
+        // An element running past the end is an error, not a panic
+        assert!(from_binary(
+            &type_info,
+            &bytes[..bytes.len() - 6],
+            &DecodeOptions::default()
+        )
+        .is_err());
+    }
 }
@@ -0,0 +1,629 @@
+use futures_util::{StreamExt, TryStreamExt};
+use sqlx::{PgConnection, Pool, Postgres, Row as SqlxRow};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::db::dump::{DumpData, DumpRequest, DumpStats};
+use crate::db::errors::{DbError, DbResult};
+use crate::db::export::ExportWriter;
+
+use super::ddl;
+use super::relation::{quote_ident, quote_literal};
+
+/// Session settings the script starts with, as pg_dump writes them. An empty search path
+/// makes every name in the script schema-qualified.
+const PREAMBLE: &str = "\
+SET statement_timeout = 0;
+SET lock_timeout = 0;
+SET client_encoding = 'UTF8';
+SET standard_conforming_strings = on;
+SET check_function_bodies = false;
+SET client_min_messages = warning;
+SELECT pg_catalog.set_config('search_path', '', false);
+";
+
+/// Kinds of object a dump creates, in the order they're created when nothing
+/// else decides it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum ObjectKind {
+    Extension,
+    Type,
+    Function,
+    Sequence,
+    Table,
+    View,
+    MaterializedView,
+}
+
+#[derive(Debug)]
+struct Object {
+    /// Key shared with the dependency query, e.g. `class:16384`
+    key: String,
+    kind: ObjectKind,
+    id: String,
+    schema: String,
+    name: String,
+    /// A partitioned table, whose rows are dumped from its partitions
+    partitioned: bool,
+}
+
+/// Objects covered by the request, leaving out anything an extension or another object owns.
+/// Besides the chosen schemas and tables, this takes in the types, functions, sequences and
+/// extensions they need wherever those live, following `pg_depend` from each object and its
+/// parts (column defaults, constraints, view rules, triggers). Tables and views are only
+/// dumped when chosen.
+async fn objects(conn: &mut PgConnection, request: &DumpRequest) -> DbResult<Vec<Object>> {
+    let rows = sqlx::query(
+        r#"
+        WITH RECURSIVE chosen AS (
+            SELECT unnest($2::TEXT[])::oid AS oid
+            -- Partitions hold a chosen partitioned table's rows
+            UNION
+            SELECT i.inhrelid
+            FROM pg_inherits i
+            JOIN pg_class c ON c.oid = i.inhrelid
+            WHERE i.inhparent = ANY($2::TEXT[]::oid[]) AND c.relispartition
+        ),
+        needed (classid, objid) AS (
+            SELECT 'pg_class'::regclass::oid, c.oid
+            FROM pg_class c
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            WHERE n.nspname = ANY($1::TEXT[]) OR c.oid IN (SELECT oid FROM chosen)
+            UNION
+            SELECT 'pg_type'::regclass::oid, t.oid
+            FROM pg_type t
+            JOIN pg_namespace n ON n.oid = t.typnamespace
+            WHERE n.nspname = ANY($1::TEXT[])
+            UNION
+            SELECT 'pg_proc'::regclass::oid, p.oid
+            FROM pg_proc p
+            JOIN pg_namespace n ON n.oid = p.pronamespace
+            WHERE n.nspname = ANY($1::TEXT[])
+            UNION
+            SELECT d.refclassid, d.refobjid
+            FROM needed
+            CROSS JOIN LATERAL (
+                SELECT needed.classid, needed.objid
+                UNION ALL
+                SELECT 'pg_attrdef'::regclass::oid, ad.oid FROM pg_attrdef ad
+                WHERE needed.classid = 'pg_class'::regclass AND ad.adrelid = needed.objid
+                UNION ALL
+                SELECT 'pg_constraint'::regclass::oid, co.oid FROM pg_constraint co
+                WHERE co.contype <> 'f' AND (
+                    (needed.classid = 'pg_class'::regclass AND co.conrelid = needed.objid)
+                    OR (needed.classid = 'pg_type'::regclass AND co.contypid = needed.objid)
+                )
+                UNION ALL
+                SELECT 'pg_rewrite'::regclass::oid, r.oid FROM pg_rewrite r
+                WHERE needed.classid = 'pg_class'::regclass AND r.ev_class = needed.objid
+                UNION ALL
+                SELECT 'pg_trigger'::regclass::oid, tg.oid FROM pg_trigger tg
+                WHERE needed.classid = 'pg_class'::regclass AND tg.tgrelid = needed.objid
+                    AND NOT tg.tgisinternal
+                UNION ALL
+                -- A composite type's attributes are recorded against its pg_class entry
+                SELECT 'pg_class'::regclass::oid, t.typrelid FROM pg_type t
+                JOIN pg_class c ON c.oid = t.typrelid
+                WHERE needed.classid = 'pg_type'::regclass AND t.oid = needed.objid
+                    AND c.relkind = 'c'
+            ) AS part (classid, objid)
+            JOIN pg_depend d ON d.classid = part.classid AND d.objid = part.objid
+            -- Below FirstNormalObjectId everything was created by initdb
+            WHERE d.deptype IN ('n', 'i') AND d.refobjid >= 16384
+                -- A table or view somewhere else is only dumped when chosen
+                AND NOT EXISTS (
+                    SELECT 1 FROM pg_class c
+                    WHERE d.refclassid = 'pg_class'::regclass AND c.oid = d.refobjid
+                        AND c.relkind IN ('r', 'p', 'v', 'm', 'f')
+                )
+        )
+        SELECT 'class:' || c.oid AS key, c.relkind::TEXT AS kind, c.oid::TEXT AS id,
+            n.nspname AS schema_name, c.relname::TEXT AS name
+        FROM pg_class c
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE c.relkind IN ('r', 'p', 'v', 'm', 'S')
+            AND (
+                n.nspname = ANY($1::TEXT[])
+                OR c.oid IN (SELECT oid FROM chosen)
+                -- Sequences behind a chosen table's serial columns
+                OR EXISTS (
+                    SELECT 1 FROM pg_depend d
+                    WHERE d.classid = 'pg_class'::regclass AND d.objid = c.oid
+                        AND d.refobjid IN (SELECT oid FROM chosen) AND d.deptype = 'a'
+                )
+                -- Sequences another schema's defaults take values from
+                OR (c.relkind = 'S' AND ('pg_class'::regclass, c.oid) IN (SELECT * FROM needed))
+            )
+            -- Extension members, and sequences behind identity columns
+            AND NOT EXISTS (
+                SELECT 1 FROM pg_depend d
+                WHERE d.classid = 'pg_class'::regclass AND d.objid = c.oid AND d.deptype IN ('e', 'i')
+            )
+        UNION ALL
+        SELECT 'type:' || t.oid, 'type', t.oid::TEXT, n.nspname, t.typname::TEXT
+        FROM pg_type t
+        JOIN pg_namespace n ON n.oid = t.typnamespace
+        LEFT JOIN pg_class r ON r.oid = t.typrelid
+        WHERE ('pg_type'::regclass, t.oid) IN (SELECT * FROM needed)
+            AND (t.typtype IN ('e', 'd', 'r') OR (t.typtype = 'c' AND r.relkind = 'c'))
+            AND NOT EXISTS (
+                SELECT 1 FROM pg_depend d
+                WHERE d.classid = 'pg_type'::regclass AND d.objid = t.oid AND d.deptype = 'e'
+            )
+        UNION ALL
+        SELECT 'proc:' || p.oid, 'function', p.oid::TEXT, n.nspname, p.proname::TEXT
+        FROM pg_proc p
+        JOIN pg_namespace n ON n.oid = p.pronamespace
+        WHERE ('pg_proc'::regclass, p.oid) IN (SELECT * FROM needed)
+            AND p.prokind IN ('f', 'p')
+            AND NOT EXISTS (
+                SELECT 1 FROM pg_depend d
+                WHERE d.classid = 'pg_proc'::regclass AND d.objid = p.oid AND d.deptype = 'e'
+            )
+        UNION ALL
+        -- Extensions owning anything the other objects need
+        SELECT DISTINCT 'extension:' || e.oid, 'extension', e.oid::TEXT, n.nspname, e.extname::TEXT
+        FROM needed
+        JOIN pg_depend d ON d.classid = needed.classid AND d.objid = needed.objid AND d.deptype = 'e'
+        JOIN pg_extension e ON e.oid = d.refobjid
+        JOIN pg_namespace n ON n.oid = e.extnamespace
+        WHERE e.oid >= 16384
+        "#,
+    )
+    .bind(&request.schemas)
+    .bind(&request.table_ids)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|row| {
+            let relkind = row.get::<String, _>("kind");
+            let kind = match relkind.as_str() {
+                "extension" => ObjectKind::Extension,
+                "type" => ObjectKind::Type,
+                "function" => ObjectKind::Function,
+                "S" => ObjectKind::Sequence,
+                "r" | "p" => ObjectKind::Table,
+                "v" => ObjectKind::View,
+                "m" => ObjectKind::MaterializedView,
+                _ => return None,
+            };
+            Some(Object {
+                key: row.get("key"),
+                kind,
+                id: row.get("id"),
+                schema: row.get("schema_name"),
+                name: row.get("name"),
+                partitioned: relkind == "p",
+            })
+        })
+        .collect())
+}
+
+/// SQL giving the object key a `pg_depend` entry stands for. Parts of an object (a view's
+/// rewrite rule, a column default, a CHECK constraint) count as the object itself, and an
+/// array type as its element type. Foreign keys are added after every table exists, so
+/// they don't order anything.
+fn object_key(classid: &str, objid: &str) -> String {
+    format!(
+        r#"CASE {classid}
+            WHEN 'pg_class'::regclass THEN (
+                SELECT CASE
+                    WHEN c.relkind = 'c' THEN 'type:' || c.reltype
+                    WHEN c.relkind IN ('r', 'p', 'v', 'm', 'S') THEN 'class:' || c.oid
+                END
+                FROM pg_class c WHERE c.oid = {objid}
+            )
+            WHEN 'pg_type'::regclass THEN (
+                SELECT CASE
+                    WHEN r.relkind <> 'c' THEN 'class:' || r.oid
+                    ELSE 'type:' || b.oid
+                END
+                FROM pg_type t
+                JOIN pg_type b ON b.oid = CASE WHEN t.typcategory = 'A' AND t.typelem <> 0 THEN t.typelem ELSE t.oid END
+                LEFT JOIN pg_class r ON r.oid = b.typrelid
+                WHERE t.oid = {objid}
+            )
+            WHEN 'pg_proc'::regclass THEN 'proc:' || {objid}
+            WHEN 'pg_rewrite'::regclass THEN (
+                SELECT 'class:' || ev_class FROM pg_rewrite WHERE oid = {objid}
+            )
+            WHEN 'pg_attrdef'::regclass THEN (
+                SELECT 'class:' || adrelid FROM pg_attrdef WHERE oid = {objid}
+            )
+            WHEN 'pg_constraint'::regclass THEN (
+                SELECT CASE
+                    WHEN co.contype = 'f' THEN NULL
+                    WHEN co.contypid <> 0 THEN 'type:' || co.contypid
+                    ELSE 'class:' || co.conrelid
+                END
+                FROM pg_constraint co WHERE co.oid = {objid}
+            )
+        END"#
+    )
+}
+
+/// Pairs of (dependent, referenced) object keys between user objects. Ownership links
+/// (`deptype = 'a'`) are left out: a serial column's sequence is owned by its table but
+/// has to exist before it, and the ownership is restored once both exist.
+async fn dependencies(conn: &mut PgConnection) -> DbResult<Vec<(String, String)>> {
+    let query = format!(
+        r#"
+        SELECT DISTINCT dependent, referenced
+        FROM (
+            SELECT {} AS dependent, {} AS referenced
+            FROM pg_depend d
+            -- Below FirstNormalObjectId everything was created by initdb
+            WHERE d.deptype = 'n' AND d.objid >= 16384 AND d.refobjid >= 16384
+            UNION
+            SELECT 'class:' || inhrelid, 'class:' || inhparent FROM pg_inherits
+        ) AS edges
+        WHERE dependent IS NOT NULL AND referenced IS NOT NULL AND dependent <> referenced
+        "#,
+        object_key("d.classid", "d.objid"),
+        object_key("d.refclassid", "d.refobjid")
+    );
+
+    let rows = sqlx::query(&query).fetch_all(&mut *conn).await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| (row.get("dependent"), row.get("referenced")))
+        .collect())
+}
+
+/// Orders objects so each comes after everything it depends on, otherwise by kind, schema
+/// and name so the same database always dumps the same way. Objects caught in a cycle are
+/// placed in that fallback order.
+fn dependency_order(mut objects: Vec<Object>, edges: &[(String, String)]) -> Vec<Object> {
+    objects.sort_by(|a, b| (a.kind, &a.schema, &a.name).cmp(&(b.kind, &b.schema, &b.name)));
+
+    let index = objects
+        .iter()
+        .enumerate()
+        .map(|(i, object)| (object.key.as_str(), i))
+        .collect::<HashMap<_, _>>();
+
+    let mut waiting_on = vec![0usize; objects.len()];
+    let mut dependents = vec![Vec::new(); objects.len()];
+    let mut seen = HashSet::new();
+    for (dependent, referenced) in edges {
+        if let (Some(&d), Some(&r)) = (
+            index.get(dependent.as_str()),
+            index.get(referenced.as_str()),
+        ) {
+            if d != r && seen.insert((d, r)) {
+                waiting_on[d] += 1;
+                dependents[r].push(d);
+            }
+        }
+    }
+
+    let mut ready = (0..objects.len())
+        .filter(|&i| waiting_on[i] == 0)
+        .collect::<BTreeSet<_>>();
+    let mut placed = vec![false; objects.len()];
+    let mut order = Vec::with_capacity(objects.len());
+
+    while order.len() < objects.len() {
+        let next = match ready.pop_first() {
+            Some(next) => next,
+            // Break a cycle at the first object still waiting
+            None => (0..objects.len()).find(|&i| !placed[i]).unwrap_or_default(),
+        };
+        if placed[next] {
+            continue;
+        }
+        placed[next] = true;
+        order.push(next);
+
+        for &d in &dependents[next] {
+            waiting_on[d] = waiting_on[d].saturating_sub(1);
+            if waiting_on[d] == 0 && !placed[d] {
+                ready.insert(d);
+            }
+        }
+    }
+
+    let mut objects = objects.into_iter().map(Some).collect::<Vec<_>>();
+    order
+        .into_iter()
+        .filter_map(|i| objects[i].take())
+        .collect()
+}
+
+/// Columns rows are written for; generated columns are computed again on load
+async fn data_columns(conn: &mut PgConnection, id: &str) -> DbResult<(Vec<String>, bool)> {
+    let row = sqlx::query(
+        r#"
+        SELECT
+            array_agg(a.attname::TEXT ORDER BY a.attnum) FILTER (WHERE a.attgenerated = '') AS columns,
+            bool_or(a.attidentity = 'a') AS always_identity
+        FROM pg_attribute a
+        WHERE a.attrelid = $1::oid AND a.attnum > 0 AND NOT a.attisdropped
+        "#,
+    )
+    .bind(id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok((
+        row.get::<Option<Vec<String>>, _>("columns")
+            .unwrap_or_default(),
+        row.get::<Option<bool>, _>("always_identity")
+            .unwrap_or_default(),
+    ))
+}
+
+/// Writes a table's rows and returns how many there were
+async fn write_rows(
+    conn: &mut PgConnection,
+    object: &Object,
+    data: &DumpData,
+    out: &mut ExportWriter,
+) -> DbResult<u64> {
+    let (columns, always_identity) = data_columns(conn, &object.id).await?;
+    if columns.is_empty() {
+        return Ok(0);
+    }
+
+    let name = ddl::qualified(&object.schema, &object.name);
+    let column_list = columns
+        .iter()
+        .map(|column| quote_ident(column))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut rows = 0;
+
+    match data {
+        DumpData::None => {}
+        DumpData::Copy => {
+            let header = format!("COPY {} ({}) FROM stdin;\n", name, column_list);
+            out.write_str(&header).await?;
+
+            // Text format escapes line breaks, so every line is a row
+            let statement = format!("COPY {} ({}) TO STDOUT", name, column_list);
+            let mut stream = conn.copy_out_raw(&statement).await?;
+            while let Some(chunk) = stream.next().await {
+                let chunk = chunk?;
+                rows += chunk.iter().filter(|&&b| b == b'\n').count() as u64;
+                out.write_utf8(&chunk).await?;
+            }
+
+            out.write_str("\\.\n\n").await?;
+        }
+        DumpData::Insert { batch_size } => {
+            // The server quotes every value, so the text reads back exactly
+            let values = columns
+                .iter()
+                .map(|column| format!("quote_nullable({}::TEXT)", quote_ident(column)))
+                .collect::<Vec<_>>()
+                .join(" || ', ' || ");
+            let sql = format!("SELECT '(' || {} || ')' FROM ONLY {}", values, name);
+            let insert = format!(
+                "INSERT INTO {} ({}){} VALUES\n  ",
+                name,
+                column_list,
+                if always_identity {
+                    " OVERRIDING SYSTEM VALUE"
+                } else {
+                    ""
+                }
+            );
+
+            let batch_size = (*batch_size).max(1) as u64;
+            let mut stream = sqlx::query_scalar::<_, String>(&sql).fetch(&mut *conn);
+            while let Some(tuple) = stream.try_next().await? {
+                let prefix = if rows % batch_size == 0 {
+                    insert.as_str()
+                } else {
+                    ",\n  "
+                };
+                out.write_str(prefix).await?;
+                out.write_str(&tuple).await?;
+                rows += 1;
+                if rows % batch_size == 0 {
+                    out.write_str(";\n").await?;
+                }
+            }
+            if rows % batch_size != 0 {
+                out.write_str(";\n").await?;
+            }
+            if rows > 0 {
+                out.write_str("\n").await?;
+            }
+        }
+    }
+
+    Ok(rows)
+}
+
+/// `setval` calls restoring a sequence, or the identity sequences of a table, to where the
+/// database had them
+async fn sequence_values(conn: &mut PgConnection, object: &Object) -> DbResult<Vec<String>> {
+    let name = ddl::qualified(&object.schema, &object.name);
+
+    // (expression naming the sequence in the script, sequence to read)
+    let sequences = if object.kind == ObjectKind::Sequence {
+        vec![(quote_literal(&name), name.clone())]
+    } else {
+        sqlx::query(
+            r#"
+            SELECT a.attname::TEXT AS column_name, pg_get_serial_sequence($2, a.attname) AS sequence
+            FROM pg_attribute a
+            WHERE a.attrelid = $1::oid AND a.attidentity <> '' AND NOT a.attisdropped
+            ORDER BY a.attnum
+            "#,
+        )
+        .bind(&object.id)
+        .bind(&name)
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .map(|row| {
+            let column: String = row.get("column_name");
+            (
+                format!(
+                    "pg_get_serial_sequence({}, {})",
+                    quote_literal(&name),
+                    quote_literal(&column)
+                ),
+                row.get("sequence"),
+            )
+        })
+        .collect()
+    };
+
+    let mut statements = Vec::with_capacity(sequences.len());
+    for (target, sequence) in sequences {
+        let row = sqlx::query(&format!("SELECT last_value, is_called FROM {}", sequence))
+            .fetch_one(&mut *conn)
+            .await?;
+        statements.push(format!(
+            "SELECT pg_catalog.setval({}, {}, {});",
+            target,
+            row.get::<i64, _>("last_value"),
+            row.get::<bool, _>("is_called")
+        ));
+    }
+    Ok(statements)
+}
+
+async fn write_section(out: &mut ExportWriter, title: &str, statements: &[String]) -> DbResult<()> {
+    if statements.is_empty() {
+        return Ok(());
+    }
+    out.write_str(&format!("--\n-- {}\n--\n\n", title)).await?;
+    for statement in statements {
+        out.write_str(statement).await?;
+        out.write_str("\n\n").await?;
+    }
+    Ok(())
+}
+
+/// Writes a SQL script recreating the chosen schemas and tables without needing pg_dump.
+/// Everything is read in one repeatable-read transaction, so the schema and rows come from
+/// the same snapshot. Owners and privileges are left out, so the script can be run as any
+/// role on another server.
+pub(crate) async fn dump(
+    pool: &Pool<Postgres>,
+    request: &DumpRequest,
+    out: &mut ExportWriter,
+) -> DbResult<DumpStats> {
+    if request.schemas.is_empty() && request.table_ids.is_empty() {
+        return Err(DbError::Query(
+            "Choose at least one schema or table to dump".to_string(),
+        ));
+    }
+
+    let mut tx = ddl::begin(pool).await?;
+
+    let objects = objects(&mut *tx, request).await?;
+    let edges = dependencies(&mut *tx).await?;
+    let objects = dependency_order(objects, &edges);
+
+    let mut stats = DumpStats::default();
+    out.write_str("--\n-- Dumped by Sqratch\n--\n\n").await?;
+    out.write_str(PREAMBLE).await?;
+    out.write_str("\n").await?;
+
+    let schemas = objects
+        .iter()
+        .map(|object| object.schema.as_str())
+        .chain(request.schemas.iter().map(String::as_str))
+        .collect::<BTreeSet<_>>();
+    let schema_statements = schemas
+        .iter()
+        .map(|schema| ddl::create_schema(schema))
+        .collect::<Vec<_>>();
+    write_section(out, "Schemas", &schema_statements).await?;
+    stats.objects += schema_statements.len() as u32;
+
+    let mut created = Vec::with_capacity(objects.len());
+    let mut post_data = ddl::TableDdl::default();
+    let mut owned_by = Vec::new();
+    let mut row_security = Vec::new();
+    let mut comments = Vec::new();
+    for object in &objects {
+        let statement = match object.kind {
+            ObjectKind::Extension => format!(
+                "CREATE EXTENSION IF NOT EXISTS {} WITH SCHEMA {};",
+                quote_ident(&object.name),
+                quote_ident(&object.schema)
+            ),
+            ObjectKind::Type => ddl::custom_type(&mut *tx, &object.id).await?,
+            ObjectKind::Function => ddl::function(&mut *tx, &object.id).await?,
+            ObjectKind::Sequence => {
+                let sequence = ddl::sequence(&mut *tx, &object.id).await?;
+                owned_by.extend(sequence.owned_by);
+                sequence.create
+            }
+            ObjectKind::Table => {
+                let table = ddl::table(&mut *tx, &object.id).await?;
+                post_data.keys.extend(table.keys);
+                post_data.foreign_keys.extend(table.foreign_keys);
+                post_data.indexes.extend(table.indexes);
+                post_data.triggers.extend(table.triggers);
+                row_security.extend(ddl::row_security(&mut *tx, &object.id).await?);
+                table.create
+            }
+            ObjectKind::View | ObjectKind::MaterializedView => {
+                ddl::view(&mut *tx, &object.id).await?
+            }
+        };
+        if !matches!(
+            object.kind,
+            ObjectKind::Extension | ObjectKind::Function | ObjectKind::Type
+        ) {
+            comments.extend(ddl::comments(&mut *tx, &object.id).await?);
+        }
+        created.push(statement);
+    }
+    write_section(
+        out,
+        "Extensions, types, functions, sequences, tables and views",
+        &created,
+    )
+    .await?;
+    stats.objects += created.len() as u32;
+
+    if !matches!(request.data, DumpData::None) {
+        let mut wrote_header = false;
+        let mut after_rows = Vec::new();
+        for object in &objects {
+            match object.kind {
+                ObjectKind::Table => {
+                    if !wrote_header {
+                        out.write_str("--\n-- Data\n--\n\n").await?;
+                        wrote_header = true;
+                    }
+                    if !object.partitioned {
+                        stats.rows += write_rows(&mut *tx, object, &request.data, out).await?;
+                    }
+                    after_rows.extend(sequence_values(&mut *tx, object).await?);
+                }
+                ObjectKind::Sequence => {
+                    after_rows.extend(sequence_values(&mut *tx, object).await?);
+                }
+                ObjectKind::MaterializedView => {
+                    after_rows.push(format!(
+                        "REFRESH MATERIALIZED VIEW {};",
+                        ddl::qualified(&object.schema, &object.name)
+                    ));
+                }
+                _ => {}
+            }
+        }
+        write_section(out, "Sequence values and materialized views", &after_rows).await?;
+    }
+
+    write_section(out, "Keys", &post_data.keys).await?;
+    write_section(out, "Foreign keys", &post_data.foreign_keys).await?;
+    write_section(out, "Indexes", &post_data.indexes).await?;
+    write_section(out, "Triggers", &post_data.triggers).await?;
+    write_section(out, "Sequence ownership", &owned_by).await?;
+    write_section(out, "Row level security", &row_security).await?;
+    write_section(out, "Comments", &comments).await?;
+
+    tx.rollback().await?;
+    Ok(stats)
+}
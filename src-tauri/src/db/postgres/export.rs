@@ -0,0 +1,198 @@
+use futures_util::StreamExt;
+use sqlx::{postgres::PgPoolCopyExt, Pool, Postgres};
+
+use crate::db::errors::{DbError, DbResult};
+use crate::db::export::csv::{CsvOptions, QuoteStyle, RecordCounter};
+use crate::db::export::{ExportSource, ExportWriter};
+
+use super::relation::{quote_literal, Relation};
+
+/// Option list for `COPY ... TO STDOUT WITH (...)` matching `options`
+fn copy_options(options: &CsvOptions) -> String {
+    let mut list = vec![
+        "FORMAT csv".to_string(),
+        format!(
+            "DELIMITER {}",
+            quote_literal(&options.delimiter.to_string())
+        ),
+        format!("QUOTE {}", quote_literal(&options.quote.to_string())),
+        format!("NULL {}", quote_literal(&options.null_value)),
+        format!("HEADER {}", options.header),
+    ];
+    if options.quote_style == QuoteStyle::Always {
+        list.push("FORCE_QUOTE *".to_string());
+    }
+    list.join(", ")
+}
+
+/// Offsets and bytes of the SQL outside comments. Strings, quoted identifiers and
+/// dollar-quoted bodies are represented by their opening quote only.
+fn code_bytes(sql: &str) -> Vec<(usize, u8)> {
+    let bytes = sql.as_bytes();
+    let mut code = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            quote @ (b'\'' | b'"') => {
+                code.push((i, quote));
+                // E'...' strings escape with backslashes; others only double the quote
+                let escapes = quote == b'\'' && i > 0 && bytes[i - 1].eq_ignore_ascii_case(&b'e');
+                i += 1;
+                while i < bytes.len() {
+                    if escapes && bytes[i] == b'\\' {
+                        i += 2;
+                    } else if bytes[i] == quote {
+                        i += 1;
+                        if bytes.get(i) != Some(&quote) {
+                            break;
+                        }
+                        i += 1;
+                    } else {
+                        i += 1;
+                    }
+                }
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                i = sql[i..].find('\n').map_or(bytes.len(), |n| i + n);
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                // Block comments nest
+                let mut depth = 0;
+                while i < bytes.len() {
+                    if bytes[i..].starts_with(b"/*") {
+                        depth += 1;
+                        i += 2;
+                    } else if bytes[i..].starts_with(b"*/") {
+                        depth -= 1;
+                        i += 2;
+                        if depth == 0 {
+                            break;
+                        }
+                    } else {
+                        i += 1;
+                    }
+                }
+            }
+            b'$' => {
+                code.push((i, b'$'));
+                // A tag is $$ or $name$ with a name not starting with a digit; $1 is a parameter
+                let name = &sql[i + 1..];
+                let tag_len = name
+                    .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .filter(|&n| name[n..].starts_with('$'))
+                    .filter(|&n| n == 0 || !name.starts_with(|c: char| c.is_ascii_digit()));
+                match tag_len {
+                    Some(n) => {
+                        let tag = &sql[i..i + n + 2];
+                        let body = i + tag.len();
+                        i = sql[body..]
+                            .find(tag)
+                            .map_or(bytes.len(), |end| body + end + tag.len());
+                    }
+                    None => i += 1,
+                }
+            }
+            b => {
+                code.push((i, b));
+                i += 1;
+            }
+        }
+    }
+
+    code
+}
+
+/// The statement of a query export without its terminating `;` and what follows it.
+/// A second statement is rejected, since `COPY (...)` takes exactly one query.
+fn single_statement(sql: &str) -> DbResult<String> {
+    let code = code_bytes(sql);
+    let Some(end) = code.iter().position(|&(_, b)| b == b';') else {
+        return Ok(sql.trim().to_string());
+    };
+
+    if code[end..]
+        .iter()
+        .any(|&(_, b)| b != b';' && !b.is_ascii_whitespace())
+    {
+        return Err(DbError::Query(
+            "Only a single statement can be exported".to_string(),
+        ));
+    }
+
+    Ok(sql[..code[end].0].trim().to_string())
+}
+
+/// Query producing the rows to export, for use inside `COPY (...)` or as a subquery.
+/// Wrap it with a line break before the closing parenthesis, so a trailing
+/// `-- comment` doesn't comment the parenthesis out.
+pub(crate) async fn source_query(pool: &Pool<Postgres>, source: &ExportSource) -> DbResult<String> {
+    match source {
+        ExportSource::Query { sql } => single_statement(sql),
+        // Views can't be copied directly, so always go through a SELECT
+        ExportSource::Table { table_id } => {
+            let relation = Relation::load(pool, table_id).await?;
+            Ok(format!("SELECT * FROM {}", relation.qualified_name()))
+        }
+    }
+}
+
+/// Streams the export through `COPY ... TO STDOUT`, so it never sits in memory
+pub(crate) async fn export_csv(
+    pool: &Pool<Postgres>,
+    source: &ExportSource,
+    options: &CsvOptions,
+    out: &mut ExportWriter,
+) -> DbResult<u64> {
+    options.validate()?;
+
+    let statement = format!(
+        "COPY ({}\n) TO STDOUT WITH ({})",
+        source_query(pool, source).await?,
+        copy_options(options)
+    );
+
+    let mut counter = RecordCounter::new(options.quote);
+    let mut stream = pool.copy_out_raw(&statement).await?;
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk?;
+        counter.feed(&chunk);
+        out.write_utf8(&chunk).await?;
+    }
+
+    let header = if options.header { 1 } else { 0 };
+    Ok(counter.records.saturating_sub(header))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_the_terminating_semicolon_and_trailing_comments() {
+        assert_eq!(single_statement(" SELECT 1; ").unwrap(), "SELECT 1");
+        assert_eq!(single_statement("SELECT 1; -- x").unwrap(), "SELECT 1");
+        assert_eq!(single_statement("SELECT 1 -- x").unwrap(), "SELECT 1 -- x");
+    }
+
+    #[test]
+    fn ignores_semicolons_in_strings_and_comments() {
+        for sql in [
+            "SELECT ';'",
+            "SELECT E'\\';'",
+            "SELECT 1 AS \";\"",
+            "SELECT $$;$$, $fn$ ; $fn$",
+            "SELECT 1 /* ; /* ; */ ; */",
+            "SELECT 1 -- ;\n+ 1",
+        ] {
+            assert_eq!(single_statement(sql).unwrap(), sql, "{}", sql);
+        }
+    }
+
+    #[test]
+    fn rejects_several_statements() {
+        assert_eq!(single_statement("SELECT 1;; /* x */").unwrap(), "SELECT 1");
+        assert!(single_statement("SELECT 1; SELECT 2").is_err());
+        assert!(single_statement("SELECT 1; -- x\nDELETE FROM t").is_err());
+    }
+}
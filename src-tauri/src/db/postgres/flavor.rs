@@ -0,0 +1,150 @@
+use sqlx::{Pool, Postgres, Row as SqlxRow};
+use url::Url;
+
+use crate::db::errors::DbResult;
+use crate::db::types::{ServerFlavor, ServerInfo};
+
+/// Schemas every Postgres-wire server treats as internal
+const SYSTEM_SCHEMAS: &[&str] = &["pg_catalog", "information_schema", "pg_toast"];
+
+/// Internal schemas CockroachDB adds on top of the Postgres ones
+const COCKROACH_SCHEMAS: &[&str] = &["crdb_internal", "pg_extension"];
+
+/// Schemas Supabase creates and manages for its own services
+const SUPABASE_SCHEMAS: &[&str] = &[
+    "auth",
+    "storage",
+    "realtime",
+    "_realtime",
+    "supabase_functions",
+    "supabase_migrations",
+    "extensions",
+    "graphql",
+    "graphql_public",
+    "pgbouncer",
+    "pgsodium",
+    "pgsodium_masks",
+    "vault",
+    "net",
+    "_analytics",
+];
+
+/// Detects which Postgres-wire server we're connected to.
+/// Forks identify themselves in `version()`; hosted Postgres has to be recognized by
+/// its host name or the settings and roles the provider installs.
+pub(crate) async fn detect(pool: &Pool<Postgres>, connection_string: &str) -> DbResult<ServerInfo> {
+    let row = sqlx::query(
+        r#"
+        SELECT
+            version() AS version,
+            current_setting('server_version') AS server_version,
+            current_setting('server_version_num') AS server_version_num
+        "#,
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let description: String = row.get("version");
+    let server_version: String = row.get("server_version");
+    let version_num = row
+        .get::<String, _>("server_version_num")
+        .trim()
+        .parse()
+        .ok();
+
+    // e.g. "CockroachDB CCL v23.1.11 (x86_64-pc-linux-gnu, ...)"
+    if description.starts_with("CockroachDB") {
+        let version = description
+            .split_whitespace()
+            .find(|part| part.starts_with('v'))
+            .unwrap_or(&server_version)
+            .to_string();
+
+        return Ok(ServerInfo {
+            flavor: ServerFlavor::CockroachDb,
+            version,
+            version_num,
+        });
+    }
+
+    // e.g. "PostgreSQL 11.2-YB-2.20.1.0-b0 on x86_64-pc-linux-gnu, ..."
+    if description.contains("-YB-") {
+        return Ok(ServerInfo {
+            flavor: ServerFlavor::YugabyteDb,
+            version: server_version,
+            version_num,
+        });
+    }
+
+    let host = Url::parse(connection_string)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_lowercase))
+        .unwrap_or_default();
+
+    let row = sqlx::query(
+        r#"
+        SELECT
+            current_setting('neon.timeline_id', true) IS NOT NULL AS is_neon,
+            EXISTS (SELECT 1 FROM pg_roles WHERE rolname = 'supabase_admin') AS is_supabase
+        "#,
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let flavor = if host.ends_with(".neon.tech") || row.get::<bool, _>("is_neon") {
+        ServerFlavor::Neon
+    } else if host.ends_with(".supabase.co")
+        || host.ends_with(".supabase.com")
+        || row.get::<bool, _>("is_supabase")
+    {
+        ServerFlavor::Supabase
+    } else {
+        ServerFlavor::Postgres
+    };
+
+    Ok(ServerInfo {
+        flavor,
+        version: server_version,
+        version_num,
+    })
+}
+
+/// Whether the server records extension membership in `pg_depend`.
+/// CockroachDB exposes the catalog but never populates extension dependencies.
+pub(crate) fn tracks_extensions(flavor: ServerFlavor) -> bool {
+    flavor != ServerFlavor::CockroachDb
+}
+
+/// Relation kinds listed in the entity tree, as a SQL list for `relkind IN (...)`.
+/// Sequences aren't listed until the tree has an entity for them.
+pub(crate) fn relkinds(flavor: ServerFlavor) -> &'static str {
+    match flavor {
+        // No foreign tables
+        ServerFlavor::CockroachDb => "'r', 'v', 'm'",
+        _ => "'r', 'v', 'm', 'f'",
+    }
+}
+
+/// SQL predicate over `n.nspname` that marks schemas as internal to the server
+pub(crate) fn system_schema_predicate(flavor: ServerFlavor) -> String {
+    let extra: &[&str] = match flavor {
+        ServerFlavor::CockroachDb => COCKROACH_SCHEMAS,
+        ServerFlavor::Supabase => SUPABASE_SCHEMAS,
+        _ => &[],
+    };
+
+    let names = SYSTEM_SCHEMAS
+        .iter()
+        .chain(extra)
+        .map(|name| format!("'{}'", name))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    format!("(n.nspname IN ({}) OR n.nspname LIKE 'pg_%')", names)
+}
+
+/// Whether DDL can be rebuilt from the catalog with the server's deparsers
+/// (pg_get_functiondef, pg_get_partkeydef, ...). CockroachDB has `SHOW CREATE` instead.
+pub(crate) fn rebuilds_ddl(flavor: ServerFlavor) -> bool {
+    flavor != ServerFlavor::CockroachDb
+}
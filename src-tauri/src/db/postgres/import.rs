@@ -0,0 +1,427 @@
+use async_trait::async_trait;
+use sqlx::{PgConnection, Pool, Postgres};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::db::errors::{DbError, DbResult};
+use crate::db::export::csv::{CsvOptions, QuoteStyle};
+use crate::db::export::TextEncoding;
+use crate::db::import::columnar::{self, ParquetRows};
+use crate::db::import::{
+    ImportFormat, ImportProgress, ImportReport, ImportRequest, ImportTarget, Importer, RecordReader,
+};
+
+use super::relation::{quote_ident, Relation};
+
+/// A record ready to send, kept so a failed batch can be retried row by row
+struct PendingRow {
+    line: u64,
+    data: String,
+}
+
+/// How rows are sent to the server
+enum Loader {
+    /// `COPY ... FROM STDIN`, with each row as a CSV record
+    Copy(String),
+    /// An `INSERT ... SELECT` from `jsonb_to_recordset`, with each row as a JSON object.
+    /// Used for Parquet, whose lists and structs have no CSV form.
+    JsonRecordset(String),
+}
+
+/// Where rows come from
+enum Source {
+    Records {
+        reader: RecordReader,
+        /// Position of each mapped column in the file's records
+        indexes: Vec<usize>,
+        format: CsvOptions,
+    },
+    Parquet {
+        rows: ParquetRows,
+        bytes_total: u64,
+    },
+}
+
+impl Source {
+    /// The next row, or the line and reason it can't be loaded
+    async fn next(&mut self) -> DbResult<Option<Result<PendingRow, (u64, String)>>> {
+        match self {
+            Source::Records {
+                reader,
+                indexes,
+                format,
+            } => Ok(reader.next_record().await?.map(|record| {
+                record
+                    .values
+                    .map(|values| PendingRow {
+                        line: record.line,
+                        data: format.record(indexes.iter().map(|&i| values[i].as_deref())),
+                    })
+                    .map_err(|message| (record.line, message))
+            })),
+            Source::Parquet { rows, .. } => Ok(rows
+                .next_row()
+                .await?
+                .map(|(line, data)| Ok(PendingRow { line, data }))),
+        }
+    }
+
+    fn bytes_read(&self) -> u64 {
+        match self {
+            Source::Records { reader, .. } => reader.bytes_read(),
+            // Parquet is compressed, so estimate from the share of rows read
+            Source::Parquet { rows, bytes_total } => match rows.total_rows {
+                0 => 0,
+                total => bytes_total * rows.rows_read() / total,
+            },
+        }
+    }
+}
+
+/// Rows are sent as CSV with every value quoted, so empty strings and NULLs stay apart
+fn copy_format() -> CsvOptions {
+    CsvOptions {
+        delimiter: ',',
+        quote: '"',
+        quote_style: QuoteStyle::Always,
+        null_value: String::new(),
+        header: false,
+        encoding: TextEncoding::Utf8,
+    }
+}
+
+async fn copy_rows(conn: &mut PgConnection, statement: &str, data: &[u8]) -> sqlx::Result<u64> {
+    let mut copy = conn.copy_in_raw(statement).await?;
+    if let Err(e) = copy.send(data).await {
+        let _ = copy.abort(e.to_string()).await;
+        return Err(e);
+    }
+    copy.finish().await
+}
+
+async fn execute(conn: &mut PgConnection, sql: &str) -> sqlx::Result<()> {
+    sqlx::query(sql).execute(conn).await.map(|_| ())
+}
+
+async fn load_rows(
+    conn: &mut PgConnection,
+    loader: &Loader,
+    rows: &[PendingRow],
+) -> sqlx::Result<u64> {
+    match loader {
+        Loader::Copy(statement) => {
+            let data = rows.iter().map(|row| row.data.as_str()).collect::<String>();
+            copy_rows(conn, statement, data.as_bytes()).await
+        }
+        Loader::JsonRecordset(statement) => {
+            let data = rows
+                .iter()
+                .map(|row| row.data.as_str())
+                .collect::<Vec<_>>()
+                .join(",");
+            let result = sqlx::query(statement)
+                .bind(format!("[{}]", data))
+                .execute(conn)
+                .await?;
+            Ok(result.rows_affected())
+        }
+    }
+}
+
+/// Loads a batch under a savepoint. If the server rejects it, each row is retried on
+/// its own so the bad ones can be reported and the rest still load.
+async fn load_batch(
+    conn: &mut PgConnection,
+    loader: &Loader,
+    batch: &[PendingRow],
+    report: &mut ImportReport,
+) -> DbResult<()> {
+    execute(conn, "SAVEPOINT import_batch").await?;
+    match load_rows(conn, loader, batch).await {
+        Ok(rows) => {
+            execute(conn, "RELEASE SAVEPOINT import_batch").await?;
+            report.rows_loaded += rows;
+            return Ok(());
+        }
+        Err(sqlx::Error::Database(_)) => {
+            execute(conn, "ROLLBACK TO SAVEPOINT import_batch").await?;
+        }
+        Err(e) => return Err(e.into()),
+    }
+
+    for row in batch {
+        execute(conn, "SAVEPOINT import_row").await?;
+        match load_rows(conn, loader, std::slice::from_ref(row)).await {
+            Ok(rows) => {
+                execute(conn, "RELEASE SAVEPOINT import_row").await?;
+                report.rows_loaded += rows;
+            }
+            Err(sqlx::Error::Database(e)) => {
+                execute(conn, "ROLLBACK TO SAVEPOINT import_row").await?;
+                report.fail(row.line, e.message().to_string());
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    execute(conn, "RELEASE SAVEPOINT import_batch").await?;
+    Ok(())
+}
+
+/// The table rows go into, created if needed
+struct Target {
+    id: String,
+    qualified_name: String,
+    /// Types of the mapped columns, by name
+    types: HashMap<String, String>,
+}
+
+async fn prepare_target(
+    pool: &Pool<Postgres>,
+    conn: &mut PgConnection,
+    request: &ImportRequest,
+) -> DbResult<Target> {
+    match &request.target {
+        ImportTarget::Existing { table_id } => {
+            let relation = Relation::load(pool, table_id).await?;
+            let mut types = HashMap::new();
+            for mapping in &request.mapping {
+                let column = relation.column(&mapping.target)?;
+                types.insert(column.name.clone(), column.data_type.clone());
+            }
+            Ok(Target {
+                id: relation.id.clone(),
+                qualified_name: relation.qualified_name(),
+                types,
+            })
+        }
+        ImportTarget::New {
+            schema,
+            name,
+            columns,
+        } => {
+            let mut types = HashMap::new();
+            for mapping in &request.mapping {
+                let column = columns
+                    .iter()
+                    .find(|c| c.name == mapping.target)
+                    .ok_or_else(|| {
+                        DbError::Config(format!(
+                            "{} is mapped to {}, which isn't a column of the new table",
+                            mapping.source, mapping.target
+                        ))
+                    })?;
+                types.insert(column.name.clone(), column.data_type.clone());
+            }
+
+            let qualified_name = format!("{}.{}", quote_ident(schema), quote_ident(name));
+            let definitions = columns
+                .iter()
+                .map(|c| format!("{} {}", quote_ident(&c.name), c.data_type))
+                .collect::<Vec<_>>()
+                .join(", ");
+            execute(
+                conn,
+                &format!("CREATE TABLE {} ({})", qualified_name, definitions),
+            )
+            .await?;
+
+            let id: String = sqlx::query_scalar("SELECT $1::regclass::oid::TEXT")
+                .bind(&qualified_name)
+                .fetch_one(&mut *conn)
+                .await?;
+            Ok(Target {
+                id,
+                qualified_name,
+                types,
+            })
+        }
+    }
+}
+
+/// Opens the file and builds the statement that loads its rows into `target`
+async fn open_source(
+    request: &ImportRequest,
+    path: &Path,
+    target: &Target,
+    bytes_total: u64,
+) -> DbResult<(Source, Loader)> {
+    let targets = request
+        .mapping
+        .iter()
+        .map(|mapping| quote_ident(&mapping.target))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    if let ImportFormat::Parquet = request.format {
+        let rows = ParquetRows::open(path).await?;
+        for mapping in &request.mapping {
+            if !rows
+                .columns()
+                .iter()
+                .any(|(name, _)| name == &mapping.source)
+            {
+                return Err(DbError::Config(format!(
+                    "The file has no column {}",
+                    mapping.source
+                )));
+            }
+        }
+
+        // Binary values arrive as hex text, which bytea would otherwise take literally,
+        // in lists too
+        let binary_type = |source: &str| {
+            rows.columns()
+                .iter()
+                .find(|(name, _)| name == source)
+                .map(|(_, data_type)| columnar::pg_type(data_type))
+                .filter(|pg_type| pg_type == "bytea" || pg_type == "bytea[]")
+        };
+        let sources = request
+            .mapping
+            .iter()
+            .map(|mapping| {
+                let column = format!("r.{}", quote_ident(&mapping.source));
+                match binary_type(&mapping.source).as_deref() {
+                    Some("bytea") => format!("decode({}, 'hex')", column),
+                    Some(_) => format!(
+                        "CASE WHEN {column} IS NULL THEN NULL ELSE ARRAY(SELECT decode(e.v, 'hex') \
+                         FROM unnest({column}) WITH ORDINALITY AS e(v, i) ORDER BY e.i) END"
+                    ),
+                    None => column,
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let definitions = request
+            .mapping
+            .iter()
+            .map(|mapping| {
+                let data_type = match binary_type(&mapping.source).as_deref() {
+                    Some("bytea") => "text",
+                    Some(_) => "text[]",
+                    None => &target.types[&mapping.target],
+                };
+                format!("{} {}", quote_ident(&mapping.source), data_type)
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let statement = format!(
+            "INSERT INTO {} ({}) SELECT {} FROM jsonb_to_recordset($1::jsonb) AS r({})",
+            target.qualified_name, targets, sources, definitions
+        );
+
+        return Ok((
+            Source::Parquet { rows, bytes_total },
+            Loader::JsonRecordset(statement),
+        ));
+    }
+
+    let reader = RecordReader::open(path, &request.format).await?;
+    let indexes = request
+        .mapping
+        .iter()
+        .map(|mapping| {
+            reader
+                .columns()
+                .iter()
+                .position(|c| c == &mapping.source)
+                .ok_or_else(|| {
+                    DbError::Config(format!("The file has no column {}", mapping.source))
+                })
+        })
+        .collect::<DbResult<Vec<_>>>()?;
+    let statement = format!(
+        "COPY {} ({}) FROM STDIN WITH (FORMAT csv)",
+        target.qualified_name, targets
+    );
+
+    Ok((
+        Source::Records {
+            reader,
+            indexes,
+            format: copy_format(),
+        },
+        Loader::Copy(statement),
+    ))
+}
+
+/// Loads a file into a table in one transaction, in batches: text formats through
+/// `COPY FROM STDIN`, Parquet through `jsonb_to_recordset`. Row errors are collected
+/// rather than ending the import; with `rollback_on_error` any of them undoes
+/// everything, including a newly created table.
+/// It has its own handle to the pool, so the window's client isn't locked meanwhile.
+pub(crate) struct PgImporter {
+    pub pool: Pool<Postgres>,
+}
+
+#[async_trait]
+impl Importer for PgImporter {
+    async fn import(
+        &self,
+        request: &ImportRequest,
+        path: &Path,
+        progress: &(dyn Fn(ImportProgress) + Send + Sync),
+    ) -> DbResult<ImportReport> {
+        import(&self.pool, request, path, progress).await
+    }
+}
+
+async fn import(
+    pool: &Pool<Postgres>,
+    request: &ImportRequest,
+    path: &Path,
+    progress: &(dyn Fn(ImportProgress) + Send + Sync),
+) -> DbResult<ImportReport> {
+    if request.mapping.is_empty() {
+        return Err(DbError::Config("No columns are mapped".to_string()));
+    }
+
+    let bytes_total = tokio::fs::metadata(path).await?.len();
+
+    let mut tx = pool.begin().await?;
+    let target = prepare_target(pool, &mut tx, request).await?;
+    let (mut source, loader) = open_source(request, path, &target, bytes_total).await?;
+
+    let batch_size = request.batch_size.max(1) as usize;
+    let mut report = ImportReport::new(request.import_id);
+    report.table_id = Some(target.id.clone());
+
+    let mut batch = Vec::with_capacity(batch_size);
+    loop {
+        let next = source.next().await?;
+        let done = next.is_none();
+
+        match next {
+            Some(Ok(row)) => {
+                report.rows_read += 1;
+                batch.push(row);
+            }
+            Some(Err((line, message))) => {
+                report.rows_read += 1;
+                report.fail(line, message);
+            }
+            None => {}
+        }
+
+        if batch.len() >= batch_size || (done && !batch.is_empty()) {
+            load_batch(&mut tx, &loader, &batch, &mut report).await?;
+            batch.clear();
+            progress(report.progress(source.bytes_read(), bytes_total));
+        }
+
+        if done {
+            break;
+        }
+    }
+
+    if report.rows_failed > 0 && request.rollback_on_error {
+        tx.rollback().await?;
+    } else {
+        tx.commit().await?;
+        report.committed = true;
+    }
+
+    progress(report.progress(source.bytes_read(), bytes_total));
+    Ok(report)
+}
@@ -0,0 +1,76 @@
+use sqlx::{Pool, Postgres, Row as SqlxRow};
+
+use crate::db::errors::DbResult;
+use crate::db::migrations::{AppliedMigration, MigrationTool};
+
+/// Reads a migration tool's tracking table, or None if the database doesn't have one
+pub(crate) async fn history(
+    pool: &Pool<Postgres>,
+    tool: MigrationTool,
+) -> DbResult<Option<Vec<AppliedMigration>>> {
+    let mut table = None;
+    for name in tool.tracking_tables() {
+        // Resolved through the search path, like the tool itself does
+        table = sqlx::query_scalar::<_, Option<String>>("SELECT to_regclass($1)::TEXT")
+            .bind(name)
+            .fetch_one(pool)
+            .await?;
+        if table.is_some() {
+            break;
+        }
+    }
+    let Some(table) = table else {
+        return Ok(None);
+    };
+
+    let sql = match tool {
+        MigrationTool::Sqlx => format!(
+            r#"
+            SELECT version::TEXT AS id, encode(checksum, 'hex') AS checksum,
+                installed_on::TEXT AS applied_at, success
+            FROM {}
+            "#,
+            table
+        ),
+        // Rolled back attempts stay in the table next to the retry
+        MigrationTool::Prisma => format!(
+            r#"
+            SELECT migration_name AS id, checksum, finished_at::TEXT AS applied_at,
+                finished_at IS NOT NULL AS success
+            FROM {}
+            WHERE rolled_back_at IS NULL
+            ORDER BY started_at
+            "#,
+            table
+        ),
+        // created_at is the migration's journal timestamp, not when it was applied
+        MigrationTool::Drizzle => format!(
+            r#"
+            SELECT created_at::TEXT AS id, hash AS checksum, NULL::TEXT AS applied_at,
+                TRUE AS success
+            FROM {}
+            "#,
+            table
+        ),
+        MigrationTool::Rails => format!(
+            r#"
+            SELECT version::TEXT AS id, NULL::TEXT AS checksum, NULL::TEXT AS applied_at,
+                TRUE AS success
+            FROM {}
+            "#,
+            table
+        ),
+    };
+
+    let rows = sqlx::query(&sql).fetch_all(pool).await?;
+    Ok(Some(
+        rows.iter()
+            .map(|row| AppliedMigration {
+                id: row.get("id"),
+                checksum: row.get("checksum"),
+                applied_at: row.get("applied_at"),
+                success: row.get("success"),
+            })
+            .collect(),
+    ))
+}
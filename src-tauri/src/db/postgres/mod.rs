@@ -1,20 +1,132 @@
+mod browse;
+mod catalog;
+mod columnar;
+pub(crate) mod ddl;
+mod decode;
+mod dump;
+mod export;
+mod flavor;
+mod import;
+mod migrations;
+pub(crate) mod relation;
+mod search;
+mod temporal;
+mod typegen;
+
 use async_trait::async_trait;
-use serde_json::Value;
 use sqlx::{
     postgres::{PgPoolOptions, PgRow},
-    Column, Pool, Postgres, Row as SqlxRow,
+    Column, Pool, Postgres, Row as SqlxRow, Transaction,
 };
 use std::collections::HashMap;
+use std::path::Path;
 
 use crate::db::{
+    binary::BinaryFormat,
+    browse::{BrowsePage, BrowseRequest},
+    catalog::CatalogSnapshot,
+    changes::{self, Dialect, StagedChange, StepRunner, TableInfo, TableLoader},
     client::DatabaseClient,
+    dump::{DumpRequest, DumpStats},
+    editing::{self, ColumnChanges},
     errors::{DbError, DbResult},
-    types::{ColumnDefinition, DbEntity, QueryResult, Row, SchemaEntity, SchemaLevelEntity},
+    export::{columnar::ColumnarFormat, csv::CsvOptions, ExportSource, ExportWriter},
+    import::Importer,
+    migrations::{AppliedMigration, MigrationTool},
+    search::Searcher,
+    temporal::DisplayZone,
+    typegen::TypeModel,
+    types::{
+        ColumnDefinition, DbEntity, QueryResult, Row, SchemaEntity, SchemaLevelEntity,
+        ServerFlavor, ServerInfo, TimeZoneMode,
+    },
+};
+
+use self::decode::DecodeOptions;
+use self::relation::{quote_ident, Relation};
+
+const DIALECT: Dialect = Dialect {
+    quote: '"',
+    numbered_params: true,
+    lock_rows: true,
+    backslash_escapes: false,
 };
 
+/// Decodes every column of a row into its display value
+fn decode_row(row: &PgRow, opts: &DecodeOptions) -> DbResult<Row> {
+    let mut values = HashMap::new();
+    for (i, col) in row.columns().iter().enumerate() {
+        let value = decode::to_json(row.try_get_raw(i)?, opts)?;
+        values.insert(
+            col.name().to_string(),
+            if value.is_null() {
+                "NULL".to_string()
+            } else {
+                value.to_string()
+            },
+        );
+    }
+    Ok(Row { values })
+}
+
+/// Loads the tables a change set touches
+struct ChangeTables<'a>(&'a Pool<Postgres>);
+
+#[async_trait]
+impl TableLoader for ChangeTables<'_> {
+    async fn load_table(&mut self, table_id: &str) -> DbResult<TableInfo> {
+        let relation = Relation::load(self.0, table_id).await?;
+        Ok(TableInfo {
+            sql_name: relation.qualified_name(),
+            key_columns: relation.key_columns,
+            columns: relation
+                .columns
+                .into_iter()
+                .map(|c| (c.name, Some(c.data_type)))
+                .collect(),
+            name: relation.name,
+        })
+    }
+}
+
+/// Runs the statements of a change set in one transaction
+struct ChangeRunner<'a> {
+    tx: Transaction<'static, Postgres>,
+    opts: &'a DecodeOptions,
+}
+
+#[async_trait]
+impl StepRunner for ChangeRunner<'_> {
+    async fn fetch_row(&mut self, sql: &str, params: Vec<Option<&str>>) -> DbResult<Option<Row>> {
+        let mut query = sqlx::query(sql);
+        for param in params {
+            query = query.bind(param);
+        }
+
+        match query.fetch_optional(&mut *self.tx).await? {
+            Some(row) => Ok(Some(decode_row(&row, self.opts)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn execute(&mut self, sql: &str, params: Vec<Option<&str>>) -> DbResult<u64> {
+        let mut query = sqlx::query(sql);
+        for param in params {
+            query = query.bind(param);
+        }
+        Ok(query.execute(&mut *self.tx).await?.rows_affected())
+    }
+}
+
 pub struct PostgresClient {
     connection_string: String,
     pool: Option<Pool<Postgres>>,
+    decode_options: DecodeOptions,
+    time_zone: TimeZoneMode,
+    /// Zone the server would use for new sessions, read on connect
+    session_time_zone: Option<chrono_tz::Tz>,
+    /// Flavor and version of the server, detected on connect
+    server_info: Option<ServerInfo>,
 }
 
 impl PostgresClient {
@@ -22,9 +134,91 @@ impl PostgresClient {
         Ok(Self {
             connection_string: connection_string.to_string(),
             pool: None,
+            decode_options: DecodeOptions::default(),
+            time_zone: TimeZoneMode::default(),
+            session_time_zone: None,
+            server_info: None,
         })
     }
 
+    /// Reads the TimeZone new sessions for this database and role would get.
+    /// sqlx pins `TimeZone=UTC` on every connection, so `current_setting()` and
+    /// `pg_settings.reset_val` only ever report UTC.
+    async fn fetch_session_time_zone(
+        pool: &Pool<Postgres>,
+        flavor: ServerFlavor,
+    ) -> DbResult<Option<chrono_tz::Tz>> {
+        // CockroachDB has no server-wide TimeZone setting; sessions start in UTC
+        if flavor == ServerFlavor::CockroachDb {
+            return Ok(Some(chrono_tz::UTC));
+        }
+
+        // ALTER ROLE / ALTER DATABASE ... SET timezone, the most specific first
+        let role_default: Option<String> = sqlx::query_scalar(
+            r#"
+            SELECT substring(cfg FROM '=(.*)$')
+            FROM pg_db_role_setting s
+            CROSS JOIN LATERAL unnest(s.setconfig) AS cfg
+            WHERE s.setdatabase IN (
+                    0, (SELECT oid FROM pg_database WHERE datname = current_database())
+                )
+              AND s.setrole IN (0, (SELECT oid FROM pg_roles WHERE rolname = current_user))
+              AND lower(split_part(cfg, '=', 1)) = 'timezone'
+            ORDER BY s.setrole DESC, s.setdatabase DESC
+            LIMIT 1
+            "#,
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        let zone = match role_default {
+            Some(zone) => zone,
+            None => {
+                // postgresql.conf's value, readable by superusers and pg_read_all_settings
+                let file_default: Option<String> = sqlx::query_scalar(
+                    r#"
+                    SELECT setting FROM pg_file_settings
+                    WHERE lower(name) = 'timezone' AND error IS NULL
+                    ORDER BY seqno DESC
+                    LIMIT 1
+                    "#,
+                )
+                .fetch_optional(pool)
+                .await
+                .ok()
+                .flatten();
+
+                match file_default {
+                    Some(zone) => zone,
+                    // initdb writes the system zone to both TimeZone and log_timezone, and
+                    // clients can't override the latter
+                    None => {
+                        sqlx::query_scalar("SELECT current_setting('log_timezone')")
+                            .fetch_one(pool)
+                            .await?
+                    }
+                }
+            }
+        };
+
+        let zone = zone.trim().trim_matches('\'');
+        Ok(zone.parse().ok())
+    }
+
+    fn update_display_zone(&mut self) {
+        self.decode_options.time_zone = DisplayZone::resolve(
+            self.time_zone,
+            self.session_time_zone.map(DisplayZone::Named),
+        );
+    }
+
+    fn flavor(&self) -> ServerFlavor {
+        self.server_info
+            .as_ref()
+            .map(|info| info.flavor)
+            .unwrap_or_default()
+    }
+
     // This function gets the pool or returns an error if not connected
     fn get_pool(&self) -> DbResult<&Pool<Postgres>> {
         self.pool
@@ -64,6 +258,12 @@ impl DatabaseClient for PostgresClient {
             .connect(&self.connection_string)
             .await?;
 
+        let server_info = flavor::detect(&pool, &self.connection_string).await?;
+        self.session_time_zone = Self::fetch_session_time_zone(&pool, server_info.flavor).await?;
+        self.decode_options.flavor = server_info.flavor;
+        self.server_info = Some(server_info);
+        self.update_display_zone();
+
         self.pool = Some(pool);
         Ok(())
     }
@@ -74,6 +274,7 @@ impl DatabaseClient for PostgresClient {
                 pool.close().await;
             }
         }
+        self.server_info = None;
         Ok(())
     }
 
@@ -88,6 +289,19 @@ impl DatabaseClient for PostgresClient {
         self.connect().await
     }
 
+    fn set_time_zone(&mut self, mode: TimeZoneMode) {
+        self.time_zone = mode;
+        self.update_display_zone();
+    }
+
+    fn set_binary_format(&mut self, format: BinaryFormat) {
+        self.decode_options.binary = format;
+    }
+
+    fn server_info(&self) -> Option<ServerInfo> {
+        self.server_info.clone()
+    }
+
     async fn execute_query(&self, sql: &str) -> DbResult<QueryResult> {
         let pool = self.get_pool()?;
         let rows = sqlx::query(sql).fetch_all(pool).await?;
@@ -123,15 +337,7 @@ impl DatabaseClient for PostgresClient {
 
         let mut result_rows = Vec::new();
         for row in rows {
-            let mut values = HashMap::new();
-            for (i, col) in row.columns().iter().enumerate() {
-                let value: Option<Value> = row.try_get(i)?;
-                values.insert(
-                    col.name().to_string(),
-                    value.map_or_else(|| "NULL".to_string(), |v| v.to_string()),
-                );
-            }
-            result_rows.push(Row { values });
+            result_rows.push(decode_row(&row, &self.decode_options)?);
         }
 
         Ok(QueryResult {
@@ -149,30 +355,240 @@ impl DatabaseClient for PostgresClient {
         })
     }
 
+    async fn browse_table(&self, request: &BrowseRequest) -> DbResult<BrowsePage> {
+        let pool = self.get_pool()?;
+        browse::browse_table(pool, request, |row| decode_row(row, &self.decode_options)).await
+    }
+
+    fn searcher(&self) -> DbResult<Box<dyn Searcher>> {
+        Ok(Box::new(search::PgSearcher {
+            pool: self.get_pool()?.clone(),
+            flavor: self.flavor(),
+        }))
+    }
+
+    async fn export_csv(
+        &self,
+        source: &ExportSource,
+        options: &CsvOptions,
+        out: &mut ExportWriter,
+    ) -> DbResult<u64> {
+        let pool = self.get_pool()?;
+        export::export_csv(pool, source, options, out).await
+    }
+
+    async fn export_columnar(
+        &self,
+        source: &ExportSource,
+        format: ColumnarFormat,
+        path: &Path,
+    ) -> DbResult<u64> {
+        let pool = self.get_pool()?;
+        columnar::export_columnar(pool, source, format, path).await
+    }
+
+    async fn read_export_source(&self, source: &ExportSource) -> DbResult<QueryResult> {
+        let pool = self.get_pool()?;
+        let sql = export::source_query(pool, source).await?;
+        self.execute_query(&sql).await
+    }
+
+    fn importer(&self) -> DbResult<Box<dyn Importer>> {
+        Ok(Box::new(import::PgImporter {
+            pool: self.get_pool()?.clone(),
+        }))
+    }
+
+    async fn dump_sql(&self, request: &DumpRequest, out: &mut ExportWriter) -> DbResult<DumpStats> {
+        if !flavor::rebuilds_ddl(self.flavor()) {
+            return Err(DbError::Unsupported(
+                "Dumping isn't available for CockroachDB; use SHOW CREATE ALL TABLES".to_string(),
+            ));
+        }
+        let pool = self.get_pool()?;
+        dump::dump(pool, request, out).await
+    }
+
+    async fn entity_ddl(&self, entity_id: &str) -> DbResult<String> {
+        if !flavor::rebuilds_ddl(self.flavor()) {
+            return Err(DbError::Unsupported(
+                "Generating DDL isn't available for CockroachDB; use SHOW CREATE".to_string(),
+            ));
+        }
+        let pool = self.get_pool()?;
+        let mut tx = ddl::begin(pool).await?;
+        let ddl = ddl::entity(&mut *tx, entity_id).await?;
+        tx.rollback().await?;
+        Ok(ddl)
+    }
+
+    async fn catalog_snapshot(&self, schemas: &[String]) -> DbResult<CatalogSnapshot> {
+        if !flavor::rebuilds_ddl(self.flavor()) {
+            return Err(DbError::Unsupported(
+                "Schema snapshots aren't available for CockroachDB".to_string(),
+            ));
+        }
+        let pool = self.get_pool()?;
+        catalog::snapshot(pool, self.flavor(), schemas).await
+    }
+
+    async fn migration_history(
+        &self,
+        tool: MigrationTool,
+    ) -> DbResult<Option<Vec<AppliedMigration>>> {
+        let pool = self.get_pool()?;
+        migrations::history(pool, tool).await
+    }
+
+    async fn type_model(&self, schemas: &[String]) -> DbResult<TypeModel> {
+        let pool = self.get_pool()?;
+        typegen::type_model(pool, self.flavor(), schemas).await
+    }
+
+    async fn fetch_blob(
+        &self,
+        table_id: &str,
+        key: &HashMap<String, String>,
+        column: &str,
+    ) -> DbResult<Vec<u8>> {
+        let pool = self.get_pool()?;
+        let relation = Relation::load(pool, table_id).await?;
+        let column = relation.column(column)?;
+        let (predicate, params) = relation.key_predicate(key, 1)?;
+
+        let sql = format!(
+            "SELECT {}::BYTEA FROM {} WHERE {} LIMIT 2",
+            quote_ident(&column.name),
+            relation.qualified_name(),
+            predicate
+        );
+
+        let mut query = sqlx::query_scalar::<_, Option<Vec<u8>>>(&sql);
+        for param in params {
+            query = query.bind(param);
+        }
+
+        let mut rows = query.fetch_all(pool).await?;
+        match rows.len() {
+            0 => Err(DbError::NotFound("Row not found".to_string())),
+            1 => Ok(rows.pop().flatten().unwrap_or_default()),
+            _ => Err(DbError::Query(
+                "Row key matches more than one row".to_string(),
+            )),
+        }
+    }
+
+    async fn update_row(
+        &self,
+        table_id: &str,
+        key: &HashMap<String, String>,
+        changes: &ColumnChanges,
+    ) -> DbResult<Row> {
+        let pool = self.get_pool()?;
+        let relation = Relation::load(pool, table_id).await?;
+        editing::check_key(&relation.name, &relation.key_columns, key)?;
+        editing::check_changes(changes)?;
+
+        let names = editing::sorted(changes.keys());
+        let mut assignments = Vec::with_capacity(names.len());
+        for (i, name) in names.iter().enumerate() {
+            let column = relation.column(name)?;
+            assignments.push(format!(
+                "{} = ${}::{}",
+                quote_ident(&column.name),
+                i + 1,
+                column.data_type
+            ));
+        }
+
+        let (predicate, key_params) = relation.key_predicate(key, names.len() + 1)?;
+
+        let sql = format!(
+            "UPDATE {} SET {} WHERE {} RETURNING *",
+            relation.qualified_name(),
+            assignments.join(", "),
+            predicate
+        );
+
+        let mut query = sqlx::query(&sql);
+        for name in names {
+            query = query.bind(changes[name].as_deref());
+        }
+        for param in key_params {
+            query = query.bind(param);
+        }
+
+        // Commit only once the row decodes, so an error never hides a saved edit
+        let mut tx = pool.begin().await?;
+        let row = query
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| DbError::NotFound("Row not found".to_string()))?;
+
+        let row = decode_row(&row, &self.decode_options)?;
+        tx.commit().await?;
+        Ok(row)
+    }
+
+    async fn preview_changes(&self, staged: &[StagedChange]) -> DbResult<Vec<String>> {
+        let steps =
+            changes::plan_all(&mut ChangeTables(self.get_pool()?), staged, &DIALECT).await?;
+        Ok(changes::preview(&steps, &DIALECT))
+    }
+
+    async fn apply_changes(&self, staged: &[StagedChange]) -> DbResult<u64> {
+        let pool = self.get_pool()?;
+        let steps = changes::plan_all(&mut ChangeTables(pool), staged, &DIALECT).await?;
+        let mut runner = ChangeRunner {
+            tx: pool.begin().await?,
+            opts: &self.decode_options,
+        };
+
+        match changes::run(&mut runner, &steps, &DIALECT).await {
+            Ok(rows_written) => {
+                runner.tx.commit().await?;
+                Ok(rows_written)
+            }
+            Err(e) => {
+                runner.tx.rollback().await?;
+                Err(e)
+            }
+        }
+    }
+
     async fn get_all_entities(&self) -> DbResult<HashMap<String, DbEntity>> {
         let pool = self.get_pool()?;
         let mut entities = HashMap::new();
         let mut schema_children_map: HashMap<String, Vec<String>> = HashMap::new();
 
+        let flavor = self.flavor();
+        let system_predicate = flavor::system_schema_predicate(flavor);
+        let (extension_name, extension_join) = if flavor::tracks_extensions(flavor) {
+            (
+                "e.extname",
+                r#"
+            LEFT JOIN pg_depend d ON d.objid = n.oid AND d.deptype = 'e'
+            LEFT JOIN pg_extension e ON e.oid = d.refobjid"#,
+            )
+        } else {
+            ("NULL::TEXT", "")
+        };
+
         // Query 1: Get all schemas
-        let schema_query = r#"
+        let schema_query = format!(
+            r#"
             SELECT
                 n.oid::TEXT AS schema_id,
                 n.nspname AS schema_name,
-                CASE
-                    WHEN n.nspname IN ('pg_catalog', 'information_schema', 'pg_toast')
-                         OR n.nspname LIKE 'pg_%'
-                    THEN true
-                    ELSE false
-                END AS is_system,
-                e.extname AS extension_name
-            FROM pg_namespace n
-            LEFT JOIN pg_depend d ON d.objid = n.oid AND d.deptype = 'e'
-            LEFT JOIN pg_extension e ON e.oid = d.refobjid
+                {} AS is_system,
+                {} AS extension_name
+            FROM pg_namespace n{}
             ORDER BY n.nspname
-        "#;
+        "#,
+            system_predicate, extension_name, extension_join
+        );
 
-        let schema_rows = sqlx::query(schema_query).fetch_all(pool).await?;
+        let schema_rows = sqlx::query(&schema_query).fetch_all(pool).await?;
         for row in schema_rows {
             let id: String = row.get("schema_id");
             let name: String = row.get("schema_name");
@@ -193,28 +609,27 @@ impl DatabaseClient for PostgresClient {
         }
 
         // Query 2: Get tables, views, materialized views, foreign tables
-        let class_query = r#"
+        let class_query = format!(
+            r#"
             SELECT
                 c.oid::TEXT AS id,
                 c.relname AS name,
                 c.relkind::TEXT AS kind,
                 n.oid::TEXT AS schema_id,
-                CASE
-                    WHEN n.nspname IN ('pg_catalog', 'information_schema', 'pg_toast')
-                         OR n.nspname LIKE 'pg_%'
-                    THEN true
-                    ELSE false
-                END AS is_system,
-                e.extname AS extension_name
+                {} AS is_system,
+                {} AS extension_name
             FROM pg_class c
-            JOIN pg_namespace n ON c.relnamespace = n.oid
-            LEFT JOIN pg_depend d ON d.objid = n.oid AND d.deptype = 'e'
-            LEFT JOIN pg_extension e ON e.oid = d.refobjid
-            WHERE c.relkind IN ('r', 'v', 'm', 'f', 'S')
+            JOIN pg_namespace n ON c.relnamespace = n.oid{}
+            WHERE c.relkind IN ({})
             ORDER BY n.nspname, c.relname
-        "#;
-
-        let class_rows = sqlx::query(class_query).fetch_all(pool).await?;
+        "#,
+            system_predicate,
+            extension_name,
+            extension_join,
+            flavor::relkinds(flavor)
+        );
+
+        let class_rows = sqlx::query(&class_query).fetch_all(pool).await?;
         for row in class_rows {
             let id: String = row.get("id");
             let name: String = row.get("name");
@@ -240,7 +655,6 @@ impl DatabaseClient for PostgresClient {
                 "v" => DbEntity::View(schema_level),
                 "m" => DbEntity::MaterializedView(schema_level),
                 "f" => DbEntity::ForeignTable(schema_level),
-                // "S" => DbEntity::Sequence(schema_level),
                 _ => continue,
             };
 
@@ -0,0 +1,154 @@
+use sqlx::{Pool, Postgres, Row as SqlxRow};
+
+use crate::db::errors::{DbError, DbResult};
+pub(crate) use crate::db::ident::quote_double as quote_ident;
+use crate::db::ident::quote_literal_with;
+
+/// Quotes a string literal so it can be safely interpolated into SQL
+pub(crate) fn quote_literal(value: &str) -> String {
+    quote_literal_with(value, false)
+}
+
+/// A column of a relation as described by pg_attribute
+#[derive(Debug, Clone)]
+pub(crate) struct RelationColumn {
+    pub name: String,
+    /// Type as rendered by format_type(), usable in casts
+    pub data_type: String,
+    pub nullable: bool,
+    pub default_value: Option<String>,
+}
+
+/// Catalog information about a table-like relation, looked up by OID
+#[derive(Debug, Clone)]
+pub(crate) struct Relation {
+    pub id: String,
+    pub schema: String,
+    pub name: String,
+    pub kind: String,
+    pub columns: Vec<RelationColumn>,
+    /// Columns of the primary key, or of the first non-null unique key
+    pub key_columns: Vec<String>,
+}
+
+impl Relation {
+    /// Loads a relation and its columns by OID
+    pub async fn load(pool: &Pool<Postgres>, id: &str) -> DbResult<Self> {
+        let row = sqlx::query(
+            r#"
+            SELECT n.nspname AS schema_name, c.relname AS name, c.relkind::TEXT AS kind
+            FROM pg_class c
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            WHERE c.oid = $1::oid
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| DbError::NotFound(format!("Relation {}", id)))?;
+
+        let column_rows = sqlx::query(
+            r#"
+            SELECT
+                a.attname AS name,
+                format_type(a.atttypid, a.atttypmod) AS data_type,
+                NOT a.attnotnull AS nullable,
+                pg_get_expr(d.adbin, d.adrelid) AS default_value
+            FROM pg_attribute a
+            LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum
+            WHERE a.attrelid = $1::oid AND a.attnum > 0 AND NOT a.attisdropped
+            ORDER BY a.attnum
+            "#,
+        )
+        .bind(id)
+        .fetch_all(pool)
+        .await?;
+
+        let columns = column_rows
+            .iter()
+            .map(|row| RelationColumn {
+                name: row.get("name"),
+                data_type: row.get("data_type"),
+                nullable: row.get("nullable"),
+                default_value: row.get("default_value"),
+            })
+            .collect::<Vec<_>>();
+
+        // Prefer the primary key, then fall back to a unique index on non-null columns
+        let key_row = sqlx::query(
+            r#"
+            SELECT array_agg(a.attname::TEXT ORDER BY k.ord) AS key_columns
+            FROM pg_index i
+            CROSS JOIN LATERAL unnest(i.indkey) WITH ORDINALITY AS k(attnum, ord)
+            JOIN pg_attribute a ON a.attrelid = i.indrelid AND a.attnum = k.attnum
+            WHERE i.indrelid = $1::oid
+              AND i.indisunique
+              AND i.indpred IS NULL
+              AND i.indexprs IS NULL
+              AND k.ord <= i.indnkeyatts
+            GROUP BY i.indexrelid, i.indisprimary
+            HAVING i.indisprimary OR bool_and(a.attnotnull)
+            ORDER BY i.indisprimary DESC, count(*), i.indexrelid
+            LIMIT 1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+        let key_columns = key_row
+            .map(|row| row.get::<Vec<String>, _>("key_columns"))
+            .unwrap_or_default();
+
+        Ok(Self {
+            id: id.to_string(),
+            schema: row.get("schema_name"),
+            name: row.get("name"),
+            kind: row.get("kind"),
+            columns,
+            key_columns,
+        })
+    }
+
+    /// Schema-qualified, quoted name of the relation
+    pub fn qualified_name(&self) -> String {
+        format!("{}.{}", quote_ident(&self.schema), quote_ident(&self.name))
+    }
+
+    pub fn column(&self, name: &str) -> DbResult<&RelationColumn> {
+        self.columns
+            .iter()
+            .find(|c| c.name == name)
+            .ok_or_else(|| DbError::NotFound(format!("Column {} on {}", name, self.name)))
+    }
+
+    /// Builds a WHERE clause matching a row by its key values.
+    /// Values are bound as text starting at `$first_param` and cast to the column type.
+    pub fn key_predicate<'a>(
+        &self,
+        key: &'a std::collections::HashMap<String, String>,
+        first_param: usize,
+    ) -> DbResult<(String, Vec<&'a str>)> {
+        if key.is_empty() {
+            return Err(DbError::Query("A row key is required".to_string()));
+        }
+
+        let mut names = key.keys().collect::<Vec<_>>();
+        names.sort();
+
+        let mut clauses = Vec::with_capacity(names.len());
+        let mut params = Vec::with_capacity(names.len());
+        for (i, name) in names.into_iter().enumerate() {
+            let column = self.column(name)?;
+            clauses.push(format!(
+                "{} = ${}::{}",
+                quote_ident(&column.name),
+                first_param + i,
+                column.data_type
+            ));
+            params.push(key[name].as_str());
+        }
+
+        Ok((clauses.join(" AND "), params))
+    }
+}
@@ -0,0 +1,267 @@
+use async_trait::async_trait;
+use sqlx::{Pool, Postgres, Row as SqlxRow};
+use std::collections::HashMap;
+
+use crate::db::errors::DbResult;
+use crate::db::search::{
+    is_uuid, CancelToken, ColumnMatch, SearchMode, SearchProgress, SearchRequest, SearchSummary,
+    Searcher, SkippedTable, TableMatches,
+};
+use crate::db::types::ServerFlavor;
+
+use super::browse::escape_like;
+use super::flavor;
+use super::relation::{quote_ident, Relation};
+
+/// SQLSTATE for a statement cancelled by a timeout or pg_cancel_backend()
+const QUERY_CANCELED: &str = "57014";
+
+/// A table with the columns a term can be compared with
+struct Candidate {
+    table_id: String,
+    schema: String,
+    name: String,
+    columns: Vec<String>,
+    /// Parallel to `columns`; UUID columns are compared by value rather than as text
+    uuid_columns: Vec<bool>,
+}
+
+impl Candidate {
+    fn skipped(&self, reason: String) -> SkippedTable {
+        SkippedTable {
+            table_id: self.table_id.clone(),
+            schema: self.schema.clone(),
+            name: self.name.clone(),
+            reason,
+        }
+    }
+}
+
+/// Tables in the chosen schemas with text-like or (when the term is a UUID) UUID columns
+async fn candidates(
+    pool: &Pool<Postgres>,
+    flavor: ServerFlavor,
+    request: &SearchRequest,
+) -> DbResult<Vec<Candidate>> {
+    let type_predicate = if is_uuid(&request.term) {
+        "(t.typcategory = 'S' OR t.typname = 'uuid')"
+    } else {
+        "t.typcategory = 'S'"
+    };
+
+    let query = format!(
+        r#"
+        SELECT
+            c.oid::TEXT AS table_id,
+            n.nspname AS schema_name,
+            c.relname AS table_name,
+            array_agg(a.attname::TEXT ORDER BY a.attnum) AS columns,
+            array_agg(t.typname = 'uuid' ORDER BY a.attnum) AS uuid_columns
+        FROM pg_class c
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        JOIN pg_attribute a ON a.attrelid = c.oid AND a.attnum > 0 AND NOT a.attisdropped
+        JOIN pg_type t ON t.oid = a.atttypid
+        WHERE c.relkind IN ('r', 'p', 'm')
+            AND {}
+            AND CASE
+                WHEN cardinality($1::TEXT[]) = 0 THEN NOT {}
+                ELSE n.nspname = ANY($1::TEXT[])
+            END
+        GROUP BY c.oid, n.nspname, c.relname
+        ORDER BY n.nspname, c.relname
+        "#,
+        type_predicate,
+        flavor::system_schema_predicate(flavor)
+    );
+
+    let rows = sqlx::query(&query)
+        .bind(&request.schemas)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| Candidate {
+            table_id: row.get("table_id"),
+            schema: row.get("schema_name"),
+            name: row.get("table_name"),
+            columns: row.get("columns"),
+            uuid_columns: row.get("uuid_columns"),
+        })
+        .collect())
+}
+
+/// Predicate matching the term against one column; `$1` is the term and `$2` its LIKE pattern
+fn column_predicate(column: &str, is_uuid_column: bool, mode: SearchMode) -> String {
+    let column = quote_ident(column);
+    match (is_uuid_column, mode) {
+        (true, _) => format!("{} = $1::uuid", column),
+        (false, SearchMode::Contains) => format!("{}::TEXT ILIKE $2", column),
+        (false, SearchMode::Exact) => format!("{}::TEXT = $1", column),
+    }
+}
+
+/// Searches one table in its own transaction, so the statement timeout only applies there.
+/// Returns `None` when nothing matched or the search was cancelled.
+async fn search_table(
+    pool: &Pool<Postgres>,
+    relation: &Relation,
+    candidate: &Candidate,
+    request: &SearchRequest,
+    token: &mut CancelToken,
+) -> Result<Option<TableMatches>, sqlx::Error> {
+    let predicates = candidate
+        .columns
+        .iter()
+        .zip(&candidate.uuid_columns)
+        .map(|(column, is_uuid_column)| column_predicate(column, *is_uuid_column, request.mode))
+        .collect::<Vec<_>>();
+
+    let mut select = relation
+        .key_columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| format!("{}::TEXT AS \"k{}\"", quote_ident(column), i))
+        .collect::<Vec<_>>();
+    select.extend(
+        predicates
+            .iter()
+            .enumerate()
+            .map(|(i, predicate)| format!("COALESCE({}, FALSE) AS \"m{}\"", predicate, i)),
+    );
+
+    let sql = format!(
+        "SELECT {} FROM {} WHERE {} LIMIT $3",
+        select.join(", "),
+        relation.qualified_name(),
+        predicates.join(" OR ")
+    );
+
+    let mut tx = pool.begin().await?;
+    let pid: i32 = sqlx::query_scalar("SELECT pg_backend_pid()")
+        .fetch_one(&mut *tx)
+        .await?;
+    sqlx::query("SELECT set_config('statement_timeout', $1, true)")
+        .bind(format!("{}ms", request.table_timeout_ms))
+        .execute(&mut *tx)
+        .await?;
+
+    let query = sqlx::query(&sql)
+        .bind(&request.term)
+        .bind(format!("%{}%", escape_like(&request.term)))
+        .bind(request.rows_per_table as i64 + 1);
+
+    let rows = tokio::select! {
+        rows = query.fetch_all(&mut *tx) => rows?,
+        _ = token.cancelled() => {
+            // Dropping the future leaves the statement running on the server
+            let _ = sqlx::query("SELECT pg_cancel_backend($1)")
+                .bind(pid)
+                .execute(pool)
+                .await;
+            return Ok(None);
+        }
+    };
+    tx.rollback().await?;
+
+    if rows.is_empty() {
+        return Ok(None);
+    }
+
+    let truncated = rows.len() > request.rows_per_table as usize;
+    let rows = &rows[..rows.len().min(request.rows_per_table as usize)];
+
+    let mut columns = Vec::new();
+    for (i, column) in candidate.columns.iter().enumerate() {
+        let matching = rows
+            .iter()
+            .filter(|row| row.get::<bool, _>(format!("m{}", i).as_str()))
+            .collect::<Vec<_>>();
+        if matching.is_empty() {
+            continue;
+        }
+
+        let keys = matching
+            .iter()
+            .filter(|_| !relation.key_columns.is_empty())
+            .map(|row| {
+                relation
+                    .key_columns
+                    .iter()
+                    .enumerate()
+                    .map(|(k, name)| (name.clone(), row.get(format!("k{}", k).as_str())))
+                    .collect::<HashMap<_, _>>()
+            })
+            .collect();
+
+        columns.push(ColumnMatch {
+            column: column.clone(),
+            rows: keys,
+            row_count: matching.len() as u32,
+        });
+    }
+
+    Ok(Some(TableMatches {
+        table_id: candidate.table_id.clone(),
+        schema: candidate.schema.clone(),
+        name: candidate.name.clone(),
+        columns,
+        truncated,
+    }))
+}
+
+/// Searches every candidate table in turn, reporting each one as it finishes.
+/// Tables that fail or time out are skipped rather than ending the search.
+/// It has its own handle to the pool, so the window's client isn't locked meanwhile.
+pub(crate) struct PgSearcher {
+    pub pool: Pool<Postgres>,
+    pub flavor: ServerFlavor,
+}
+
+#[async_trait]
+impl Searcher for PgSearcher {
+    async fn search(
+        &self,
+        request: &SearchRequest,
+        mut token: CancelToken,
+        progress: &(dyn Fn(SearchProgress) + Send + Sync),
+    ) -> DbResult<SearchSummary> {
+        request.validate()?;
+        let pool = &self.pool;
+        let candidates = candidates(pool, self.flavor, request).await?;
+        let mut summary = SearchSummary::new(request.search_id, candidates.len() as u32);
+
+        for candidate in &candidates {
+            if token.is_cancelled() {
+                break;
+            }
+
+            let (matches, skipped) = match Relation::load(pool, &candidate.table_id).await {
+                Ok(relation) => {
+                    match search_table(pool, &relation, candidate, request, &mut token).await {
+                        Ok(matches) => (matches, None),
+                        Err(sqlx::Error::Database(e))
+                            if e.code().as_deref() == Some(QUERY_CANCELED) =>
+                        {
+                            let reason = format!("Timed out after {} ms", request.table_timeout_ms);
+                            (None, Some(candidate.skipped(reason)))
+                        }
+                        Err(sqlx::Error::Database(e)) => {
+                            (None, Some(candidate.skipped(e.message().to_string())))
+                        }
+                        Err(e) => return Err(e.into()),
+                    }
+                }
+                Err(e) => (None, Some(candidate.skipped(e.to_string()))),
+            };
+
+            if token.is_cancelled() {
+                break;
+            }
+            progress(summary.record(matches, skipped));
+        }
+
+        summary.cancelled = token.is_cancelled();
+        Ok(summary)
+    }
+}
@@ -0,0 +1,303 @@
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, TimeZone, Utc};
+use serde_json::Value as JsonValue;
+
+use crate::db::errors::{DbError, DbResult};
+use crate::db::temporal::{format_offset, DisplayZone, DATETIME_FORMAT, DATE_FORMAT, TIME_FORMAT};
+
+// Postgres encodes ±infinity as the extreme values of the underlying integer
+const TIMESTAMP_INFINITY: i64 = i64::MAX;
+const TIMESTAMP_NEG_INFINITY: i64 = i64::MIN;
+const DATE_INFINITY: i32 = i32::MAX;
+const DATE_NEG_INFINITY: i32 = i32::MIN;
+
+fn pg_epoch() -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2000, 1, 1)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+}
+
+fn read_i64(bytes: &[u8]) -> DbResult<i64> {
+    bytes
+        .get(..8)
+        .and_then(|b| b.try_into().ok())
+        .map(i64::from_be_bytes)
+        .ok_or_else(|| DbError::Parsing("Invalid temporal value".to_string()))
+}
+
+fn read_i32(bytes: &[u8]) -> DbResult<i32> {
+    bytes
+        .get(..4)
+        .and_then(|b| b.try_into().ok())
+        .map(i32::from_be_bytes)
+        .ok_or_else(|| DbError::Parsing("Invalid temporal value".to_string()))
+}
+
+fn out_of_range() -> DbError {
+    DbError::Parsing("Temporal value out of range".to_string())
+}
+
+fn naive_timestamp(micros: i64) -> DbResult<NaiveDateTime> {
+    pg_epoch()
+        .checked_add_signed(TimeDelta::microseconds(micros))
+        .ok_or_else(out_of_range)
+}
+
+pub(crate) fn date(bytes: &[u8]) -> DbResult<JsonValue> {
+    let value = match read_i32(bytes)? {
+        DATE_INFINITY => "infinity".to_string(),
+        DATE_NEG_INFINITY => "-infinity".to_string(),
+        days => pg_epoch()
+            .date()
+            .checked_add_signed(TimeDelta::days(days as i64))
+            .ok_or_else(out_of_range)?
+            .format(DATE_FORMAT)
+            .to_string(),
+    };
+
+    Ok(JsonValue::String(value))
+}
+
+pub(crate) fn time(bytes: &[u8]) -> DbResult<JsonValue> {
+    Ok(JsonValue::String(format_time(read_i64(bytes)?)?))
+}
+
+pub(crate) fn timetz(bytes: &[u8]) -> DbResult<JsonValue> {
+    let time = format_time(read_i64(bytes)?)?;
+    // The zone is stored in seconds *west* of UTC
+    let offset = -read_i32(bytes.get(8..).unwrap_or_default())?;
+
+    Ok(JsonValue::String(format!(
+        "{}{}",
+        time,
+        format_offset(offset)
+    )))
+}
+
+fn format_time(micros: i64) -> DbResult<String> {
+    // 24:00:00 is a valid TIME in Postgres but not in chrono
+    if micros == 86_400_000_000 {
+        return Ok("24:00:00".to_string());
+    }
+
+    let seconds = u32::try_from(micros.div_euclid(1_000_000)).map_err(|_| out_of_range())?;
+    let nanos = (micros.rem_euclid(1_000_000) * 1_000) as u32;
+
+    NaiveTime::from_num_seconds_from_midnight_opt(seconds, nanos)
+        .map(|t| t.format(TIME_FORMAT).to_string())
+        .ok_or_else(out_of_range)
+}
+
+pub(crate) fn timestamp(bytes: &[u8]) -> DbResult<JsonValue> {
+    let value = match read_i64(bytes)? {
+        TIMESTAMP_INFINITY => "infinity".to_string(),
+        TIMESTAMP_NEG_INFINITY => "-infinity".to_string(),
+        micros => naive_timestamp(micros)?.format(DATETIME_FORMAT).to_string(),
+    };
+
+    Ok(JsonValue::String(value))
+}
+
+pub(crate) fn timestamptz(bytes: &[u8], zone: &DisplayZone) -> DbResult<JsonValue> {
+    let value = match read_i64(bytes)? {
+        TIMESTAMP_INFINITY => "infinity".to_string(),
+        TIMESTAMP_NEG_INFINITY => "-infinity".to_string(),
+        micros => zone.format(Utc.from_utc_datetime(&naive_timestamp(micros)?)),
+    };
+
+    Ok(JsonValue::String(value))
+}
+
+/// Rewrites a `DateStyle=ISO` text value in the format the binary path produces.
+/// Text is in the session zone and marks years before 1 AD with a ` BC` suffix.
+pub(crate) fn from_text(type_name: &str, text: &str, zone: &DisplayZone) -> JsonValue {
+    let (ad, bc) = match text.strip_suffix(" BC") {
+        Some(ad) => (ad, true),
+        None => (text, false),
+    };
+
+    let formatted = match type_name {
+        "DATE" => NaiveDate::parse_from_str(ad, "%Y-%m-%d")
+            .ok()
+            .and_then(|date| astronomical(date, bc))
+            .map(|date| date.format(DATE_FORMAT).to_string()),
+        "TIMESTAMP" => NaiveDateTime::parse_from_str(ad, "%Y-%m-%d %H:%M:%S%.f")
+            .ok()
+            .and_then(|dt| astronomical(dt, bc))
+            .map(|dt| dt.format(DATETIME_FORMAT).to_string()),
+        // The offset can have seconds, which chrono's parser doesn't take, so it's split off
+        "TIMESTAMPTZ" => ad.rfind(['+', '-']).and_then(|at| {
+            let local = NaiveDateTime::parse_from_str(&ad[..at], "%Y-%m-%d %H:%M:%S%.f").ok()?;
+            let utc = astronomical(local, bc)?
+                .checked_sub_signed(TimeDelta::seconds(text_offset(&ad[at..])?.into()))?;
+            Some(zone.format(Utc.from_utc_datetime(&utc)))
+        }),
+        "TIMETZ" => text.rfind(['+', '-']).and_then(|at| {
+            Some(format!(
+                "{}{}",
+                &text[..at],
+                format_offset(text_offset(&text[at..])?)
+            ))
+        }),
+        // TIME is already ISO, and ±infinity don't parse so are kept as they are
+        _ => None,
+    };
+
+    JsonValue::String(formatted.unwrap_or_else(|| text.to_string()))
+}
+
+/// Year `n` BC is year `1 - n` in the astronomical numbering the binary path uses
+fn astronomical<T: Datelike>(value: T, bc: bool) -> Option<T> {
+    if bc {
+        value.with_year(1 - value.year())
+    } else {
+        Some(value)
+    }
+}
+
+/// Seconds east of UTC of a text offset: `+02`, `+05:30` or `+05:30:15`
+fn text_offset(offset: &str) -> Option<i32> {
+    let sign = if offset.starts_with('-') { -1 } else { 1 };
+    let mut parts = offset.get(1..)?.split(':').map(|part| part.parse::<i32>());
+    let hours = parts.next()?.ok()?;
+    let minutes = parts.next().unwrap_or(Ok(0)).ok()?;
+    let seconds = parts.next().unwrap_or(Ok(0)).ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(sign * (hours * 3600 + minutes * 60 + seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    fn text(value: DbResult<JsonValue>) -> String {
+        value.unwrap().as_str().unwrap().to_string()
+    }
+
+    fn parse(type_name: &str, value: &str, zone: &DisplayZone) -> String {
+        from_text(type_name, value, zone)
+            .as_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn decodes_dates() {
+        assert_eq!(text(date(&0i32.to_be_bytes())), "2000-01-01");
+        assert_eq!(text(date(&(-1i32).to_be_bytes())), "1999-12-31");
+        // 1 BC is year 0
+        assert_eq!(text(date(&(-730_485i32).to_be_bytes())), "0000-01-01");
+        assert_eq!(text(date(&i32::MAX.to_be_bytes())), "infinity");
+        assert_eq!(text(date(&i32::MIN.to_be_bytes())), "-infinity");
+        assert!(date(&[0, 0]).is_err());
+    }
+
+    #[test]
+    fn decodes_timestamps() {
+        assert_eq!(text(timestamp(&0i64.to_be_bytes())), "2000-01-01T00:00:00");
+        assert_eq!(
+            text(timestamp(&1_500_000i64.to_be_bytes())),
+            "2000-01-01T00:00:01.500"
+        );
+        assert_eq!(text(timestamp(&i64::MAX.to_be_bytes())), "infinity");
+        assert_eq!(text(timestamp(&i64::MIN.to_be_bytes())), "-infinity");
+    }
+
+    #[test]
+    fn decodes_zoned_timestamps_in_the_display_zone() {
+        let bytes = 0i64.to_be_bytes();
+        assert_eq!(
+            text(timestamptz(&bytes, &DisplayZone::Utc)),
+            "2000-01-01T00:00:00Z"
+        );
+        assert_eq!(
+            text(timestamptz(
+                &bytes,
+                &DisplayZone::Named(chrono_tz::Asia::Kolkata)
+            )),
+            "2000-01-01T05:30:00+05:30"
+        );
+        assert_eq!(
+            text(timestamptz(
+                &bytes,
+                &DisplayZone::Fixed(FixedOffset::west_opt(3600).unwrap())
+            )),
+            "1999-12-31T23:00:00-01:00"
+        );
+        assert_eq!(
+            text(timestamptz(&i64::MAX.to_be_bytes(), &DisplayZone::Utc)),
+            "infinity"
+        );
+    }
+
+    #[test]
+    fn decodes_times_with_zones() {
+        assert_eq!(text(time(&86_400_000_000i64.to_be_bytes())), "24:00:00");
+
+        // The zone is stored in seconds west of UTC
+        let mut bytes = 3_600_000_000i64.to_be_bytes().to_vec();
+        bytes.extend_from_slice(&(-19_815i32).to_be_bytes());
+        assert_eq!(text(timetz(&bytes)), "01:00:00+05:30:15");
+    }
+
+    #[test]
+    fn rewrites_text_dates_and_timestamps() {
+        let utc = DisplayZone::Utc;
+        assert_eq!(parse("DATE", "2024-03-01", &utc), "2024-03-01");
+        assert_eq!(parse("DATE", "0044-03-15 BC", &utc), "-0043-03-15");
+        assert_eq!(parse("DATE", "infinity", &utc), "infinity");
+        assert_eq!(
+            parse("TIMESTAMP", "2024-03-01 10:00:00.25", &utc),
+            "2024-03-01T10:00:00.250"
+        );
+        assert_eq!(
+            parse("TIMESTAMP", "0001-01-01 00:00:00 BC", &utc),
+            "0000-01-01T00:00:00"
+        );
+        assert_eq!(parse("TIME", "24:00:00", &utc), "24:00:00");
+    }
+
+    #[test]
+    fn rewrites_text_offsets() {
+        let utc = DisplayZone::Utc;
+        assert_eq!(
+            parse("TIMESTAMPTZ", "2024-03-01 10:00:00+05:30", &utc),
+            "2024-03-01T04:30:00Z"
+        );
+        assert_eq!(
+            parse("TIMESTAMPTZ", "2024-03-01 10:00:00-03", &utc),
+            "2024-03-01T13:00:00Z"
+        );
+        // Local mean time offsets have seconds
+        assert_eq!(
+            parse("TIMESTAMPTZ", "2024-03-01 10:00:00+05:30:15", &utc),
+            "2024-03-01T04:29:45Z"
+        );
+        assert_eq!(
+            parse("TIMESTAMPTZ", "0044-03-15 12:00:00+00 BC", &utc),
+            "-0043-03-15T12:00:00Z"
+        );
+        assert_eq!(
+            parse(
+                "TIMESTAMPTZ",
+                "2024-03-01 04:30:00+00",
+                &DisplayZone::Named(chrono_tz::Asia::Kolkata)
+            ),
+            "2024-03-01T10:00:00+05:30"
+        );
+        assert_eq!(parse("TIMESTAMPTZ", "-infinity", &utc), "-infinity");
+
+        assert_eq!(parse("TIMETZ", "10:00:00+02", &utc), "10:00:00+02:00");
+        assert_eq!(
+            parse("TIMETZ", "10:00:00.5-03:30", &utc),
+            "10:00:00.5-03:30"
+        );
+        assert_eq!(
+            parse("TIMETZ", "10:00:00+05:30:15", &utc),
+            "10:00:00+05:30:15"
+        );
+    }
+}
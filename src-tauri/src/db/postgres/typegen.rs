@@ -0,0 +1,194 @@
+use sqlx::{Pool, Postgres, Row as SqlxRow};
+
+use crate::db::errors::DbResult;
+use crate::db::typegen::{ScalarType, TypeModel, TypedColumn, TypedEnum, TypedRelation};
+use crate::db::types::ServerFlavor;
+
+use super::flavor;
+
+fn scalar_type(type_name: &str, type_kind: &str, type_schema: String) -> ScalarType {
+    if type_kind == "e" {
+        return ScalarType::Enum {
+            schema: type_schema,
+            name: type_name.to_string(),
+        };
+    }
+    match type_name {
+        "bool" => ScalarType::Bool,
+        "int2" => ScalarType::Int16,
+        "int4" => ScalarType::Int32,
+        "int8" => ScalarType::Int64,
+        "oid" => ScalarType::Oid,
+        "float4" => ScalarType::Float32,
+        "float8" => ScalarType::Float64,
+        "numeric" => ScalarType::Numeric,
+        "money" => ScalarType::Money,
+        "text" | "varchar" | "bpchar" | "char" | "name" | "citext" | "xml" => ScalarType::Text,
+        "uuid" => ScalarType::Uuid,
+        "json" | "jsonb" => ScalarType::Json,
+        "bytea" => ScalarType::Bytes,
+        "date" => ScalarType::Date,
+        "time" => ScalarType::Time,
+        "timestamp" => ScalarType::Timestamp,
+        "timestamptz" => ScalarType::TimestampTz,
+        "interval" => ScalarType::Interval,
+        "inet" | "cidr" => ScalarType::Inet,
+        _ => ScalarType::Other(type_name.to_string()),
+    }
+}
+
+/// Reads the columns of the chosen schemas' tables and views, with the enums they use.
+/// Domains are read as their base type, and views' columns are nullable since Postgres
+/// can't tell whether they are.
+pub(crate) async fn type_model(
+    pool: &Pool<Postgres>,
+    flavor: ServerFlavor,
+    schemas: &[String],
+) -> DbResult<TypeModel> {
+    let schema_filter = format!(
+        r#"CASE
+            WHEN cardinality($1::TEXT[]) = 0 THEN NOT {}
+            ELSE n.nspname = ANY($1::TEXT[])
+        END"#,
+        flavor::system_schema_predicate(flavor)
+    );
+
+    let column_rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            c.oid::TEXT AS relation_id,
+            n.nspname::TEXT AS schema_name,
+            c.relname::TEXT AS relation_name,
+            a.attname::TEXT AS name,
+            NOT a.attnotnull AND NOT (t.typtype = 'd' AND t.typnotnull) AS nullable,
+            bt.typcategory = 'A' AS is_array,
+            et.oid::INT8 AS type_id,
+            et.typname::TEXT AS type_name,
+            et.typtype::TEXT AS type_kind,
+            en.nspname::TEXT AS type_schema
+        FROM pg_class c
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        JOIN pg_attribute a ON a.attrelid = c.oid AND a.attnum > 0 AND NOT a.attisdropped
+        JOIN pg_type t ON t.oid = a.atttypid
+        -- Domains are read as the type they're over
+        JOIN pg_type bt ON bt.oid = CASE WHEN t.typtype = 'd' THEN t.typbasetype ELSE t.oid END
+        JOIN pg_type et ON et.oid = CASE WHEN bt.typcategory = 'A' THEN bt.typelem ELSE bt.oid END
+        JOIN pg_namespace en ON en.oid = et.typnamespace
+        WHERE c.relkind IN ('r', 'p', 'v', 'm', 'f') AND NOT c.relispartition AND {}
+        ORDER BY n.nspname, c.relname, a.attnum
+        "#,
+        schema_filter
+    ))
+    .bind(schemas)
+    .fetch_all(pool)
+    .await?;
+
+    let mut relations = Vec::<TypedRelation>::new();
+    let mut relation_id = None;
+    let mut enum_ids = Vec::new();
+    for row in &column_rows {
+        let id: String = row.get("relation_id");
+        if relation_id.as_ref() != Some(&id) {
+            relations.push(TypedRelation {
+                schema: row.get("schema_name"),
+                name: row.get("relation_name"),
+                columns: Vec::new(),
+            });
+            relation_id = Some(id);
+        }
+
+        let type_kind: String = row.get("type_kind");
+        if type_kind == "e" {
+            enum_ids.push(row.get::<i64, _>("type_id"));
+        }
+        let scalar = scalar_type(
+            &row.get::<String, _>("type_name"),
+            &type_kind,
+            row.get("type_schema"),
+        );
+        if let Some(relation) = relations.last_mut() {
+            relation.columns.push(TypedColumn {
+                name: row.get("name"),
+                scalar,
+                is_array: row.get("is_array"),
+                nullable: row.get("nullable"),
+            });
+        }
+    }
+
+    // Enums in the chosen schemas, and those elsewhere that their columns use
+    let enum_rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            n.nspname::TEXT AS schema_name,
+            t.typname::TEXT AS name,
+            ARRAY(
+                SELECT e.enumlabel::TEXT FROM pg_enum e
+                WHERE e.enumtypid = t.oid
+                ORDER BY e.enumsortorder
+            ) AS labels
+        FROM pg_type t
+        JOIN pg_namespace n ON n.oid = t.typnamespace
+        WHERE t.typtype = 'e' AND ({} OR t.oid = ANY($2::INT8[]::oid[]))
+        ORDER BY n.nspname, t.typname
+        "#,
+        schema_filter
+    ))
+    .bind(schemas)
+    .bind(&enum_ids)
+    .fetch_all(pool)
+    .await?;
+    let enums = enum_rows
+        .iter()
+        .map(|row| TypedEnum {
+            schema: row.get("schema_name"),
+            name: row.get("name"),
+            labels: row.get("labels"),
+        })
+        .collect();
+
+    Ok(TypeModel { relations, enums })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scalar(type_name: &str) -> ScalarType {
+        scalar_type(type_name, "b", "pg_catalog".to_string())
+    }
+
+    #[test]
+    fn maps_built_in_types() {
+        assert_eq!(scalar("int8"), ScalarType::Int64);
+        assert_eq!(scalar("oid"), ScalarType::Oid);
+        assert_eq!(scalar("money"), ScalarType::Money);
+        assert_eq!(scalar("bpchar"), ScalarType::Text);
+        assert_eq!(scalar("jsonb"), ScalarType::Json);
+        assert_eq!(scalar("cidr"), ScalarType::Inet);
+        assert_eq!(scalar("timestamptz"), ScalarType::TimestampTz);
+    }
+
+    #[test]
+    fn maps_extension_types_by_name() {
+        assert_eq!(
+            scalar_type("citext", "b", "public".to_string()),
+            ScalarType::Text
+        );
+        assert_eq!(
+            scalar_type("tsvector", "b", "pg_catalog".to_string()),
+            ScalarType::Other("tsvector".to_string())
+        );
+    }
+
+    #[test]
+    fn enums_keep_their_schema() {
+        assert_eq!(
+            scalar_type("mood", "e", "app".to_string()),
+            ScalarType::Enum {
+                schema: "app".to_string(),
+                name: "mood".to_string(),
+            }
+        );
+    }
+}
@@ -0,0 +1,848 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::db::catalog::{
+    CatalogSnapshot, ColumnSnapshot, ConstraintKind, ConstraintSnapshot, EnumSnapshot,
+    FunctionSnapshot, IndexSnapshot, SequenceSnapshot, TableSnapshot,
+};
+use crate::db::postgres::ddl::{create_schema, qualified};
+use crate::db::postgres::relation::{quote_ident, quote_literal};
+
+/// Where one side of a comparison comes from
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum SnapshotSource {
+    /// The window's own connection
+    Current,
+    /// Another database, by connection string
+    Connection { url: String },
+    /// A snapshot saved in the project
+    Saved { name: String },
+    /// The schema snapshot committed in `.sqratch/schema`
+    Committed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum ObjectType {
+    Schema,
+    Table,
+    Column,
+    Index,
+    Constraint,
+    Function,
+    Enum,
+    Sequence,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum ChangeKind {
+    /// Only in the source, so the migration creates it
+    Added,
+    /// Only in the target, so the migration drops it
+    Removed,
+    Changed,
+}
+
+/// One difference between the source and target schemas
+#[taurpc::ipc_type]
+#[serde(rename_all = "camelCase")]
+#[derive(Debug)]
+pub struct SchemaChange {
+    pub object: ObjectType,
+    pub change: ChangeKind,
+    pub schema: String,
+    /// Table the column, index or constraint belongs to
+    pub table: Option<String>,
+    pub name: String,
+    /// Definition in the source, if it has the object
+    pub source: Option<String>,
+    /// Definition in the target, if it has the object
+    pub target: Option<String>,
+}
+
+#[taurpc::ipc_type]
+#[serde(rename_all = "camelCase")]
+#[derive(Debug)]
+pub struct SchemaDiff {
+    pub changes: Vec<SchemaChange>,
+    /// Statements that bring the target in line with the source, in the order to run them.
+    /// Changes that can't be made safely are left as `--` comments.
+    pub migration: Vec<String>,
+}
+
+/// Pairs up items of two lists by key, in key order
+fn pair_by<'a, T, K: Ord>(
+    source: &'a [T],
+    target: &'a [T],
+    key: impl Fn(&'a T) -> K,
+) -> Vec<(Option<&'a T>, Option<&'a T>)> {
+    let mut pairs = BTreeMap::<K, (Option<&T>, Option<&T>)>::new();
+    for item in source {
+        pairs.entry(key(item)).or_default().0 = Some(item);
+    }
+    for item in target {
+        pairs.entry(key(item)).or_default().1 = Some(item);
+    }
+    pairs.into_values().collect()
+}
+
+/// Migration statements grouped by when they have to run
+#[derive(Debug, Default)]
+struct Migration {
+    create_schemas: Vec<String>,
+    create_types: Vec<String>,
+    /// Before tables, whose serial columns take their defaults from them
+    create_sequences: Vec<String>,
+    /// Created or replaced before tables, whose defaults, checks and generated columns
+    /// may call them
+    functions: Vec<String>,
+    /// Foreign keys go first, so the keys and columns they use can be dropped
+    drop_foreign_keys: Vec<String>,
+    drop_constraints: Vec<String>,
+    drop_indexes: Vec<String>,
+    create_tables: Vec<String>,
+    alter_columns: Vec<String>,
+    /// Once the columns owning them exist
+    sequence_owners: Vec<String>,
+    drop_tables: Vec<String>,
+    /// Once nothing uses them any more
+    drop_functions: Vec<String>,
+    drop_sequences: Vec<String>,
+    add_constraints: Vec<String>,
+    add_indexes: Vec<String>,
+    add_foreign_keys: Vec<String>,
+    drop_types: Vec<String>,
+}
+
+impl Migration {
+    fn into_statements(self) -> Vec<String> {
+        [
+            self.create_schemas,
+            self.create_types,
+            self.create_sequences,
+            self.functions,
+            self.drop_foreign_keys,
+            self.drop_constraints,
+            self.drop_indexes,
+            self.create_tables,
+            self.alter_columns,
+            self.sequence_owners,
+            self.drop_tables,
+            self.drop_functions,
+            self.drop_sequences,
+            self.add_constraints,
+            self.add_indexes,
+            self.add_foreign_keys,
+            self.drop_types,
+        ]
+        .concat()
+    }
+}
+
+struct Differ {
+    changes: Vec<SchemaChange>,
+    migration: Migration,
+}
+
+impl Differ {
+    /// Adds a change, leaving its definitions for the caller to fill in
+    fn record(
+        &mut self,
+        object: ObjectType,
+        change: ChangeKind,
+        schema: &str,
+        table: Option<&str>,
+        name: &str,
+    ) -> &mut SchemaChange {
+        self.changes.push(SchemaChange {
+            object,
+            change,
+            schema: schema.to_string(),
+            table: table.map(str::to_string),
+            name: name.to_string(),
+            source: None,
+            target: None,
+        });
+        self.changes.last_mut().unwrap()
+    }
+
+    fn schemas(&mut self, source: &[String], target: &[String]) {
+        for pair in pair_by(source, target, |s| s.as_str()) {
+            match pair {
+                (Some(schema), None) => {
+                    self.record(ObjectType::Schema, ChangeKind::Added, schema, None, schema);
+                    self.migration.create_schemas.push(create_schema(schema));
+                }
+                // Schemas are never dropped, since they may hold objects outside the comparison
+                (None, Some(schema)) => {
+                    self.record(
+                        ObjectType::Schema,
+                        ChangeKind::Removed,
+                        schema,
+                        None,
+                        schema,
+                    );
+                    self.migration.drop_types.push(format!(
+                        "-- Schema {} isn't in the source; drop it by hand if it's no longer used",
+                        quote_ident(schema)
+                    ));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn enums(&mut self, source: &[EnumSnapshot], target: &[EnumSnapshot]) {
+        let describe = |e: &EnumSnapshot| {
+            e.labels
+                .iter()
+                .map(|label| quote_literal(label))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        for pair in pair_by(source, target, |e| (e.schema.as_str(), e.name.as_str())) {
+            match pair {
+                (Some(s), None) => {
+                    self.record(
+                        ObjectType::Enum,
+                        ChangeKind::Added,
+                        &s.schema,
+                        None,
+                        &s.name,
+                    )
+                    .source = Some(describe(s));
+                    self.migration.create_types.push(format!(
+                        "CREATE TYPE {} AS ENUM ({});",
+                        qualified(&s.schema, &s.name),
+                        describe(s)
+                    ));
+                }
+                (None, Some(t)) => {
+                    self.record(
+                        ObjectType::Enum,
+                        ChangeKind::Removed,
+                        &t.schema,
+                        None,
+                        &t.name,
+                    )
+                    .target = Some(describe(t));
+                    self.migration
+                        .drop_types
+                        .push(format!("DROP TYPE {};", qualified(&t.schema, &t.name)));
+                }
+                (Some(s), Some(t)) if s.labels != t.labels => {
+                    let change = self.record(
+                        ObjectType::Enum,
+                        ChangeKind::Changed,
+                        &s.schema,
+                        None,
+                        &s.name,
+                    );
+                    change.source = Some(describe(s));
+                    change.target = Some(describe(t));
+                    let name = qualified(&s.schema, &s.name);
+
+                    // Labels can be added in place, but not removed or reordered
+                    let kept = s
+                        .labels
+                        .iter()
+                        .filter(|label| t.labels.contains(label))
+                        .collect::<Vec<_>>();
+                    let in_order = t.labels.iter().collect::<Vec<_>>() == kept;
+                    if !in_order {
+                        self.migration.create_types.push(format!(
+                            "-- {} drops or reorders labels, which needs the type recreated by hand",
+                            name
+                        ));
+                        continue;
+                    }
+
+                    for (i, label) in s.labels.iter().enumerate() {
+                        if t.labels.contains(label) {
+                            continue;
+                        }
+                        let position = match i.checked_sub(1).map(|prev| &s.labels[prev]) {
+                            Some(prev) => format!(" AFTER {}", quote_literal(prev)),
+                            None => match s.labels.iter().find(|l| t.labels.contains(l)) {
+                                Some(next) => format!(" BEFORE {}", quote_literal(next)),
+                                None => String::new(),
+                            },
+                        };
+                        self.migration.create_types.push(format!(
+                            "ALTER TYPE {} ADD VALUE {}{};",
+                            name,
+                            quote_literal(label),
+                            position
+                        ));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn functions(&mut self, source: &[FunctionSnapshot], target: &[FunctionSnapshot]) {
+        let signature = |f: &FunctionSnapshot| {
+            format!(
+                "{} {}({})",
+                if f.is_procedure {
+                    "PROCEDURE"
+                } else {
+                    "FUNCTION"
+                },
+                qualified(&f.schema, &f.name),
+                f.arguments
+            )
+        };
+        let display_name = |f: &FunctionSnapshot| format!("{}({})", f.name, f.arguments);
+
+        for pair in pair_by(source, target, |f| {
+            (f.schema.as_str(), f.name.as_str(), f.arguments.as_str())
+        }) {
+            match pair {
+                (Some(s), None) => {
+                    self.record(
+                        ObjectType::Function,
+                        ChangeKind::Added,
+                        &s.schema,
+                        None,
+                        &display_name(s),
+                    )
+                    .source = Some(s.definition.clone());
+                    self.migration.functions.push(s.definition.clone());
+                }
+                (None, Some(t)) => {
+                    self.record(
+                        ObjectType::Function,
+                        ChangeKind::Removed,
+                        &t.schema,
+                        None,
+                        &display_name(t),
+                    )
+                    .target = Some(t.definition.clone());
+                    self.migration
+                        .drop_functions
+                        .push(format!("DROP {};", signature(t)));
+                }
+                (Some(s), Some(t)) if s.definition != t.definition => {
+                    let change = self.record(
+                        ObjectType::Function,
+                        ChangeKind::Changed,
+                        &s.schema,
+                        None,
+                        &display_name(s),
+                    );
+                    change.source = Some(s.definition.clone());
+                    change.target = Some(t.definition.clone());
+                    // Replaced rather than dropped, so defaults and triggers using it survive.
+                    // A changed return type makes this fail, and needs a DROP by hand.
+                    self.migration.functions.push(s.definition.clone());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn sequences(&mut self, source: &[SequenceSnapshot], target: &[SequenceSnapshot]) {
+        let describe = |s: &SequenceSnapshot| match &s.owned_by {
+            Some(column) => format!("{} OWNED BY {}", s.options, column),
+            None => s.options.clone(),
+        };
+
+        for pair in pair_by(source, target, |s| (s.schema.as_str(), s.name.as_str())) {
+            let (change, s, t) = match pair {
+                (Some(s), None) => (ChangeKind::Added, Some(s), None),
+                (None, Some(t)) => (ChangeKind::Removed, None, Some(t)),
+                (Some(s), Some(t)) if s != t => (ChangeKind::Changed, Some(s), Some(t)),
+                _ => continue,
+            };
+            let sequence = s.or(t).unwrap();
+            let name = qualified(&sequence.schema, &sequence.name);
+            let recorded = self.record(
+                ObjectType::Sequence,
+                change,
+                &sequence.schema,
+                None,
+                &sequence.name,
+            );
+            recorded.source = s.map(describe);
+            recorded.target = t.map(describe);
+
+            match (s, t) {
+                (Some(s), None) => {
+                    self.migration
+                        .create_sequences
+                        .push(format!("CREATE SEQUENCE {} {};", name, s.options));
+                }
+                // A sequence owned by a dropped table or column is already gone
+                (None, Some(_)) => {
+                    self.migration
+                        .drop_sequences
+                        .push(format!("DROP SEQUENCE IF EXISTS {};", name));
+                }
+                (Some(s), Some(t)) if s.options != t.options => {
+                    self.migration
+                        .create_sequences
+                        .push(format!("ALTER SEQUENCE {} {};", name, s.options));
+                }
+                _ => {}
+            }
+            if let Some(s) = s {
+                if s.owned_by != t.and_then(|t| t.owned_by.clone()) {
+                    self.migration.sequence_owners.push(format!(
+                        "ALTER SEQUENCE {} OWNED BY {};",
+                        name,
+                        s.owned_by.as_deref().unwrap_or("NONE")
+                    ));
+                }
+            }
+        }
+    }
+
+    fn tables(&mut self, source: &[TableSnapshot], target: &[TableSnapshot]) {
+        for pair in pair_by(source, target, |t| (t.schema.as_str(), t.name.as_str())) {
+            match pair {
+                (Some(s), None) => {
+                    self.record(
+                        ObjectType::Table,
+                        ChangeKind::Added,
+                        &s.schema,
+                        None,
+                        &s.name,
+                    );
+                    let columns = s
+                        .columns
+                        .iter()
+                        .map(|c| format!("{} {}", quote_ident(&c.name), c.definition()))
+                        .collect::<Vec<_>>();
+                    self.migration.create_tables.push(format!(
+                        "CREATE TABLE {} (\n    {}\n);",
+                        qualified(&s.schema, &s.name),
+                        columns.join(",\n    ")
+                    ));
+                    self.indexes(s, &s.indexes, &[]);
+                    self.constraints(s, &s.constraints, &[]);
+                }
+                (None, Some(t)) => {
+                    self.record(
+                        ObjectType::Table,
+                        ChangeKind::Removed,
+                        &t.schema,
+                        None,
+                        &t.name,
+                    );
+                    self.migration
+                        .drop_tables
+                        .push(format!("DROP TABLE {};", qualified(&t.schema, &t.name)));
+                }
+                (Some(s), Some(t)) => {
+                    self.columns(s, &s.columns, &t.columns);
+                    self.indexes(s, &s.indexes, &t.indexes);
+                    self.constraints(s, &s.constraints, &t.constraints);
+                }
+                (None, None) => {}
+            }
+        }
+    }
+
+    fn columns(
+        &mut self,
+        table: &TableSnapshot,
+        source: &[ColumnSnapshot],
+        target: &[ColumnSnapshot],
+    ) {
+        let name = qualified(&table.schema, &table.name);
+        for pair in pair_by(source, target, |c| c.name.as_str()) {
+            match pair {
+                (Some(s), None) => {
+                    self.record(
+                        ObjectType::Column,
+                        ChangeKind::Added,
+                        &table.schema,
+                        Some(&table.name),
+                        &s.name,
+                    )
+                    .source = Some(s.definition());
+                    self.migration.alter_columns.push(format!(
+                        "ALTER TABLE {} ADD COLUMN {} {};",
+                        name,
+                        quote_ident(&s.name),
+                        s.definition()
+                    ));
+                }
+                (None, Some(t)) => {
+                    self.record(
+                        ObjectType::Column,
+                        ChangeKind::Removed,
+                        &table.schema,
+                        Some(&table.name),
+                        &t.name,
+                    )
+                    .target = Some(t.definition());
+                    self.migration.alter_columns.push(format!(
+                        "ALTER TABLE {} DROP COLUMN {};",
+                        name,
+                        quote_ident(&t.name)
+                    ));
+                }
+                (Some(s), Some(t)) if s != t => {
+                    let change = self.record(
+                        ObjectType::Column,
+                        ChangeKind::Changed,
+                        &table.schema,
+                        Some(&table.name),
+                        &s.name,
+                    );
+                    change.source = Some(s.definition());
+                    change.target = Some(t.definition());
+                    let alter =
+                        format!("ALTER TABLE {} ALTER COLUMN {}", name, quote_ident(&s.name));
+                    if s.generated != t.generated {
+                        self.migration.alter_columns.push(format!(
+                            "-- {}.{} changes how it's generated, which needs the column recreated by hand",
+                            name,
+                            quote_ident(&s.name)
+                        ));
+                    }
+                    if s.data_type != t.data_type {
+                        self.migration.alter_columns.push(format!(
+                            "{} TYPE {} USING {}::{};",
+                            alter,
+                            s.data_type,
+                            quote_ident(&s.name),
+                            s.data_type
+                        ));
+                    }
+                    if s.default_value != t.default_value {
+                        self.migration.alter_columns.push(match &s.default_value {
+                            Some(default_value) => {
+                                format!("{} SET DEFAULT {};", alter, default_value)
+                            }
+                            None => format!("{} DROP DEFAULT;", alter),
+                        });
+                    }
+                    if s.nullable != t.nullable {
+                        self.migration.alter_columns.push(if s.nullable {
+                            format!("{} DROP NOT NULL;", alter)
+                        } else {
+                            format!("{} SET NOT NULL;", alter)
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn indexes(
+        &mut self,
+        table: &TableSnapshot,
+        source: &[IndexSnapshot],
+        target: &[IndexSnapshot],
+    ) {
+        for pair in pair_by(source, target, |i| i.name.as_str()) {
+            let (change, s, t) = match pair {
+                (Some(s), None) => (ChangeKind::Added, Some(s), None),
+                (None, Some(t)) => (ChangeKind::Removed, None, Some(t)),
+                (Some(s), Some(t)) if s.definition != t.definition => {
+                    (ChangeKind::Changed, Some(s), Some(t))
+                }
+                _ => continue,
+            };
+            let name = s.or(t).map(|i| i.name.as_str()).unwrap_or_default();
+            let recorded = self.record(
+                ObjectType::Index,
+                change,
+                &table.schema,
+                Some(&table.name),
+                name,
+            );
+            recorded.source = s.map(|i| i.definition.clone());
+            recorded.target = t.map(|i| i.definition.clone());
+            if let Some(t) = t {
+                self.migration
+                    .drop_indexes
+                    .push(format!("DROP INDEX {};", qualified(&table.schema, &t.name)));
+            }
+            if let Some(s) = s {
+                self.migration
+                    .add_indexes
+                    .push(format!("{};", s.definition));
+            }
+        }
+    }
+
+    fn constraints(
+        &mut self,
+        table: &TableSnapshot,
+        source: &[ConstraintSnapshot],
+        target: &[ConstraintSnapshot],
+    ) {
+        let name = qualified(&table.schema, &table.name);
+        for pair in pair_by(source, target, |c| c.name.as_str()) {
+            let (change, s, t) = match pair {
+                (Some(s), None) => (ChangeKind::Added, Some(s), None),
+                (None, Some(t)) => (ChangeKind::Removed, None, Some(t)),
+                (Some(s), Some(t)) if s != t => (ChangeKind::Changed, Some(s), Some(t)),
+                _ => continue,
+            };
+            let constraint = s.or(t).map(|c| c.name.as_str()).unwrap_or_default();
+            let recorded = self.record(
+                ObjectType::Constraint,
+                change,
+                &table.schema,
+                Some(&table.name),
+                constraint,
+            );
+            recorded.source = s.map(|c| c.definition.clone());
+            recorded.target = t.map(|c| c.definition.clone());
+            if let Some(t) = t {
+                let statement = format!(
+                    "ALTER TABLE {} DROP CONSTRAINT {};",
+                    name,
+                    quote_ident(&t.name)
+                );
+                if t.kind == ConstraintKind::ForeignKey {
+                    self.migration.drop_foreign_keys.push(statement);
+                } else {
+                    self.migration.drop_constraints.push(statement);
+                }
+            }
+            if let Some(s) = s {
+                let statement = format!(
+                    "ALTER TABLE {} ADD CONSTRAINT {} {};",
+                    name,
+                    quote_ident(&s.name),
+                    s.definition
+                );
+                if s.kind == ConstraintKind::ForeignKey {
+                    self.migration.add_foreign_keys.push(statement);
+                } else {
+                    self.migration.add_constraints.push(statement);
+                }
+            }
+        }
+    }
+}
+
+/// Compares two snapshots and builds the migration that turns `target` into `source`
+pub fn diff(source: &CatalogSnapshot, target: &CatalogSnapshot) -> SchemaDiff {
+    let mut differ = Differ {
+        changes: Vec::new(),
+        migration: Migration::default(),
+    };
+
+    differ.schemas(&source.schemas, &target.schemas);
+    differ.enums(&source.enums, &target.enums);
+    differ.sequences(&source.sequences, &target.sequences);
+    differ.tables(&source.tables, &target.tables);
+    differ.functions(&source.functions, &target.functions);
+
+    SchemaDiff {
+        changes: differ.changes,
+        migration: differ.migration.into_statements(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(name: &str, data_type: &str, default_value: Option<&str>) -> ColumnSnapshot {
+        ColumnSnapshot {
+            name: name.to_string(),
+            data_type: data_type.to_string(),
+            nullable: false,
+            default_value: default_value.map(str::to_string),
+            generated: None,
+        }
+    }
+
+    fn table(name: &str, columns: Vec<ColumnSnapshot>) -> TableSnapshot {
+        TableSnapshot {
+            schema: "public".to_string(),
+            name: name.to_string(),
+            columns,
+            indexes: Vec::new(),
+            constraints: Vec::new(),
+        }
+    }
+
+    fn function(name: &str, body: &str) -> FunctionSnapshot {
+        FunctionSnapshot {
+            schema: "public".to_string(),
+            name: name.to_string(),
+            arguments: String::new(),
+            is_procedure: false,
+            definition: format!(
+                "CREATE OR REPLACE FUNCTION public.{}() RETURNS text LANGUAGE sql AS $$ {} $$;",
+                name, body
+            ),
+        }
+    }
+
+    fn sequence(name: &str, owned_by: Option<&str>) -> SequenceSnapshot {
+        SequenceSnapshot {
+            schema: "public".to_string(),
+            name: name.to_string(),
+            options: "AS integer INCREMENT BY 1 MINVALUE 1 MAXVALUE 2147483647 START WITH 1 CACHE 1 NO CYCLE".to_string(),
+            owned_by: owned_by.map(str::to_string),
+        }
+    }
+
+    fn snapshot() -> CatalogSnapshot {
+        CatalogSnapshot {
+            version: crate::db::catalog::SNAPSHOT_VERSION,
+            schemas: vec!["public".to_string()],
+            ..Default::default()
+        }
+    }
+
+    fn position(migration: &[String], prefix: &str) -> usize {
+        migration
+            .iter()
+            .position(|statement| statement.starts_with(prefix))
+            .unwrap_or_else(|| panic!("no statement starting with {:?}", prefix))
+    }
+
+    #[test]
+    fn identical_snapshots_have_no_changes() {
+        let mut source = snapshot();
+        source
+            .tables
+            .push(table("users", vec![column("id", "integer", None)]));
+
+        let result = diff(&source, &source.clone());
+        assert!(result.changes.is_empty());
+        assert!(result.migration.is_empty());
+    }
+
+    #[test]
+    fn creates_sequences_and_functions_before_tables_using_them() {
+        let mut source = snapshot();
+        source.sequences.push(sequence(
+            "users_id_seq",
+            Some("\"public\".\"users\".\"id\""),
+        ));
+        source
+            .functions
+            .push(function("default_name", "SELECT 'anonymous'"));
+        source.tables.push(table(
+            "users",
+            vec![
+                column(
+                    "id",
+                    "integer",
+                    Some("nextval('public.users_id_seq'::regclass)"),
+                ),
+                column("name", "text", Some("public.default_name()")),
+            ],
+        ));
+
+        let result = diff(&source, &snapshot());
+        let migration = &result.migration;
+        let create_sequence = position(migration, "CREATE SEQUENCE \"public\".\"users_id_seq\"");
+        let create_function = position(migration, "CREATE OR REPLACE FUNCTION public.default_name");
+        let create_table = position(migration, "CREATE TABLE \"public\".\"users\"");
+        let owned_by = position(
+            migration,
+            "ALTER SEQUENCE \"public\".\"users_id_seq\" OWNED BY",
+        );
+        assert!(create_sequence < create_table);
+        assert!(create_function < create_table);
+        assert!(create_table < owned_by);
+        assert_eq!(
+            migration[owned_by],
+            "ALTER SEQUENCE \"public\".\"users_id_seq\" OWNED BY \"public\".\"users\".\"id\";"
+        );
+    }
+
+    #[test]
+    fn drops_functions_and_sequences_after_the_columns_using_them() {
+        let mut target = snapshot();
+        target.sequences.push(sequence("legacy_seq", None));
+        target
+            .functions
+            .push(function("legacy_default", "SELECT 'x'"));
+        target.tables.push(table(
+            "users",
+            vec![
+                column("id", "integer", None),
+                column("legacy", "text", Some("public.legacy_default()")),
+            ],
+        ));
+        let mut source = snapshot();
+        source
+            .tables
+            .push(table("users", vec![column("id", "integer", None)]));
+
+        let result = diff(&source, &target);
+        let migration = &result.migration;
+        let drop_column = position(migration, "ALTER TABLE \"public\".\"users\" DROP COLUMN");
+        let drop_function = position(migration, "DROP FUNCTION \"public\".\"legacy_default\"()");
+        let drop_sequence = position(
+            migration,
+            "DROP SEQUENCE IF EXISTS \"public\".\"legacy_seq\"",
+        );
+        assert!(drop_column < drop_function);
+        assert!(drop_column < drop_sequence);
+    }
+
+    #[test]
+    fn adds_enum_labels_in_place() {
+        let enum_snapshot = |labels: &[&str]| EnumSnapshot {
+            schema: "public".to_string(),
+            name: "mood".to_string(),
+            labels: labels.iter().map(|l| l.to_string()).collect(),
+        };
+        let mut source = snapshot();
+        source.enums.push(enum_snapshot(&["sad", "ok", "happy"]));
+        let mut target = snapshot();
+        target.enums.push(enum_snapshot(&["sad", "happy"]));
+
+        let result = diff(&source, &target);
+        assert_eq!(result.changes.len(), 1);
+        assert_eq!(result.changes[0].change, ChangeKind::Changed);
+        assert_eq!(
+            result.migration,
+            vec!["ALTER TYPE \"public\".\"mood\" ADD VALUE 'ok' AFTER 'sad';"]
+        );
+    }
+
+    #[test]
+    fn alters_changed_columns() {
+        let mut source = snapshot();
+        source
+            .tables
+            .push(table("users", vec![column("age", "bigint", Some("0"))]));
+        let mut target = snapshot();
+        let mut age = column("age", "integer", None);
+        age.nullable = true;
+        target.tables.push(table("users", vec![age]));
+
+        let result = diff(&source, &target);
+        assert_eq!(
+            result.migration,
+            vec![
+                "ALTER TABLE \"public\".\"users\" ALTER COLUMN \"age\" TYPE bigint USING \"age\"::bigint;",
+                "ALTER TABLE \"public\".\"users\" ALTER COLUMN \"age\" SET DEFAULT 0;",
+                "ALTER TABLE \"public\".\"users\" ALTER COLUMN \"age\" SET NOT NULL;",
+            ]
+        );
+    }
+
+    #[test]
+    fn quotes_names_in_the_migration() {
+        let mut source = snapshot();
+        source.schemas.push("my \"app\"".to_string());
+
+        let result = diff(&source, &snapshot());
+        assert_eq!(
+            result.migration,
+            vec!["CREATE SCHEMA IF NOT EXISTS \"my \"\"app\"\"\";"]
+        );
+    }
+}
@@ -0,0 +1,200 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::sync::watch;
+
+use crate::db::errors::{DbError, DbResult};
+
+/// Most rows read from one table; matches are held in memory and sent to the window
+pub const MAX_ROWS_PER_TABLE: u32 = 1_000;
+/// Longest time limit for one table; a limit of 0 would disable the statement timeout
+pub const MAX_TABLE_TIMEOUT_MS: u32 = 60_000;
+
+/// How a search term is compared with column values
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum SearchMode {
+    /// Case-insensitive substring match
+    #[default]
+    Contains,
+    /// Whole value match
+    Exact,
+}
+
+#[taurpc::ipc_type]
+#[serde(rename_all = "camelCase")]
+#[derive(Debug)]
+pub struct SearchRequest {
+    /// Chosen by the caller and echoed in progress events, so events of an
+    /// earlier search can be told apart
+    pub search_id: u32,
+    pub term: String,
+    pub mode: SearchMode,
+    /// Schemas to search; empty means every non-system schema
+    pub schemas: Vec<String>,
+    /// Maximum number of matching rows read from each table
+    pub rows_per_table: u32,
+    /// Time limit for the query on each table, in milliseconds
+    pub table_timeout_ms: u32,
+}
+
+impl SearchRequest {
+    pub fn validate(&self) -> DbResult<()> {
+        if !(1..=MAX_ROWS_PER_TABLE).contains(&self.rows_per_table) {
+            return Err(DbError::Config(format!(
+                "Rows per table must be between 1 and {}",
+                MAX_ROWS_PER_TABLE
+            )));
+        }
+        if !(1..=MAX_TABLE_TIMEOUT_MS).contains(&self.table_timeout_ms) {
+            return Err(DbError::Config(format!(
+                "Time limit per table must be between 1 and {} ms",
+                MAX_TABLE_TIMEOUT_MS
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Rows of a table where one column matched
+#[taurpc::ipc_type]
+#[serde(rename_all = "camelCase")]
+#[derive(Debug)]
+pub struct ColumnMatch {
+    pub column: String,
+    /// Keys of the matching rows, for opening them; empty if the table has no key
+    pub rows: Vec<HashMap<String, String>>,
+    /// Number of matching rows read, which may exceed `rows` when there are no keys
+    pub row_count: u32,
+}
+
+#[taurpc::ipc_type]
+#[serde(rename_all = "camelCase")]
+#[derive(Debug)]
+pub struct TableMatches {
+    pub table_id: String,
+    pub schema: String,
+    pub name: String,
+    pub columns: Vec<ColumnMatch>,
+    /// More rows matched than `rows_per_table`
+    pub truncated: bool,
+}
+
+/// Why a table could not be searched, e.g. the query timed out
+#[taurpc::ipc_type]
+#[serde(rename_all = "camelCase")]
+#[derive(Debug)]
+pub struct SkippedTable {
+    pub table_id: String,
+    pub schema: String,
+    pub name: String,
+    pub reason: String,
+}
+
+/// Emitted after each table is searched
+#[taurpc::ipc_type]
+#[serde(rename_all = "camelCase")]
+#[derive(Debug)]
+pub struct SearchProgress {
+    pub search_id: u32,
+    pub tables_searched: u32,
+    pub tables_total: u32,
+    /// Matches in the table just searched, if any
+    pub matches: Option<TableMatches>,
+    pub skipped: Option<SkippedTable>,
+}
+
+#[taurpc::ipc_type]
+#[serde(rename_all = "camelCase")]
+#[derive(Debug)]
+pub struct SearchSummary {
+    pub search_id: u32,
+    pub tables_searched: u32,
+    pub tables_total: u32,
+    pub matches: Vec<TableMatches>,
+    pub skipped: Vec<SkippedTable>,
+    pub cancelled: bool,
+}
+
+impl SearchSummary {
+    pub fn new(search_id: u32, tables_total: u32) -> Self {
+        Self {
+            search_id,
+            tables_searched: 0,
+            tables_total,
+            matches: Vec::new(),
+            skipped: Vec::new(),
+            cancelled: false,
+        }
+    }
+
+    /// Records one searched table and returns the event to report it with
+    pub fn record(
+        &mut self,
+        matches: Option<TableMatches>,
+        skipped: Option<SkippedTable>,
+    ) -> SearchProgress {
+        self.tables_searched += 1;
+        let progress = SearchProgress {
+            search_id: self.search_id,
+            tables_searched: self.tables_searched,
+            tables_total: self.tables_total,
+            matches: matches.clone(),
+            skipped: skipped.clone(),
+        };
+        self.matches.extend(matches);
+        self.skipped.extend(skipped);
+        progress
+    }
+}
+
+/// Stops a running search; held by the window while the search runs
+#[derive(Debug)]
+pub struct SearchCancel(watch::Sender<bool>);
+
+/// Observed by a running search to stop early
+#[derive(Debug, Clone)]
+pub struct CancelToken(watch::Receiver<bool>);
+
+impl SearchCancel {
+    pub fn new() -> (Self, CancelToken) {
+        let (tx, rx) = watch::channel(false);
+        (Self(tx), CancelToken(rx))
+    }
+
+    pub fn cancel(&self) {
+        self.0.send_replace(true);
+    }
+}
+
+impl CancelToken {
+    pub fn is_cancelled(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// Resolves once the search is cancelled
+    pub async fn cancelled(&mut self) {
+        // An error means the canceller was dropped, so the search can never be cancelled
+        if self.0.wait_for(|cancelled| *cancelled).await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+/// Searches the text-like and UUID columns of the chosen schemas for a term
+#[async_trait]
+pub trait Searcher: Send + Sync {
+    /// Calls `progress` after each table until done or `token` is cancelled
+    async fn search(
+        &self,
+        request: &SearchRequest,
+        token: CancelToken,
+        progress: &(dyn Fn(SearchProgress) + Send + Sync),
+    ) -> DbResult<SearchSummary>;
+}
+
+/// Whether `term` is a UUID, so UUID columns can be compared with it
+pub fn is_uuid(term: &str) -> bool {
+    let hex = term.trim_matches(|c| c == '{' || c == '}').replace('-', "");
+    hex.len() == 32 && hex.chars().all(|c| c.is_ascii_hexdigit())
+}
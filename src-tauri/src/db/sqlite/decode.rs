@@ -0,0 +1,61 @@
+use serde_json::Value as JsonValue;
+use sqlx::sqlite::SqliteValueRef;
+use sqlx::{TypeInfo, Value, ValueRef};
+
+use crate::db::binary::{BinaryFormat, BinaryPreview};
+use crate::db::errors::{DbError, DbResult};
+
+/// Options controlling how values are decoded for the frontend
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DecodeOptions {
+    pub binary: BinaryFormat,
+}
+
+/// SQLite is dynamically typed, so values are decoded by their storage class
+/// rather than the declared column type. Dates and times are stored as TEXT
+/// or numbers and are passed through unchanged.
+pub(crate) fn to_json(v: SqliteValueRef, opts: &DecodeOptions) -> DbResult<JsonValue> {
+    if v.is_null() {
+        return Ok(JsonValue::Null);
+    }
+
+    let res = match v.type_info().name() {
+        "INTEGER" | "BOOLEAN" => {
+            if let Ok(v) = ValueRef::to_owned(&v).try_decode_unchecked::<i64>() {
+                JsonValue::Number(v.into())
+            } else {
+                JsonValue::Null
+            }
+        }
+        "REAL" | "NUMERIC" => {
+            if let Ok(v) = ValueRef::to_owned(&v).try_decode_unchecked::<f64>() {
+                JsonValue::from(v)
+            } else {
+                JsonValue::Null
+            }
+        }
+        "TEXT" | "DATE" | "TIME" | "DATETIME" => {
+            if let Ok(v) = ValueRef::to_owned(&v).try_decode_unchecked::<String>() {
+                JsonValue::String(v)
+            } else {
+                JsonValue::Null
+            }
+        }
+        "BLOB" => {
+            if let Ok(v) = ValueRef::to_owned(&v).try_decode_unchecked::<Vec<u8>>() {
+                BinaryPreview::new(&v, &opts.binary).to_json()
+            } else {
+                JsonValue::Null
+            }
+        }
+        "NULL" => JsonValue::Null,
+        _ => {
+            return Err(DbError::Unsupported(format!(
+                "Unsupported data type: {}",
+                v.type_info().name()
+            )))
+        }
+    };
+
+    Ok(res)
+}
@@ -0,0 +1,88 @@
+use sqlx::{Pool, Row as SqlxRow, Sqlite};
+
+use crate::db::errors::DbResult;
+use crate::db::migrations::{AppliedMigration, MigrationTool};
+
+use super::quote_ident;
+
+/// Reads a migration tool's tracking table in the main database, or None if it doesn't
+/// have one
+pub(crate) async fn history(
+    pool: &Pool<Sqlite>,
+    tool: MigrationTool,
+) -> DbResult<Option<Vec<AppliedMigration>>> {
+    let mut table = None;
+    // Schema-qualified names are where Postgres keeps a tool's table
+    for name in tool
+        .tracking_tables()
+        .iter()
+        .filter(|name| !name.contains('.'))
+    {
+        let exists = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM main.sqlite_master WHERE type = 'table' AND name = ?",
+        )
+        .bind(name)
+        .fetch_one(pool)
+        .await?;
+        if exists > 0 {
+            table = Some(format!("main.{}", quote_ident(name)));
+            break;
+        }
+    }
+    let Some(table) = table else {
+        return Ok(None);
+    };
+
+    // Values are cast to text and integers, since a column's declared type doesn't bind
+    // what's stored in it
+    let sql = match tool {
+        MigrationTool::Sqlx => format!(
+            r#"
+            SELECT CAST(version AS TEXT) AS id, lower(hex(checksum)) AS checksum,
+                CAST(installed_on AS TEXT) AS applied_at, CAST(success AS INTEGER) AS success
+            FROM {}
+            "#,
+            table
+        ),
+        // Rolled back attempts stay in the table next to the retry
+        MigrationTool::Prisma => format!(
+            r#"
+            SELECT CAST(migration_name AS TEXT) AS id, CAST(checksum AS TEXT) AS checksum,
+                CAST(finished_at AS TEXT) AS applied_at, finished_at IS NOT NULL AS success
+            FROM {}
+            WHERE rolled_back_at IS NULL
+            ORDER BY started_at
+            "#,
+            table
+        ),
+        // created_at is the migration's journal timestamp, not when it was applied
+        MigrationTool::Drizzle => format!(
+            r#"
+            SELECT CAST(created_at AS TEXT) AS id, CAST(hash AS TEXT) AS checksum,
+                NULL AS applied_at, 1 AS success
+            FROM {}
+            "#,
+            table
+        ),
+        MigrationTool::Rails => format!(
+            r#"
+            SELECT CAST(version AS TEXT) AS id, NULL AS checksum, NULL AS applied_at,
+                1 AS success
+            FROM {}
+            "#,
+            table
+        ),
+    };
+
+    let rows = sqlx::query(&sql).fetch_all(pool).await?;
+    Ok(Some(
+        rows.iter()
+            .map(|row| AppliedMigration {
+                id: row.get("id"),
+                checksum: row.get("checksum"),
+                applied_at: row.get("applied_at"),
+                success: row.get::<i64, _>("success") != 0,
+            })
+            .collect(),
+    ))
+}
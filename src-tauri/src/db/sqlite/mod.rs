@@ -0,0 +1,576 @@
+mod decode;
+mod migrations;
+
+use async_trait::async_trait;
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions, SqliteRow},
+    Column, Pool, Row as SqlxRow, Sqlite, Transaction, Value, ValueRef,
+};
+use std::collections::HashMap;
+use std::str::FromStr;
+use url::Url;
+
+use crate::db::{
+    binary::BinaryFormat,
+    changes::{self, Dialect, StagedChange, StepRunner, TableInfo, TableLoader},
+    client::DatabaseClient,
+    driver,
+    editing::{self, ColumnChanges},
+    errors::{DbError, DbResult},
+    ident::{quote_double as quote_ident, split_table_id},
+    migrations::{AppliedMigration, MigrationTool},
+    types::{
+        ColumnDefinition, DbEntity, QueryResult, Row, SchemaEntity, SchemaLevelEntity,
+        ServerFlavor, ServerInfo, TimeZoneMode,
+    },
+};
+
+use self::decode::DecodeOptions;
+
+/// File extensions opened directly as SQLite databases
+pub const FILE_EXTENSIONS: &[&str] = &["db", "sqlite", "sqlite3", "db3"];
+
+/// Implicit row identifiers that may be used as a row key
+const ROWID_ALIASES: &[&str] = &["rowid", "_rowid_", "oid"];
+
+/// Entity IDs are quoted identifiers, e.g. `"main"` or `"main"."users"`
+fn table_id(schema: &str, name: &str) -> String {
+    format!("{}.{}", quote_ident(schema), quote_ident(name))
+}
+
+// SQLite has no row locks; a write transaction locks the whole database instead
+const DIALECT: Dialect = Dialect {
+    quote: '"',
+    numbered_params: false,
+    lock_rows: false,
+    backslash_escapes: false,
+};
+
+/// Decodes every column of a row into its display value
+fn decode_row(row: &SqliteRow, opts: &DecodeOptions) -> DbResult<Row> {
+    let mut values = HashMap::new();
+    for (i, col) in row.columns().iter().enumerate() {
+        let value = decode::to_json(row.try_get_raw(i)?, opts)?;
+        values.insert(
+            col.name().to_string(),
+            if value.is_null() {
+                "NULL".to_string()
+            } else {
+                value.to_string()
+            },
+        );
+    }
+    Ok(Row { values })
+}
+
+/// Loads the tables a change set touches
+struct ChangeTables<'a>(&'a Pool<Sqlite>);
+
+#[async_trait]
+impl TableLoader for ChangeTables<'_> {
+    async fn load_table(&mut self, table_id: &str) -> DbResult<TableInfo> {
+        let (schema, table) = split_table_id(table_id, '"')?;
+        let columns: Vec<String> = sqlx::query_scalar("SELECT name FROM pragma_table_info(?, ?)")
+            .bind(&table)
+            .bind(&schema)
+            .fetch_all(self.0)
+            .await?;
+
+        Ok(TableInfo {
+            sql_name: table_id.to_string(),
+            key_columns: SqliteClient::key_columns(self.0, &schema, &table).await?,
+            // Column affinity converts text parameters
+            columns: columns.into_iter().map(|name| (name, None)).collect(),
+            name: table,
+        })
+    }
+}
+
+/// Runs the statements of a change set in one transaction
+struct ChangeRunner<'a> {
+    tx: Transaction<'static, Sqlite>,
+    opts: &'a DecodeOptions,
+}
+
+#[async_trait]
+impl StepRunner for ChangeRunner<'_> {
+    async fn fetch_row(&mut self, sql: &str, params: Vec<Option<&str>>) -> DbResult<Option<Row>> {
+        let mut query = sqlx::query(sql);
+        for param in params {
+            query = query.bind(param);
+        }
+
+        match query.fetch_optional(&mut *self.tx).await? {
+            Some(row) => Ok(Some(decode_row(&row, self.opts)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn execute(&mut self, sql: &str, params: Vec<Option<&str>>) -> DbResult<u64> {
+        let mut query = sqlx::query(sql);
+        for param in params {
+            query = query.bind(param);
+        }
+        Ok(query.execute(&mut *self.tx).await?.rows_affected())
+    }
+}
+
+pub struct SqliteClient {
+    connection_string: String,
+    pool: Option<Pool<Sqlite>>,
+    decode_options: DecodeOptions,
+    /// Version of the SQLite library, read on connect
+    server_info: Option<ServerInfo>,
+}
+
+impl SqliteClient {
+    pub fn new(connection_string: &str) -> DbResult<Self> {
+        Ok(Self {
+            connection_string: connection_string.to_string(),
+            pool: None,
+            decode_options: DecodeOptions::default(),
+            server_info: None,
+        })
+    }
+
+    async fn fetch_server_info(pool: &Pool<Sqlite>) -> DbResult<ServerInfo> {
+        let version: String = sqlx::query_scalar("SELECT sqlite_version()")
+            .fetch_one(pool)
+            .await?;
+
+        // Same scheme as SQLITE_VERSION_NUMBER, e.g. 3045001
+        let parts = version
+            .split('.')
+            .map(|part| part.parse::<u32>().ok())
+            .collect::<Option<Vec<_>>>();
+        let version_num = match parts.as_deref() {
+            Some([major, minor, patch]) => Some(major * 1_000_000 + minor * 1_000 + patch),
+            _ => None,
+        };
+
+        Ok(ServerInfo {
+            flavor: ServerFlavor::Sqlite,
+            version,
+            version_num,
+        })
+    }
+
+    /// Columns of the primary key, or of the first unique index on non-null columns
+    async fn key_columns(pool: &Pool<Sqlite>, schema: &str, table: &str) -> DbResult<Vec<String>> {
+        let columns = sqlx::query("SELECT name, pk, \"notnull\" FROM pragma_table_info(?, ?)")
+            .bind(table)
+            .bind(schema)
+            .fetch_all(pool)
+            .await?;
+
+        let mut primary_key = columns
+            .iter()
+            .filter(|row| row.get::<i64, _>("pk") > 0)
+            .map(|row| (row.get::<i64, _>("pk"), row.get::<String, _>("name")))
+            .collect::<Vec<_>>();
+        if !primary_key.is_empty() {
+            primary_key.sort();
+            return Ok(primary_key.into_iter().map(|(_, name)| name).collect());
+        }
+
+        let not_null = columns
+            .iter()
+            .filter(|row| row.get::<bool, _>("notnull"))
+            .map(|row| row.get::<String, _>("name"))
+            .collect::<Vec<_>>();
+
+        let indexes: Vec<String> = sqlx::query_scalar(
+            "SELECT name FROM pragma_index_list(?, ?) WHERE \"unique\" AND NOT partial ORDER BY seq",
+        )
+        .bind(table)
+        .bind(schema)
+        .fetch_all(pool)
+        .await?;
+
+        for index in indexes {
+            // Expression key parts have no column name
+            let key: Vec<Option<String>> =
+                sqlx::query_scalar("SELECT name FROM pragma_index_info(?, ?) ORDER BY seqno")
+                    .bind(&index)
+                    .bind(schema)
+                    .fetch_all(pool)
+                    .await?;
+
+            if let Some(key) = key.into_iter().collect::<Option<Vec<_>>>() {
+                if key.iter().all(|name| not_null.contains(name)) {
+                    return Ok(key);
+                }
+            }
+        }
+
+        Ok(Vec::new())
+    }
+
+    // This function gets the pool or returns an error if not connected
+    fn get_pool(&self) -> DbResult<&Pool<Sqlite>> {
+        self.pool
+            .as_ref()
+            .ok_or_else(|| DbError::Connection("Database client is not connected".to_string()))
+    }
+}
+
+#[async_trait]
+impl DatabaseClient for SqliteClient {
+    fn get_connection_string(&self) -> String {
+        self.connection_string.clone()
+    }
+
+    async fn is_connected(&self) -> DbResult<bool> {
+        match self.get_pool() {
+            Ok(pool) => Ok(!pool.is_closed()),
+            Err(_) => Ok(false),
+        }
+    }
+
+    async fn test_connection(&self) -> DbResult<()> {
+        let pool = self.get_pool()?;
+        sqlx::query("SELECT 1").execute(pool).await?;
+        Ok(())
+    }
+
+    async fn connect(&mut self) -> DbResult<()> {
+        // Check if already connected
+        if let Ok(true) = self.is_connected().await {
+            return Ok(());
+        }
+
+        // Never create a new file for a mistyped path
+        let mut options =
+            SqliteConnectOptions::from_str(&self.connection_string)?.create_if_missing(false);
+        // sqlx reads the URL's path as-is, which isn't a valid path for Windows drives
+        if let Some(path) = Url::parse(&self.connection_string)
+            .ok()
+            .as_ref()
+            .and_then(driver::url_file_path)
+        {
+            options = options.filename(path);
+        }
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(4)
+            .connect_with(options)
+            .await?;
+
+        self.server_info = Some(Self::fetch_server_info(&pool).await?);
+        self.pool = Some(pool);
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> DbResult<()> {
+        if let Ok(true) = self.is_connected().await {
+            if let Some(pool) = self.pool.take() {
+                pool.close().await;
+            }
+        }
+        self.server_info = None;
+        Ok(())
+    }
+
+    async fn reconnect(&mut self) -> DbResult<()> {
+        self.disconnect().await?;
+        self.connect().await
+    }
+
+    async fn reconnect_with_string(&mut self, connection_string: &str) -> DbResult<()> {
+        self.disconnect().await?;
+        self.connection_string = connection_string.to_string();
+        self.connect().await
+    }
+
+    // SQLite has no zoned timestamp type; values are passed through as stored
+    fn set_time_zone(&mut self, _mode: TimeZoneMode) {}
+
+    fn server_info(&self) -> Option<ServerInfo> {
+        self.server_info.clone()
+    }
+
+    fn set_binary_format(&mut self, format: BinaryFormat) {
+        self.decode_options.binary = format;
+    }
+
+    async fn execute_query(&self, sql: &str) -> DbResult<QueryResult> {
+        let pool = self.get_pool()?;
+        let rows = sqlx::query(sql).fetch_all(pool).await?;
+
+        if rows.is_empty() {
+            return Ok(QueryResult {
+                timestamp: std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs(),
+                query: sql.to_string(),
+                rows_affected: None,
+                execution_time_ms: 0,
+                columns: Vec::new(),
+                rows: Vec::new(),
+                warnings: Vec::new(),
+                result_index: 0,
+            });
+        }
+
+        let sqlite_row: &SqliteRow = rows.first().unwrap();
+        let columns = sqlite_row
+            .columns()
+            .iter()
+            .map(|col| ColumnDefinition {
+                name: col.name().to_string(),
+                data_type: col.type_info().to_string(),
+                nullable: true,      // Default to true since we can't easily determine
+                primary_key: false,  // Cannot determine from result alone
+                default_value: None, // Cannot determine from result alone
+            })
+            .collect();
+
+        let mut result_rows = Vec::new();
+        for row in rows {
+            result_rows.push(decode_row(&row, &self.decode_options)?);
+        }
+
+        Ok(QueryResult {
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            query: sql.to_string(),
+            rows_affected: None,
+            execution_time_ms: 0,
+            columns,
+            rows: result_rows,
+            warnings: Vec::new(),
+            result_index: 0,
+        })
+    }
+
+    async fn fetch_blob(
+        &self,
+        table_id: &str,
+        key: &HashMap<String, String>,
+        column: &str,
+    ) -> DbResult<Vec<u8>> {
+        let pool = self.get_pool()?;
+        let (schema, table) = split_table_id(table_id, '"')?;
+
+        if key.is_empty() {
+            return Err(DbError::Query("A row key is required".to_string()));
+        }
+
+        let columns: Vec<String> = sqlx::query_scalar("SELECT name FROM pragma_table_info(?, ?)")
+            .bind(&table)
+            .bind(&schema)
+            .fetch_all(pool)
+            .await?;
+
+        let is_column = |name: &str| {
+            columns.iter().any(|c| c == name)
+                || ROWID_ALIASES.contains(&name.to_lowercase().as_str())
+        };
+        for name in key.keys().map(String::as_str).chain([column]) {
+            if !is_column(name) {
+                return Err(DbError::NotFound(format!("Column {} on {}", name, table)));
+            }
+        }
+
+        let mut names = key.keys().collect::<Vec<_>>();
+        names.sort();
+
+        let predicate = names
+            .iter()
+            .map(|name| format!("{} = ?", quote_ident(name)))
+            .collect::<Vec<_>>()
+            .join(" AND ");
+
+        let sql = format!(
+            "SELECT {} FROM {} WHERE {} LIMIT 2",
+            quote_ident(column),
+            table_id,
+            predicate
+        );
+
+        let mut query = sqlx::query(&sql);
+        for name in names {
+            query = query.bind(&key[name]);
+        }
+
+        let mut rows = query.fetch_all(pool).await?;
+        match rows.len() {
+            0 => Err(DbError::NotFound("Row not found".to_string())),
+            1 => {
+                let row = rows.pop().unwrap();
+                let value = row.try_get_raw(0)?;
+                let bytes = ValueRef::to_owned(&value).try_decode_unchecked::<Option<Vec<u8>>>()?;
+                Ok(bytes.unwrap_or_default())
+            }
+            _ => Err(DbError::Query(
+                "Row key matches more than one row".to_string(),
+            )),
+        }
+    }
+
+    async fn update_row(
+        &self,
+        table_id: &str,
+        key: &HashMap<String, String>,
+        changes: &ColumnChanges,
+    ) -> DbResult<Row> {
+        let pool = self.get_pool()?;
+        let (schema, table) = split_table_id(table_id, '"')?;
+
+        let key_columns = Self::key_columns(pool, &schema, &table).await?;
+        editing::check_key(&table, &key_columns, key)?;
+        editing::check_changes(changes)?;
+
+        let columns: Vec<String> = sqlx::query_scalar("SELECT name FROM pragma_table_info(?, ?)")
+            .bind(&table)
+            .bind(&schema)
+            .fetch_all(pool)
+            .await?;
+        for name in changes.keys() {
+            if !columns.contains(name) {
+                return Err(DbError::NotFound(format!("Column {} on {}", name, table)));
+            }
+        }
+
+        let names = editing::sorted(changes.keys());
+        let assignments = names
+            .iter()
+            .map(|name| format!("{} = ?", quote_ident(name)))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let key_names = editing::sorted(key.keys());
+        let predicate = key_names
+            .iter()
+            .map(|name| format!("{} = ?", quote_ident(name)))
+            .collect::<Vec<_>>()
+            .join(" AND ");
+
+        let sql = format!(
+            "UPDATE {} SET {} WHERE {} RETURNING *",
+            table_id, assignments, predicate
+        );
+
+        let mut query = sqlx::query(&sql);
+        for name in names {
+            query = query.bind(changes[name].as_deref());
+        }
+        for name in key_names {
+            query = query.bind(&key[name]);
+        }
+
+        // Commit only once the row decodes, so an error never hides a saved edit
+        let mut tx = pool.begin().await?;
+        let row = query
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| DbError::NotFound("Row not found".to_string()))?;
+
+        let row = decode_row(&row, &self.decode_options)?;
+        tx.commit().await?;
+        Ok(row)
+    }
+
+    async fn preview_changes(&self, staged: &[StagedChange]) -> DbResult<Vec<String>> {
+        let steps =
+            changes::plan_all(&mut ChangeTables(self.get_pool()?), staged, &DIALECT).await?;
+        Ok(changes::preview(&steps, &DIALECT))
+    }
+
+    async fn apply_changes(&self, staged: &[StagedChange]) -> DbResult<u64> {
+        let pool = self.get_pool()?;
+        let steps = changes::plan_all(&mut ChangeTables(pool), staged, &DIALECT).await?;
+        let mut runner = ChangeRunner {
+            tx: pool.begin().await?,
+            opts: &self.decode_options,
+        };
+
+        match changes::run(&mut runner, &steps, &DIALECT).await {
+            Ok(rows_written) => {
+                runner.tx.commit().await?;
+                Ok(rows_written)
+            }
+            Err(e) => {
+                runner.tx.rollback().await?;
+                Err(e)
+            }
+        }
+    }
+
+    async fn migration_history(
+        &self,
+        tool: MigrationTool,
+    ) -> DbResult<Option<Vec<AppliedMigration>>> {
+        let pool = self.get_pool()?;
+        migrations::history(pool, tool).await
+    }
+
+    async fn get_all_entities(&self) -> DbResult<HashMap<String, DbEntity>> {
+        let pool = self.get_pool()?;
+        let mut entities = HashMap::new();
+
+        // Query 1: Get all attached databases (main, temp and any ATTACHed files)
+        let schema_rows = sqlx::query("SELECT name FROM pragma_database_list ORDER BY seq")
+            .fetch_all(pool)
+            .await?;
+
+        for schema_row in schema_rows {
+            let schema_name: String = schema_row.get("name");
+            let schema_id = quote_ident(&schema_name);
+            let mut children = Vec::new();
+
+            // Query 2: Get tables and views of this database
+            let table_query = format!(
+                r#"
+                SELECT name, type
+                FROM {}.sqlite_master
+                WHERE type IN ('table', 'view')
+                ORDER BY name
+                "#,
+                schema_id
+            );
+
+            let table_rows = sqlx::query(&table_query).fetch_all(pool).await?;
+            for row in table_rows {
+                let name: String = row.get("name");
+                let kind: String = row.get("type");
+
+                let id = table_id(&schema_name, &name);
+                let is_system = name.starts_with("sqlite_");
+
+                let schema_level = SchemaLevelEntity {
+                    id: id.clone(),
+                    name,
+                    is_system,
+                    schema_id: schema_id.clone(),
+                    extension_name: None,
+                };
+
+                let entity = match kind.as_str() {
+                    "table" => DbEntity::Table(schema_level),
+                    "view" => DbEntity::View(schema_level),
+                    _ => continue,
+                };
+
+                children.push(id.clone());
+                entities.insert(id, entity);
+            }
+
+            entities.insert(
+                schema_id.clone(),
+                DbEntity::Schema(SchemaEntity {
+                    id: schema_id,
+                    is_system: schema_name == "temp",
+                    name: schema_name,
+                    extension_name: None,
+                    children,
+                }),
+            );
+        }
+
+        Ok(entities)
+    }
+}
@@ -0,0 +1,125 @@
+use chrono::{DateTime, FixedOffset, Local, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
+
+use crate::db::types::TimeZoneMode;
+
+// Dates are written with a signed, astronomical year (1 BC is year 0000, 2 BC is -0001)
+pub const DATE_FORMAT: &str = "%Y-%m-%d";
+pub const DATETIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.f";
+pub const TIME_FORMAT: &str = "%H:%M:%S%.f";
+
+/// The zone zoned timestamps are rendered in, resolved from a `TimeZoneMode`
+#[derive(Debug, Clone, Copy, Default)]
+pub enum DisplayZone {
+    #[default]
+    Utc,
+    Local,
+    Named(Tz),
+    /// A server zone only known by its current offset, e.g. MySQL's `CEST`
+    Fixed(FixedOffset),
+}
+
+impl DisplayZone {
+    /// Resolves a mode against the zone reported by the server, if it could be parsed
+    pub fn resolve(mode: TimeZoneMode, session_zone: Option<DisplayZone>) -> Self {
+        match (mode, session_zone) {
+            (TimeZoneMode::Utc, _) => DisplayZone::Utc,
+            (TimeZoneMode::Local, _) => DisplayZone::Local,
+            (TimeZoneMode::Session, Some(zone)) => zone,
+            // Zones the server accepts but chrono-tz doesn't know (e.g. POSIX specs) fall back to UTC
+            (TimeZoneMode::Session, None) => DisplayZone::Utc,
+        }
+    }
+
+    /// Formats a UTC instant as RFC 3339 in this zone
+    pub fn format(&self, utc: DateTime<Utc>) -> String {
+        match self {
+            DisplayZone::Utc => format!("{}Z", utc.format(DATETIME_FORMAT)),
+            DisplayZone::Local => format_zoned(utc.with_timezone(&Local)),
+            DisplayZone::Named(tz) => format_zoned(utc.with_timezone(tz)),
+            DisplayZone::Fixed(offset) => format_zoned(utc.with_timezone(offset)),
+        }
+    }
+}
+
+fn format_zoned<Z: TimeZone>(dt: DateTime<Z>) -> String {
+    let offset = dt.offset().fix().local_minus_utc();
+    format!("{}{}", dt.format(DATETIME_FORMAT), format_offset(offset))
+}
+
+/// Formats an offset east of UTC as `±HH:MM`, adding seconds only when there are some,
+/// as in the local mean time zones used before standard time (New York's was -04:56:02)
+pub fn format_offset(seconds: i32) -> String {
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let seconds = seconds.unsigned_abs();
+    let (hours, minutes) = (seconds / 3600, (seconds % 3600) / 60);
+    match seconds % 60 {
+        0 => format!("{}{:02}:{:02}", sign, hours, minutes),
+        rest => format!("{}{:02}:{:02}:{:02}", sign, hours, minutes, rest),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn utc(year: i32, month: u32, day: u32, hour: u32) -> DateTime<Utc> {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+            .and_utc()
+    }
+
+    #[test]
+    fn formats_offsets() {
+        assert_eq!(format_offset(0), "+00:00");
+        assert_eq!(format_offset(19800), "+05:30");
+        assert_eq!(format_offset(-3600), "-01:00");
+        assert_eq!(format_offset(-17762), "-04:56:02");
+        assert_eq!(format_offset(1172), "+00:19:32");
+    }
+
+    #[test]
+    fn formats_instants_in_each_zone() {
+        let instant = utc(2024, 3, 1, 10);
+        assert_eq!(DisplayZone::Utc.format(instant), "2024-03-01T10:00:00Z");
+        assert_eq!(
+            DisplayZone::Named(chrono_tz::Asia::Kolkata).format(instant),
+            "2024-03-01T15:30:00+05:30"
+        );
+        assert_eq!(
+            DisplayZone::Fixed(FixedOffset::west_opt(3 * 3600).unwrap()).format(instant),
+            "2024-03-01T07:00:00-03:00"
+        );
+    }
+
+    #[test]
+    fn keeps_the_seconds_of_local_mean_time() {
+        // New York kept local mean time until noon on 1883-11-18
+        let zone = DisplayZone::Named(chrono_tz::America::New_York);
+        assert_eq!(
+            zone.format(utc(1880, 1, 1, 0)),
+            "1879-12-31T19:03:58-04:56:02"
+        );
+        assert_eq!(zone.format(utc(1890, 1, 1, 0)), "1889-12-31T19:00:00-05:00");
+    }
+
+    #[test]
+    fn session_zone_falls_back_to_utc() {
+        let named = Some(DisplayZone::Named(chrono_tz::Europe::Berlin));
+        assert!(matches!(
+            DisplayZone::resolve(TimeZoneMode::Session, named),
+            DisplayZone::Named(chrono_tz::Europe::Berlin)
+        ));
+        assert!(matches!(
+            DisplayZone::resolve(TimeZoneMode::Session, None),
+            DisplayZone::Utc
+        ));
+        assert!(matches!(
+            DisplayZone::resolve(TimeZoneMode::Utc, named),
+            DisplayZone::Utc
+        ));
+    }
+}
@@ -0,0 +1,263 @@
+pub mod rust;
+pub mod typescript;
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Language types are generated in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum TypegenTarget {
+    /// Interfaces matching what node-postgres returns
+    TypeScript,
+    /// Zod schemas, with their inferred TypeScript types
+    Zod,
+    /// Structs deriving serde and `sqlx::FromRow`
+    Rust,
+}
+
+impl TypegenTarget {
+    /// File type shown in the save dialog, its extension and the suggested file name
+    pub fn file_type(self) -> (&'static str, &'static str, &'static str) {
+        match self {
+            TypegenTarget::TypeScript => ("TypeScript", "ts", "db.ts"),
+            TypegenTarget::Zod => ("TypeScript", "ts", "db.schema.ts"),
+            TypegenTarget::Rust => ("Rust", "rs", "db.rs"),
+        }
+    }
+}
+
+#[taurpc::ipc_type]
+#[serde(rename_all = "camelCase")]
+#[derive(Debug)]
+pub struct TypegenRequest {
+    pub target: TypegenTarget,
+    /// Schemas to generate types for; empty for every non-system schema
+    pub schemas: Vec<String>,
+    /// File to write, relative to the project root. Asks where to save when not set.
+    pub path: Option<String>,
+}
+
+#[taurpc::ipc_type]
+#[serde(rename_all = "camelCase")]
+#[derive(Debug)]
+pub struct TypegenSummary {
+    pub path: String,
+    /// Tables, views and enums written
+    pub types: u32,
+}
+
+/// A column type, independent of the database it came from
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScalarType {
+    Bool,
+    Int16,
+    Int32,
+    Int64,
+    /// Postgres object identifier, an unsigned 32-bit integer
+    Oid,
+    Float32,
+    Float64,
+    /// Arbitrary precision decimal
+    Numeric,
+    /// Postgres money, which the server formats in its locale
+    Money,
+    Text,
+    Uuid,
+    Json,
+    Bytes,
+    Date,
+    Time,
+    Timestamp,
+    TimestampTz,
+    Interval,
+    Inet,
+    Enum {
+        schema: String,
+        name: String,
+    },
+    /// A type with no mapping, by its database name; generated as its text form
+    Other(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct TypedColumn {
+    pub name: String,
+    pub scalar: ScalarType,
+    pub is_array: bool,
+    pub nullable: bool,
+}
+
+/// A table or view and the type of each column
+#[derive(Debug, Clone)]
+pub struct TypedRelation {
+    pub schema: String,
+    pub name: String,
+    pub columns: Vec<TypedColumn>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TypedEnum {
+    pub schema: String,
+    pub name: String,
+    pub labels: Vec<String>,
+}
+
+/// Everything types are generated from, each list in a stable order
+#[derive(Debug, Clone, Default)]
+pub struct TypeModel {
+    pub relations: Vec<TypedRelation>,
+    pub enums: Vec<TypedEnum>,
+}
+
+impl TypeModel {
+    pub fn type_count(&self) -> u32 {
+        (self.relations.len() + self.enums.len()) as u32
+    }
+
+    pub fn render(&self, target: TypegenTarget) -> String {
+        match target {
+            TypegenTarget::TypeScript => typescript::interfaces(self),
+            TypegenTarget::Zod => typescript::zod(self),
+            TypegenTarget::Rust => rust::structs(self),
+        }
+    }
+}
+
+/// Comment opening every generated file
+pub(crate) const HEADER: &str =
+    "Generated by Sqratch from the database schema. Regenerate it rather than editing by hand.";
+
+/// `user_accounts` -> `UserAccounts`. Types outside the default `public` schema are
+/// prefixed with their schema, so `auth.users` becomes `AuthUsers`.
+pub(crate) fn type_name(schema: &str, name: &str) -> String {
+    let mut type_name = if schema == "public" {
+        pascal_case(name)
+    } else {
+        format!("{}{}", pascal_case(schema), pascal_case(name))
+    };
+    if !type_name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        type_name.insert(0, 'T');
+    }
+    type_name
+}
+
+/// Unique names for a model's enums and relations. Names that would clash are told apart:
+/// an enum named like another type gets an `Enum` suffix, and any clash left, such as
+/// `auth.users` and `public.auth_users`, numbers the later types in model order.
+pub(crate) struct TypeNames {
+    enums: HashMap<(String, String), String>,
+    relations: HashMap<(String, String), String>,
+}
+
+impl TypeNames {
+    pub(crate) fn new(model: &TypeModel) -> Self {
+        let mut uses = HashMap::<String, usize>::new();
+        let enums = model.enums.iter().map(|e| (&e.schema, &e.name));
+        let relations = model.relations.iter().map(|r| (&r.schema, &r.name));
+        for (schema, name) in enums.chain(relations) {
+            *uses.entry(type_name(schema, name)).or_default() += 1;
+        }
+
+        let mut taken = NameSet::default();
+
+        let mut names = Self {
+            enums: HashMap::new(),
+            relations: HashMap::new(),
+        };
+        for e in &model.enums {
+            let mut name = type_name(&e.schema, &e.name);
+            if uses[&name] > 1 {
+                name.push_str("Enum");
+            }
+            names
+                .enums
+                .insert((e.schema.clone(), e.name.clone()), taken.claim(name));
+        }
+        for relation in &model.relations {
+            let name = taken.claim(type_name(&relation.schema, &relation.name));
+            names
+                .relations
+                .insert((relation.schema.clone(), relation.name.clone()), name);
+        }
+        names
+    }
+
+    pub(crate) fn enum_name(&self, schema: &str, name: &str) -> String {
+        self.enums
+            .get(&(schema.to_string(), name.to_string()))
+            .cloned()
+            .unwrap_or_else(|| type_name(schema, name))
+    }
+
+    pub(crate) fn relation_name(&self, schema: &str, name: &str) -> String {
+        self.relations
+            .get(&(schema.to_string(), name.to_string()))
+            .cloned()
+            .unwrap_or_else(|| type_name(schema, name))
+    }
+}
+
+/// Names handed out so far in one scope. A name that's taken again is numbered from 2.
+#[derive(Default)]
+pub(crate) struct NameSet(HashSet<String>);
+
+impl NameSet {
+    pub(crate) fn claim(&mut self, name: String) -> String {
+        let mut unique = name.clone();
+        let mut n = 2;
+        while !self.0.insert(unique.clone()) {
+            unique = format!("{}{}", name, n);
+            n += 1;
+        }
+        unique
+    }
+}
+
+pub(crate) fn pascal_case(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            let first = chars.next().unwrap_or_default().to_ascii_uppercase();
+            std::iter::once(first).chain(chars).collect::<String>()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn relation(schema: &str, name: &str) -> TypedRelation {
+        TypedRelation {
+            schema: schema.to_string(),
+            name: name.to_string(),
+            columns: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn tells_clashing_type_names_apart() {
+        let model = TypeModel {
+            relations: vec![
+                relation("auth", "users"),
+                relation("public", "auth_users"),
+                relation("public", "status"),
+                relation("public", "orders"),
+            ],
+            enums: vec![TypedEnum {
+                schema: "public".to_string(),
+                name: "status".to_string(),
+                labels: vec!["open".to_string()],
+            }],
+        };
+
+        let names = TypeNames::new(&model);
+        assert_eq!(names.enum_name("public", "status"), "StatusEnum");
+        assert_eq!(names.relation_name("public", "status"), "Status");
+        assert_eq!(names.relation_name("auth", "users"), "AuthUsers");
+        assert_eq!(names.relation_name("public", "auth_users"), "AuthUsers2");
+        assert_eq!(names.relation_name("public", "orders"), "Orders");
+    }
+}
@@ -0,0 +1,262 @@
+use super::{pascal_case, NameSet, ScalarType, TypeModel, TypeNames, TypedColumn, HEADER};
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern", "false",
+    "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref",
+    "return", "static", "struct", "trait", "true", "type", "unsafe", "use", "where", "while",
+    "abstract", "become", "box", "do", "final", "gen", "macro", "override", "priv", "try",
+    "typeof", "unsized", "virtual", "yield",
+];
+
+fn string_literal(value: &str) -> String {
+    format!("{:?}", value)
+}
+
+/// The column as a lowercase field unique among `fields`, and whether that differs from
+/// the column name. Columns like `userId` and `userid` would share a field, so the later
+/// one is numbered.
+fn field_name(column: &str, fields: &mut NameSet) -> (String, bool) {
+    let mut field = column
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    if !field.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        field.insert(0, '_');
+    }
+    // These can't be raw identifiers
+    if matches!(field.as_str(), "self" | "super" | "crate" | "_") {
+        field.push('_');
+    }
+
+    let field = fields.claim(field);
+    let renamed = field != column;
+    if KEYWORDS.contains(&field.as_str()) {
+        return (format!("r#{}", field), renamed);
+    }
+    (field, renamed)
+}
+
+/// The enum label as a PascalCase variant unique among `variants`, as `a-b` and `a_b`
+/// would otherwise both be `AB`
+fn variant_name(label: &str, variants: &mut NameSet) -> String {
+    let mut variant = pascal_case(label);
+    if !variant.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        variant.insert(0, 'V');
+    }
+    variants.claim(variant)
+}
+
+fn rust_type(scalar: &ScalarType, names: &TypeNames) -> String {
+    match scalar {
+        ScalarType::Bool => "bool",
+        ScalarType::Int16 => "i16",
+        ScalarType::Int32 => "i32",
+        ScalarType::Int64 => "i64",
+        ScalarType::Oid => "sqlx::postgres::types::Oid",
+        ScalarType::Float32 => "f32",
+        ScalarType::Float64 => "f64",
+        ScalarType::Numeric => "sqlx::types::Decimal",
+        ScalarType::Money => "sqlx::postgres::types::PgMoney",
+        ScalarType::Uuid => "sqlx::types::Uuid",
+        ScalarType::Json => "serde_json::Value",
+        ScalarType::Bytes => "Vec<u8>",
+        ScalarType::Date => "sqlx::types::chrono::NaiveDate",
+        ScalarType::Time => "sqlx::types::chrono::NaiveTime",
+        ScalarType::Timestamp => "sqlx::types::chrono::NaiveDateTime",
+        ScalarType::TimestampTz => "sqlx::types::chrono::DateTime<sqlx::types::chrono::Utc>",
+        ScalarType::Inet => "sqlx::types::ipnetwork::IpNetwork",
+        ScalarType::Enum { schema, name } => return names.enum_name(schema, name),
+        // PgInterval has no serde support and other types have no sqlx mapping, so these
+        // need a `::text` cast in the query
+        ScalarType::Text | ScalarType::Interval | ScalarType::Other(_) => "String",
+    }
+    .to_string()
+}
+
+fn column_type(column: &TypedColumn, names: &TypeNames) -> String {
+    let mut ty = rust_type(&column.scalar, names);
+    if column.is_array {
+        ty = format!("Vec<{}>", ty);
+    }
+    if column.nullable {
+        ty = format!("Option<{}>", ty);
+    }
+    ty
+}
+
+/// Enums deriving `sqlx::Type` and one `FromRow` struct per table or view
+pub fn structs(model: &TypeModel) -> String {
+    let names = TypeNames::new(model);
+    let mut out = format!("// {}\n", HEADER);
+
+    for e in &model.enums {
+        // sqlx looks the type up by name, so it's qualified outside the search path
+        let sql_name = if e.schema == "public" {
+            e.name.clone()
+        } else {
+            format!("{}.{}", e.schema, e.name)
+        };
+        out.push_str(&format!(
+            "\n#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, sqlx::Type)]\n#[sqlx(type_name = {})]\npub enum {} {{\n",
+            string_literal(&sql_name),
+            names.enum_name(&e.schema, &e.name)
+        ));
+        let mut variants = NameSet::default();
+        for label in &e.labels {
+            let variant = variant_name(label, &mut variants);
+            if variant != *label {
+                out.push_str(&format!(
+                    "    #[serde(rename = {0})]\n    #[sqlx(rename = {0})]\n",
+                    string_literal(label)
+                ));
+            }
+            out.push_str(&format!("    {},\n", variant));
+        }
+        out.push_str("}\n");
+    }
+
+    for relation in &model.relations {
+        out.push_str(&format!(
+            "\n#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, sqlx::FromRow)]\npub struct {} {{\n",
+            names.relation_name(&relation.schema, &relation.name)
+        ));
+        let mut fields = NameSet::default();
+        for column in &relation.columns {
+            let (field, renamed) = field_name(&column.name, &mut fields);
+            if renamed {
+                out.push_str(&format!(
+                    "    #[serde(rename = {0})]\n    #[sqlx(rename = {0})]\n",
+                    string_literal(&column.name)
+                ));
+            }
+            let comment = match &column.scalar {
+                ScalarType::Interval => " // interval".to_string(),
+                ScalarType::Money => " // money, in the currency's minor unit".to_string(),
+                ScalarType::Other(name) => format!(" // {}", name),
+                _ => String::new(),
+            };
+            // PgMoney has no serde support
+            if column.scalar == ScalarType::Money {
+                out.push_str("    #[serde(skip)]\n");
+            }
+            out.push_str(&format!(
+                "    pub {}: {},{}\n",
+                field,
+                column_type(column, &names),
+                comment
+            ));
+        }
+        out.push_str("}\n");
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::typegen::{TypedEnum, TypedRelation};
+
+    fn column(name: &str, scalar: ScalarType, is_array: bool, nullable: bool) -> TypedColumn {
+        TypedColumn {
+            name: name.to_string(),
+            scalar,
+            is_array,
+            nullable,
+        }
+    }
+
+    #[test]
+    fn renders_enums_and_structs() {
+        let mood = ScalarType::Enum {
+            schema: "public".to_string(),
+            name: "mood".to_string(),
+        };
+        let model = TypeModel {
+            enums: vec![TypedEnum {
+                schema: "public".to_string(),
+                name: "mood".to_string(),
+                labels: ["Happy", "very-sad", "very_sad", "1st"]
+                    .map(str::to_string)
+                    .to_vec(),
+            }],
+            relations: vec![TypedRelation {
+                schema: "public".to_string(),
+                name: "users".to_string(),
+                columns: vec![
+                    column("id", ScalarType::Int64, false, false),
+                    column("userId", ScalarType::Int32, false, true),
+                    column("userid", ScalarType::Text, false, false),
+                    column("type", ScalarType::Text, false, false),
+                    column("self", ScalarType::Text, false, false),
+                    column("tags", ScalarType::Text, true, true),
+                    column("balance", ScalarType::Money, false, false),
+                    column("mood", mood, false, true),
+                ],
+            }],
+        };
+
+        let expected = format!(
+            r##"// {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, sqlx::Type)]
+#[sqlx(type_name = "mood")]
+pub enum Mood {{
+    Happy,
+    #[serde(rename = "very-sad")]
+    #[sqlx(rename = "very-sad")]
+    VerySad,
+    #[serde(rename = "very_sad")]
+    #[sqlx(rename = "very_sad")]
+    VerySad2,
+    #[serde(rename = "1st")]
+    #[sqlx(rename = "1st")]
+    V1st,
+}}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, sqlx::FromRow)]
+pub struct Users {{
+    pub id: i64,
+    #[serde(rename = "userId")]
+    #[sqlx(rename = "userId")]
+    pub userid: Option<i32>,
+    #[serde(rename = "userid")]
+    #[sqlx(rename = "userid")]
+    pub userid2: String,
+    pub r#type: String,
+    #[serde(rename = "self")]
+    #[sqlx(rename = "self")]
+    pub self_: String,
+    pub tags: Option<Vec<String>>,
+    #[serde(skip)]
+    pub balance: sqlx::postgres::types::PgMoney, // money, in the currency's minor unit
+    pub mood: Option<Mood>,
+}}
+"##,
+            HEADER
+        );
+        assert_eq!(structs(&model), expected);
+    }
+
+    #[test]
+    fn numbers_clashing_fields() {
+        let mut fields = NameSet::default();
+        assert_eq!(field_name("a-b", &mut fields), ("a_b".to_string(), true));
+        assert_eq!(field_name("a_b", &mut fields), ("a_b2".to_string(), true));
+        assert_eq!(field_name("2fa", &mut fields), ("_2fa".to_string(), true));
+        assert_eq!(
+            field_name("match", &mut fields),
+            ("r#match".to_string(), false)
+        );
+        assert_eq!(
+            field_name("Match", &mut fields),
+            ("match2".to_string(), true)
+        );
+    }
+}
@@ -0,0 +1,266 @@
+use super::{ScalarType, TypeModel, TypeNames, TypedColumn, HEADER};
+
+fn string_literal(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+/// Object keys are quoted unless they're plain identifiers
+fn property_name(name: &str) -> String {
+    let is_identifier = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if is_identifier {
+        name.to_string()
+    } else {
+        string_literal(name)
+    }
+}
+
+/// Types as node-postgres parses them by default: 64-bit integers and numerics stay
+/// strings so they don't lose precision
+fn ts_type(scalar: &ScalarType, names: &TypeNames) -> String {
+    match scalar {
+        ScalarType::Bool => "boolean".to_string(),
+        ScalarType::Int16
+        | ScalarType::Int32
+        | ScalarType::Oid
+        | ScalarType::Float32
+        | ScalarType::Float64 => "number".to_string(),
+        ScalarType::Json => "unknown".to_string(),
+        ScalarType::Bytes => "Uint8Array".to_string(),
+        ScalarType::Date | ScalarType::Timestamp | ScalarType::TimestampTz => "Date".to_string(),
+        ScalarType::Enum { schema, name } => names.enum_name(schema, name),
+        ScalarType::Int64
+        | ScalarType::Numeric
+        | ScalarType::Money
+        | ScalarType::Text
+        | ScalarType::Uuid
+        | ScalarType::Time
+        | ScalarType::Interval
+        | ScalarType::Inet
+        | ScalarType::Other(_) => "string".to_string(),
+    }
+}
+
+fn zod_type(scalar: &ScalarType, names: &TypeNames) -> String {
+    match scalar {
+        ScalarType::Bool => "z.boolean()".to_string(),
+        ScalarType::Int16 | ScalarType::Int32 | ScalarType::Oid => "z.number().int()".to_string(),
+        ScalarType::Float32 | ScalarType::Float64 => "z.number()".to_string(),
+        ScalarType::Uuid => "z.string().uuid()".to_string(),
+        ScalarType::Json => "z.unknown()".to_string(),
+        ScalarType::Bytes => "z.instanceof(Uint8Array)".to_string(),
+        ScalarType::Date | ScalarType::Timestamp | ScalarType::TimestampTz => {
+            "z.date()".to_string()
+        }
+        ScalarType::Enum { schema, name } => names.enum_name(schema, name),
+        ScalarType::Int64
+        | ScalarType::Numeric
+        | ScalarType::Money
+        | ScalarType::Text
+        | ScalarType::Time
+        | ScalarType::Interval
+        | ScalarType::Inet
+        | ScalarType::Other(_) => "z.string()".to_string(),
+    }
+}
+
+fn column_type(column: &TypedColumn, names: &TypeNames) -> String {
+    let mut ty = ts_type(&column.scalar, names);
+    if column.is_array {
+        ty.push_str("[]");
+    }
+    if column.nullable {
+        ty.push_str(" | null");
+    }
+    ty
+}
+
+fn column_schema(column: &TypedColumn, names: &TypeNames) -> String {
+    let mut schema = zod_type(&column.scalar, names);
+    if column.is_array {
+        schema = format!("z.array({})", schema);
+    }
+    if column.nullable {
+        schema.push_str(".nullable()");
+    }
+    schema
+}
+
+/// Enum unions and one interface per table or view
+pub fn interfaces(model: &TypeModel) -> String {
+    let names = TypeNames::new(model);
+    let mut out = format!("// {}\n", HEADER);
+
+    for e in &model.enums {
+        let labels = e
+            .labels
+            .iter()
+            .map(|label| string_literal(label))
+            .collect::<Vec<_>>();
+        out.push_str(&format!(
+            "\nexport type {} = {};\n",
+            names.enum_name(&e.schema, &e.name),
+            if labels.is_empty() {
+                "never".to_string()
+            } else {
+                labels.join(" | ")
+            }
+        ));
+    }
+
+    for relation in &model.relations {
+        out.push_str(&format!(
+            "\nexport interface {} {{\n",
+            names.relation_name(&relation.schema, &relation.name)
+        ));
+        for column in &relation.columns {
+            out.push_str(&format!(
+                "  {}: {};\n",
+                property_name(&column.name),
+                column_type(column, &names)
+            ));
+        }
+        out.push_str("}\n");
+    }
+
+    out
+}
+
+/// Zod schemas exported under the same name as their inferred types. Enums come first,
+/// as the table schemas refer to them.
+pub fn zod(model: &TypeModel) -> String {
+    let names = TypeNames::new(model);
+    let mut out = format!("// {}\n\nimport {{ z }} from \"zod\";\n", HEADER);
+
+    for e in &model.enums {
+        let name = names.enum_name(&e.schema, &e.name);
+        let schema = if e.labels.is_empty() {
+            "z.never()".to_string()
+        } else {
+            let labels = e
+                .labels
+                .iter()
+                .map(|label| string_literal(label))
+                .collect::<Vec<_>>();
+            format!("z.enum([{}])", labels.join(", "))
+        };
+        out.push_str(&format!(
+            "\nexport const {name} = {schema};\nexport type {name} = z.infer<typeof {name}>;\n",
+        ));
+    }
+
+    for relation in &model.relations {
+        let name = names.relation_name(&relation.schema, &relation.name);
+        out.push_str(&format!("\nexport const {} = z.object({{\n", name));
+        for column in &relation.columns {
+            out.push_str(&format!(
+                "  {}: {},\n",
+                property_name(&column.name),
+                column_schema(column, &names)
+            ));
+        }
+        out.push_str(&format!(
+            "}});\nexport type {name} = z.infer<typeof {name}>;\n"
+        ));
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::typegen::{TypedEnum, TypedRelation};
+
+    fn column(name: &str, scalar: ScalarType, is_array: bool, nullable: bool) -> TypedColumn {
+        TypedColumn {
+            name: name.to_string(),
+            scalar,
+            is_array,
+            nullable,
+        }
+    }
+
+    fn model() -> TypeModel {
+        let typed_enum = |name: &str, labels: &[&str]| TypedEnum {
+            schema: "public".to_string(),
+            name: name.to_string(),
+            labels: labels.iter().map(|label| label.to_string()).collect(),
+        };
+        let mood = ScalarType::Enum {
+            schema: "public".to_string(),
+            name: "mood".to_string(),
+        };
+        TypeModel {
+            enums: vec![
+                typed_enum("mood", &["happy", "very \"sad\""]),
+                typed_enum("empty", &[]),
+            ],
+            relations: vec![TypedRelation {
+                schema: "auth".to_string(),
+                name: "users".to_string(),
+                columns: vec![
+                    column("id", ScalarType::Int64, false, false),
+                    column("userId", ScalarType::Int32, false, true),
+                    column("display name", ScalarType::Text, false, false),
+                    column("tags", ScalarType::Uuid, true, false),
+                    column("mood", mood, false, true),
+                    column("created_at", ScalarType::TimestampTz, false, false),
+                ],
+            }],
+        }
+    }
+
+    #[test]
+    fn renders_interfaces() {
+        let expected = format!(
+            r#"// {}
+
+export type Mood = "happy" | "very \"sad\"";
+
+export type Empty = never;
+
+export interface AuthUsers {{
+  id: string;
+  userId: number | null;
+  "display name": string;
+  tags: string[];
+  mood: Mood | null;
+  created_at: Date;
+}}
+"#,
+            HEADER
+        );
+        assert_eq!(interfaces(&model()), expected);
+    }
+
+    #[test]
+    fn renders_zod_schemas() {
+        let expected = format!(
+            r#"// {}
+
+import {{ z }} from "zod";
+
+export const Mood = z.enum(["happy", "very \"sad\""]);
+export type Mood = z.infer<typeof Mood>;
+
+export const Empty = z.never();
+export type Empty = z.infer<typeof Empty>;
+
+export const AuthUsers = z.object({{
+  id: z.string(),
+  userId: z.number().int().nullable(),
+  "display name": z.string(),
+  tags: z.array(z.string().uuid()),
+  mood: Mood.nullable(),
+  created_at: z.date(),
+}});
+export type AuthUsers = z.infer<typeof AuthUsers>;
+"#,
+            HEADER
+        );
+        assert_eq!(zod(&model()), expected);
+    }
+}
@@ -69,6 +69,61 @@ impl From<Row> for HashMap<String, serde_json::Value> {
     }
 }
 
+/// Which zone timestamps with a time zone are displayed in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum TimeZoneMode {
+    /// Always display in UTC
+    #[default]
+    Utc,
+    /// Use the TimeZone configured for the database session
+    Session,
+    /// Use the time zone of this machine
+    Local,
+}
+
+/// Database engine a client is talking to, including wire-compatible forks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum ServerFlavor {
+    #[default]
+    Postgres,
+    CockroachDb,
+    YugabyteDb,
+    Neon,
+    Supabase,
+    MySql,
+    MariaDb,
+    Sqlite,
+    DuckDb,
+    ClickHouse,
+}
+
+/// Server flavor and version, detected on connect
+#[taurpc::ipc_type]
+#[serde(rename_all = "camelCase")]
+#[derive(Debug)]
+pub struct ServerInfo {
+    pub flavor: ServerFlavor,
+    /// Version as reported by the server, e.g. "16.2" or "v23.1.11"
+    pub version: String,
+    /// Numeric version when the server reports one, e.g. 160002
+    pub version_num: Option<u32>,
+}
+
+/// A binary value that was written to disk
+#[taurpc::ipc_type]
+#[serde(rename_all = "camelCase")]
+#[derive(Debug)]
+pub struct SavedBlob {
+    /// Path the value was written to
+    pub path: String,
+    /// Size of the value in bytes
+    pub size: u64,
+    /// Detected MIME type, if the content is a known format
+    pub mime_type: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
 #[serde(rename_all = "camelCase")]
 pub struct SchemaEntity {
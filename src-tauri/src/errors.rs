@@ -1,3 +1,4 @@
+use crate::db::changes::ChangeConflict;
 use crate::db::errors::DbError;
 use crate::project::ConfigError;
 
@@ -36,12 +37,29 @@ enum ErrorKind {
     Other(String),
 }
 
+#[derive(serde::Serialize)]
+struct ConflictError<'a> {
+    kind: &'static str,
+    message: String,
+    conflicts: &'a [ChangeConflict],
+}
+
 // we must manually implement serde::Serialize
 impl serde::Serialize for AppError {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::ser::Serializer,
     {
+        // Conflicts carry the details the UI needs to show which rows changed
+        if let AppError::Db(DbError::Conflict(conflicts)) = self {
+            return ConflictError {
+                kind: "conflict",
+                message: self.to_string(),
+                conflicts,
+            }
+            .serialize(serializer);
+        }
+
         let error_message = self.to_string();
         let error_kind = match self {
             AppError::Io(_) => ErrorKind::Io(error_message),
@@ -1,19 +1,112 @@
-use super::ProjectHandle;
+use serde_json::{Map, Value as JsonValue};
+use std::fs;
+use std::path::Path;
+use url::Url;
 
-/// Represents the user-defined configuration for a project
-#[derive(Debug, serde::Deserialize, serde::Serialize)]
+use crate::db::binary::BinaryFormat;
+use crate::db::types::TimeZoneMode;
+
+use super::{jsonc, ProjectHandle};
+
+/// Name of the config file in the project (.sqratch) directory
+pub const CONFIG_FILE: &str = "config.jsonc";
+/// Older projects have a strict JSON `{ name, db }` config, migrated to `config.jsonc` on load
+const LEGACY_CONFIG_FILE: &str = "config.json";
+
+/// Represents the user-defined configuration for a project, as documented in the README.
+/// Unknown keys are rejected, so a misspelled one is reported instead of silently ignored.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
 pub struct ProjectConfig {
+    /// Connection string used as-is, instead of reading one from the environment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Env file to read variables from, relative to the project root (default: ".env")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env_file: Option<String>,
+    /// Environment variable holding the connection string
+    #[serde(default = "default_connection_variable")]
+    pub connection_variable: String,
+    /// Environment variables holding each part of the connection, used when
+    /// `connectionVariable` isn't set or is empty
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connection_params: Option<ConnectionParams>,
+    #[serde(default)]
+    pub settings: ProjectSettings,
+}
+
+/// Names of the environment variables holding each connection parameter
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConnectionParams {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub database: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct ProjectSettings {
     /// Optional project name; if not set, it will be inferred from the directory or database name.
-    pub name: Option<String>,
-    /// Database connection string or path to a .env file with a DATABASE_URL variable.
-    /// Format for .env path can include an environment name, e.g., "../.env|ENV_NAME".
-    pub db: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_name: Option<String>,
+    /// Whether to save query history
+    #[serde(default = "default_true")]
+    pub save_queries: bool,
+    /// Zone used to display timestamps with a time zone: "utc", "session" or "local".
+    #[serde(default)]
+    pub time_zone: TimeZoneMode,
+    /// Encoding and length of binary cell previews
+    #[serde(default)]
+    pub binary_preview: BinaryFormat,
+}
+
+fn default_connection_variable() -> String {
+    "DATABASE_URL".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for ProjectSettings {
+    fn default() -> Self {
+        Self {
+            project_name: None,
+            save_queries: true,
+            time_zone: TimeZoneMode::default(),
+            binary_preview: BinaryFormat::default(),
+        }
+    }
+}
+
+impl Default for ProjectConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            env_file: None,
+            connection_variable: default_connection_variable(),
+            connection_params: None,
+            settings: ProjectSettings::default(),
+        }
+    }
 }
 
 impl ProjectConfig {
+    /// Whether a project directory has a config file, in either format
+    pub fn exists(dir: &Path) -> bool {
+        dir.join(CONFIG_FILE).exists() || dir.join(LEGACY_CONFIG_FILE).exists()
+    }
+
     /// Loads a project configuration given a ProjectRef.
-    /// Attempts to read a config.json file from the project directory.
-    /// If the file doesn't exist or can't be read, returns a default configuration.
+    /// Reads config.jsonc from the project directory, or migrates an old config.json to it.
     pub fn load(project_ref: &ProjectHandle) -> Result<Self, ConfigError> {
         // If the project is temporary, return a default configuration
         if project_ref.is_temp {
@@ -25,20 +118,111 @@ impl ProjectConfig {
                 .to_string();
 
             return Ok(ProjectConfig {
-                name: Some(name),
-                db: url.to_string(),
+                url: Some(url.to_string()),
+                settings: ProjectSettings {
+                    project_name: Some(name),
+                    ..ProjectSettings::default()
+                },
+                ..ProjectConfig::default()
             });
         }
 
-        // For non-temporary projects, attempt to read the config file
-        let config_path = project_ref.path.join("config.json");
-        match std::fs::read_to_string(&config_path) {
-            Ok(content) => {
-                serde_json::from_str(&content).map_err(|e| ConfigError::Parse(e.to_string()))
+        let config_path = project_ref.path.join(CONFIG_FILE);
+        if config_path.exists() {
+            return Self::parse(&config_path, &fs::read_to_string(&config_path)?);
+        }
+
+        let legacy_path = project_ref.path.join(LEGACY_CONFIG_FILE);
+        let config = Self::parse(&legacy_path, &fs::read_to_string(&legacy_path)?)?;
+
+        // Move the project to the documented format, so there's only one file to edit
+        config.save(&project_ref.path)?;
+        fs::remove_file(&legacy_path)?;
+        log::info!(
+            "Migrated {} to {}",
+            legacy_path.display(),
+            config_path.display()
+        );
+
+        Ok(config)
+    }
+
+    /// Parses a config file's JSONC, accepting the old `{ name, db }` shape too
+    fn parse(path: &Path, content: &str) -> Result<Self, ConfigError> {
+        let json = jsonc::strip(content);
+        let value: JsonValue =
+            serde_json::from_str(&json).map_err(|e| ConfigError::syntax(path, &e))?;
+
+        match value {
+            JsonValue::Object(map) if is_legacy(&map) => serde_json::from_value(migrate(map))
+                .map_err(|e| ConfigError::Parse(format!("{}: {}", path.display(), e))),
+            // Parsed again as a config so type errors point at their line too
+            _ => serde_json::from_str(&json).map_err(|e| ConfigError::syntax(path, &e)),
+        }
+    }
+
+    /// Writes the config to the project directory's config.jsonc
+    pub fn save(&self, dir: &Path) -> Result<(), ConfigError> {
+        let mut json = serde_json::to_string_pretty(self)?;
+        json.push('\n');
+        fs::write(dir.join(CONFIG_FILE), json)?;
+        Ok(())
+    }
+}
+
+fn is_legacy(map: &Map<String, JsonValue>) -> bool {
+    map.contains_key("db") || map.contains_key("name")
+}
+
+/// Converts `{ name, db, timeZone }` to the current shape. `db` was a connection string or
+/// an env file path relative to the .sqratch directory, optionally followed by `|VARIABLE`.
+fn migrate(mut legacy: Map<String, JsonValue>) -> JsonValue {
+    let mut settings = Map::new();
+    if let Some(name) = legacy.remove("name") {
+        settings.insert("projectName".to_string(), name);
+    }
+    if let Some(time_zone) = legacy.remove("timeZone") {
+        settings.insert("timeZone".to_string(), time_zone);
+    }
+
+    let mut config = Map::new();
+    if let Some(JsonValue::String(db)) = legacy.remove("db") {
+        if Url::parse(&db).is_ok() {
+            config.insert("url".to_string(), JsonValue::String(db));
+        } else {
+            let (file, variable) = match db.split_once('|') {
+                Some((file, variable)) => (file.trim(), Some(variable.trim())),
+                None => (db.trim(), None),
+            };
+
+            let file = Path::new(file);
+            let env_file = if file.is_absolute() {
+                file.to_path_buf()
+            } else {
+                match file.strip_prefix("..") {
+                    Ok(in_root) => in_root.to_path_buf(),
+                    Err(_) => Path::new(".sqratch").join(file),
+                }
+            };
+            if env_file != Path::new(".env") {
+                config.insert(
+                    "envFile".to_string(),
+                    JsonValue::String(env_file.to_string_lossy().to_string()),
+                );
+            }
+            if let Some(variable) = variable {
+                config.insert(
+                    "connectionVariable".to_string(),
+                    JsonValue::String(variable.to_string()),
+                );
             }
-            Err(e) => Err(ConfigError::Io(e)),
         }
     }
+
+    // Anything else is kept, so it's reported as an unknown field
+    config.extend(legacy);
+    config.insert("settings".to_string(), JsonValue::Object(settings));
+    JsonValue::Object(config)
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -49,10 +233,35 @@ pub enum ConfigError {
     #[error("Parse error: {0}")]
     Parse(String),
 
+    #[error("{path}:{line}:{column}: {message}")]
+    Syntax {
+        path: String,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+
     #[error("Other error: {0}")]
     Other(String),
 }
 
+impl ConfigError {
+    /// A parse error at the position serde_json reports
+    fn syntax(path: &Path, error: &serde_json::Error) -> Self {
+        let position = format!(" at line {} column {}", error.line(), error.column());
+        let message = error.to_string();
+        ConfigError::Syntax {
+            path: path.display().to_string(),
+            line: error.line(),
+            column: error.column(),
+            message: message
+                .strip_suffix(&position)
+                .unwrap_or(&message)
+                .to_string(),
+        }
+    }
+}
+
 impl From<serde_json::Error> for ConfigError {
     fn from(error: serde_json::Error) -> Self {
         ConfigError::Parse(error.to_string())
@@ -64,3 +273,50 @@ impl From<&str> for ConfigError {
         ConfigError::Other(error.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> Result<ProjectConfig, ConfigError> {
+        ProjectConfig::parse(Path::new("config.jsonc"), content)
+    }
+
+    #[test]
+    fn parses_jsonc() {
+        let config = parse(
+            r#"{
+                // Read from the environment
+                "connectionVariable": "APP_DATABASE_URL",
+                "settings": { "saveQueries": false, },
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(config.connection_variable, "APP_DATABASE_URL");
+        assert!(!config.settings.save_queries);
+    }
+
+    #[test]
+    fn reports_unknown_keys_with_their_line() {
+        let error = parse("{\n  \"settings\": {\n    \"saveQuery\": false\n  }\n}").unwrap_err();
+        let ConfigError::Syntax { line, message, .. } = error else {
+            panic!("expected a syntax error, got {:?}", error);
+        };
+        assert_eq!(line, 3);
+        assert!(message.contains("unknown field `saveQuery`"), "{}", message);
+    }
+
+    #[test]
+    fn migrates_legacy_configs() {
+        let config = parse(r#"{ "name": "Shop", "db": "../.env.local|SHOP_URL" }"#).unwrap();
+        assert_eq!(config.env_file.as_deref(), Some(".env.local"));
+        assert_eq!(config.connection_variable, "SHOP_URL");
+        assert_eq!(config.settings.project_name.as_deref(), Some("Shop"));
+    }
+
+    #[test]
+    fn reports_unknown_legacy_keys() {
+        let error = parse(r#"{ "name": "Shop", "database": "x" }"#).unwrap_err();
+        assert!(error.to_string().contains("unknown field `database`"));
+    }
+}
@@ -0,0 +1,491 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::errors::AppError;
+
+use super::ConfigError;
+
+/// Where a variable the project read came from
+#[taurpc::ipc_type]
+#[serde(rename_all = "camelCase")]
+#[derive(Debug)]
+pub struct EnvVarSource {
+    pub name: String,
+    /// Env file the value came from, or None for the process environment
+    pub file: Option<String>,
+}
+
+/// Part of a value, with `${VAR}` references kept until every file is read
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(String),
+    Var {
+        name: String,
+        default: Option<String>,
+        /// `${VAR:-default}` also replaces an empty value, `${VAR-default}` only a missing one
+        default_if_empty: bool,
+    },
+}
+
+#[derive(Debug)]
+struct ParseError {
+    line: usize,
+    column: usize,
+    message: String,
+}
+
+/// How backslashes in a value are read
+#[derive(Clone, Copy, PartialEq)]
+enum Escapes {
+    /// Double quotes: `\n`, `\r`, `\t`, `\"`, `\\` and `\$`
+    All,
+    /// Unquoted: only `\$`, to write a literal dollar sign
+    Dollar,
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Splits a value into literals and variable references
+fn segments(text: &str, escapes: Escapes) -> Vec<Segment> {
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut segments = Vec::new();
+    let mut literal = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\\' && i + 1 < chars.len() {
+            let escaped = match (escapes, chars[i + 1]) {
+                (_, '$') => Some('$'),
+                (Escapes::All, 'n') => Some('\n'),
+                (Escapes::All, 'r') => Some('\r'),
+                (Escapes::All, 't') => Some('\t'),
+                (Escapes::All, '"') => Some('"'),
+                (Escapes::All, '\\') => Some('\\'),
+                _ => None,
+            };
+            if let Some(escaped) = escaped {
+                literal.push(escaped);
+                i += 2;
+                continue;
+            }
+        }
+
+        if c == '$' {
+            let reference = if chars.get(i + 1) == Some(&'{') {
+                chars[i + 2..].iter().position(|&c| c == '}').map(|end| {
+                    (
+                        chars[i + 2..i + 2 + end].iter().collect::<String>(),
+                        end + 3,
+                    )
+                })
+            } else {
+                let len = chars[i + 1..]
+                    .iter()
+                    .take_while(|&&c| is_name_char(c))
+                    .count();
+                (len > 0 && !chars[i + 1].is_ascii_digit()).then(|| {
+                    (
+                        chars[i + 1..i + 1 + len].iter().collect::<String>(),
+                        len + 1,
+                    )
+                })
+            };
+
+            if let Some((reference, len)) = reference {
+                let (name, default, default_if_empty) = match reference.split_once(":-") {
+                    Some((name, default)) => (name, Some(default.to_string()), true),
+                    None => match reference.split_once('-') {
+                        Some((name, default)) => (name, Some(default.to_string()), false),
+                        None => (reference.as_str(), None, false),
+                    },
+                };
+                if !literal.is_empty() {
+                    segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                }
+                segments.push(Segment::Var {
+                    name: name.to_string(),
+                    default,
+                    default_if_empty,
+                });
+                i += len;
+                continue;
+            }
+        }
+
+        literal.push(c);
+        i += 1;
+    }
+    if !literal.is_empty() {
+        segments.push(Segment::Literal(literal));
+    }
+    segments
+}
+
+/// Parses an env file: `KEY=value` lines with an optional `export` prefix, `#` comments,
+/// single, double or backtick quoted values that may span lines, and `${VAR}` references
+/// in double quoted and unquoted values. Lines that don't assign anything are skipped.
+fn parse(content: &str) -> Result<Vec<(String, Vec<Segment>)>, ParseError> {
+    let content = content.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+    let chars = content.chars().collect::<Vec<_>>();
+    let mut entries = Vec::new();
+    let mut i = 0;
+    let mut line = 1;
+
+    let rest_of_line = |i: usize| {
+        chars[i..]
+            .iter()
+            .position(|&c| c == '\n')
+            .map_or(chars.len(), |end| i + end)
+    };
+
+    while i < chars.len() {
+        let line_end = rest_of_line(i);
+        let text = chars[i..line_end].iter().collect::<String>();
+        let trimmed = text.trim_start();
+
+        let Some((key, _)) = trimmed
+            .split_once('=')
+            .filter(|_| !trimmed.starts_with('#'))
+        else {
+            i = line_end + 1;
+            line += 1;
+            continue;
+        };
+        let key = key.trim();
+        let key = key
+            .strip_prefix("export")
+            .filter(|rest| rest.starts_with([' ', '\t']))
+            .map_or(key, str::trim);
+        if key.is_empty() || !key.chars().all(|c| is_name_char(c) || c == '.' || c == '-') {
+            i = line_end + 1;
+            line += 1;
+            continue;
+        }
+
+        // Start of the value, after `=` and any spaces
+        let mut start = i + text.find('=').map_or(0, |eq| text[..eq].chars().count()) + 1;
+        while start < line_end && matches!(chars[start], ' ' | '\t') {
+            start += 1;
+        }
+
+        let quote = chars
+            .get(start)
+            .copied()
+            .filter(|c| matches!(c, '"' | '\'' | '`'));
+        let (value, next) = match quote {
+            Some(quote) => {
+                let mut end = start + 1;
+                while end < chars.len() && chars[end] != quote {
+                    if quote == '"' && chars[end] == '\\' {
+                        end += 1;
+                    }
+                    end += 1;
+                }
+                if end >= chars.len() {
+                    return Err(ParseError {
+                        line,
+                        column: start - i + 1,
+                        message: format!("Unterminated {} quoted value for {}", quote, key),
+                    });
+                }
+
+                let inner = chars[start + 1..end].iter().collect::<String>();
+                let value = if quote == '"' {
+                    segments(&inner, Escapes::All)
+                } else {
+                    vec![Segment::Literal(inner)]
+                };
+                // Anything after the closing quote, like a comment, is ignored
+                (value, rest_of_line(end + 1))
+            }
+            None => {
+                let raw = chars[start..line_end].iter().collect::<String>();
+                // A `#` starts a comment when it follows whitespace
+                let raw = raw
+                    .char_indices()
+                    .find(|&(at, c)| c == '#' && (at == 0 || raw[..at].ends_with([' ', '\t'])))
+                    .map_or(raw.as_str(), |(at, _)| &raw[..at])
+                    .trim_end();
+                (segments(raw, Escapes::Dollar), line_end)
+            }
+        };
+
+        line += chars[i..next.min(chars.len())]
+            .iter()
+            .filter(|&&c| c == '\n')
+            .count()
+            + 1;
+        entries.push((key.to_string(), value));
+        i = next + 1;
+    }
+
+    Ok(entries)
+}
+
+/// dotenv-flow's files, lowest priority first: `.env`, `.env.local`, `.env.<mode>` and
+/// `.env.<mode>.local`, where the mode is `NODE_ENV` or "development"
+pub fn env_files(root: &Path, base: &str) -> Vec<PathBuf> {
+    let mode = std::env::var("NODE_ENV")
+        .ok()
+        .filter(|mode| !mode.is_empty())
+        .unwrap_or_else(|| "development".to_string());
+
+    let mut files = vec![base.to_string()];
+    // Like dotenv-flow, tests leave .env.local out so they run the same everywhere
+    if mode != "test" {
+        files.push(format!("{}.local", base));
+    }
+    files.push(format!("{}.{}", base, mode));
+    files.push(format!("{}.{}.local", base, mode));
+
+    files.into_iter().map(|file| root.join(file)).collect()
+}
+
+/// Variables from a project's env files, falling back to the process environment.
+/// Keeps track of where each variable that was read came from.
+pub struct EnvVars {
+    vars: HashMap<String, (Vec<Segment>, PathBuf)>,
+    used: Vec<EnvVarSource>,
+}
+
+impl EnvVars {
+    /// Reads the layered env files that exist, later files overriding earlier ones
+    pub fn load(root: &Path, base: &str) -> Result<Self, AppError> {
+        let mut vars = HashMap::new();
+        for path in env_files(root, base) {
+            let content = match fs::read_to_string(&path) {
+                Ok(content) => content,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(AppError::Config(ConfigError::Io(e))),
+            };
+
+            let entries = parse(&content).map_err(|e| {
+                AppError::Config(ConfigError::Syntax {
+                    path: path.display().to_string(),
+                    line: e.line,
+                    column: e.column,
+                    message: e.message,
+                })
+            })?;
+            for (key, value) in entries {
+                vars.insert(key, (value, path.clone()));
+            }
+        }
+
+        Ok(Self {
+            vars,
+            used: Vec::new(),
+        })
+    }
+
+    /// A variable's value with references expanded, if it's set and not empty
+    pub fn get(&mut self, name: &str) -> Option<String> {
+        let mut read = Vec::new();
+        let value = self
+            .resolve(name, &mut Vec::new(), &mut read)
+            .filter(|value| !value.is_empty())?;
+
+        // The variables it refers to are listed too, since changing them changes it
+        for name in read {
+            if !self.used.iter().any(|source| source.name == name) {
+                let file = self
+                    .vars
+                    .get(&name)
+                    .map(|(_, path)| path.to_string_lossy().to_string());
+                self.used.push(EnvVarSource { name, file });
+            }
+        }
+        Some(value)
+    }
+
+    /// Variables set to literal values, as if read from a `.env` file
+    #[cfg(test)]
+    pub fn from_values(values: &[(&str, &str)]) -> Self {
+        let vars = values
+            .iter()
+            .map(|(name, value)| {
+                let segments = vec![Segment::Literal(value.to_string())];
+                (name.to_string(), (segments, PathBuf::from(".env")))
+            })
+            .collect();
+        Self {
+            vars,
+            used: Vec::new(),
+        }
+    }
+
+    /// Where each variable read so far came from
+    pub fn into_sources(self) -> Vec<EnvVarSource> {
+        self.used
+    }
+
+    /// `stack` holds the variables being expanded, so a cycle expands to nothing. Every
+    /// variable that's set is added to `read`, the one asked for first.
+    fn resolve(
+        &self,
+        name: &str,
+        stack: &mut Vec<String>,
+        read: &mut Vec<String>,
+    ) -> Option<String> {
+        let Some((segments, _)) = self.vars.get(name) else {
+            let value = std::env::var(name).ok()?;
+            read.push(name.to_string());
+            return Some(value);
+        };
+        if stack.iter().any(|n| n == name) {
+            return None;
+        }
+
+        read.push(name.to_string());
+        stack.push(name.to_string());
+        let mut value = String::new();
+        for segment in segments {
+            match segment {
+                Segment::Literal(text) => value.push_str(text),
+                Segment::Var {
+                    name,
+                    default,
+                    default_if_empty,
+                } => {
+                    let resolved = self
+                        .resolve(name, stack, read)
+                        .filter(|v| !(*default_if_empty && v.is_empty()));
+                    if let Some(resolved) = resolved.or_else(|| default.clone()) {
+                        value.push_str(&resolved);
+                    }
+                }
+            }
+        }
+        stack.pop();
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn literal(text: &str) -> Segment {
+        Segment::Literal(text.to_string())
+    }
+
+    fn var(name: &str, default: Option<&str>, default_if_empty: bool) -> Segment {
+        Segment::Var {
+            name: name.to_string(),
+            default: default.map(str::to_string),
+            default_if_empty,
+        }
+    }
+
+    #[test]
+    fn splits_references_from_literals() {
+        assert_eq!(
+            segments("a${B}c$D-e", Escapes::Dollar),
+            vec![
+                literal("a"),
+                var("B", None, false),
+                literal("c"),
+                var("D", None, false),
+                literal("-e"),
+            ]
+        );
+        assert_eq!(
+            segments("${A:-x}${B-y}", Escapes::Dollar),
+            vec![var("A", Some("x"), true), var("B", Some("y"), false)]
+        );
+    }
+
+    #[test]
+    fn keeps_text_that_isnt_a_reference() {
+        assert_eq!(segments("$1 ${A", Escapes::Dollar), vec![literal("$1 ${A")]);
+        assert_eq!(
+            segments("cost: $", Escapes::Dollar),
+            vec![literal("cost: $")]
+        );
+    }
+
+    #[test]
+    fn reads_escapes_by_quoting() {
+        assert_eq!(
+            segments(r"\$HOME\n", Escapes::Dollar),
+            vec![literal(r"$HOME\n")]
+        );
+        assert_eq!(
+            segments(r#"\$HOME\n\"q\"\\"#, Escapes::All),
+            vec![literal("$HOME\n\"q\"\\")]
+        );
+    }
+
+    #[test]
+    fn parses_assignments() {
+        let content = "\u{feff}# comment\r\nexport A=1\r\nB = \"two\\nlines\" # note\nC='${A}'\nD=`multi\nline`\nE=value # comment\nF=val#ue\nG=\nbad line\nH=\"x\"\n";
+        let entries = parse(content).unwrap();
+        let expected = vec![
+            ("A", vec![literal("1")]),
+            ("B", vec![literal("two\nlines")]),
+            ("C", vec![literal("${A}")]),
+            ("D", vec![literal("multi\nline")]),
+            ("E", vec![literal("value")]),
+            ("F", vec![literal("val#ue")]),
+            ("G", vec![]),
+            ("H", vec![literal("x")]),
+        ];
+        assert_eq!(
+            entries,
+            expected
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn reports_unterminated_quotes_where_they_start() {
+        let error = parse("A=1\n\nB=\"open\nC=2").unwrap_err();
+        assert_eq!((error.line, error.column), (3, 3));
+        assert!(error.message.contains('B'));
+    }
+
+    #[test]
+    fn lists_the_variables_a_value_refers_to() {
+        let mut env = EnvVars {
+            vars: HashMap::from([
+                (
+                    "DATABASE_URL".to_string(),
+                    (
+                        segments(
+                            "postgres://${DB_USER}@${SQRATCH_TEST_UNSET_HOST:-localhost}/app",
+                            Escapes::Dollar,
+                        ),
+                        PathBuf::from(".env"),
+                    ),
+                ),
+                (
+                    "DB_USER".to_string(),
+                    (vec![literal("app")], PathBuf::from(".env.local")),
+                ),
+            ]),
+            used: Vec::new(),
+        };
+
+        assert_eq!(
+            env.get("DATABASE_URL").as_deref(),
+            Some("postgres://app@localhost/app")
+        );
+        let sources = env
+            .into_sources()
+            .into_iter()
+            .map(|source| (source.name, source.file))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sources,
+            vec![
+                ("DATABASE_URL".to_string(), Some(".env".to_string())),
+                ("DB_USER".to_string(), Some(".env.local".to_string())),
+            ]
+        );
+    }
+}
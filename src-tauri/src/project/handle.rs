@@ -1,11 +1,13 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use url::Url;
 
+use crate::db::{duckdb, sqlite};
 use crate::errors::AppError;
 use crate::utils;
 
 use super::helpers::hash_str;
+use super::ProjectConfig;
 
 #[taurpc::ipc_type]
 #[derive(Debug)]
@@ -32,6 +34,8 @@ impl ProjectHandle {
     ///
     /// The path may be:
     /// - A connection string (converted to a hashed directory in app data)
+    /// - A SQLite database file (opened like a `sqlite://` connection string)
+    /// - A DuckDB database, Parquet/CSV/JSON file, or a folder of them (opened with DuckDB)
     /// - An absolute path
     /// - A relative path (resolved against current working directory)
     ///
@@ -41,15 +45,7 @@ impl ProjectHandle {
 
         // First, check if the input is a valid URL (connection string)
         if let Ok(url) = Url::parse(input) {
-            let id = hash_str(input);
-            let path = app_data_dir.join("projects").join(&id);
-            let is_temp = !path.exists();
-            return Ok(Self {
-                id,
-                path,
-                is_temp,
-                url: Some(url),
-            });
+            return Ok(Self::from_url(input, url, &app_data_dir));
         }
 
         // Handle file paths, resolving .. and . segments
@@ -71,6 +67,27 @@ impl ProjectHandle {
             )));
         }
 
+        // Database files don't need a .sqratch directory and open as a connection string
+        if resolved_path.is_file() && is_sqlite_file(&resolved_path) {
+            let url = sqlite_url(&resolved_path)?;
+            return Ok(Self::from_url(url.as_str(), url.clone(), &app_data_dir));
+        }
+
+        if resolved_path.is_file()
+            && (duckdb::is_database_file(&resolved_path) || duckdb::is_data_file(&resolved_path))
+        {
+            return Self::from_duckdb_path(&resolved_path, &app_data_dir);
+        }
+
+        // Folders of data files without a .sqratch directory are queried in place
+        if resolved_path.is_dir()
+            && !resolved_path.ends_with(".sqratch")
+            && !resolved_path.join(".sqratch").exists()
+            && duckdb::is_data_dir(&resolved_path)
+        {
+            return Self::from_duckdb_path(&resolved_path, &app_data_dir);
+        }
+
         // Determine project directory
         let project_path = match (resolved_path.is_dir(), resolved_path.ends_with(".sqratch")) {
             // Case 1: It's a directory ending with .sqratch
@@ -99,9 +116,9 @@ impl ProjectHandle {
                 })?,
         };
 
-        if !project_path.join("config.json").exists() {
+        if !ProjectConfig::exists(&project_path) {
             return Err(AppError::Other(format!(
-                "No config.json found in: {}",
+                "No config.jsonc found in: {}",
                 project_path.display()
             )));
         }
@@ -115,4 +132,38 @@ impl ProjectHandle {
             url: None,
         })
     }
+
+    fn from_duckdb_path(path: &Path, app_data_dir: &Path) -> Result<Self, AppError> {
+        let url = duckdb::file_url(path)
+            .ok_or_else(|| AppError::Other(format!("Invalid database path: {}", path.display())))?;
+        Ok(Self::from_url(url.as_str(), url.clone(), app_data_dir))
+    }
+
+    /// Projects opened from a connection string live in a hashed directory in app data
+    fn from_url(conn_str: &str, url: Url, app_data_dir: &Path) -> Self {
+        let id = hash_str(conn_str);
+        let path = app_data_dir.join("projects").join(&id);
+        let is_temp = !path.exists();
+        Self {
+            id,
+            path,
+            is_temp,
+            url: Some(url),
+        }
+    }
+}
+
+fn is_sqlite_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| sqlite::FILE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+fn sqlite_url(path: &Path) -> Result<Url, AppError> {
+    let file_url = Url::from_file_path(path)
+        .map_err(|_| AppError::Other(format!("Invalid database path: {}", path.display())))?;
+
+    let mut url = Url::parse("sqlite://").map_err(|e| AppError::Other(e.to_string()))?;
+    url.set_path(file_url.path());
+    Ok(url)
 }
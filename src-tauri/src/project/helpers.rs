@@ -1,69 +1,184 @@
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
 use sha2::{Digest, Sha256};
-use std::{
-    fs,
-    path::{Path, PathBuf},
-};
+use std::path::Path;
 use url::Url;
 
 use crate::errors::AppError;
 use crate::utils;
 
-use super::config;
+use super::config::ConnectionParams;
+use super::dotenv::{EnvVarSource, EnvVars};
+use super::{ConfigError, ProjectConfig};
 
-/// Resolves a database URL from either:
-/// - A direct connection string
-/// - A path to an .env file with optional environment variable name (e.g. "../.env|DB_URL")
-pub fn resolve_db_url(db_url: &str, cwd: &Path) -> Result<Url, AppError> {
-    // Try direct URL first
-    if let Ok(url) = Url::parse(db_url) {
-        return Ok(url);
+/// Characters left as they are in URL components; everything else is percent-encoded,
+/// including `%` itself so values that look encoded survive the round trip
+const COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+fn encode(value: &str) -> String {
+    utf8_percent_encode(value, COMPONENT).to_string()
+}
+
+/// Resolves the database URL from the config: its `url` if set, otherwise the
+/// `connectionVariable`, otherwise a URL built from the `connectionParams`. Variables are
+/// read from the layered env files in the project root, falling back to the process
+/// environment. Also returns where each variable that was used came from.
+pub fn resolve_db_url(
+    config: &ProjectConfig,
+    root: &Path,
+) -> Result<(Url, Vec<EnvVarSource>), AppError> {
+    if let Some(url) = &config.url {
+        let url = Url::parse(url).map_err(|e| {
+            AppError::Config(ConfigError::Other(format!(
+                "Invalid connection string in config: {}",
+                e
+            )))
+        })?;
+        return Ok((url, Vec::new()));
     }
 
-    // Parse env file path and optional var name
-    let (file_path, var_name) = match db_url.split_once('|') {
-        Some((path, var)) => (path.trim(), var.trim()),
-        None => (db_url.trim(), "DATABASE_URL"),
-    };
+    let env_file = config.env_file.as_deref().unwrap_or(".env");
+    let mut env = EnvVars::load(root, env_file)?;
 
-    // Resolve absolute path
-    let abs_path = if Path::new(file_path).is_relative() {
-        cwd.join(file_path)
+    let var_name = config.connection_variable.as_str();
+    let url = if let Some(db_url) = env.get(var_name) {
+        // Parse and validate the URL
+        Url::parse(&db_url).map_err(|e| {
+            AppError::Config(ConfigError::Other(format!(
+                "Invalid database URL in {}: {}",
+                var_name, e
+            )))
+        })?
     } else {
-        PathBuf::from(file_path)
+        params_url(config.connection_params.as_ref(), &mut env)?.ok_or_else(|| {
+            AppError::Config(ConfigError::Other(format!(
+                "No {} found in {} files or the environment, and no connection parameters are set",
+                var_name, env_file
+            )))
+        })?
     };
 
-    // Read and parse .env file
-    let env_content =
-        fs::read_to_string(&abs_path).map_err(|e| AppError::Config(config::ConfigError::Io(e)))?;
-
-    // Find the env var, handling comments and empty lines
-    let db_url = env_content
-        .lines()
-        .find_map(|line| {
-            let line = line.trim();
-            match line.split_once('=') {
-                Some((key, value)) if !line.starts_with('#') && key.trim() == var_name => {
-                    Some(value.trim().trim_matches(|c| c == '"' || c == '\''))
-                }
-                _ => None,
-            }
-        })
-        .ok_or_else(|| {
-            AppError::Config(config::ConfigError::Other(format!(
-                "No {} found in .env file",
-                var_name
-            )))
-        })?;
+    Ok((url, env.into_sources()))
+}
+
+/// Builds a Postgres URL from connection parameters, each read from the variable named in
+/// `connectionParams` or else the matching libpq `PG*` variable. Returns None if neither a
+/// host nor a database is set.
+fn params_url(
+    params: Option<&ConnectionParams>,
+    env: &mut EnvVars,
+) -> Result<Option<Url>, AppError> {
+    let params = params.cloned().unwrap_or_default();
+    let mut get = |configured: &Option<String>, fallback: &str| {
+        configured
+            .as_deref()
+            .and_then(|name| env.get(name))
+            .or_else(|| env.get(fallback))
+    };
+
+    let host = get(&params.host, "PGHOST");
+    let database = get(&params.database, "PGDATABASE");
+    if host.is_none() && database.is_none() {
+        return Ok(None);
+    }
+    let port = get(&params.port, "PGPORT");
+    let user = get(&params.user, "PGUSER");
+    let password = get(&params.password, "PGPASSWORD");
+    let ssl_mode = env.get("PGSSLMODE");
+
+    let mut url = "postgres://".to_string();
+    if user.is_some() || password.is_some() {
+        url.push_str(&encode(user.as_deref().unwrap_or_default()));
+        if let Some(password) = &password {
+            url.push(':');
+            url.push_str(&encode(password));
+        }
+        url.push('@');
+    }
+
+    let mut query = Vec::new();
+    let mut port = port;
+    match host.as_deref() {
+        // A socket directory can't be the URL's host, so it goes in the query
+        Some(host) if host.starts_with('/') => {
+            url.push_str("localhost");
+            query.push(format!("host={}", encode(host)));
+        }
+        Some(host) => {
+            let (host, host_port) = split_host(host)?;
+            url.push_str(&host);
+            // A port written with the host is the one meant for it
+            port = host_port.or(port);
+        }
+        None => url.push_str("localhost"),
+    }
+    if let Some(port) = &port {
+        url.push_str(&format!(":{}", port));
+    }
+    url.push('/');
+    if let Some(database) = &database {
+        url.push_str(&encode(database));
+    }
+    if let Some(ssl_mode) = &ssl_mode {
+        query.push(format!("sslmode={}", encode(ssl_mode)));
+    }
+    if !query.is_empty() {
+        url.push('?');
+        url.push_str(&query.join("&"));
+    }
 
-    // Parse and validate the URL
-    Url::parse(db_url).map_err(|e| {
-        AppError::Config(config::ConfigError::Other(format!(
-            "Invalid database URL in .env file: {}",
+    Url::parse(&url).map(Some).map_err(|e| {
+        AppError::Config(ConfigError::Other(format!(
+            "Invalid connection parameters: {}",
             e
         )))
     })
 }
 
+/// Splits `host:port`, `[ipv6]:port` or a bare IPv6 address into the URL's host, bracketed
+/// when it's IPv6, and the port if one was given. libpq's comma-separated lists of hosts
+/// can't be connected to, so they're rejected.
+fn split_host(host: &str) -> Result<(String, Option<String>), AppError> {
+    let invalid = |message: String| AppError::Config(ConfigError::Other(message));
+    if host.contains(',') {
+        return Err(invalid(format!(
+            "Host \"{}\" lists several hosts, but only one can be connected to",
+            host
+        )));
+    }
+
+    let (host, port) = match host.strip_prefix('[') {
+        Some(rest) => {
+            let (address, after) = rest
+                .split_once(']')
+                .ok_or_else(|| invalid(format!("Host \"[{}\" is missing its ']'", rest)))?;
+            let port = match after {
+                "" => None,
+                _ => Some(after.strip_prefix(':').ok_or_else(|| {
+                    invalid(format!("Unexpected \"{}\" after host [{}]", after, address))
+                })?),
+            };
+            (format!("[{}]", address), port)
+        }
+        // More than one colon is an IPv6 address without a port
+        None if host.matches(':').count() > 1 => (format!("[{}]", host), None),
+        None => match host.split_once(':') {
+            Some((host, port)) => (host.to_string(), Some(port)),
+            None => (host.to_string(), None),
+        },
+    };
+
+    match port {
+        Some(port) if port.is_empty() || !port.chars().all(|c| c.is_ascii_digit()) => Err(invalid(
+            format!("Invalid port \"{}\" in host {}", port, host),
+        )),
+        port => Ok((host, port.map(str::to_string))),
+    }
+}
+
 /// Infer a project name based on location:
 /// - For app data projects (from connection strings): use database name from the connection string
 /// - For local directory projects: use parent directory name
@@ -114,3 +229,127 @@ pub fn hash_str(conn_str: &str) -> String {
         u16::from_be_bytes(hash[6..8].try_into().unwrap())
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use percent_encoding::percent_decode_str;
+    use sqlx::postgres::{PgConnectOptions, PgSslMode};
+
+    /// Builds a URL from `PG*` variables, unset ones left empty so the process
+    /// environment isn't read, and parses it back as sqlx would
+    fn build(values: &[(&str, &str)]) -> (Url, PgConnectOptions) {
+        let mut all = vec![
+            ("PGHOST", ""),
+            ("PGPORT", ""),
+            ("PGDATABASE", ""),
+            ("PGUSER", ""),
+            ("PGPASSWORD", ""),
+            ("PGSSLMODE", ""),
+        ];
+        all.retain(|(name, _)| !values.iter().any(|(set, _)| set == name));
+        all.extend_from_slice(values);
+
+        let mut env = EnvVars::from_values(&all);
+        let url = params_url(None, &mut env).unwrap().unwrap();
+        let options = url.as_str().parse::<PgConnectOptions>().unwrap();
+        (url, options)
+    }
+
+    fn password(url: &Url) -> String {
+        let password = url.password().unwrap();
+        percent_decode_str(password)
+            .decode_utf8()
+            .unwrap()
+            .into_owned()
+    }
+
+    #[test]
+    fn encodes_credentials_and_database() {
+        let (url, options) = build(&[
+            ("PGHOST", "db.internal"),
+            ("PGUSER", "app@corp"),
+            ("PGPASSWORD", "p@ss:w/rd%20"),
+            ("PGDATABASE", "my db"),
+        ]);
+        assert_eq!(options.get_host(), "db.internal");
+        assert_eq!(options.get_username(), "app@corp");
+        assert_eq!(password(&url), "p@ss:w/rd%20");
+        assert_eq!(options.get_database(), Some("my db"));
+    }
+
+    #[test]
+    fn puts_socket_directories_in_the_query() {
+        let (url, options) = build(&[
+            ("PGHOST", "/var/run/postgresql"),
+            ("PGPORT", "5433"),
+            ("PGDATABASE", "app"),
+        ]);
+        assert_eq!(url.host_str(), Some("localhost"));
+        assert_eq!(
+            options
+                .get_socket()
+                .map(|path| path.to_string_lossy().into_owned()),
+            Some("/var/run/postgresql".to_string())
+        );
+        assert_eq!(options.get_port(), 5433);
+    }
+
+    #[test]
+    fn passes_the_ssl_mode_on() {
+        let (_, options) = build(&[("PGHOST", "db.internal"), ("PGSSLMODE", "verify-full")]);
+        assert!(matches!(options.get_ssl_mode(), PgSslMode::VerifyFull));
+    }
+
+    #[test]
+    fn a_port_with_the_host_overrides_pgport() {
+        let (_, options) = build(&[("PGHOST", "db.internal:6543"), ("PGPORT", "5432")]);
+        assert_eq!(options.get_host(), "db.internal");
+        assert_eq!(options.get_port(), 6543);
+
+        let (_, options) = build(&[("PGHOST", "[::1]:6543"), ("PGPORT", "5432")]);
+        assert_eq!(options.get_port(), 6543);
+
+        let (_, options) = build(&[("PGHOST", "db.internal"), ("PGPORT", "5432")]);
+        assert_eq!(options.get_port(), 5432);
+    }
+
+    #[test]
+    fn needs_a_host_or_database() {
+        let mut env = EnvVars::from_values(&[("PGHOST", ""), ("PGDATABASE", "")]);
+        assert!(params_url(None, &mut env).unwrap().is_none());
+    }
+
+    fn split(host: &str) -> (String, Option<String>) {
+        split_host(host).unwrap()
+    }
+
+    #[test]
+    fn splits_a_port_from_the_host() {
+        assert_eq!(split("db.internal"), ("db.internal".to_string(), None));
+        assert_eq!(
+            split("localhost:5433"),
+            ("localhost".to_string(), Some("5433".to_string()))
+        );
+    }
+
+    #[test]
+    fn brackets_ipv6_addresses() {
+        assert_eq!(split("::1"), ("[::1]".to_string(), None));
+        assert_eq!(split("[::1]"), ("[::1]".to_string(), None));
+        assert_eq!(
+            split("[fe80::1]:6543"),
+            ("[fe80::1]".to_string(), Some("6543".to_string()))
+        );
+    }
+
+    #[test]
+    fn rejects_host_lists_and_bad_ports() {
+        assert!(split_host("db1,db2").is_err());
+        assert!(split_host("db1:5432,db2:5432").is_err());
+        assert!(split_host("localhost:").is_err());
+        assert!(split_host("localhost:pg").is_err());
+        assert!(split_host("[::1").is_err());
+        assert!(split_host("[::1]5432").is_err());
+    }
+}
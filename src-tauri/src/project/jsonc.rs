@@ -0,0 +1,140 @@
+/// Turns JSONC into JSON by blanking out comments and trailing commas. Everything else
+/// keeps its byte offset, so parse errors point at the right line and column of the source.
+pub fn strip(source: &str) -> String {
+    let mut bytes = source.as_bytes().to_vec();
+    blank_comments(&mut bytes);
+    blank_trailing_commas(&mut bytes);
+    // Only ASCII bytes of comments and commas were replaced, with spaces
+    String::from_utf8(bytes).unwrap_or_default()
+}
+
+/// Replaces a byte with a space, keeping line breaks so line numbers don't move
+fn blank(byte: &mut u8) {
+    if *byte != b'\n' && *byte != b'\r' {
+        *byte = b' ';
+    }
+}
+
+fn blank_comments(bytes: &mut [u8]) {
+    let mut i = 0;
+    let mut in_string = false;
+    while i < bytes.len() {
+        if in_string {
+            match bytes[i] {
+                b'\\' => i += 1,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            i += 1;
+            continue;
+        }
+
+        match (bytes[i], bytes.get(i + 1)) {
+            (b'"', _) => {
+                in_string = true;
+                i += 1;
+            }
+            (b'/', Some(b'/')) => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    blank(&mut bytes[i]);
+                    i += 1;
+                }
+            }
+            (b'/', Some(b'*')) => {
+                // An unterminated comment runs to the end, and the parser reports the gap
+                let end = bytes[i + 2..]
+                    .windows(2)
+                    .position(|w| w == b"*/")
+                    .map_or(bytes.len(), |p| i + 2 + p + 2);
+                for byte in &mut bytes[i..end] {
+                    blank(byte);
+                }
+                i = end;
+            }
+            _ => i += 1,
+        }
+    }
+}
+
+/// Blanks commas that are followed only by whitespace before a closing bracket
+fn blank_trailing_commas(bytes: &mut [u8]) {
+    let mut in_string = false;
+    let mut pending_comma = None;
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = bytes[i];
+        if in_string {
+            match byte {
+                b'\\' => i += 1,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            i += 1;
+            continue;
+        }
+
+        match byte {
+            b'"' => {
+                in_string = true;
+                pending_comma = None;
+            }
+            b',' => pending_comma = Some(i),
+            b'}' | b']' => {
+                if let Some(comma) = pending_comma.take() {
+                    bytes[comma] = b' ';
+                }
+            }
+            b' ' | b'\t' | b'\n' | b'\r' => {}
+            _ => pending_comma = None,
+        }
+        i += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blanks_comments_keeping_offsets() {
+        let source = "{\n  // name\n  \"a\": 1, /* inline */ \"b\": 2\n}";
+        let stripped = strip(source);
+        assert_eq!(stripped.len(), source.len());
+        assert_eq!(stripped.lines().count(), source.lines().count());
+        assert_eq!(
+            stripped,
+            "{\n         \n  \"a\": 1,              \"b\": 2\n}"
+        );
+    }
+
+    #[test]
+    fn keeps_comment_markers_inside_strings() {
+        let source = r#"{ "url": "postgres://host/db", "note": "a /* b */ c", "q": "\"//\"" }"#;
+        assert_eq!(strip(source), source);
+    }
+
+    #[test]
+    fn blanks_trailing_commas_only() {
+        let source = "{ \"a\": [1, 2,], \"b\": \",}\", }";
+        let stripped = strip(source);
+        assert_eq!(stripped, "{ \"a\": [1, 2 ], \"b\": \",}\"  }");
+        assert!(serde_json::from_str::<serde_json::Value>(&stripped).is_ok());
+    }
+
+    #[test]
+    fn blanks_trailing_commas_before_comments() {
+        let stripped = strip("[1, // last\n]");
+        assert_eq!(stripped, "[1         \n]");
+    }
+
+    #[test]
+    fn keeps_line_breaks_in_block_comments() {
+        let stripped = strip("/* one\ntwo */ {}");
+        assert_eq!(stripped, "      \n       {}");
+    }
+
+    #[test]
+    fn runs_an_unterminated_comment_to_the_end() {
+        assert_eq!(strip("{} /* open"), "{}        ");
+    }
+}
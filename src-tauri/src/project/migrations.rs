@@ -0,0 +1,487 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha384};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::db::migrations::{AppliedMigration, MigrationTool};
+use crate::errors::AppError;
+
+use super::ProjectHandle;
+
+/// Directories under the project root that never hold the project's own migrations
+const SKIPPED_DIRS: &[&str] = &["node_modules", "target", "vendor", "dist", "build"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum MigrationState {
+    /// Applied from the file as it is now
+    Applied,
+    /// In the directory but not applied yet
+    Pending,
+    /// Applied, but its file is no longer in the directory
+    Missing,
+    /// Applied from a file that has changed since
+    ChecksumMismatch,
+    /// Started but never finished
+    Failed,
+}
+
+#[taurpc::ipc_type]
+#[serde(rename_all = "camelCase")]
+#[derive(Debug)]
+pub struct MigrationStatus {
+    pub id: String,
+    pub name: String,
+    pub state: MigrationState,
+    /// The migration's file, unless it's missing
+    pub path: Option<String>,
+    pub applied_at: Option<String>,
+}
+
+/// Status of every migration in one directory
+#[taurpc::ipc_type]
+#[serde(rename_all = "camelCase")]
+#[derive(Debug)]
+pub struct MigrationReport {
+    pub tool: MigrationTool,
+    pub directory: String,
+    /// Whether the database has the tool's tracking table
+    pub tracked: bool,
+    pub migrations: Vec<MigrationStatus>,
+}
+
+struct MigrationFile {
+    id: String,
+    name: String,
+    path: PathBuf,
+    /// Checksum as the tool records it, for tools that record one
+    checksum: Option<String>,
+}
+
+/// A migration directory found next to the project
+pub struct MigrationDir {
+    pub tool: MigrationTool,
+    path: PathBuf,
+    files: Vec<MigrationFile>,
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Splits `<digits>_<rest>` file names, as sqlx and Rails name migrations
+fn split_version(stem: &str) -> Option<(&str, &str)> {
+    let (version, rest) = stem.split_once('_')?;
+    (!version.is_empty() && version.chars().all(|c| c.is_ascii_digit())).then_some((version, rest))
+}
+
+fn sorted_entries(dir: &Path) -> Result<Vec<PathBuf>, AppError> {
+    let mut entries = fs::read_dir(dir)?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .collect::<Vec<_>>();
+    entries.sort();
+    Ok(entries)
+}
+
+/// `migrations/<version>_<description>.sql`, or `.up.sql` for reversible ones
+fn sqlx_files(dir: &Path) -> Result<Vec<MigrationFile>, AppError> {
+    let mut files = Vec::new();
+    for path in sorted_entries(dir)? {
+        let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let Some(stem) = file_name.strip_suffix(".sql") else {
+            continue;
+        };
+        if stem.ends_with(".down") {
+            continue;
+        }
+        let Some((version, description)) = split_version(stem.trim_end_matches(".up")) else {
+            continue;
+        };
+        files.push(MigrationFile {
+            id: version
+                .parse::<i64>()
+                .map_or(version.to_string(), |v| v.to_string()),
+            name: description.replace('_', " "),
+            checksum: Some(format!("{:x}", Sha384::digest(fs::read(&path)?))),
+            path,
+        });
+    }
+    Ok(files)
+}
+
+/// `prisma/migrations/<name>/migration.sql`
+fn prisma_files(dir: &Path) -> Result<Vec<MigrationFile>, AppError> {
+    let mut files = Vec::new();
+    for path in sorted_entries(dir)? {
+        let script = path.join("migration.sql");
+        if !script.is_file() {
+            continue;
+        }
+        let name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        files.push(MigrationFile {
+            id: name.clone(),
+            name,
+            checksum: Some(sha256_hex(&fs::read(&script)?)),
+            path: script,
+        });
+    }
+    Ok(files)
+}
+
+#[derive(Deserialize)]
+struct DrizzleJournal {
+    entries: Vec<DrizzleEntry>,
+}
+
+#[derive(Deserialize)]
+struct DrizzleEntry {
+    when: u64,
+    tag: String,
+}
+
+/// The migrations listed in Drizzle's `meta/_journal.json`, each in `<tag>.sql`
+fn drizzle_files(dir: &Path) -> Result<Vec<MigrationFile>, AppError> {
+    let journal = fs::read_to_string(dir.join("meta").join("_journal.json"))?;
+    let journal: DrizzleJournal = serde_json::from_str(&journal).map_err(|e| {
+        AppError::Other(format!(
+            "Invalid Drizzle journal in {}: {}",
+            dir.display(),
+            e
+        ))
+    })?;
+
+    journal
+        .entries
+        .into_iter()
+        .map(|entry| {
+            let path = dir.join(format!("{}.sql", entry.tag));
+            Ok(MigrationFile {
+                id: entry.when.to_string(),
+                checksum: fs::read(&path).ok().map(|sql| sha256_hex(&sql)),
+                name: entry.tag,
+                path,
+            })
+        })
+        .collect()
+}
+
+/// `db/migrate/<version>_<name>.rb`
+fn rails_files(dir: &Path) -> Result<Vec<MigrationFile>, AppError> {
+    let mut files = Vec::new();
+    for path in sorted_entries(dir)? {
+        let Some(stem) = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(|n| n.strip_suffix(".rb"))
+        else {
+            continue;
+        };
+        let Some((version, name)) = split_version(stem) else {
+            continue;
+        };
+        files.push(MigrationFile {
+            id: version.to_string(),
+            name: name.replace('_', " "),
+            checksum: None,
+            path,
+        });
+    }
+    Ok(files)
+}
+
+/// Where each tool keeps its migrations, relative to the directory of the app using it
+fn find_in(base: &Path, found: &mut Vec<(MigrationTool, PathBuf)>) {
+    for dir in ["drizzle", "migrations"] {
+        let path = base.join(dir);
+        if path.join("meta").join("_journal.json").is_file() {
+            found.push((MigrationTool::Drizzle, path));
+        } else if dir == "migrations" && path.is_dir() {
+            found.push((MigrationTool::Sqlx, path));
+        }
+    }
+
+    let prisma = base.join("prisma").join("migrations");
+    if prisma.is_dir() {
+        found.push((MigrationTool::Prisma, prisma));
+    }
+
+    let rails = base.join("db").join("migrate");
+    if rails.is_dir() {
+        found.push((MigrationTool::Rails, rails));
+    }
+}
+
+impl MigrationDir {
+    fn read(tool: MigrationTool, path: PathBuf) -> Result<Self, AppError> {
+        let files = match tool {
+            MigrationTool::Sqlx => sqlx_files(&path)?,
+            MigrationTool::Prisma => prisma_files(&path)?,
+            MigrationTool::Drizzle => drizzle_files(&path)?,
+            MigrationTool::Rails => rails_files(&path)?,
+        };
+        Ok(Self { tool, path, files })
+    }
+
+    /// Matches the directory's files with the tracking table's rows. `applied` is None when
+    /// the database has no tracking table, so every migration is pending.
+    pub fn report(self, applied: Option<Vec<AppliedMigration>>) -> MigrationReport {
+        // Numeric ids sort by value, so version 10 comes after version 9
+        let sort_key = |id: &str| (id.parse::<u64>().ok(), id.to_string());
+
+        let tracked = applied.is_some();
+        let mut pairs = BTreeMap::<_, (Option<MigrationFile>, Option<AppliedMigration>)>::new();
+        for file in self.files {
+            pairs.entry(sort_key(&file.id)).or_default().0 = Some(file);
+        }
+        for migration in applied.unwrap_or_default() {
+            pairs.entry(sort_key(&migration.id)).or_default().1 = Some(migration);
+        }
+
+        let migrations = pairs
+            .into_iter()
+            .map(|((_, id), (file, applied))| {
+                let state = match (&file, &applied) {
+                    (_, Some(applied)) if !applied.success => MigrationState::Failed,
+                    (None, _) => MigrationState::Missing,
+                    (Some(_), None) => MigrationState::Pending,
+                    (Some(file), Some(applied)) => match (&file.checksum, &applied.checksum) {
+                        (Some(a), Some(b)) if !a.eq_ignore_ascii_case(b) => {
+                            MigrationState::ChecksumMismatch
+                        }
+                        _ => MigrationState::Applied,
+                    },
+                };
+                MigrationStatus {
+                    name: file.as_ref().map_or_else(|| id.clone(), |f| f.name.clone()),
+                    id,
+                    state,
+                    path: file.map(|f| f.path.to_string_lossy().to_string()),
+                    applied_at: applied.and_then(|a| a.applied_at),
+                }
+            })
+            .collect();
+
+        MigrationReport {
+            tool: self.tool,
+            directory: self.path.to_string_lossy().to_string(),
+            tracked,
+            migrations,
+        }
+    }
+}
+
+impl ProjectHandle {
+    /// Migration directories in the project root and its immediate subdirectories, which
+    /// covers apps kept in a folder of a monorepo
+    pub fn migration_dirs(&self) -> Result<Vec<MigrationDir>, AppError> {
+        let Some(root) = self.path.parent().filter(|_| !self.is_temp) else {
+            return Ok(Vec::new());
+        };
+
+        let mut found = Vec::new();
+        find_in(root, &mut found);
+        for path in sorted_entries(root)? {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            if path.is_dir() && !name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_ref()) {
+                find_in(&path, &mut found);
+            }
+        }
+
+        let mut dirs = Vec::new();
+        for (tool, path) in found {
+            let dir = MigrationDir::read(tool, path)?;
+            // Plenty of other tools use a `migrations` folder, so it only counts for sqlx
+            // when it has sqlx's file names
+            if tool != MigrationTool::Sqlx || !dir.files.is_empty() {
+                dirs.push(dir);
+            }
+        }
+        Ok(dirs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A directory under the system temp dir, removed when dropped
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "sqratch-migrations-{}-{}",
+                name,
+                std::process::id()
+            ));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+
+        fn write(&self, relative: &str, content: &str) {
+            let path = self.0.join(relative);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn file(id: &str, checksum: Option<&str>) -> MigrationFile {
+        MigrationFile {
+            id: id.to_string(),
+            name: format!("migration {}", id),
+            path: PathBuf::from(format!("{}.sql", id)),
+            checksum: checksum.map(str::to_string),
+        }
+    }
+
+    fn applied(id: &str, checksum: Option<&str>, success: bool) -> AppliedMigration {
+        AppliedMigration {
+            id: id.to_string(),
+            checksum: checksum.map(str::to_string),
+            applied_at: Some("2024-01-01T00:00:00Z".to_string()),
+            success,
+        }
+    }
+
+    fn states(report: &MigrationReport) -> Vec<(&str, MigrationState)> {
+        report
+            .migrations
+            .iter()
+            .map(|m| (m.id.as_str(), m.state))
+            .collect()
+    }
+
+    #[test]
+    fn reads_sqlx_files_with_sha384_checksums() {
+        let dir = TempDir::new("sqlx");
+        dir.write(
+            "20240101000000_create_users.sql",
+            "CREATE TABLE users (id int);\n",
+        );
+        dir.write(
+            "0002_add_email.up.sql",
+            "ALTER TABLE users ADD email text;\n",
+        );
+        dir.write("0002_add_email.down.sql", "ALTER TABLE users DROP email;\n");
+        dir.write("README.md", "");
+        dir.write("seed_data.sql", "");
+
+        let files = sqlx_files(&dir.0).unwrap();
+        let ids = files.iter().map(|f| f.id.as_str()).collect::<Vec<_>>();
+        // Versions are numbers, so leading zeros go as sqlx reads them
+        assert_eq!(ids, vec!["2", "20240101000000"]);
+        assert_eq!(files[0].name, "add email");
+        assert_eq!(
+            files[1].checksum.as_deref(),
+            Some(
+                "6337697883ead93bde2c64aa4c89983e494facc84418dc07c69854725a0401e9\
+                 abccd1b1a56f06b2871d89dd25d29d40"
+            )
+        );
+    }
+
+    #[test]
+    fn reads_prisma_and_drizzle_files_with_sha256_checksums() {
+        let dir = TempDir::new("prisma");
+        dir.write(
+            "20240101000000_init/migration.sql",
+            "CREATE TABLE posts (id int);\n",
+        );
+        dir.write("migration_lock.toml", "");
+
+        let files = prisma_files(&dir.0).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].id, "20240101000000_init");
+        assert_eq!(
+            files[0].checksum.as_deref(),
+            Some("dcd8b5e29ba86b98d4a2cc76eebd048877bae391cde26818ba2a1e43d258ae56")
+        );
+
+        let dir = TempDir::new("drizzle");
+        dir.write(
+            "meta/_journal.json",
+            r#"{ "version": "7", "entries": [
+                { "idx": 0, "when": 1704067200000, "tag": "0000_add_title", "breakpoints": true },
+                { "idx": 1, "when": 1704153600000, "tag": "0001_gone", "breakpoints": true }
+            ] }"#,
+        );
+        dir.write("0000_add_title.sql", "ALTER TABLE posts ADD title text;\n");
+
+        let files = drizzle_files(&dir.0).unwrap();
+        assert_eq!(files[0].id, "1704067200000");
+        assert_eq!(files[0].name, "0000_add_title");
+        assert_eq!(
+            files[0].checksum.as_deref(),
+            Some("96ee607c9f3c7f32bb2b2a9feeac86dc98e393d87ad7880dcb0730b32447e231")
+        );
+        // A journal entry whose file was deleted has nothing to compare
+        assert_eq!(files[1].checksum, None);
+    }
+
+    #[test]
+    fn reports_each_state_in_version_order() {
+        let dir = MigrationDir {
+            tool: MigrationTool::Sqlx,
+            path: PathBuf::from("migrations"),
+            files: vec![
+                file("1", Some("aa")),
+                file("10", Some("ee")),
+                file("2", Some("bb")),
+                file("9", Some("dd")),
+            ],
+        };
+        let report = dir.report(Some(vec![
+            applied("1", Some("AA"), true),
+            applied("2", Some("changed"), true),
+            applied("3", Some("cc"), true),
+            applied("9", Some("dd"), false),
+        ]));
+
+        assert!(report.tracked);
+        assert_eq!(
+            states(&report),
+            vec![
+                ("1", MigrationState::Applied),
+                ("2", MigrationState::ChecksumMismatch),
+                ("3", MigrationState::Missing),
+                ("9", MigrationState::Failed),
+                ("10", MigrationState::Pending),
+            ]
+        );
+
+        let missing = &report.migrations[2];
+        assert_eq!(missing.name, "3");
+        assert_eq!(missing.path, None);
+        assert_eq!(report.migrations[4].applied_at, None);
+    }
+
+    #[test]
+    fn everything_is_pending_without_a_tracking_table() {
+        let dir = MigrationDir {
+            tool: MigrationTool::Rails,
+            path: PathBuf::from("db/migrate"),
+            files: vec![file("20240101000000", None), file("20240102000000", None)],
+        };
+        let report = dir.report(None);
+
+        assert!(!report.tracked);
+        assert!(report
+            .migrations
+            .iter()
+            .all(|m| m.state == MigrationState::Pending));
+    }
+}
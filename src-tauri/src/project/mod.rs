@@ -1,14 +1,23 @@
 mod config;
+mod dotenv;
 mod handle;
 mod helpers;
+mod jsonc;
+mod migrations;
+mod snapshots;
 
 use url::Url;
 
+use crate::db::binary::BinaryFormat;
+use crate::db::types::TimeZoneMode;
 use crate::errors::AppError;
 
 pub use self::config::{ConfigError, ProjectConfig};
+pub use self::dotenv::EnvVarSource;
 pub use self::handle::ProjectHandle;
 use self::helpers::{infer_project_name, resolve_db_url};
+pub use self::migrations::MigrationReport;
+pub use self::snapshots::SnapshotInfo;
 
 /// Runtime reference to a project
 #[taurpc::ipc_type]
@@ -22,6 +31,12 @@ pub struct Project {
     /// Database connection string
     #[specta(type = String)]
     pub db_url: Url,
+    /// Zone used to display timestamps with a time zone
+    pub time_zone: TimeZoneMode,
+    /// How binary cells are previewed
+    pub binary_preview: BinaryFormat,
+    /// Environment variables the connection was built from, and the file each came from
+    pub env_sources: Vec<EnvVarSource>,
 }
 
 impl Project {
@@ -30,10 +45,12 @@ impl Project {
         // Try to load config from the directory
         let config = ProjectConfig::load(handle)?;
 
-        let db_url = resolve_db_url(&config.db, &handle.path)?;
+        // Env files and relative paths in the config are relative to the project root
+        let root = handle.path.parent().unwrap_or(&handle.path);
+        let (db_url, env_sources) = resolve_db_url(&config, root)?;
 
         // Determine the project name if not provided in the config
-        let name = match config.name {
+        let name = match config.settings.project_name {
             Some(name) => name,
             None => infer_project_name(&handle.path, &db_url)?,
         };
@@ -42,6 +59,9 @@ impl Project {
             name,
             handle: handle.clone(),
             db_url,
+            time_zone: config.settings.time_zone,
+            binary_preview: config.settings.binary_preview,
+            env_sources,
         })
     }
 
@@ -0,0 +1,155 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::db::catalog::{CatalogSnapshot, SNAPSHOT_VERSION};
+use crate::errors::AppError;
+
+use super::{ConfigError, ProjectHandle};
+
+/// A catalog snapshot saved in the project
+#[taurpc::ipc_type]
+#[serde(rename_all = "camelCase")]
+#[derive(Debug)]
+pub struct SnapshotInfo {
+    pub name: String,
+    pub path: String,
+}
+
+/// Reads a snapshot file. One written in another format is rejected rather than read with
+/// its missing fields empty, which would show everything they hold as removed.
+fn read_snapshot(path: &Path) -> Result<CatalogSnapshot, AppError> {
+    let json = fs::read_to_string(path).map_err(ConfigError::Io)?;
+    let value: serde_json::Value = serde_json::from_str(&json).map_err(ConfigError::from)?;
+
+    let version = value.get("version").and_then(serde_json::Value::as_u64);
+    if version != Some(SNAPSHOT_VERSION as u64) {
+        return Err(AppError::Other(format!(
+            "{} is in snapshot format {}, but this version of Sqratch reads format {}; take the snapshot again",
+            path.display(),
+            version.map_or_else(|| "unknown".to_string(), |v| v.to_string()),
+            SNAPSHOT_VERSION
+        )));
+    }
+
+    Ok(serde_json::from_value(value).map_err(ConfigError::from)?)
+}
+
+impl ProjectHandle {
+    /// Directory saved catalog snapshots are kept in
+    pub fn snapshots_dir(&self) -> PathBuf {
+        self.path.join("snapshots")
+    }
+
+    fn snapshot_path(&self, name: &str) -> Result<PathBuf, AppError> {
+        if self.is_temp {
+            return Err(AppError::Other(
+                "Snapshots are saved in the project directory, so open a project to use them"
+                    .to_string(),
+            ));
+        }
+
+        // The name becomes a file name, so it can't reach outside the directory
+        let valid = !name.is_empty()
+            && !name.starts_with('.')
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if !valid {
+            return Err(AppError::Other(format!(
+                "Invalid snapshot name \"{}\"; use letters, digits, '-', '_' and '.'",
+                name
+            )));
+        }
+
+        Ok(self.snapshots_dir().join(format!("{}.json", name)))
+    }
+
+    pub fn save_snapshot(
+        &self,
+        name: &str,
+        snapshot: &CatalogSnapshot,
+    ) -> Result<SnapshotInfo, AppError> {
+        let path = self.snapshot_path(name)?;
+        fs::create_dir_all(self.snapshots_dir())?;
+
+        let mut json = serde_json::to_string_pretty(snapshot).map_err(ConfigError::from)?;
+        json.push('\n');
+        fs::write(&path, json)?;
+
+        Ok(SnapshotInfo {
+            name: name.to_string(),
+            path: path.to_string_lossy().to_string(),
+        })
+    }
+
+    pub fn load_snapshot(&self, name: &str) -> Result<CatalogSnapshot, AppError> {
+        read_snapshot(&self.snapshot_path(name)?)
+    }
+
+    /// Directory the committed schema snapshot is written to, shared with the team in git
+    pub fn schema_dir(&self) -> PathBuf {
+        self.path.join("schema")
+    }
+
+    fn schema_snapshot_path(&self) -> Result<PathBuf, AppError> {
+        if self.is_temp {
+            return Err(AppError::Other(
+                "The schema snapshot is written to the project directory, so open a project to use it"
+                    .to_string(),
+            ));
+        }
+        Ok(self.schema_dir().join("catalog.json"))
+    }
+
+    /// Writes the schema snapshot meant to be committed. The snapshot is sorted, so writing
+    /// an unchanged schema leaves the file byte-for-byte the same.
+    pub fn write_schema_snapshot(
+        &self,
+        snapshot: &CatalogSnapshot,
+    ) -> Result<SnapshotInfo, AppError> {
+        let path = self.schema_snapshot_path()?;
+        fs::create_dir_all(self.schema_dir())?;
+
+        let mut json = serde_json::to_string_pretty(snapshot).map_err(ConfigError::from)?;
+        json.push('\n');
+        fs::write(&path, json)?;
+
+        Ok(SnapshotInfo {
+            name: "catalog".to_string(),
+            path: path.to_string_lossy().to_string(),
+        })
+    }
+
+    /// The committed schema snapshot, if one has been written
+    pub fn read_schema_snapshot(&self) -> Result<Option<CatalogSnapshot>, AppError> {
+        let path = self.schema_dir().join("catalog.json");
+        if self.is_temp || !path.exists() {
+            return Ok(None);
+        }
+
+        read_snapshot(&path).map(Some)
+    }
+
+    /// Saved snapshots, by name
+    pub fn list_snapshots(&self) -> Result<Vec<SnapshotInfo>, AppError> {
+        let dir = self.snapshots_dir();
+        if self.is_temp || !dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut snapshots = fs::read_dir(&dir)?
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension()? != "json" {
+                    return None;
+                }
+                Some(SnapshotInfo {
+                    name: path.file_stem()?.to_string_lossy().to_string(),
+                    path: path.to_string_lossy().to_string(),
+                })
+            })
+            .collect::<Vec<_>>();
+        snapshots.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(snapshots)
+    }
+}
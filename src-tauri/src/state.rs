@@ -1,16 +1,25 @@
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
 
 use tauri::{AppHandle, Manager, Runtime, Window};
 use tokio::sync::Mutex;
 
+use crate::db::changes::ChangeSet;
 use crate::db::client::{create_client, DatabaseClient};
+use crate::db::search::SearchCancel;
 use crate::errors::AppError;
 use crate::project::Project;
 
 pub struct WindowState {
     project: Arc<Project>,
-    client: Arc<Mutex<dyn DatabaseClient>>,
+    client: Arc<Mutex<Box<dyn DatabaseClient>>>,
+    /// Row changes staged for review before they're applied
+    changes: Arc<Mutex<ChangeSet>>,
+    /// Cancels the window's running search, if any
+    search: Arc<Mutex<Option<SearchCancel>>>,
+    /// File picked for the window's last import preview, which is the one imported
+    import_path: Arc<Mutex<Option<PathBuf>>>,
 }
 
 pub struct AppState {
@@ -28,7 +37,7 @@ impl AppState {
 
 pub fn get_window_client(
     window: &Window<impl Runtime>,
-) -> Result<Arc<Mutex<dyn DatabaseClient>>, AppError> {
+) -> Result<Arc<Mutex<Box<dyn DatabaseClient>>>, AppError> {
     let app = window.app_handle();
     let state = app.state::<AppState>();
     let windows = state.windows.read().unwrap();
@@ -40,6 +49,48 @@ pub fn get_window_client(
     return Ok(window_state.client.clone());
 }
 
+pub fn get_window_changes(
+    window: &Window<impl Runtime>,
+) -> Result<Arc<Mutex<ChangeSet>>, AppError> {
+    let app = window.app_handle();
+    let state = app.state::<AppState>();
+    let windows = state.windows.read().unwrap();
+
+    let window_state = windows
+        .get(window.label())
+        .ok_or(AppError::Other("Window not found".to_string()))?;
+
+    return Ok(window_state.changes.clone());
+}
+
+pub fn get_window_search(
+    window: &Window<impl Runtime>,
+) -> Result<Arc<Mutex<Option<SearchCancel>>>, AppError> {
+    let app = window.app_handle();
+    let state = app.state::<AppState>();
+    let windows = state.windows.read().unwrap();
+
+    let window_state = windows
+        .get(window.label())
+        .ok_or(AppError::Other("Window not found".to_string()))?;
+
+    return Ok(window_state.search.clone());
+}
+
+pub fn get_window_import_path(
+    window: &Window<impl Runtime>,
+) -> Result<Arc<Mutex<Option<PathBuf>>>, AppError> {
+    let app = window.app_handle();
+    let state = app.state::<AppState>();
+    let windows = state.windows.read().unwrap();
+
+    let window_state = windows
+        .get(window.label())
+        .ok_or(AppError::Other("Window not found".to_string()))?;
+
+    return Ok(window_state.import_path.clone());
+}
+
 pub fn get_window_project(window: &Window<impl Runtime>) -> Result<Arc<Project>, AppError> {
     let app = window.app_handle();
     let state = app.state::<AppState>();
@@ -55,12 +106,17 @@ pub fn get_window_project(window: &Window<impl Runtime>) -> Result<Arc<Project>,
 pub fn init_project_window(app: &AppHandle, project: Project) -> Result<(), AppError> {
     let state = app.state::<AppState>();
 
-    let client = create_client(&project.db_url)?;
+    let mut client = create_client(&project.db_url)?;
+    client.set_time_zone(project.time_zone);
+    client.set_binary_format(project.binary_preview);
 
     let window_label = project.window_label();
     let window_state = WindowState {
         project: Arc::new(project),
         client: Arc::new(Mutex::new(client)),
+        changes: Arc::new(Mutex::new(ChangeSet::default())),
+        search: Arc::new(Mutex::new(None)),
+        import_path: Arc::new(Mutex::new(None)),
     };
 
     state
@@ -0,0 +1,52 @@
+use std::path::PathBuf;
+
+use tauri::{Runtime, Window};
+use tauri_plugin_dialog::DialogExt;
+
+/// Asks where to save a file, returning `None` if the user cancels
+pub async fn pick_save_path<R: Runtime>(
+    window: &Window<R>,
+    filter_name: &str,
+    extensions: &[&str],
+    file_name: &str,
+) -> Option<PathBuf> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+
+    window
+        .dialog()
+        .file()
+        .set_parent(window)
+        .add_filter(filter_name, extensions)
+        .set_file_name(file_name)
+        .save_file(move |path| {
+            let _ = tx.send(path);
+        });
+
+    rx.await
+        .ok()
+        .flatten()
+        .and_then(|path| path.into_path().ok())
+}
+
+/// Asks for a file to open, returning `None` if the user cancels
+pub async fn pick_open_path<R: Runtime>(
+    window: &Window<R>,
+    filter_name: &str,
+    extensions: &[&str],
+) -> Option<PathBuf> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+
+    window
+        .dialog()
+        .file()
+        .set_parent(window)
+        .add_filter(filter_name, extensions)
+        .pick_file(move |path| {
+            let _ = tx.send(path);
+        });
+
+    rx.await
+        .ok()
+        .flatten()
+        .and_then(|path| path.into_path().ok())
+}
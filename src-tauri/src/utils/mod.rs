@@ -1,2 +1,3 @@
+pub mod dialogs;
 pub mod paths;
 pub mod plugins;
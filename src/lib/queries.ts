@@ -18,6 +18,14 @@ const querySchema = {
 			queryKey: null,
 			queryFn: taurpc.db.get_all_entities,
 		},
+		driver: {
+			queryKey: null,
+			queryFn: taurpc.db.get_driver,
+		},
+		serverInfo: {
+			queryKey: null,
+			queryFn: taurpc.db.get_server_info,
+		},
 	},
 } as const satisfies Parameters<typeof createQueryKeyStore>[0]
 